@@ -29,6 +29,31 @@ impl Program {
     pub fn get_name_with_check_mode(&self) -> String {
         format!("{}-{}", self.get_name(), self.get_check_mode())
     }
+    /// The number of statements in the bodies of the methods of the program,
+    /// a rough measure of its size.
+    pub fn statement_count(&self) -> usize {
+        match self {
+            Program::Legacy(program) => program
+                .methods
+                .iter()
+                .flat_map(|method| &method.basic_blocks)
+                .map(|block| block.stmts.len())
+                .sum(),
+            Program::Low(program) => {
+                let procedures = program
+                    .procedures
+                    .iter()
+                    .flat_map(|procedure| &procedure.basic_blocks)
+                    .map(|block| block.statements.len());
+                let methods = program
+                    .methods
+                    .iter()
+                    .filter_map(|method| method.body.as_ref())
+                    .map(|body| body.len());
+                procedures.chain(methods).sum()
+            }
+        }
+    }
 }
 
 impl<'v> ToViper<'v, viper::Program<'v>> for Program {
@@ -7,7 +7,7 @@
 use crate::vir::{program::Program, Position};
 use log::{debug, trace};
 use rustc_hash::{FxHashMap, FxHashSet};
-use viper::VerificationResult;
+use viper::{VerificationResult, VerificationResultKind};
 
 pub enum NormalizationInfo {
     LegacyProgram { original_position_ids: Vec<u64> },
@@ -104,7 +104,7 @@ impl NormalizationInfo {
 
     /// Denormalize a verification result.
     pub fn denormalize_result(&self, result: &mut VerificationResult) {
-        if let VerificationResult::Failure(ref mut ver_errors) = result {
+        if let VerificationResultKind::Failure(ref mut ver_errors) = result.kind {
             ver_errors.iter_mut().for_each(|ver_error| {
                 if let Some(pos) = ver_error.pos_id.as_mut() {
                     self.denormalize_position_string(pos);
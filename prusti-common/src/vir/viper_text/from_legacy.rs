@@ -0,0 +1,503 @@
+// © 2023, ETH Zurich
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! Printing of legacy VIR programs, following `to_viper`.
+
+use super::{
+    syntax::{
+        Annotation, Axiom, BinaryOp, Collection, Domain, DomainFunction, Expr, Function, Method,
+        Predicate, Program, Quantifier, Stmt, StmtKind, Type, UnaryOp, VarDecl,
+    },
+    unsupported, Result,
+};
+use crate::config;
+use vir::legacy::{
+    self as vir_legacy, borrow_id, BinaryOpKind, CfgMethod, Const, ContainerOpKind, PermAmount,
+    Position, Successor, UnaryOpKind, RETURN_LABEL,
+};
+
+/// The predicate that is inhaled before applying the magic wand of a borrow.
+pub(super) const DEAD_BORROW_TOKEN: &str = "DeadBorrowToken$";
+
+/// The function that represents the symbolic read permission amount.
+pub(super) const READ_PERMISSION: &str = "read$";
+
+pub(super) fn program(program: &vir_legacy::Program) -> Result<Program> {
+    if !program.backend_types.is_empty() {
+        return unsupported("backend types");
+    }
+    if config::enable_verify_only_basic_block_path() {
+        return unsupported("verifying a single basic block path");
+    }
+    let mut functions = program
+        .functions
+        .iter()
+        .map(function)
+        .collect::<Result<Vec<_>>>()?;
+    functions.push(Function {
+        annotations: Vec::new(),
+        name: READ_PERMISSION.to_string(),
+        parameters: Vec::new(),
+        return_type: Type::Perm,
+        pres: Vec::new(),
+        posts: vec![
+            Expr::binary(BinaryOp::Lt, Expr::NoPerm, Expr::Result),
+            Expr::binary(BinaryOp::Lt, Expr::Result, Expr::FullPerm),
+        ],
+        body: None,
+    });
+    let mut methods = Vec::new();
+    if !config::verify_only_preamble() {
+        for method in &program.methods {
+            methods.push(cfg_method(method)?);
+        }
+        for method in &program.builtin_methods {
+            methods.push(Method {
+                annotations: Vec::new(),
+                name: method.name.clone(),
+                parameters: variables(&method.formal_args)?,
+                returns: variables(&method.formal_returns)?,
+                pres: exprs(&method.pres)?,
+                posts: exprs(&method.posts)?,
+                body: None,
+            });
+        }
+    }
+    Ok(Program {
+        annotations: vec![
+            Annotation::new("vir", "legacy"),
+            Annotation::new("program", &program.name),
+        ],
+        domains: program.domains.iter().map(domain).collect::<Result<_>>()?,
+        fields: program
+            .fields
+            .iter()
+            .map(|field| Ok(VarDecl::new(&field.name, ty(&field.typ)?)))
+            .collect::<Result<_>>()?,
+        functions,
+        predicates: program
+            .viper_predicates
+            .iter()
+            .map(predicate)
+            .collect::<Result<_>>()?,
+        methods,
+    })
+}
+
+fn domain(domain: &vir_legacy::Domain) -> Result<Domain> {
+    if !domain.type_vars.is_empty() {
+        return unsupported(format!("the type parameters of domain {}", domain.name));
+    }
+    Ok(Domain {
+        name: domain.name.clone(),
+        type_vars: Vec::new(),
+        functions: domain
+            .functions
+            .iter()
+            .map(|function| {
+                Ok(DomainFunction {
+                    unique: function.unique,
+                    name: function.name.clone(),
+                    parameters: variables(&function.formal_args)?,
+                    return_type: ty(&function.return_type)?,
+                })
+            })
+            .collect::<Result<_>>()?,
+        axioms: domain
+            .axioms
+            .iter()
+            .map(|axiom| {
+                Ok(Axiom {
+                    comment: axiom.comment.clone(),
+                    name: axiom.name.clone(),
+                    body: expr(&axiom.expr)?,
+                })
+            })
+            .collect::<Result<_>>()?,
+    })
+}
+
+fn function(function: &vir_legacy::Function) -> Result<Function> {
+    Ok(Function {
+        annotations: Vec::new(),
+        name: function.name.clone(),
+        parameters: variables(&function.formal_args)?,
+        return_type: ty(&function.return_type)?,
+        pres: exprs(&function.pres)?,
+        posts: exprs(&function.posts)?,
+        body: function.body.as_ref().map(expr).transpose()?,
+    })
+}
+
+fn predicate(predicate: &vir_legacy::Predicate) -> Result<Predicate> {
+    let (name, this, body) = match predicate {
+        vir_legacy::Predicate::Struct(predicate) => {
+            (&predicate.name, &predicate.this, predicate.body.clone())
+        }
+        vir_legacy::Predicate::Enum(enum_predicate) => {
+            (&enum_predicate.name, &enum_predicate.this, predicate.body())
+        }
+        vir_legacy::Predicate::Bodyless(name, this) => (name, this, None),
+    };
+    Ok(Predicate {
+        name: name.clone(),
+        parameters: vec![self::variable(this)?],
+        body: body.as_ref().map(expr).transpose()?,
+    })
+}
+
+/// The first block is printed first, the others are sorted by label.
+fn cfg_method(method: &CfgMethod) -> Result<Method> {
+    let labels = method.basic_blocks_labels();
+    let mut body = Vec::new();
+    for local in &method.local_vars {
+        body.push(StmtKind::Var(variable(local)?).into());
+    }
+    let mut blocks: Vec<_> = method.basic_blocks.iter().enumerate().collect();
+    if let Some((_, rest)) = blocks.split_first_mut() {
+        rest.sort_by_key(|(index, _)| &labels[*index]);
+    }
+    for (index, block) in blocks {
+        body.push(StmtKind::Comment(String::new()).into());
+        body.push(StmtKind::Label(labels[index].clone()).into());
+        body.extend(statements(&block.stmts)?);
+        match &block.successor {
+            Successor::Undefined => {
+                return unsupported(format!("the block {} without a successor", labels[index]))
+            }
+            Successor::Return => body.push(StmtKind::Goto(RETURN_LABEL.to_string()).into()),
+            Successor::Goto(target) => {
+                body.push(StmtKind::Goto(labels[target.index()].clone()).into())
+            }
+            Successor::GotoSwitch(targets, default_target) => {
+                for (test, target) in targets {
+                    let goto = StmtKind::Goto(labels[target.index()].clone()).into();
+                    body.push(StmtKind::If(expr(test)?, vec![goto], Vec::new()).into());
+                }
+                body.push(StmtKind::Goto(labels[default_target.index()].clone()).into());
+            }
+        }
+    }
+    body.push(StmtKind::Label(RETURN_LABEL.to_string()).into());
+    Ok(Method {
+        annotations: Vec::new(),
+        name: method.name(),
+        parameters: Vec::new(),
+        returns: variables(method.get_formal_returns())?,
+        pres: Vec::new(),
+        posts: Vec::new(),
+        body: Some(body),
+    })
+}
+
+fn statements(statements: &[vir_legacy::Stmt]) -> Result<Vec<Stmt>> {
+    let mut result = Vec::new();
+    for statement in statements {
+        stmt(statement, &mut result)?;
+    }
+    Ok(result)
+}
+
+fn positioned(kind: StmtKind, position: &Position) -> Stmt {
+    Stmt {
+        kind,
+        annotation: (!position.is_default())
+            .then(|| Annotation::position(position.line(), position.column(), position.id())),
+    }
+}
+
+fn predicate_access(
+    name: &str,
+    arguments: &[vir_legacy::Expr],
+    permission: PermAmount,
+) -> Result<Expr> {
+    Ok(Expr::acc(
+        Expr::Call(name.to_string(), exprs(arguments)?),
+        perm_amount(permission),
+    ))
+}
+
+fn dead_borrow_token(borrow: isize) -> Expr {
+    Expr::acc(
+        Expr::Call(
+            DEAD_BORROW_TOKEN.to_string(),
+            vec![Expr::Int(borrow.to_string())],
+        ),
+        Expr::FullPerm,
+    )
+}
+
+/// Pushes the statements that encode `statement`.
+fn stmt(statement: &vir_legacy::Stmt, result: &mut Vec<Stmt>) -> Result<()> {
+    use vir_legacy::Stmt as S;
+    let statement = match statement {
+        S::Comment(comment) => StmtKind::Comment(comment.clone()).into(),
+        S::Label(label) => StmtKind::Label(label.clone()).into(),
+        S::Inhale(expression) => StmtKind::Inhale(expr(expression)?).into(),
+        S::Exhale(expression, position) => {
+            positioned(StmtKind::Exhale(expr(expression)?), position)
+        }
+        S::Assert(expression, position) => {
+            positioned(StmtKind::Assert(expr(expression)?), position)
+        }
+        S::Refute(expression, position) => {
+            positioned(StmtKind::Refute(expr(expression)?), position)
+        }
+        S::MethodCall(method_name, arguments, targets) => StmtKind::Call(
+            targets.iter().map(|target| local(&target.name)).collect(),
+            method_name.clone(),
+            exprs(arguments)?,
+        )
+        .into(),
+        S::Assign(target, value, _) => StmtKind::Assign(expr(target)?, expr(value)?).into(),
+        S::Fold(name, arguments, permission, _, position) => positioned(
+            StmtKind::Fold(predicate_access(name, arguments, *permission)?),
+            position,
+        ),
+        S::Unfold(name, arguments, permission, _) => {
+            StmtKind::Unfold(predicate_access(name, arguments, *permission)?).into()
+        }
+        S::Obtain(..)
+        | S::BeginFrame
+        | S::EndFrame
+        | S::TransferPerm(..)
+        | S::ExpireBorrows(_)
+        | S::Downcast(..) => StmtKind::Comment(statement.to_string()).into(),
+        S::PackageMagicWand(wand, body, _, variables, position) => {
+            let mut package_body = Vec::new();
+            for variable in variables {
+                package_body.push(StmtKind::Var(self::variable(variable)?).into());
+            }
+            for statement in body {
+                package_statement(statement, &mut package_body)?;
+            }
+            positioned(StmtKind::Package(expr(wand)?, package_body), position)
+        }
+        S::ApplyMagicWand(wand, position) => {
+            let vir_legacy::Expr::MagicWand(_, _, Some(borrow), _) = wand else {
+                return unsupported(format!("applying the magic wand {wand} without a borrow"));
+            };
+            let token = dead_borrow_token(borrow_id(*borrow) as isize);
+            result.push(StmtKind::Inhale(token).into());
+            positioned(StmtKind::Apply(expr(wand)?), position)
+        }
+        S::If(guard, then_branch, else_branch) => StmtKind::If(
+            expr(guard)?,
+            statements(then_branch)?,
+            statements(else_branch)?,
+        )
+        .into(),
+    };
+    result.push(statement);
+    Ok(())
+}
+
+/// Silicon needs help in showing that a package statement has access to the
+/// paths that it uses, so the footprints of assignments, exhales and folds
+/// are asserted first.
+fn package_statement(statement: &vir_legacy::Stmt, result: &mut Vec<Stmt>) -> Result<()> {
+    let footprint = match statement {
+        vir_legacy::Stmt::Assign(_, value, _) => value,
+        vir_legacy::Stmt::Exhale(expression, _) => expression,
+        vir_legacy::Stmt::Fold(_, arguments, ..) if arguments.len() == 1 => &arguments[0],
+        vir_legacy::Stmt::If(guard, then_branch, else_branch) => {
+            let mut then_statements = Vec::new();
+            for statement in then_branch {
+                package_statement(statement, &mut then_statements)?;
+            }
+            let mut else_statements = Vec::new();
+            for statement in else_branch {
+                package_statement(statement, &mut else_statements)?;
+            }
+            result.push(StmtKind::If(expr(guard)?, then_statements, else_statements).into());
+            return Ok(());
+        }
+        _ => return stmt(statement, result),
+    };
+    for access in footprint.compute_footprint(PermAmount::Read) {
+        result.push(StmtKind::Assert(expr(&access)?).into());
+    }
+    stmt(statement, result)
+}
+
+fn local(name: &str) -> Expr {
+    if name == "__result" {
+        Expr::Result
+    } else {
+        Expr::Local(name.to_string())
+    }
+}
+
+fn perm_amount(permission: PermAmount) -> Expr {
+    let read = || Expr::Call(READ_PERMISSION.to_string(), Vec::new());
+    match permission {
+        PermAmount::Write => Expr::FullPerm,
+        PermAmount::Read => read(),
+        PermAmount::Remaining => Expr::binary(BinaryOp::Sub, Expr::FullPerm, read()),
+    }
+}
+
+fn exprs(expressions: &[vir_legacy::Expr]) -> Result<Vec<Expr>> {
+    expressions.iter().map(expr).collect()
+}
+
+fn boxed(expression: &vir_legacy::Expr) -> Result<Box<Expr>> {
+    expr(expression).map(Box::new)
+}
+
+fn expr(expression: &vir_legacy::Expr) -> Result<Expr> {
+    use vir_legacy::Expr as E;
+    let expr = match expression {
+        E::Local(variable, _) => local(&variable.name),
+        E::Variant(base, field, _) | E::Field(base, field, _) => {
+            Expr::Field(boxed(base)?, field.name.clone())
+        }
+        E::LabelledOld(label, base, _) => Expr::Old(Some(label.clone()), boxed(base)?),
+        E::Const(constant, _) => match constant {
+            Const::Bool(value) => Expr::Bool(*value),
+            Const::Int(value) => Expr::Int(value.to_string()),
+            Const::BigInt(value) => Expr::Int(value.clone()),
+            Const::FnPtr => Expr::Null,
+            Const::Float(_) | Const::BitVector(_) => {
+                return unsupported(format!("the constant {constant}"))
+            }
+        },
+        E::MagicWand(left, right, borrow, _) => {
+            let borrow = borrow.map_or(-1, |borrow| borrow_id(borrow) as isize);
+            Expr::binary(
+                BinaryOp::Wand,
+                Expr::binary(BinaryOp::And, dead_borrow_token(borrow), expr(left)?),
+                expr(right)?,
+            )
+        }
+        E::PredicateAccessPredicate(name, argument, permission, _) => {
+            predicate_access(name, std::slice::from_ref(&**argument), *permission)?
+        }
+        E::FieldAccessPredicate(location, permission, _) => {
+            Expr::acc(expr(location)?, perm_amount(*permission))
+        }
+        E::UnaryOp(op, argument, _) => {
+            let op = match op {
+                UnaryOpKind::Not => UnaryOp::Not,
+                UnaryOpKind::Minus => UnaryOp::Minus,
+                UnaryOpKind::IsNaN => return unsupported("floating point operations"),
+            };
+            Expr::Unary(op, boxed(argument)?)
+        }
+        E::BinOp(op, left, right, _) => {
+            let op = match op {
+                BinaryOpKind::EqCmp => BinaryOp::Eq,
+                BinaryOpKind::NeCmp => BinaryOp::Ne,
+                BinaryOpKind::GtCmp => BinaryOp::Gt,
+                BinaryOpKind::GeCmp => BinaryOp::Ge,
+                BinaryOpKind::LtCmp => BinaryOp::Lt,
+                BinaryOpKind::LeCmp => BinaryOp::Le,
+                BinaryOpKind::Add => BinaryOp::Add,
+                BinaryOpKind::Sub => BinaryOp::Sub,
+                BinaryOpKind::Mul => BinaryOp::Mul,
+                BinaryOpKind::Div => BinaryOp::Div,
+                BinaryOpKind::Mod => BinaryOp::Mod,
+                BinaryOpKind::And => BinaryOp::And,
+                BinaryOpKind::Or => BinaryOp::Or,
+                BinaryOpKind::Implies => BinaryOp::Implies,
+                BinaryOpKind::BitAnd
+                | BinaryOpKind::BitOr
+                | BinaryOpKind::BitXor
+                | BinaryOpKind::Shl
+                | BinaryOpKind::LShr
+                | BinaryOpKind::AShr
+                | BinaryOpKind::Min
+                | BinaryOpKind::Max => {
+                    return unsupported(format!("the bitvector or float operation {op}"))
+                }
+            };
+            Expr::Binary(op, boxed(left)?, boxed(right)?)
+        }
+        E::ContainerOp(op, left, right, _) => match op {
+            ContainerOpKind::SeqIndex => Expr::Index(boxed(left)?, boxed(right)?),
+            ContainerOpKind::SeqConcat => {
+                Expr::Binary(BinaryOp::Concat, boxed(left)?, boxed(right)?)
+            }
+            ContainerOpKind::SeqLen => Expr::Length(boxed(left)?),
+        },
+        E::Seq(seq_type, elements, _) => match seq_type {
+            vir_legacy::Type::Seq(element_type) if elements.is_empty() => {
+                Expr::Empty(Collection::Seq, vec![ty(element_type)?])
+            }
+            _ => Expr::Explicit(Collection::Seq, exprs(elements)?),
+        },
+        E::Map(map_type, elements, _) => match map_type {
+            vir_legacy::Type::Map(key_type, val_type) if elements.is_empty() => {
+                Expr::Empty(Collection::Map, vec![ty(key_type)?, ty(val_type)?])
+            }
+            _ => return unsupported("map literals"),
+        },
+        E::Unfolding(name, arguments, base, permission, _, _) => Expr::Unfolding(
+            Box::new(predicate_access(name, arguments, *permission)?),
+            boxed(base)?,
+        ),
+        E::Cond(guard, then_expr, else_expr, _) => {
+            Expr::Cond(boxed(guard)?, boxed(then_expr)?, boxed(else_expr)?)
+        }
+        E::ForAll(variables, triggers, body, _) | E::Exists(variables, triggers, body, _) => {
+            let quantifier = if matches!(expression, E::ForAll(..)) {
+                Quantifier::Forall
+            } else {
+                Quantifier::Exists
+            };
+            Expr::Quantifier(
+                quantifier,
+                self::variables(variables)?,
+                triggers
+                    .iter()
+                    .map(|trigger| exprs(trigger.elements()))
+                    .collect::<Result<_>>()?,
+                boxed(body)?,
+            )
+        }
+        E::LetExpr(variable, def, body, _) => {
+            Expr::Let(variable.name.clone(), boxed(def)?, boxed(body)?)
+        }
+        E::FuncApp(function_name, arguments, ..) => {
+            Expr::Call(function_name.clone(), exprs(arguments)?)
+        }
+        E::DomainFuncApp(function, arguments, _) => {
+            Expr::Call(function.name.clone(), exprs(arguments)?)
+        }
+        E::InhaleExhale(inhale, exhale, _) => Expr::InhaleExhale(boxed(inhale)?, boxed(exhale)?),
+        E::Downcast(base, ..) => expr(base)?,
+        E::AddrOf(..) | E::SnapApp(..) | E::Cast(..) => {
+            return unsupported(format!("the expression {expression}"))
+        }
+    };
+    Ok(expr)
+}
+
+fn variables(variables: &[vir_legacy::LocalVar]) -> Result<Vec<VarDecl>> {
+    variables.iter().map(variable).collect()
+}
+
+fn variable(variable: &vir_legacy::LocalVar) -> Result<VarDecl> {
+    Ok(VarDecl::new(&variable.name, ty(&variable.typ)?))
+}
+
+fn ty(ty: &vir_legacy::Type) -> Result<Type> {
+    let ty = match ty {
+        vir_legacy::Type::Int => Type::Int,
+        vir_legacy::Type::Bool => Type::Bool,
+        vir_legacy::Type::Ref | vir_legacy::Type::TypedRef(_) => Type::Ref,
+        vir_legacy::Type::Domain(name) => Type::Domain(name.clone()),
+        vir_legacy::Type::Snapshot(name) => Type::Domain(format!("Snap${name}")),
+        vir_legacy::Type::Seq(element_type) => Type::Seq(Box::new(self::ty(element_type)?)),
+        vir_legacy::Type::Map(key_type, val_type) => {
+            Type::Map(Box::new(self::ty(key_type)?), Box::new(self::ty(val_type)?))
+        }
+        vir_legacy::Type::Float(_) | vir_legacy::Type::BitVector(_) => {
+            return unsupported(format!("the backend type {ty}"))
+        }
+    };
+    Ok(ty)
+}
@@ -0,0 +1,499 @@
+// © 2023, ETH Zurich
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! Printing of `vir::low` programs, following `low_to_viper`.
+
+use super::{
+    syntax::{
+        Annotation, Axiom, BinaryOp, Collection, Domain, DomainFunction, Expr, Function, Method,
+        Predicate, Program, Quantifier, Stmt, StmtKind, Type, UnaryOp, VarDecl,
+    },
+    unsupported, Result,
+};
+use vir::{
+    common::position::Position,
+    legacy::RETURN_LABEL,
+    low::{
+        self as vir_low,
+        ast::{expression, statement, ty, variable::VariableDecl},
+        ConstantValue, ContainerOpKind, Expression, Statement, Successor,
+    },
+};
+
+pub(super) fn program(program: &vir_low::Program) -> Result<Program> {
+    let mut methods = Vec::new();
+    for procedure in &program.procedures {
+        methods.push(procedure_decl(procedure)?);
+    }
+    for method in &program.methods {
+        methods.push(method_decl(method)?);
+    }
+    Ok(Program {
+        annotations: vec![
+            Annotation::new("vir", "low"),
+            Annotation::new("program", &program.name),
+            Annotation::new("check_mode", program.check_mode),
+        ],
+        domains: program
+            .domains
+            .iter()
+            .map(domain_decl)
+            .collect::<Result<_>>()?,
+        fields: Vec::new(),
+        functions: program
+            .functions
+            .iter()
+            .map(function_decl)
+            .collect::<Result<_>>()?,
+        predicates: program
+            .predicates
+            .iter()
+            .map(|predicate| {
+                Ok(Predicate {
+                    name: predicate.name.clone(),
+                    parameters: variables(&predicate.parameters)?,
+                    body: predicate.body.as_ref().map(expr).transpose()?,
+                })
+            })
+            .collect::<Result<_>>()?,
+        methods,
+    })
+}
+
+fn domain_decl(domain: &vir_low::DomainDecl) -> Result<Domain> {
+    Ok(Domain {
+        name: domain.name.clone(),
+        type_vars: Vec::new(),
+        functions: domain
+            .functions
+            .iter()
+            .map(|function| {
+                Ok(DomainFunction {
+                    unique: function.is_unique,
+                    name: function.name.clone(),
+                    parameters: variables(&function.parameters)?,
+                    return_type: ty(&function.return_type)?,
+                })
+            })
+            .collect::<Result<_>>()?,
+        axioms: domain
+            .axioms
+            .iter()
+            .map(|axiom| {
+                Ok(Axiom {
+                    comment: axiom.comment.clone(),
+                    name: axiom.name.clone(),
+                    body: expr(&axiom.body)?,
+                })
+            })
+            .collect::<Result<_>>()?,
+    })
+}
+
+fn function_decl(function: &vir_low::FunctionDecl) -> Result<Function> {
+    Ok(Function {
+        annotations: vec![Annotation::new("kind", &function.kind)],
+        name: function.name.clone(),
+        parameters: variables(&function.parameters)?,
+        return_type: ty(&function.return_type)?,
+        pres: exprs(&function.pres)?,
+        posts: exprs(&function.posts)?,
+        body: function.body.as_ref().map(expr).transpose()?,
+    })
+}
+
+fn method_decl(method: &vir_low::MethodDecl) -> Result<Method> {
+    Ok(Method {
+        annotations: vec![Annotation::new("kind", &method.kind)],
+        name: method.name.clone(),
+        parameters: variables(&method.parameters)?,
+        returns: variables(&method.targets)?,
+        pres: exprs(&method.pres)?,
+        posts: exprs(&method.posts)?,
+        body: method.body.as_deref().map(statements).transpose()?,
+    })
+}
+
+fn procedure_decl(procedure: &vir_low::ProcedureDecl) -> Result<Method> {
+    let mut body = Vec::new();
+    for local in &procedure.locals {
+        body.push(StmtKind::Var(variable(local)?).into());
+    }
+    for block in &procedure.basic_blocks {
+        body.push(StmtKind::Comment(String::new()).into());
+        body.push(StmtKind::Label(block.label.name.clone()).into());
+        body.extend(statements(&block.statements)?);
+        match &block.successor {
+            Successor::Return => body.push(StmtKind::Goto(RETURN_LABEL.to_string()).into()),
+            Successor::Goto(target) => body.push(StmtKind::Goto(target.name.clone()).into()),
+            Successor::GotoSwitch(targets) => {
+                for (test, target) in targets {
+                    let goto = StmtKind::Goto(target.name.clone()).into();
+                    body.push(StmtKind::If(expr(test)?, vec![goto], Vec::new()).into());
+                }
+                body.push(StmtKind::Assert(Expr::Bool(false)).into());
+            }
+        }
+    }
+    body.push(StmtKind::Label(RETURN_LABEL.to_string()).into());
+    Ok(Method {
+        annotations: Vec::new(),
+        name: procedure.name.clone(),
+        parameters: Vec::new(),
+        returns: Vec::new(),
+        pres: Vec::new(),
+        posts: Vec::new(),
+        body: Some(body),
+    })
+}
+
+fn statements(statements: &[Statement]) -> Result<Vec<Stmt>> {
+    statements.iter().map(stmt).collect()
+}
+
+fn positioned(kind: StmtKind, position: Position) -> Stmt {
+    Stmt {
+        kind,
+        annotation: (!position.is_default())
+            .then(|| Annotation::position(position.line, position.column, position.id)),
+    }
+}
+
+fn stmt(statement: &Statement) -> Result<Stmt> {
+    let statement = match statement {
+        Statement::Comment(statement::Comment { comment }) => {
+            StmtKind::Comment(comment.clone()).into()
+        }
+        Statement::LogEvent(statement::LogEvent { expression }) => Stmt {
+            kind: StmtKind::Inhale(expr(expression)?),
+            annotation: Some(Annotation::new("log_event", "")),
+        },
+        Statement::Assume(statement) => positioned(
+            StmtKind::Assume(expr(&statement.expression)?),
+            statement.position,
+        ),
+        Statement::Assert(statement) => positioned(
+            StmtKind::Assert(expr(&statement.expression)?),
+            statement.position,
+        ),
+        Statement::Inhale(statement) => positioned(
+            StmtKind::Inhale(expr(&statement.expression)?),
+            statement.position,
+        ),
+        Statement::Exhale(statement) => positioned(
+            StmtKind::Exhale(expr(&statement.expression)?),
+            statement.position,
+        ),
+        Statement::Fold(statement) => positioned(
+            StmtKind::Fold(expr(&statement.expression)?),
+            statement.position,
+        ),
+        Statement::Unfold(statement) => positioned(
+            StmtKind::Unfold(expr(&statement.expression)?),
+            statement.position,
+        ),
+        Statement::ApplyMagicWand(statement) => positioned(
+            StmtKind::Apply(expr(&statement.expression)?),
+            statement.position,
+        ),
+        Statement::MethodCall(statement) => positioned(
+            StmtKind::Call(
+                exprs(&statement.targets)?,
+                statement.method_name.clone(),
+                exprs(&statement.arguments)?,
+            ),
+            statement.position,
+        ),
+        Statement::Assign(statement) => positioned(
+            StmtKind::Assign(local(&statement.target.name), expr(&statement.value)?),
+            statement.position,
+        ),
+        Statement::Conditional(statement) => positioned(
+            StmtKind::If(
+                expr(&statement.guard)?,
+                statements(&statement.then_branch)?,
+                statements(&statement.else_branch)?,
+            ),
+            statement.position,
+        ),
+    };
+    Ok(statement)
+}
+
+fn local(name: &str) -> Expr {
+    if name == "__result" {
+        Expr::Result
+    } else {
+        Expr::Local(name.to_string())
+    }
+}
+
+fn exprs(expressions: &[Expression]) -> Result<Vec<Expr>> {
+    expressions.iter().map(expr).collect()
+}
+
+fn expr(expression: &Expression) -> Result<Expr> {
+    expr_in(expression, false)
+}
+
+/// Predicate instances in triggers are printed without their permission,
+/// like `low_to_viper` does.
+fn expr_in(expression: &Expression, inside_trigger: bool) -> Result<Expr> {
+    let boxed = |expression: &Expression| expr_in(expression, inside_trigger).map(Box::new);
+    let expr = match expression {
+        Expression::Local(expression) => local(&expression.variable.name),
+        Expression::Field(_) => return unsupported("field accesses in vir::low"),
+        Expression::FieldAccessPredicate(_) => return unsupported("field permissions in vir::low"),
+        Expression::LabelledOld(expression) => {
+            Expr::Old(expression.label.clone(), boxed(&expression.base)?)
+        }
+        Expression::Constant(constant) => self::constant(constant)?,
+        Expression::MagicWand(expression) => Expr::Binary(
+            BinaryOp::Wand,
+            boxed(&expression.left)?,
+            boxed(&expression.right)?,
+        ),
+        Expression::PredicateAccessPredicate(expression) => {
+            let arguments = expression
+                .arguments
+                .iter()
+                .map(|argument| expr_in(argument, inside_trigger))
+                .collect::<Result<_>>()?;
+            let location = Expr::Call(expression.name.clone(), arguments);
+            if inside_trigger {
+                location
+            } else {
+                Expr::Acc(Box::new(location), boxed(&expression.permission)?)
+            }
+        }
+        Expression::Unfolding(expression) => {
+            let arguments = exprs(&expression.arguments)?;
+            Expr::Unfolding(
+                Box::new(Expr::acc(
+                    Expr::Call(expression.predicate.clone(), arguments),
+                    self::expr(&expression.permission)?,
+                )),
+                boxed(&expression.base)?,
+            )
+        }
+        Expression::UnaryOp(expression) => {
+            let op = match expression.op_kind {
+                expression::UnaryOpKind::Not => UnaryOp::Not,
+                expression::UnaryOpKind::Minus => UnaryOp::Minus,
+            };
+            Expr::Unary(op, boxed(&expression.argument)?)
+        }
+        Expression::BinaryOp(expression) => {
+            let op = match expression.op_kind {
+                expression::BinaryOpKind::EqCmp => BinaryOp::Eq,
+                expression::BinaryOpKind::NeCmp => BinaryOp::Ne,
+                expression::BinaryOpKind::GtCmp => BinaryOp::Gt,
+                expression::BinaryOpKind::GeCmp => BinaryOp::Ge,
+                expression::BinaryOpKind::LtCmp => BinaryOp::Lt,
+                expression::BinaryOpKind::LeCmp => BinaryOp::Le,
+                expression::BinaryOpKind::Add => BinaryOp::Add,
+                expression::BinaryOpKind::Sub => BinaryOp::Sub,
+                expression::BinaryOpKind::Mul => BinaryOp::Mul,
+                expression::BinaryOpKind::Div => BinaryOp::Div,
+                expression::BinaryOpKind::Mod => BinaryOp::Mod,
+                expression::BinaryOpKind::And => BinaryOp::And,
+                expression::BinaryOpKind::Or => BinaryOp::Or,
+                expression::BinaryOpKind::Implies => BinaryOp::Implies,
+            };
+            Expr::Binary(op, boxed(&expression.left)?, boxed(&expression.right)?)
+        }
+        Expression::PermBinaryOp(expression) => {
+            let op = match expression.op_kind {
+                expression::PermBinaryOpKind::Add => BinaryOp::Add,
+                expression::PermBinaryOpKind::Sub => BinaryOp::Sub,
+                expression::PermBinaryOpKind::Mul => BinaryOp::Mul,
+                expression::PermBinaryOpKind::Div => BinaryOp::Div,
+            };
+            Expr::Binary(op, boxed(&expression.left)?, boxed(&expression.right)?)
+        }
+        Expression::ContainerOp(expression) => container_op(expression, inside_trigger)?,
+        Expression::Conditional(expression) => Expr::Cond(
+            boxed(&expression.guard)?,
+            boxed(&expression.then_expr)?,
+            boxed(&expression.else_expr)?,
+        ),
+        Expression::Quantifier(expression) => {
+            let quantifier = match expression.kind {
+                expression::QuantifierKind::ForAll => Quantifier::Forall,
+                expression::QuantifierKind::Exists => Quantifier::Exists,
+            };
+            let triggers = expression
+                .triggers
+                .iter()
+                .map(|trigger| {
+                    trigger
+                        .terms
+                        .iter()
+                        .map(|term| expr_in(term, true))
+                        .collect::<Result<_>>()
+                })
+                .collect::<Result<_>>()?;
+            Expr::Quantifier(
+                quantifier,
+                variables(&expression.variables)?,
+                triggers,
+                boxed(&expression.body)?,
+            )
+        }
+        Expression::LetExpr(expression) => Expr::Let(
+            expression.variable.name.clone(),
+            boxed(&expression.def)?,
+            boxed(&expression.body)?,
+        ),
+        Expression::FuncApp(expression) => Expr::Call(
+            expression.function_name.clone(),
+            expression
+                .arguments
+                .iter()
+                .map(|argument| expr_in(argument, inside_trigger))
+                .collect::<Result<_>>()?,
+        ),
+        Expression::DomainFuncApp(expression) => Expr::Call(
+            expression.function_name.clone(),
+            expression
+                .arguments
+                .iter()
+                .map(|argument| expr_in(argument, inside_trigger))
+                .collect::<Result<_>>()?,
+        ),
+        Expression::InhaleExhale(expression) => Expr::InhaleExhale(
+            boxed(&expression.inhale_expression)?,
+            boxed(&expression.exhale_expression)?,
+        ),
+    };
+    Ok(expr)
+}
+
+fn constant(constant: &expression::Constant) -> Result<Expr> {
+    let expr = match (&constant.ty, &constant.value) {
+        (vir_low::Type::Int, ConstantValue::Int(value)) => Expr::Int(value.to_string()),
+        (vir_low::Type::Int, ConstantValue::BigInt(value)) => Expr::Int(value.clone()),
+        (vir_low::Type::Bool, ConstantValue::Bool(value)) => Expr::Bool(*value),
+        (vir_low::Type::Perm, ConstantValue::Int(0)) => Expr::NoPerm,
+        (vir_low::Type::Perm, ConstantValue::Int(1)) => Expr::FullPerm,
+        (vir_low::Type::Perm, ConstantValue::Int(-1)) => Expr::Wildcard,
+        _ => return unsupported(format!("the constant {constant} of type {}", constant.ty)),
+    };
+    Ok(expr)
+}
+
+fn container_op(expression: &expression::ContainerOp, inside_trigger: bool) -> Result<Expr> {
+    let operands = expression
+        .operands
+        .iter()
+        .map(|operand| expr_in(operand, inside_trigger))
+        .collect::<Result<Vec<_>>>()?;
+    let element_type = || match &expression.container_type {
+        vir_low::Type::Seq(ty::Seq { element_type })
+        | vir_low::Type::Set(ty::Set { element_type })
+        | vir_low::Type::MultiSet(ty::MultiSet { element_type }) => ty(element_type),
+        container_type => unsupported(format!("the container type {container_type}")),
+    };
+    let collection = match expression.kind {
+        ContainerOpKind::SeqEmpty | ContainerOpKind::SeqConstructor => Some(Collection::Seq),
+        ContainerOpKind::SetEmpty | ContainerOpKind::SetConstructor => Some(Collection::Set),
+        ContainerOpKind::MultiSetEmpty | ContainerOpKind::MultiSetConstructor => {
+            Some(Collection::Multiset)
+        }
+        _ => None,
+    };
+    if let Some(collection) = collection {
+        // Viper prints empty constructors as empty collections.
+        return if operands.is_empty() {
+            Ok(Expr::Empty(collection, vec![element_type()?]))
+        } else {
+            Ok(Expr::Explicit(collection, operands))
+        };
+    }
+    let mut operands = operands.into_iter();
+    let mut operand = || match operands.next() {
+        Some(operand) => Ok(Box::new(operand)),
+        None => unsupported(format!("the container operation {expression}")),
+    };
+    let binary = |op, left, right| Expr::Binary(op, left, right);
+    let expr = match expression.kind {
+        ContainerOpKind::MapEmpty => match &expression.container_type {
+            vir_low::Type::Map(ty::Map { key_type, val_type }) => {
+                Expr::Empty(Collection::Map, vec![ty(key_type)?, ty(val_type)?])
+            }
+            container_type => return unsupported(format!("the map type {container_type}")),
+        },
+        ContainerOpKind::SeqIndex | ContainerOpKind::MapLookup => {
+            Expr::Index(operand()?, operand()?)
+        }
+        ContainerOpKind::SeqConcat => binary(BinaryOp::Concat, operand()?, operand()?),
+        ContainerOpKind::SeqLen
+        | ContainerOpKind::MapLen
+        | ContainerOpKind::SetCardinality
+        | ContainerOpKind::MultiSetCardinality => Expr::Length(operand()?),
+        ContainerOpKind::MapUpdate => Expr::Update(operand()?, operand()?, operand()?),
+        ContainerOpKind::MapContains => {
+            let map = operand()?;
+            binary(BinaryOp::In, operand()?, map)
+        }
+        ContainerOpKind::SetUnion | ContainerOpKind::MultiSetUnion => {
+            binary(BinaryOp::Union, operand()?, operand()?)
+        }
+        ContainerOpKind::SetIntersection | ContainerOpKind::MultiSetIntersection => {
+            binary(BinaryOp::Intersection, operand()?, operand()?)
+        }
+        ContainerOpKind::SetSubset | ContainerOpKind::MultiSetSubset => {
+            binary(BinaryOp::Subset, operand()?, operand()?)
+        }
+        ContainerOpKind::SetMinus | ContainerOpKind::MultiSetMinus => {
+            binary(BinaryOp::Setminus, operand()?, operand()?)
+        }
+        ContainerOpKind::SetContains | ContainerOpKind::MultiSetContains => {
+            binary(BinaryOp::In, operand()?, operand()?)
+        }
+        ContainerOpKind::SeqEmpty
+        | ContainerOpKind::SeqConstructor
+        | ContainerOpKind::SetEmpty
+        | ContainerOpKind::SetConstructor
+        | ContainerOpKind::MultiSetEmpty
+        | ContainerOpKind::MultiSetConstructor => unreachable!(),
+    };
+    Ok(expr)
+}
+
+fn variables(variables: &[VariableDecl]) -> Result<Vec<VarDecl>> {
+    variables.iter().map(variable).collect()
+}
+
+fn variable(variable: &VariableDecl) -> Result<VarDecl> {
+    Ok(VarDecl::new(&variable.name, ty(&variable.ty)?))
+}
+
+fn ty(ty: &vir_low::Type) -> Result<Type> {
+    let ty = match ty {
+        vir_low::Type::Int => Type::Int,
+        vir_low::Type::Bool => Type::Bool,
+        vir_low::Type::Perm => Type::Perm,
+        vir_low::Type::Ref => Type::Ref,
+        vir_low::Type::Seq(ty::Seq { element_type }) => {
+            Type::Seq(Box::new(self::ty(element_type)?))
+        }
+        vir_low::Type::Set(ty::Set { element_type }) => {
+            Type::Set(Box::new(self::ty(element_type)?))
+        }
+        vir_low::Type::MultiSet(ty::MultiSet { element_type }) => {
+            Type::Multiset(Box::new(self::ty(element_type)?))
+        }
+        vir_low::Type::Map(ty::Map { key_type, val_type }) => {
+            Type::Map(Box::new(self::ty(key_type)?), Box::new(self::ty(val_type)?))
+        }
+        vir_low::Type::Domain(ty::Domain { name }) => Type::Domain(name.clone()),
+        vir_low::Type::Float(_) | vir_low::Type::BitVector(_) => {
+            return unsupported(format!("the backend type {ty}"))
+        }
+    };
+    Ok(ty)
+}
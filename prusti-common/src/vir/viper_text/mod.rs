@@ -0,0 +1,425 @@
+// © 2023, ETH Zurich
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! A printer and a parser of VIR programs in the Viper syntax that do not
+//! need the JVM.
+//!
+//! The printer follows the encoding of [`ToViper`](super::ToViper), so that
+//! the output is the program that Viper verifies. Information that Viper
+//! ignores but that is needed to rebuild the VIR program is kept in `//@`
+//! comments: the name, kind and check mode of the program, the kinds of
+//! `vir::low` functions and methods, and the positions of statements.
+//!
+//! Parsing the output of the printer gives back an equal `vir::low` program,
+//! except for the positions of expressions and for constructs that Viper
+//! prints the same way (e.g. empty sequence constructors). For legacy
+//! programs, the parsed program is the same Viper program, but information
+//! that only matters before the fold-unfold algorithm (typed references,
+//! enum predicates, assignment kinds, statements that are printed as
+//! comments) is lost.
+
+mod from_legacy;
+mod from_low;
+mod parser;
+mod syntax;
+mod to_legacy;
+mod to_low;
+
+use super::program::Program;
+use std::fmt;
+
+#[derive(Debug)]
+pub enum ViperTextError {
+    /// The program uses a construct that the printer does not support.
+    Unsupported(String),
+    /// The text is not in the supported subset of the Viper syntax.
+    Syntax { line: usize, message: String },
+    /// The text is not the encoding of a VIR program.
+    Invalid(String),
+}
+
+impl fmt::Display for ViperTextError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ViperTextError::Unsupported(construct) => {
+                write!(f, "printing {construct} is not supported")
+            }
+            ViperTextError::Syntax { line, message } => write!(f, "line {line}: {message}"),
+            ViperTextError::Invalid(message) => write!(f, "invalid program: {message}"),
+        }
+    }
+}
+
+impl std::error::Error for ViperTextError {}
+
+type Result<T> = std::result::Result<T, ViperTextError>;
+
+fn unsupported<T>(construct: impl fmt::Display) -> Result<T> {
+    Err(ViperTextError::Unsupported(construct.to_string()))
+}
+
+fn invalid<T>(message: impl fmt::Display) -> Result<T> {
+    Err(ViperTextError::Invalid(message.to_string()))
+}
+
+/// Prints the Viper program that encodes `program`.
+pub fn print_program(program: &Program) -> Result<String> {
+    let program = match program {
+        Program::Legacy(program) => from_legacy::program(program)?,
+        Program::Low(program) => from_low::program(program)?,
+    };
+    Ok(program.to_string())
+}
+
+/// Parses a program printed by [`print_program`]. Programs without a
+/// `//@ vir low` comment are parsed as legacy programs.
+pub fn parse_program(text: &str) -> Result<Program> {
+    let program = parser::parse(text)?;
+    if syntax::find_annotation(&program.annotations, "vir") == Some("low") {
+        Ok(Program::Low(to_low::program(&program)?))
+    } else {
+        Ok(Program::Legacy(to_legacy::program(&program)?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use vir::{
+        common::{check_mode::CheckMode, position::Position},
+        low::{
+            self as vir_low,
+            ast::{expression, statement, ty, variable::VariableDecl},
+            Expression, Statement, Type,
+        },
+    };
+
+    fn local(name: &str, ty: Type) -> Expression {
+        Expression::Local(expression::Local {
+            variable: VariableDecl {
+                name: name.to_string(),
+                ty,
+            },
+            position: Position::default(),
+        })
+    }
+
+    fn int(value: i64) -> Expression {
+        Expression::constant_no_pos(expression::ConstantValue::Int(value), Type::Int)
+    }
+
+    fn binary(
+        op_kind: expression::BinaryOpKind,
+        left: Expression,
+        right: Expression,
+    ) -> Expression {
+        Expression::BinaryOp(expression::BinaryOp {
+            op_kind,
+            left: Box::new(left),
+            right: Box::new(right),
+            position: Position::default(),
+        })
+    }
+
+    fn seq_op(kind: expression::ContainerOpKind, operands: Vec<Expression>) -> Expression {
+        Expression::ContainerOp(expression::ContainerOp {
+            kind,
+            container_type: Type::Seq(ty::Seq {
+                element_type: Box::new(Type::Int),
+            }),
+            operands,
+            position: Position::default(),
+        })
+    }
+
+    /// A procedure that reads the first element of a sequence.
+    fn low_program() -> vir_low::Program {
+        let seq_type = Type::Seq(ty::Seq {
+            element_type: Box::new(Type::Int),
+        });
+        let s = local("s", seq_type.clone());
+        let position = Position::new(3, 7, 42);
+        let statements = vec![
+            Statement::Comment(statement::Comment {
+                comment: "read the first element".to_string(),
+            }),
+            Statement::Assign(statement::Assign {
+                target: VariableDecl {
+                    name: "x".to_string(),
+                    ty: Type::Int,
+                },
+                value: seq_op(
+                    expression::ContainerOpKind::SeqIndex,
+                    vec![s.clone(), int(0)],
+                ),
+                position,
+            }),
+            Statement::Assert(statement::Assert {
+                expression: binary(
+                    expression::BinaryOpKind::Implies,
+                    binary(
+                        expression::BinaryOpKind::GtCmp,
+                        seq_op(expression::ContainerOpKind::SeqLen, vec![s]),
+                        int(0),
+                    ),
+                    binary(
+                        expression::BinaryOpKind::GeCmp,
+                        local("x", Type::Int),
+                        int(-1),
+                    ),
+                ),
+                position,
+            }),
+        ];
+        let procedure = vir_low::ProcedureDecl {
+            name: "read_first".to_string(),
+            locals: vec![
+                VariableDecl {
+                    name: "x".to_string(),
+                    ty: Type::Int,
+                },
+                VariableDecl {
+                    name: "s".to_string(),
+                    ty: seq_type,
+                },
+            ],
+            basic_blocks: vec![vir_low::BasicBlock {
+                label: vir_low::Label {
+                    name: "start".to_string(),
+                },
+                statements,
+                successor: vir_low::Successor::Return,
+            }],
+        };
+        vir_low::Program {
+            name: "read_first".to_string(),
+            check_mode: CheckMode::CoreProof,
+            domains: vec![],
+            predicates: vec![],
+            functions: vec![],
+            methods: vec![],
+            procedures: vec![procedure],
+        }
+    }
+
+    /// A legacy program with every top-level declaration kind. The `read$`
+    /// function is added by the printer.
+    const LEGACY_PROGRAM: &str = r#"//@ vir legacy
+//@ program counter
+
+domain Bound {
+  unique function lower$(): Int
+  function upper$(x: Int): Int
+
+  // the upper bound is above the lower bound
+  axiom upper_bound {
+    (forall x: Int :: { upper$(x) } (upper$(x) > lower$()))
+  }
+}
+
+field val_int: Int
+
+function get(self: Ref): Int
+  requires acc(Counter(self), read$())
+{
+  (unfolding acc(Counter(self), read$()) in self.val_int)
+}
+
+function read$(): Perm
+  ensures (none < result)
+  ensures (result < write)
+
+predicate Counter(self: Ref) {
+  acc(self.val_int, write)
+}
+
+predicate Opaque(self: Ref)
+
+method increment() returns (self: Ref)
+{
+  var x: Int
+  //
+  label start
+  inhale acc(Counter(self), write)
+  unfold acc(Counter(self), write)
+  x := self.val_int
+  self.val_int := (x + 1)
+  fold acc(Counter(self), write) //@ pos 5:9:17
+  if ((x > 0)) {
+    goto positive
+  }
+  goto exit
+  //
+  label exit
+  exhale acc(Counter(self), write) //@ pos 8:1:19
+  goto end_of_method
+  //
+  label positive
+  assert (get(self) > 1) //@ pos 7:5:18
+  goto exit
+  label end_of_method
+}
+
+method havoc_int() returns (x: Int)
+  ensures (x >= lower$())
+"#;
+
+    /// A `vir::low` program with every top-level declaration kind.
+    const LOW_PROGRAM: &str = r#"//@ vir low
+//@ program declarations
+//@ check_mode Both
+
+domain Size {
+  unique function zero$(): Int
+  function size$(s: Seq[Int]): Int
+
+  // the size of a sequence
+  // is its length
+  axiom size_def {
+    (forall s: Seq[Int] :: { size$(s) } (size$(s) == |s|))
+  }
+}
+
+//@ kind CallerFor
+function first(s: Seq[Int]): Int
+  requires (|s| > 0)
+  ensures (result == s[0])
+{
+  s[0]
+}
+
+predicate Owned(x: Int) {
+  (x >= zero$())
+}
+
+predicate Opaque(x: Int)
+
+method branch()
+{
+  var x: Int
+  //
+  label start
+  x := 1 //@ pos 4:5:6
+  if ((x > 0)) {
+    goto positive
+  }
+  if ((x <= 0)) {
+    goto exit
+  }
+  assert false
+  //
+  label exit
+  goto end_of_method
+  //
+  label positive
+  inhale acc(Owned(x), write)
+  goto exit
+  label end_of_method
+}
+
+//@ kind MirOperation
+method increment(x: Int) returns (y: Int)
+  requires acc(Owned(x), write)
+  ensures (y == (x + 1))
+{
+  y := (x + 1)
+  exhale acc(Owned(x), write) //@ pos 2:3:4
+}
+
+//@ kind Havoc
+method havoc_int() returns (x: Int)
+"#;
+
+    /// Parses `text`, checks that printing the program gives back `text` and
+    /// that parsing the output gives the same program.
+    fn assert_round_trip(text: &str) -> Program {
+        let program = parse_program(text).unwrap();
+        let printed = print_program(&program).unwrap();
+        assert_eq!(printed, text);
+        assert_eq!(parse_program(&printed).unwrap(), program);
+        program
+    }
+
+    #[test]
+    fn legacy_program_round_trip() {
+        let Program::Legacy(program) = assert_round_trip(LEGACY_PROGRAM) else {
+            panic!("not parsed as a legacy program");
+        };
+        assert_eq!(program.name, "counter");
+        assert_eq!(program.domains.len(), 1);
+        assert_eq!(program.fields.len(), 1);
+        assert_eq!(program.functions.len(), 1);
+        assert_eq!(program.viper_predicates.len(), 2);
+        assert_eq!(program.methods.len(), 1);
+        assert_eq!(program.builtin_methods.len(), 1);
+    }
+
+    #[test]
+    fn low_declarations_round_trip() {
+        let Program::Low(program) = assert_round_trip(LOW_PROGRAM) else {
+            panic!("not parsed as a vir::low program");
+        };
+        assert_eq!(program.check_mode, CheckMode::Both);
+        assert_eq!(program.domains.len(), 1);
+        assert_eq!(program.functions.len(), 1);
+        assert_eq!(program.predicates.len(), 2);
+        assert_eq!(program.procedures.len(), 1);
+        assert_eq!(program.methods.len(), 2);
+        assert_eq!(program.methods[1].kind, vir_low::MethodKind::Havoc);
+    }
+
+    #[test]
+    fn unsupported_constructs() {
+        let mut program = low_program();
+        program.procedures[0].locals.push(VariableDecl {
+            name: "f".to_string(),
+            ty: Type::Float(ty::Float::F32),
+        });
+        assert!(matches!(
+            print_program(&Program::Low(program)),
+            Err(ViperTextError::Unsupported(_))
+        ));
+    }
+
+    #[test]
+    fn malformed_syntax() {
+        assert!(matches!(
+            parse_program("field f: Int\n#\n"),
+            Err(ViperTextError::Syntax { line: 2, .. })
+        ));
+        assert!(matches!(
+            parse_program("method m()\n{\n  assert (1 +\n}\n"),
+            Err(ViperTextError::Syntax { line: 4, .. })
+        ));
+        assert!(matches!(
+            parse_program("axiom a {\n  true\n}\n"),
+            Err(ViperTextError::Syntax { line: 1, .. })
+        ));
+    }
+
+    #[test]
+    fn invalid_programs() {
+        let invalid = |text: &str| matches!(parse_program(text), Err(ViperTextError::Invalid(_)));
+        // An undeclared variable.
+        assert!(invalid("//@ vir low\n\nfunction f(): Int\n{\n  x\n}\n"));
+        // An unknown check mode.
+        assert!(invalid("//@ vir low\n//@ check_mode Sometimes\n"));
+        // An undeclared field.
+        assert!(invalid("function f(r: Ref): Int\n{\n  r.g\n}\n"));
+        // A block without a successor.
+        assert!(invalid("method m()\n{\n  label start\n  inhale true\n}\n"));
+    }
+
+    #[test]
+    fn low_program_round_trip() {
+        let program = Program::Low(low_program());
+        let text = print_program(&program).unwrap();
+        assert!(text.contains("assert ((|s| > 0) ==> (x >= -1)) //@ pos 3:7:42"));
+        let parsed = parse_program(&text).unwrap();
+        assert_eq!(parsed, program);
+        assert_eq!(print_program(&parsed).unwrap(), text);
+    }
+}
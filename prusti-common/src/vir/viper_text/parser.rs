@@ -0,0 +1,888 @@
+// © 2023, ETH Zurich
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! A parser for the subset of the Viper syntax in [`syntax`](super::syntax).
+
+use super::{
+    syntax::{
+        Annotation, Axiom, BinaryOp, Collection, Domain, DomainFunction, Expr, Function, Method,
+        Predicate, Program, Quantifier, Stmt, StmtKind, Type, UnaryOp, VarDecl,
+    },
+    Result, ViperTextError,
+};
+use std::collections::HashSet;
+
+/// Annotations that describe the whole program instead of the next
+/// declaration.
+const PROGRAM_ANNOTATIONS: &[&str] = &["vir", "program", "check_mode"];
+
+const SYMBOLS: &[&str] = &[
+    "==>", "--*", "::", ":=", "==", "!=", "<=", ">=", "&&", "||", "++", "(", ")", "[", "]", "{",
+    "}", ",", ":", ".", "?", "!", "-", "+", "*", "/", "%", "<", ">", "|", ";",
+];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum TokenKind {
+    Ident(String),
+    Int(String),
+    Symbol(&'static str),
+    Comment(String),
+    Annotation(String),
+}
+
+#[derive(Debug)]
+struct Token {
+    kind: TokenKind,
+    line: usize,
+}
+
+fn syntax_error<T>(line: usize, message: impl ToString) -> Result<T> {
+    Err(ViperTextError::Syntax {
+        line,
+        message: message.to_string(),
+    })
+}
+
+fn tokenize(text: &str) -> Result<Vec<Token>> {
+    let chars: Vec<char> = text.chars().collect();
+    let mut tokens = Vec::new();
+    let mut line = 1;
+    let mut index = 0;
+    let rest_starts_with = |index: usize, prefix: &str| {
+        prefix
+            .chars()
+            .enumerate()
+            .all(|(offset, c)| chars.get(index + offset) == Some(&c))
+    };
+    'tokens: while index < chars.len() {
+        let c = chars[index];
+        if c == '\n' {
+            line += 1;
+            index += 1;
+        } else if c.is_whitespace() {
+            index += 1;
+        } else if rest_starts_with(index, "//") {
+            let end = chars[index..]
+                .iter()
+                .position(|&c| c == '\n')
+                .map_or(chars.len(), |offset| index + offset);
+            let comment: String = chars[index + 2..end].iter().collect();
+            let kind = if let Some(annotation) = comment.strip_prefix('@') {
+                TokenKind::Annotation(annotation.trim().to_string())
+            } else {
+                TokenKind::Comment(comment.strip_prefix(' ').unwrap_or(&comment).to_string())
+            };
+            tokens.push(Token { kind, line });
+            index = end;
+        } else if rest_starts_with(index, "/*") {
+            let start_line = line;
+            index += 2;
+            while !rest_starts_with(index, "*/") {
+                match chars.get(index) {
+                    Some('\n') => line += 1,
+                    Some(_) => {}
+                    None => return syntax_error(start_line, "unterminated comment"),
+                }
+                index += 1;
+            }
+            index += 2;
+        } else if c.is_ascii_digit() {
+            let start = index;
+            while index < chars.len() && chars[index].is_ascii_digit() {
+                index += 1;
+            }
+            let value = chars[start..index].iter().collect();
+            tokens.push(Token {
+                kind: TokenKind::Int(value),
+                line,
+            });
+        } else if c.is_alphabetic() || c == '_' || c == '$' {
+            let start = index;
+            while index < chars.len()
+                && (chars[index].is_alphanumeric() || matches!(chars[index], '_' | '$' | '\''))
+            {
+                index += 1;
+            }
+            let name = chars[start..index].iter().collect();
+            tokens.push(Token {
+                kind: TokenKind::Ident(name),
+                line,
+            });
+        } else {
+            for symbol in SYMBOLS {
+                if rest_starts_with(index, symbol) {
+                    tokens.push(Token {
+                        kind: TokenKind::Symbol(symbol),
+                        line,
+                    });
+                    index += symbol.len();
+                    continue 'tokens;
+                }
+            }
+            return syntax_error(line, format!("unexpected character `{c}`"));
+        }
+    }
+    Ok(tokens)
+}
+
+pub(super) fn parse(text: &str) -> Result<Program> {
+    let tokens = tokenize(text)?;
+    // Calls and function applications look the same, so the names of the
+    // methods are collected first.
+    let methods = tokens
+        .windows(2)
+        .filter_map(|window| match (&window[0].kind, &window[1].kind) {
+            (TokenKind::Ident(keyword), TokenKind::Ident(name)) if keyword == "method" => {
+                Some(name.clone())
+            }
+            _ => None,
+        })
+        .collect();
+    let mut parser = Parser {
+        tokens,
+        index: 0,
+        methods,
+    };
+    parser.program()
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    index: usize,
+    methods: HashSet<String>,
+}
+
+impl Parser {
+    /// The next token that is not a comment.
+    fn peek(&self) -> Option<&TokenKind> {
+        self.tokens[self.index..]
+            .iter()
+            .map(|token| &token.kind)
+            .find(|kind| !matches!(kind, TokenKind::Comment(_)))
+    }
+
+    fn peek_second(&self) -> Option<&TokenKind> {
+        self.tokens[self.index..]
+            .iter()
+            .map(|token| &token.kind)
+            .filter(|kind| !matches!(kind, TokenKind::Comment(_)))
+            .nth(1)
+    }
+
+    fn skip_comments(&mut self) {
+        while let Some(Token {
+            kind: TokenKind::Comment(_),
+            ..
+        }) = self.tokens.get(self.index)
+        {
+            self.index += 1;
+        }
+    }
+
+    /// Takes the comment at the current position, if any.
+    fn take_comment(&mut self) -> Option<String> {
+        match self.tokens.get(self.index) {
+            Some(Token {
+                kind: TokenKind::Comment(comment),
+                ..
+            }) => {
+                let comment = comment.clone();
+                self.index += 1;
+                Some(comment)
+            }
+            _ => None,
+        }
+    }
+
+    fn next(&mut self) -> Result<TokenKind> {
+        self.skip_comments();
+        match self.tokens.get(self.index) {
+            Some(token) => {
+                self.index += 1;
+                Ok(token.kind.clone())
+            }
+            None => syntax_error(self.line(), "unexpected end of input"),
+        }
+    }
+
+    /// The line of the next token that is not a comment.
+    fn line(&self) -> usize {
+        self.tokens[self.index..]
+            .iter()
+            .find(|token| !matches!(token.kind, TokenKind::Comment(_)))
+            .or_else(|| self.tokens.last())
+            .map_or(1, |token| token.line)
+    }
+
+    /// The line of the last consumed token.
+    fn previous_line(&self) -> usize {
+        self.index
+            .checked_sub(1)
+            .and_then(|index| self.tokens.get(index))
+            .map_or(1, |token| token.line)
+    }
+
+    fn error<T>(&self, message: impl ToString) -> Result<T> {
+        syntax_error(self.line(), message)
+    }
+
+    fn is_symbol(&self, symbol: &str) -> bool {
+        matches!(self.peek(), Some(TokenKind::Symbol(s)) if *s == symbol)
+    }
+
+    fn is_keyword(&self, keyword: &str) -> bool {
+        matches!(self.peek(), Some(TokenKind::Ident(name)) if name == keyword)
+    }
+
+    fn eat_symbol(&mut self, symbol: &str) -> bool {
+        let found = self.is_symbol(symbol);
+        if found {
+            self.skip_comments();
+            self.index += 1;
+        }
+        found
+    }
+
+    fn eat_keyword(&mut self, keyword: &str) -> bool {
+        let found = self.is_keyword(keyword);
+        if found {
+            self.skip_comments();
+            self.index += 1;
+        }
+        found
+    }
+
+    fn expect_symbol(&mut self, symbol: &str) -> Result<()> {
+        if self.eat_symbol(symbol) {
+            Ok(())
+        } else {
+            self.error(format!("expected `{symbol}`"))
+        }
+    }
+
+    fn expect_keyword(&mut self, keyword: &str) -> Result<()> {
+        if self.eat_keyword(keyword) {
+            Ok(())
+        } else {
+            self.error(format!("expected `{keyword}`"))
+        }
+    }
+
+    fn ident(&mut self) -> Result<String> {
+        match self.peek() {
+            Some(TokenKind::Ident(name)) => {
+                let name = name.clone();
+                self.next()?;
+                Ok(name)
+            }
+            _ => self.error("expected an identifier"),
+        }
+    }
+
+    /// Parses `item (, item)*` up to the closing symbol, which is consumed.
+    fn list<T>(
+        &mut self,
+        close: &str,
+        mut item: impl FnMut(&mut Self) -> Result<T>,
+    ) -> Result<Vec<T>> {
+        let mut items = Vec::new();
+        if self.eat_symbol(close) {
+            return Ok(items);
+        }
+        loop {
+            items.push(item(self)?);
+            if self.eat_symbol(close) {
+                return Ok(items);
+            }
+            self.expect_symbol(",")?;
+        }
+    }
+
+    fn program(&mut self) -> Result<Program> {
+        let mut program = Program::default();
+        let mut annotations = Vec::new();
+        loop {
+            self.skip_comments();
+            let Some(token) = self.tokens.get(self.index) else {
+                break;
+            };
+            if let TokenKind::Annotation(text) = &token.kind {
+                let annotation = Annotation::parse(text);
+                if PROGRAM_ANNOTATIONS.contains(&annotation.key.as_str()) {
+                    program.annotations.push(annotation);
+                } else {
+                    annotations.push(annotation);
+                }
+                self.index += 1;
+                continue;
+            }
+            let keyword = self.ident()?;
+            match keyword.as_str() {
+                "domain" => program.domains.push(self.domain()?),
+                "field" => {
+                    let name = self.ident()?;
+                    self.expect_symbol(":")?;
+                    program.fields.push(VarDecl::new(name, self.ty()?));
+                }
+                "function" => program
+                    .functions
+                    .push(self.function(std::mem::take(&mut annotations))?),
+                "predicate" => program.predicates.push(self.predicate()?),
+                "method" => program
+                    .methods
+                    .push(self.method(std::mem::take(&mut annotations))?),
+                _ => {
+                    return syntax_error(
+                        self.previous_line(),
+                        format!("unexpected `{keyword}` at the top level"),
+                    )
+                }
+            }
+        }
+        Ok(program)
+    }
+
+    fn domain(&mut self) -> Result<Domain> {
+        let name = self.ident()?;
+        let type_vars = if self.eat_symbol("[") {
+            self.list("]", Self::ident)?
+        } else {
+            Vec::new()
+        };
+        self.expect_symbol("{")?;
+        let mut domain = Domain {
+            name,
+            type_vars,
+            functions: Vec::new(),
+            axioms: Vec::new(),
+        };
+        loop {
+            let mut comments = Vec::new();
+            while let Some(comment) = self.take_comment() {
+                comments.push(comment);
+            }
+            if self.eat_symbol("}") {
+                return Ok(domain);
+            }
+            if self.eat_keyword("axiom") {
+                let name = match self.peek() {
+                    Some(TokenKind::Ident(_)) => self.ident()?,
+                    _ => return self.error("unnamed axioms are not supported"),
+                };
+                self.expect_symbol("{")?;
+                let body = self.expr()?;
+                self.expect_symbol("}")?;
+                domain.axioms.push(Axiom {
+                    comment: (!comments.is_empty()).then(|| comments.join("\n")),
+                    name,
+                    body,
+                });
+            } else {
+                let unique = self.eat_keyword("unique");
+                self.expect_keyword("function")?;
+                let name = self.ident()?;
+                self.expect_symbol("(")?;
+                let parameters = self.list(")", Self::var_decl)?;
+                self.expect_symbol(":")?;
+                domain.functions.push(DomainFunction {
+                    unique,
+                    name,
+                    parameters,
+                    return_type: self.ty()?,
+                });
+            }
+        }
+    }
+
+    fn function(&mut self, annotations: Vec<Annotation>) -> Result<Function> {
+        let name = self.ident()?;
+        self.expect_symbol("(")?;
+        let parameters = self.list(")", Self::var_decl)?;
+        self.expect_symbol(":")?;
+        let return_type = self.ty()?;
+        let (pres, posts) = self.specifications()?;
+        let body = if self.eat_symbol("{") {
+            let body = self.expr()?;
+            self.expect_symbol("}")?;
+            Some(body)
+        } else {
+            None
+        };
+        Ok(Function {
+            annotations,
+            name,
+            parameters,
+            return_type,
+            pres,
+            posts,
+            body,
+        })
+    }
+
+    fn predicate(&mut self) -> Result<Predicate> {
+        let name = self.ident()?;
+        self.expect_symbol("(")?;
+        let parameters = self.list(")", Self::var_decl)?;
+        let body = if self.eat_symbol("{") {
+            let body = self.expr()?;
+            self.expect_symbol("}")?;
+            Some(body)
+        } else {
+            None
+        };
+        Ok(Predicate {
+            name,
+            parameters,
+            body,
+        })
+    }
+
+    fn method(&mut self, annotations: Vec<Annotation>) -> Result<Method> {
+        let name = self.ident()?;
+        self.expect_symbol("(")?;
+        let parameters = self.list(")", Self::var_decl)?;
+        let returns = if self.eat_keyword("returns") {
+            self.expect_symbol("(")?;
+            self.list(")", Self::var_decl)?
+        } else {
+            Vec::new()
+        };
+        let (pres, posts) = self.specifications()?;
+        let body = if self.eat_symbol("{") {
+            Some(self.statements()?)
+        } else {
+            None
+        };
+        Ok(Method {
+            annotations,
+            name,
+            parameters,
+            returns,
+            pres,
+            posts,
+            body,
+        })
+    }
+
+    fn specifications(&mut self) -> Result<(Vec<Expr>, Vec<Expr>)> {
+        let mut pres = Vec::new();
+        let mut posts = Vec::new();
+        loop {
+            if self.eat_keyword("requires") {
+                pres.push(self.expr()?);
+            } else if self.eat_keyword("ensures") {
+                posts.push(self.expr()?);
+            } else {
+                return Ok((pres, posts));
+            }
+        }
+    }
+
+    fn var_decl(&mut self) -> Result<VarDecl> {
+        let name = self.ident()?;
+        self.expect_symbol(":")?;
+        Ok(VarDecl::new(name, self.ty()?))
+    }
+
+    fn ty(&mut self) -> Result<Type> {
+        let name = self.ident()?;
+        let type_argument = |parser: &mut Self| -> Result<Box<Type>> {
+            parser.expect_symbol("[")?;
+            let ty = parser.ty()?;
+            parser.expect_symbol("]")?;
+            Ok(Box::new(ty))
+        };
+        let ty = match name.as_str() {
+            "Int" => Type::Int,
+            "Bool" => Type::Bool,
+            "Perm" => Type::Perm,
+            "Ref" => Type::Ref,
+            "Seq" => Type::Seq(type_argument(self)?),
+            "Set" => Type::Set(type_argument(self)?),
+            "Multiset" => Type::Multiset(type_argument(self)?),
+            "Map" => {
+                self.expect_symbol("[")?;
+                let key_type = self.ty()?;
+                self.expect_symbol(",")?;
+                let value_type = self.ty()?;
+                self.expect_symbol("]")?;
+                Type::Map(Box::new(key_type), Box::new(value_type))
+            }
+            _ if self.is_symbol("[") => {
+                return self.error(format!("type arguments of {name} are not supported"))
+            }
+            _ => Type::Domain(name),
+        };
+        Ok(ty)
+    }
+
+    /// Parses statements up to the closing brace, which is consumed.
+    fn statements(&mut self) -> Result<Vec<Stmt>> {
+        let mut statements = Vec::new();
+        loop {
+            if let Some(comment) = self.take_comment() {
+                statements.push(StmtKind::Comment(comment).into());
+                continue;
+            }
+            if self.eat_symbol(";") {
+                continue;
+            }
+            if self.eat_symbol("}") {
+                return Ok(statements);
+            }
+            statements.push(self.statement()?);
+        }
+    }
+
+    /// Takes the annotation on the line of the last consumed token, if any.
+    fn trailing_annotation(&mut self) -> Option<Annotation> {
+        match self.tokens.get(self.index) {
+            Some(Token {
+                kind: TokenKind::Annotation(text),
+                line,
+            }) if *line == self.previous_line() => {
+                let annotation = Annotation::parse(text);
+                self.index += 1;
+                Some(annotation)
+            }
+            _ => None,
+        }
+    }
+
+    fn statement(&mut self) -> Result<Stmt> {
+        let Some(TokenKind::Ident(keyword)) = self.peek().cloned() else {
+            return self.error("expected a statement");
+        };
+        let simple: Option<fn(Expr) -> StmtKind> = match keyword.as_str() {
+            "inhale" => Some(StmtKind::Inhale),
+            "exhale" => Some(StmtKind::Exhale),
+            "assert" => Some(StmtKind::Assert),
+            "assume" => Some(StmtKind::Assume),
+            "refute" => Some(StmtKind::Refute),
+            "fold" => Some(StmtKind::Fold),
+            "unfold" => Some(StmtKind::Unfold),
+            "apply" => Some(StmtKind::Apply),
+            _ => None,
+        };
+        let kind = if let Some(constructor) = simple {
+            self.next()?;
+            constructor(self.expr()?)
+        } else {
+            match keyword.as_str() {
+                "var" => {
+                    self.next()?;
+                    StmtKind::Var(self.var_decl()?)
+                }
+                "label" => {
+                    self.next()?;
+                    StmtKind::Label(self.ident()?)
+                }
+                "goto" => {
+                    self.next()?;
+                    StmtKind::Goto(self.ident()?)
+                }
+                "package" => {
+                    self.next()?;
+                    let wand = self.expr()?;
+                    self.expect_symbol("{")?;
+                    let annotation = self.trailing_annotation();
+                    let body = self.statements()?;
+                    return Ok(Stmt {
+                        kind: StmtKind::Package(wand, body),
+                        annotation,
+                    });
+                }
+                "if" => {
+                    self.next()?;
+                    let guard = self.expr()?;
+                    self.expect_symbol("{")?;
+                    let annotation = self.trailing_annotation();
+                    let then_branch = self.statements()?;
+                    let else_branch = if self.eat_keyword("else") {
+                        self.expect_symbol("{")?;
+                        self.statements()?
+                    } else {
+                        Vec::new()
+                    };
+                    return Ok(Stmt {
+                        kind: StmtKind::If(guard, then_branch, else_branch),
+                        annotation,
+                    });
+                }
+                _ => self.assignment_or_call()?,
+            }
+        };
+        Ok(Stmt {
+            kind,
+            annotation: self.trailing_annotation(),
+        })
+    }
+
+    fn is_call(&self) -> bool {
+        matches!(
+            (self.peek(), self.peek_second()),
+            (Some(TokenKind::Ident(name)), Some(TokenKind::Symbol("(")))
+                if self.methods.contains(name)
+        )
+    }
+
+    fn call_arguments(&mut self) -> Result<(String, Vec<Expr>)> {
+        let method = self.ident()?;
+        self.expect_symbol("(")?;
+        let arguments = self.list(")", Self::expr)?;
+        Ok((method, arguments))
+    }
+
+    fn assignment_or_call(&mut self) -> Result<StmtKind> {
+        if self.is_call() {
+            let (method, arguments) = self.call_arguments()?;
+            return Ok(StmtKind::Call(Vec::new(), method, arguments));
+        }
+        let mut targets = vec![self.expr()?];
+        while self.eat_symbol(",") {
+            targets.push(self.expr()?);
+        }
+        self.expect_symbol(":=")?;
+        if self.is_call() {
+            let (method, arguments) = self.call_arguments()?;
+            return Ok(StmtKind::Call(targets, method, arguments));
+        }
+        if targets.len() > 1 {
+            return self.error("expected a method call");
+        }
+        let target = targets.pop().unwrap();
+        Ok(StmtKind::Assign(target, self.expr()?))
+    }
+
+    fn expr(&mut self) -> Result<Expr> {
+        let guard = self.wand()?;
+        if self.eat_symbol("?") {
+            let then_expr = self.expr()?;
+            self.expect_symbol(":")?;
+            let else_expr = self.expr()?;
+            return Ok(Expr::Cond(
+                Box::new(guard),
+                Box::new(then_expr),
+                Box::new(else_expr),
+            ));
+        }
+        Ok(guard)
+    }
+
+    fn wand(&mut self) -> Result<Expr> {
+        let left = self.implies()?;
+        if self.eat_symbol("--*") {
+            return Ok(Expr::binary(BinaryOp::Wand, left, self.wand()?));
+        }
+        Ok(left)
+    }
+
+    fn implies(&mut self) -> Result<Expr> {
+        let left = self.binary(0)?;
+        if self.eat_symbol("==>") {
+            return Ok(Expr::binary(BinaryOp::Implies, left, self.implies()?));
+        }
+        Ok(left)
+    }
+
+    /// Parses the left-associative binary operators, starting at the given
+    /// precedence level.
+    fn binary(&mut self, level: usize) -> Result<Expr> {
+        const LEVELS: &[&[(&str, BinaryOp)]] = &[
+            &[("||", BinaryOp::Or)],
+            &[("&&", BinaryOp::And)],
+            &[("==", BinaryOp::Eq), ("!=", BinaryOp::Ne)],
+            &[
+                ("<", BinaryOp::Lt),
+                ("<=", BinaryOp::Le),
+                (">", BinaryOp::Gt),
+                (">=", BinaryOp::Ge),
+                ("in", BinaryOp::In),
+                ("subset", BinaryOp::Subset),
+            ],
+            &[
+                ("++", BinaryOp::Concat),
+                ("union", BinaryOp::Union),
+                ("intersection", BinaryOp::Intersection),
+                ("setminus", BinaryOp::Setminus),
+            ],
+            &[("+", BinaryOp::Add), ("-", BinaryOp::Sub)],
+            &[
+                ("*", BinaryOp::Mul),
+                ("/", BinaryOp::Div),
+                ("%", BinaryOp::Mod),
+            ],
+        ];
+        let Some(operators) = LEVELS.get(level) else {
+            return self.unary();
+        };
+        let mut left = self.binary(level + 1)?;
+        'operators: loop {
+            for (operator, op) in *operators {
+                if self.eat_symbol(operator) || self.eat_keyword(operator) {
+                    let right = self.binary(level + 1)?;
+                    left = Expr::binary(*op, left, right);
+                    continue 'operators;
+                }
+            }
+            return Ok(left);
+        }
+    }
+
+    fn unary(&mut self) -> Result<Expr> {
+        if self.eat_symbol("!") {
+            return Ok(Expr::Unary(UnaryOp::Not, Box::new(self.unary()?)));
+        }
+        if self.eat_symbol("-") {
+            if let Some(TokenKind::Int(value)) = self.peek().cloned() {
+                self.next()?;
+                return Ok(Expr::Int(format!("-{value}")));
+            }
+            return Ok(Expr::Unary(UnaryOp::Minus, Box::new(self.unary()?)));
+        }
+        self.postfix()
+    }
+
+    fn postfix(&mut self) -> Result<Expr> {
+        let mut expr = self.primary()?;
+        loop {
+            if self.eat_symbol(".") {
+                expr = Expr::Field(Box::new(expr), self.ident()?);
+            } else if self.eat_symbol("[") {
+                let index = self.expr()?;
+                if self.eat_symbol(":=") {
+                    let value = self.expr()?;
+                    expr = Expr::Update(Box::new(expr), Box::new(index), Box::new(value));
+                } else {
+                    expr = Expr::Index(Box::new(expr), Box::new(index));
+                }
+                self.expect_symbol("]")?;
+            } else {
+                return Ok(expr);
+            }
+        }
+    }
+
+    fn primary(&mut self) -> Result<Expr> {
+        let token = self.next()?;
+        let expr = match token {
+            TokenKind::Int(value) => Expr::Int(value),
+            TokenKind::Symbol("(") => {
+                let expr = self.expr()?;
+                self.expect_symbol(")")?;
+                expr
+            }
+            TokenKind::Symbol("|") => {
+                let expr = self.expr()?;
+                self.expect_symbol("|")?;
+                Expr::Length(Box::new(expr))
+            }
+            TokenKind::Symbol("[") => {
+                let inhale = self.expr()?;
+                self.expect_symbol(",")?;
+                let exhale = self.expr()?;
+                self.expect_symbol("]")?;
+                Expr::InhaleExhale(Box::new(inhale), Box::new(exhale))
+            }
+            TokenKind::Ident(name) => self.named(name)?,
+            _ => {
+                return syntax_error(self.previous_line(), "expected an expression");
+            }
+        };
+        Ok(expr)
+    }
+
+    /// Parses an expression that starts with an identifier.
+    fn named(&mut self, name: String) -> Result<Expr> {
+        let expr = match name.as_str() {
+            "true" => Expr::Bool(true),
+            "false" => Expr::Bool(false),
+            "null" => Expr::Null,
+            "none" => Expr::NoPerm,
+            "write" => Expr::FullPerm,
+            "wildcard" => Expr::Wildcard,
+            "result" => Expr::Result,
+            "forall" => self.quantifier(Quantifier::Forall)?,
+            "exists" => self.quantifier(Quantifier::Exists)?,
+            "unfolding" => {
+                let predicate = self.unary()?;
+                self.expect_keyword("in")?;
+                Expr::Unfolding(Box::new(predicate), Box::new(self.expr()?))
+            }
+            "let" => {
+                let variable = self.ident()?;
+                self.expect_symbol("==")?;
+                self.expect_symbol("(")?;
+                let def = self.expr()?;
+                self.expect_symbol(")")?;
+                self.expect_keyword("in")?;
+                Expr::Let(variable, Box::new(def), Box::new(self.expr()?))
+            }
+            "old" => {
+                let label = if self.eat_symbol("[") {
+                    let label = self.ident()?;
+                    self.expect_symbol("]")?;
+                    Some(label)
+                } else {
+                    None
+                };
+                self.expect_symbol("(")?;
+                let base = self.expr()?;
+                self.expect_symbol(")")?;
+                Expr::Old(label, Box::new(base))
+            }
+            "acc" => {
+                self.expect_symbol("(")?;
+                let location = self.expr()?;
+                let permission = if self.eat_symbol(",") {
+                    self.expr()?
+                } else {
+                    Expr::FullPerm
+                };
+                self.expect_symbol(")")?;
+                Expr::acc(location, permission)
+            }
+            "Seq" | "Set" | "Multiset" | "Map" => {
+                let collection = match name.as_str() {
+                    "Seq" => Collection::Seq,
+                    "Set" => Collection::Set,
+                    "Multiset" => Collection::Multiset,
+                    _ => Collection::Map,
+                };
+                if self.eat_symbol("[") {
+                    let types = self.list("]", Self::ty)?;
+                    self.expect_symbol("(")?;
+                    self.expect_symbol(")")?;
+                    Expr::Empty(collection, types)
+                } else {
+                    self.expect_symbol("(")?;
+                    Expr::Explicit(collection, self.list(")", Self::expr)?)
+                }
+            }
+            _ if self.eat_symbol("(") => Expr::Call(name, self.list(")", Self::expr)?),
+            _ => Expr::Local(name),
+        };
+        Ok(expr)
+    }
+
+    fn quantifier(&mut self, quantifier: Quantifier) -> Result<Expr> {
+        let mut variables = vec![self.var_decl()?];
+        while self.eat_symbol(",") {
+            variables.push(self.var_decl()?);
+        }
+        self.expect_symbol("::")?;
+        let mut triggers = Vec::new();
+        while self.eat_symbol("{") {
+            triggers.push(self.list("}", Self::expr)?);
+        }
+        Ok(Expr::Quantifier(
+            quantifier,
+            variables,
+            triggers,
+            Box::new(self.expr()?),
+        ))
+    }
+}
@@ -0,0 +1,752 @@
+// © 2023, ETH Zurich
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! The subset of the Viper syntax used by the printer and the parser.
+
+use super::{invalid, Result};
+use std::fmt::{self, Write};
+use vir::legacy::RETURN_LABEL;
+
+/// Metadata that Viper ignores, printed as a `//@ key value` comment.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(super) struct Annotation {
+    pub key: String,
+    pub value: String,
+}
+
+impl Annotation {
+    pub fn new(key: &str, value: impl ToString) -> Self {
+        Annotation {
+            key: key.to_string(),
+            value: value.to_string(),
+        }
+    }
+
+    pub fn parse(text: &str) -> Self {
+        let text = text.trim();
+        let (key, value) = text.split_once(' ').unwrap_or((text, ""));
+        Annotation::new(key, value.trim())
+    }
+
+    /// The annotation that records the position of a statement.
+    pub fn position(line: i32, column: i32, id: u64) -> Self {
+        Annotation::new("pos", format!("{line}:{column}:{id}"))
+    }
+}
+
+/// The line, column and id recorded by a `pos` annotation.
+pub(super) fn parse_position(annotation: &Option<Annotation>) -> Result<Option<(i32, i32, u64)>> {
+    let Some(annotation) = annotation
+        .as_ref()
+        .filter(|annotation| annotation.key == "pos")
+    else {
+        return Ok(None);
+    };
+    let parts: Vec<_> = annotation.value.split(':').collect();
+    if let [line, column, id] = parts.as_slice() {
+        if let (Ok(line), Ok(column), Ok(id)) = (line.parse(), column.parse(), id.parse()) {
+            return Ok(Some((line, column, id)));
+        }
+    }
+    invalid(format!("malformed position {}", annotation.value))
+}
+
+pub(super) fn find_annotation<'a>(annotations: &'a [Annotation], key: &str) -> Option<&'a str> {
+    annotations
+        .iter()
+        .find(|annotation| annotation.key == key)
+        .map(|annotation| annotation.value.as_str())
+}
+
+#[derive(Debug, Default)]
+pub(super) struct Program {
+    pub annotations: Vec<Annotation>,
+    pub domains: Vec<Domain>,
+    pub fields: Vec<VarDecl>,
+    pub functions: Vec<Function>,
+    pub predicates: Vec<Predicate>,
+    pub methods: Vec<Method>,
+}
+
+#[derive(Debug)]
+pub(super) struct Domain {
+    pub name: String,
+    pub type_vars: Vec<String>,
+    pub functions: Vec<DomainFunction>,
+    pub axioms: Vec<Axiom>,
+}
+
+#[derive(Debug)]
+pub(super) struct DomainFunction {
+    pub unique: bool,
+    pub name: String,
+    pub parameters: Vec<VarDecl>,
+    pub return_type: Type,
+}
+
+#[derive(Debug)]
+pub(super) struct Axiom {
+    pub comment: Option<String>,
+    pub name: String,
+    pub body: Expr,
+}
+
+#[derive(Debug)]
+pub(super) struct Function {
+    pub annotations: Vec<Annotation>,
+    pub name: String,
+    pub parameters: Vec<VarDecl>,
+    pub return_type: Type,
+    pub pres: Vec<Expr>,
+    pub posts: Vec<Expr>,
+    pub body: Option<Expr>,
+}
+
+#[derive(Debug)]
+pub(super) struct Predicate {
+    pub name: String,
+    pub parameters: Vec<VarDecl>,
+    pub body: Option<Expr>,
+}
+
+#[derive(Debug)]
+pub(super) struct Method {
+    pub annotations: Vec<Annotation>,
+    pub name: String,
+    pub parameters: Vec<VarDecl>,
+    pub returns: Vec<VarDecl>,
+    pub pres: Vec<Expr>,
+    pub posts: Vec<Expr>,
+    pub body: Option<Vec<Stmt>>,
+}
+
+#[derive(Debug, Clone)]
+pub(super) struct VarDecl {
+    pub name: String,
+    pub ty: Type,
+}
+
+impl VarDecl {
+    pub fn new(name: impl ToString, ty: Type) -> Self {
+        VarDecl {
+            name: name.to_string(),
+            ty,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(super) enum Type {
+    Int,
+    Bool,
+    Perm,
+    Ref,
+    Seq(Box<Type>),
+    Set(Box<Type>),
+    Multiset(Box<Type>),
+    Map(Box<Type>, Box<Type>),
+    Domain(String),
+}
+
+#[derive(Debug)]
+pub(super) struct Stmt {
+    pub kind: StmtKind,
+    /// Printed at the end of the first line of the statement.
+    pub annotation: Option<Annotation>,
+}
+
+impl From<StmtKind> for Stmt {
+    fn from(kind: StmtKind) -> Self {
+        Stmt {
+            kind,
+            annotation: None,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub(super) enum StmtKind {
+    Comment(String),
+    Var(VarDecl),
+    Label(String),
+    Goto(String),
+    Inhale(Expr),
+    Exhale(Expr),
+    Assert(Expr),
+    Assume(Expr),
+    Refute(Expr),
+    Fold(Expr),
+    Unfold(Expr),
+    Apply(Expr),
+    Package(Expr, Vec<Stmt>),
+    Assign(Expr, Expr),
+    /// Targets, method name, arguments.
+    Call(Vec<Expr>, String, Vec<Expr>),
+    If(Expr, Vec<Stmt>, Vec<Stmt>),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum UnaryOp {
+    Not,
+    Minus,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum BinaryOp {
+    Wand,
+    Implies,
+    Or,
+    And,
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    In,
+    Subset,
+    Concat,
+    Union,
+    Intersection,
+    Setminus,
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Mod,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum Quantifier {
+    Forall,
+    Exists,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum Collection {
+    Seq,
+    Set,
+    Multiset,
+    Map,
+}
+
+#[derive(Debug, Clone)]
+pub(super) enum Expr {
+    Local(String),
+    Result,
+    /// A decimal literal, possibly negative.
+    Int(String),
+    Bool(bool),
+    Null,
+    NoPerm,
+    FullPerm,
+    Wildcard,
+    Field(Box<Expr>, String),
+    Old(Option<String>, Box<Expr>),
+    Unary(UnaryOp, Box<Expr>),
+    Binary(BinaryOp, Box<Expr>, Box<Expr>),
+    Cond(Box<Expr>, Box<Expr>, Box<Expr>),
+    /// Location and permission amount.
+    Acc(Box<Expr>, Box<Expr>),
+    Call(String, Vec<Expr>),
+    Index(Box<Expr>, Box<Expr>),
+    Update(Box<Expr>, Box<Expr>, Box<Expr>),
+    Length(Box<Expr>),
+    /// The predicate access predicate and the body.
+    Unfolding(Box<Expr>, Box<Expr>),
+    Quantifier(Quantifier, Vec<VarDecl>, Vec<Vec<Expr>>, Box<Expr>),
+    Let(String, Box<Expr>, Box<Expr>),
+    InhaleExhale(Box<Expr>, Box<Expr>),
+    Empty(Collection, Vec<Type>),
+    Explicit(Collection, Vec<Expr>),
+}
+
+impl Expr {
+    pub fn binary(op: BinaryOp, left: Expr, right: Expr) -> Self {
+        Expr::Binary(op, Box::new(left), Box::new(right))
+    }
+
+    pub fn acc(location: Expr, permission: Expr) -> Self {
+        Expr::Acc(Box::new(location), Box::new(permission))
+    }
+
+    /// Whether the expression can be printed as the operand of a prefix or
+    /// postfix operator without parentheses.
+    fn is_atomic(&self) -> bool {
+        matches!(
+            self,
+            Expr::Local(_)
+                | Expr::Result
+                | Expr::Bool(_)
+                | Expr::Null
+                | Expr::NoPerm
+                | Expr::FullPerm
+                | Expr::Wildcard
+                | Expr::Field(..)
+                | Expr::Old(..)
+                | Expr::Acc(..)
+                | Expr::Call(..)
+                | Expr::Index(..)
+                | Expr::Update(..)
+                | Expr::Length(..)
+                | Expr::Empty(..)
+                | Expr::Explicit(..)
+        )
+    }
+}
+
+/// A basic block of a method whose body is a control flow graph, as printed
+/// for `CfgMethod` and `ProcedureDecl`.
+pub(super) struct Block<'a> {
+    pub label: &'a str,
+    pub statements: Vec<&'a Stmt>,
+    pub successor: BlockSuccessor<'a>,
+}
+
+pub(super) enum BlockSuccessor<'a> {
+    Return,
+    Goto(&'a str),
+    /// The conditional targets and the default target. A switch without a
+    /// default target ends with `assert false`.
+    Switch(Vec<(&'a Expr, &'a str)>, Option<&'a str>),
+}
+
+/// Whether the body of a method is a control flow graph.
+pub(super) fn has_blocks(body: &[Stmt]) -> bool {
+    body.iter()
+        .any(|statement| matches!(statement.kind, StmtKind::Label(_)))
+}
+
+/// Splits the body of a method into its local variables and its basic
+/// blocks. A label starts a basic block only after the end of the previous
+/// one; comments between basic blocks are dropped.
+pub(super) fn split_blocks(body: &[Stmt]) -> Result<(Vec<&VarDecl>, Vec<Block>)> {
+    let mut locals = Vec::new();
+    let mut blocks = Vec::new();
+    let mut current: Option<Block> = None;
+    let mut switch: Option<Vec<(&Expr, &str)>> = None;
+    let mut returned = false;
+    for statement in body {
+        let Some(block) = current.as_mut() else {
+            match &statement.kind {
+                StmtKind::Comment(_) => {}
+                StmtKind::Var(variable) if blocks.is_empty() => locals.push(variable),
+                StmtKind::Label(label) if label == RETURN_LABEL => returned = true,
+                StmtKind::Label(label) if !returned => {
+                    current = Some(Block {
+                        label: label.as_str(),
+                        statements: Vec::new(),
+                        successor: BlockSuccessor::Return,
+                    });
+                }
+                _ => return invalid("a statement outside of a basic block"),
+            }
+            continue;
+        };
+        let successor = match &statement.kind {
+            StmtKind::If(guard, then_branch, else_branch) if else_branch.is_empty() => {
+                if let [Stmt {
+                    kind: StmtKind::Goto(target),
+                    ..
+                }] = then_branch.as_slice()
+                {
+                    switch
+                        .get_or_insert_with(Vec::new)
+                        .push((guard, target.as_str()));
+                    continue;
+                }
+                None
+            }
+            StmtKind::Goto(target) => Some(match switch.take() {
+                Some(targets) => BlockSuccessor::Switch(targets, Some(target.as_str())),
+                None if target == RETURN_LABEL => BlockSuccessor::Return,
+                None => BlockSuccessor::Goto(target.as_str()),
+            }),
+            StmtKind::Assert(Expr::Bool(false)) if switch.is_some() => {
+                Some(BlockSuccessor::Switch(switch.take().unwrap(), None))
+            }
+            _ => None,
+        };
+        match successor {
+            Some(successor) => {
+                let mut block = current.take().unwrap();
+                block.successor = successor;
+                blocks.push(block);
+            }
+            None if switch.is_some() => {
+                return invalid(format!(
+                    "a statement after the conditional jumps of block {}",
+                    block.label
+                ));
+            }
+            None => block.statements.push(statement),
+        }
+    }
+    if let Some(block) = current {
+        return invalid(format!("the basic block {} has no successor", block.label));
+    }
+    Ok((locals, blocks))
+}
+
+impl fmt::Display for Type {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Type::Int => write!(f, "Int"),
+            Type::Bool => write!(f, "Bool"),
+            Type::Perm => write!(f, "Perm"),
+            Type::Ref => write!(f, "Ref"),
+            Type::Seq(element_type) => write!(f, "Seq[{element_type}]"),
+            Type::Set(element_type) => write!(f, "Set[{element_type}]"),
+            Type::Multiset(element_type) => write!(f, "Multiset[{element_type}]"),
+            Type::Map(key_type, value_type) => write!(f, "Map[{key_type}, {value_type}]"),
+            Type::Domain(name) => write!(f, "{name}"),
+        }
+    }
+}
+
+impl fmt::Display for VarDecl {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}: {}", self.name, self.ty)
+    }
+}
+
+impl fmt::Display for BinaryOp {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let op = match self {
+            BinaryOp::Wand => "--*",
+            BinaryOp::Implies => "==>",
+            BinaryOp::Or => "||",
+            BinaryOp::And => "&&",
+            BinaryOp::Eq => "==",
+            BinaryOp::Ne => "!=",
+            BinaryOp::Lt => "<",
+            BinaryOp::Le => "<=",
+            BinaryOp::Gt => ">",
+            BinaryOp::Ge => ">=",
+            BinaryOp::In => "in",
+            BinaryOp::Subset => "subset",
+            BinaryOp::Concat => "++",
+            BinaryOp::Union => "union",
+            BinaryOp::Intersection => "intersection",
+            BinaryOp::Setminus => "setminus",
+            BinaryOp::Add => "+",
+            BinaryOp::Sub => "-",
+            BinaryOp::Mul => "*",
+            BinaryOp::Div => "/",
+            BinaryOp::Mod => "%",
+        };
+        write!(f, "{op}")
+    }
+}
+
+impl fmt::Display for Collection {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            Collection::Seq => "Seq",
+            Collection::Set => "Set",
+            Collection::Multiset => "Multiset",
+            Collection::Map => "Map",
+        };
+        write!(f, "{name}")
+    }
+}
+
+struct Atom<'a>(&'a Expr);
+
+impl fmt::Display for Atom<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.0.is_atomic() {
+            write!(f, "{}", self.0)
+        } else {
+            write!(f, "({})", self.0)
+        }
+    }
+}
+
+struct Commas<'a, T>(&'a [T]);
+
+impl<T: fmt::Display> fmt::Display for Commas<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (index, item) in self.0.iter().enumerate() {
+            if index > 0 {
+                write!(f, ", ")?;
+            }
+            write!(f, "{item}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Binary operations, conditionals and binders are always parenthesized, so
+/// that the output does not depend on the precedence of the operators.
+impl fmt::Display for Expr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Expr::Local(name) => write!(f, "{name}"),
+            Expr::Result => write!(f, "result"),
+            Expr::Int(value) => write!(f, "{value}"),
+            Expr::Bool(value) => write!(f, "{value}"),
+            Expr::Null => write!(f, "null"),
+            Expr::NoPerm => write!(f, "none"),
+            Expr::FullPerm => write!(f, "write"),
+            Expr::Wildcard => write!(f, "wildcard"),
+            Expr::Field(base, field) => write!(f, "{}.{field}", Atom(base)),
+            Expr::Old(Some(label), base) => write!(f, "old[{label}]({base})"),
+            Expr::Old(None, base) => write!(f, "old({base})"),
+            Expr::Unary(UnaryOp::Not, argument) => write!(f, "!{}", Atom(argument)),
+            Expr::Unary(UnaryOp::Minus, argument) => write!(f, "-{}", Atom(argument)),
+            Expr::Binary(op, left, right) => write!(f, "({left} {op} {right})"),
+            Expr::Cond(guard, then_expr, else_expr) => {
+                write!(f, "({guard} ? {then_expr} : {else_expr})")
+            }
+            Expr::Acc(location, permission) => write!(f, "acc({location}, {permission})"),
+            Expr::Call(name, arguments) => write!(f, "{name}({})", Commas(arguments)),
+            Expr::Index(base, index) => write!(f, "{}[{index}]", Atom(base)),
+            Expr::Update(base, key, value) => write!(f, "{}[{key} := {value}]", Atom(base)),
+            Expr::Length(base) => write!(f, "|{base}|"),
+            Expr::Unfolding(predicate, body) => write!(f, "(unfolding {predicate} in {body})"),
+            Expr::Quantifier(quantifier, variables, triggers, body) => {
+                let keyword = match quantifier {
+                    Quantifier::Forall => "forall",
+                    Quantifier::Exists => "exists",
+                };
+                write!(f, "({keyword} {} ::", Commas(variables))?;
+                for trigger in triggers {
+                    write!(f, " {{ {} }}", Commas(trigger))?;
+                }
+                write!(f, " {body})")
+            }
+            Expr::Let(variable, def, body) => write!(f, "(let {variable} == ({def}) in {body})"),
+            Expr::InhaleExhale(inhale, exhale) => write!(f, "[{inhale}, {exhale}]"),
+            Expr::Empty(collection, types) => write!(f, "{collection}[{}]()", Commas(types)),
+            Expr::Explicit(collection, elements) => {
+                write!(f, "{collection}({})", Commas(elements))
+            }
+        }
+    }
+}
+
+/// Writes indented lines.
+struct Printer {
+    out: String,
+    indent: usize,
+}
+
+impl Printer {
+    fn line(&mut self, text: impl fmt::Display) {
+        if self.indent > 0 {
+            write!(self.out, "{:1$}", "", self.indent * 2).unwrap();
+        }
+        writeln!(self.out, "{text}").unwrap();
+    }
+
+    fn annotations(&mut self, annotations: &[Annotation]) {
+        for annotation in annotations {
+            self.line(format_args!("{annotation}"));
+        }
+    }
+
+    fn comment(&mut self, comment: &str) {
+        for line in comment.lines().chain(comment.is_empty().then_some("")) {
+            if line.is_empty() {
+                self.line("//");
+            } else {
+                self.line(format_args!("// {line}"));
+            }
+        }
+    }
+
+    fn specifications(&mut self, pres: &[Expr], posts: &[Expr]) {
+        self.indent += 1;
+        for pre in pres {
+            self.line(format_args!("requires {pre}"));
+        }
+        for post in posts {
+            self.line(format_args!("ensures {post}"));
+        }
+        self.indent -= 1;
+    }
+
+    fn block(&mut self, header: impl fmt::Display, statements: &[Stmt]) {
+        self.line(format_args!("{header}{{"));
+        self.statements(statements);
+        self.line("}");
+    }
+
+    fn statements(&mut self, statements: &[Stmt]) {
+        self.indent += 1;
+        for statement in statements {
+            self.statement(statement);
+        }
+        self.indent -= 1;
+    }
+
+    fn statement(&mut self, statement: &Stmt) {
+        let annotation = statement
+            .annotation
+            .as_ref()
+            .map(|annotation| format!(" {annotation}"))
+            .unwrap_or_default();
+        match &statement.kind {
+            StmtKind::Comment(comment) => self.comment(comment),
+            StmtKind::Var(variable) => self.line(format_args!("var {variable}{annotation}")),
+            StmtKind::Label(label) => self.line(format_args!("label {label}{annotation}")),
+            StmtKind::Goto(label) => self.line(format_args!("goto {label}{annotation}")),
+            StmtKind::Inhale(expr) => self.line(format_args!("inhale {expr}{annotation}")),
+            StmtKind::Exhale(expr) => self.line(format_args!("exhale {expr}{annotation}")),
+            StmtKind::Assert(expr) => self.line(format_args!("assert {expr}{annotation}")),
+            StmtKind::Assume(expr) => self.line(format_args!("assume {expr}{annotation}")),
+            StmtKind::Refute(expr) => self.line(format_args!("refute {expr}{annotation}")),
+            StmtKind::Fold(expr) => self.line(format_args!("fold {expr}{annotation}")),
+            StmtKind::Unfold(expr) => self.line(format_args!("unfold {expr}{annotation}")),
+            StmtKind::Apply(expr) => self.line(format_args!("apply {expr}{annotation}")),
+            StmtKind::Package(wand, body) => {
+                self.line(format_args!("package {wand} {{{annotation}"));
+                self.statements(body);
+                self.line("}");
+            }
+            StmtKind::Assign(target, value) => {
+                self.line(format_args!("{target} := {value}{annotation}"))
+            }
+            StmtKind::Call(targets, method, arguments) if targets.is_empty() => {
+                self.line(format_args!("{method}({}){annotation}", Commas(arguments)))
+            }
+            StmtKind::Call(targets, method, arguments) => self.line(format_args!(
+                "{} := {method}({}){annotation}",
+                Commas(targets),
+                Commas(arguments)
+            )),
+            StmtKind::If(guard, then_branch, else_branch) => {
+                self.line(format_args!("if ({guard}) {{{annotation}"));
+                self.statements(then_branch);
+                if else_branch.is_empty() {
+                    self.line("}");
+                } else {
+                    self.block("} else ", else_branch);
+                }
+            }
+        }
+    }
+}
+
+impl fmt::Display for Annotation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.value.is_empty() {
+            write!(f, "//@ {}", self.key)
+        } else {
+            write!(f, "//@ {} {}", self.key, self.value)
+        }
+    }
+}
+
+impl fmt::Display for Program {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut printer = Printer {
+            out: String::new(),
+            indent: 0,
+        };
+        printer.annotations(&self.annotations);
+        for domain in &self.domains {
+            printer.line("");
+            if domain.type_vars.is_empty() {
+                printer.line(format_args!("domain {} {{", domain.name));
+            } else {
+                printer.line(format_args!(
+                    "domain {}[{}] {{",
+                    domain.name,
+                    Commas(&domain.type_vars)
+                ));
+            }
+            printer.indent += 1;
+            for function in &domain.functions {
+                printer.line(format_args!(
+                    "{}function {}({}): {}",
+                    if function.unique { "unique " } else { "" },
+                    function.name,
+                    Commas(&function.parameters),
+                    function.return_type
+                ));
+            }
+            for axiom in &domain.axioms {
+                printer.line("");
+                if let Some(comment) = &axiom.comment {
+                    printer.comment(comment);
+                }
+                printer.line(format_args!("axiom {} {{", axiom.name));
+                printer.indent += 1;
+                printer.line(&axiom.body);
+                printer.indent -= 1;
+                printer.line("}");
+            }
+            printer.indent -= 1;
+            printer.line("}");
+        }
+        if !self.fields.is_empty() {
+            printer.line("");
+        }
+        for field in &self.fields {
+            printer.line(format_args!("field {field}"));
+        }
+        for function in &self.functions {
+            printer.line("");
+            printer.annotations(&function.annotations);
+            printer.line(format_args!(
+                "function {}({}): {}",
+                function.name,
+                Commas(&function.parameters),
+                function.return_type
+            ));
+            printer.specifications(&function.pres, &function.posts);
+            if let Some(body) = &function.body {
+                printer.line("{");
+                printer.indent += 1;
+                printer.line(body);
+                printer.indent -= 1;
+                printer.line("}");
+            }
+        }
+        for predicate in &self.predicates {
+            printer.line("");
+            let header = format!(
+                "predicate {}({})",
+                predicate.name,
+                Commas(&predicate.parameters)
+            );
+            if let Some(body) = &predicate.body {
+                printer.line(format_args!("{header} {{"));
+                printer.indent += 1;
+                printer.line(body);
+                printer.indent -= 1;
+                printer.line("}");
+            } else {
+                printer.line(header);
+            }
+        }
+        for method in &self.methods {
+            printer.line("");
+            printer.annotations(&method.annotations);
+            if method.returns.is_empty() {
+                printer.line(format_args!(
+                    "method {}({})",
+                    method.name,
+                    Commas(&method.parameters)
+                ));
+            } else {
+                printer.line(format_args!(
+                    "method {}({}) returns ({})",
+                    method.name,
+                    Commas(&method.parameters),
+                    Commas(&method.returns)
+                ));
+            }
+            printer.specifications(&method.pres, &method.posts);
+            if let Some(body) = &method.body {
+                printer.block("", body);
+            }
+        }
+        write!(f, "{}", printer.out)
+    }
+}
@@ -0,0 +1,733 @@
+// © 2023, ETH Zurich
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! Conversion of a parsed program back into legacy VIR.
+
+use super::{
+    from_legacy::{DEAD_BORROW_TOKEN, READ_PERMISSION},
+    invalid,
+    syntax::{
+        find_annotation, has_blocks, parse_position, split_blocks, Annotation, BinaryOp, Block,
+        BlockSuccessor, Collection, Domain, Expr, Function, Method, Predicate, Program, Quantifier,
+        Stmt, StmtKind, Type, UnaryOp, VarDecl,
+    },
+    unsupported, Result,
+};
+use std::collections::{HashMap, HashSet};
+use vir::legacy::{
+    self as vir_legacy, AssignKind, BinaryOpKind, Borrow, CfgMethod, Const, ContainerOpKind,
+    DomainFunc, Field, LocalVar, PermAmount, Position, Successor, Trigger, UnaryOpKind,
+    RETURN_LABEL,
+};
+
+pub(super) fn program(program: &Program) -> Result<vir_legacy::Program> {
+    let mut lifting = Lifting {
+        fields: HashMap::new(),
+        functions: HashMap::new(),
+        domain_functions: HashMap::new(),
+        predicates: HashSet::new(),
+        scopes: Vec::new(),
+        result_type: None,
+        labels: HashSet::new(),
+    };
+    let mut fields = Vec::new();
+    for field in &program.fields {
+        let field = Field::new(&field.name, ty(&field.ty)?);
+        lifting.fields.insert(field.name.clone(), field.typ.clone());
+        fields.push(field);
+    }
+    let functions: Vec<_> = program
+        .functions
+        .iter()
+        .filter(|function| function.name != READ_PERMISSION)
+        .collect();
+    for function in &functions {
+        lifting.functions.insert(
+            function.name.clone(),
+            (variables(&function.parameters)?, ty(&function.return_type)?),
+        );
+    }
+    for domain in &program.domains {
+        if !domain.type_vars.is_empty() {
+            return invalid(format!("the domain {} has type parameters", domain.name));
+        }
+        for function in &domain.functions {
+            lifting.domain_functions.insert(
+                function.name.clone(),
+                DomainFunc {
+                    name: function.name.clone(),
+                    formal_args: variables(&function.parameters)?,
+                    return_type: ty(&function.return_type)?,
+                    unique: function.unique,
+                    domain_name: domain.name.clone(),
+                },
+            );
+        }
+    }
+    for predicate in &program.predicates {
+        lifting.predicates.insert(predicate.name.clone());
+    }
+    let mut methods = Vec::new();
+    let mut builtin_methods = Vec::new();
+    for method in &program.methods {
+        match &method.body {
+            Some(body) if has_blocks(body) => methods.push(lifting.cfg_method(method, body)?),
+            Some(_) => {
+                return invalid(format!(
+                    "the body of method {} is not a control flow graph",
+                    method.name
+                ))
+            }
+            None => builtin_methods.push(lifting.bodyless_method(method)?),
+        }
+    }
+    Ok(vir_legacy::Program {
+        name: find_annotation(&program.annotations, "program")
+            .unwrap_or("program")
+            .to_string(),
+        domains: program
+            .domains
+            .iter()
+            .map(|domain| lifting.domain(domain))
+            .collect::<Result<_>>()?,
+        backend_types: Vec::new(),
+        fields,
+        builtin_methods,
+        methods,
+        functions: functions
+            .into_iter()
+            .map(|function| lifting.function(function))
+            .collect::<Result<_>>()?,
+        viper_predicates: program
+            .predicates
+            .iter()
+            .map(|predicate| lifting.predicate(predicate))
+            .collect::<Result<_>>()?,
+    })
+}
+
+struct Lifting {
+    fields: HashMap<String, vir_legacy::Type>,
+    /// The parameters and the return type of each function.
+    functions: HashMap<String, (Vec<LocalVar>, vir_legacy::Type)>,
+    domain_functions: HashMap<String, DomainFunc>,
+    predicates: HashSet<String>,
+    scopes: Vec<Vec<LocalVar>>,
+    /// The return type of the function whose body or specification is
+    /// converted.
+    result_type: Option<vir_legacy::Type>,
+    /// The names of the variables and labels of the method whose body is
+    /// converted, which `CfgMethod` requires to be distinct.
+    labels: HashSet<String>,
+}
+
+fn variables(variables: &[VarDecl]) -> Result<Vec<LocalVar>> {
+    variables.iter().map(variable).collect()
+}
+
+fn variable(variable: &VarDecl) -> Result<LocalVar> {
+    Ok(LocalVar::new(&variable.name, ty(&variable.ty)?))
+}
+
+fn ty(ty: &Type) -> Result<vir_legacy::Type> {
+    let ty = match ty {
+        Type::Int => vir_legacy::Type::Int,
+        Type::Bool => vir_legacy::Type::Bool,
+        Type::Ref => vir_legacy::Type::Ref,
+        Type::Domain(name) => match name.strip_prefix("Snap$") {
+            Some(name) => vir_legacy::Type::Snapshot(name.to_string()),
+            None => vir_legacy::Type::Domain(name.clone()),
+        },
+        Type::Seq(element_type) => vir_legacy::Type::Seq(Box::new(self::ty(element_type)?)),
+        Type::Map(key_type, val_type) => {
+            vir_legacy::Type::Map(Box::new(self::ty(key_type)?), Box::new(self::ty(val_type)?))
+        }
+        Type::Perm | Type::Set(_) | Type::Multiset(_) => {
+            return unsupported(format!("the type {ty} in legacy programs"))
+        }
+    };
+    Ok(ty)
+}
+
+fn position(annotation: &Option<Annotation>) -> Result<Position> {
+    Ok(parse_position(annotation)?
+        .map(|(line, column, id)| Position::new(line, column, id))
+        .unwrap_or_default())
+}
+
+/// The inverse of the encoding of `PermAmount` by the printer.
+fn perm_amount(permission: &Expr) -> Result<PermAmount> {
+    let is_read = |expr: &Expr| match expr {
+        Expr::Call(name, arguments) => name == READ_PERMISSION && arguments.is_empty(),
+        _ => false,
+    };
+    match permission {
+        Expr::FullPerm => Ok(PermAmount::Write),
+        expr if is_read(expr) => Ok(PermAmount::Read),
+        Expr::Binary(BinaryOp::Sub, left, right)
+            if matches!(**left, Expr::FullPerm) && is_read(right) =>
+        {
+            Ok(PermAmount::Remaining)
+        }
+        _ => unsupported(format!("the permission amount {permission}")),
+    }
+}
+
+/// The borrow of an access to a dead borrow token; `-1` stands for a magic
+/// wand without a borrow.
+fn dead_borrow_token(expr: &Expr) -> Option<Option<Borrow>> {
+    let Expr::Acc(location, permission) = expr else {
+        return None;
+    };
+    match (&**location, &**permission) {
+        (Expr::Call(name, arguments), Expr::FullPerm) if name == DEAD_BORROW_TOKEN => {
+            match arguments.as_slice() {
+                [Expr::Int(borrow)] if borrow == "-1" => Some(None),
+                [Expr::Int(borrow)] => borrow.parse::<usize>().ok().map(|id| Some(id.into())),
+                _ => None,
+            }
+        }
+        _ => None,
+    }
+}
+
+fn seq_type(element_type: vir_legacy::Type) -> vir_legacy::Type {
+    vir_legacy::Type::Seq(Box::new(element_type))
+}
+
+impl Lifting {
+    fn scoped<T>(
+        &mut self,
+        variables: &[LocalVar],
+        f: impl FnOnce(&mut Self) -> Result<T>,
+    ) -> Result<T> {
+        self.scopes.push(variables.to_vec());
+        let result = f(self);
+        self.scopes.pop();
+        result
+    }
+
+    fn domain(&mut self, domain: &Domain) -> Result<vir_legacy::Domain> {
+        Ok(vir_legacy::Domain {
+            name: domain.name.clone(),
+            functions: domain
+                .functions
+                .iter()
+                .map(|function| self.domain_functions[&function.name].clone())
+                .collect(),
+            axioms: domain
+                .axioms
+                .iter()
+                .map(|axiom| {
+                    Ok(vir_legacy::DomainAxiom {
+                        comment: axiom.comment.clone(),
+                        name: axiom.name.clone(),
+                        expr: self.expr(&axiom.body)?,
+                        domain_name: domain.name.clone(),
+                    })
+                })
+                .collect::<Result<_>>()?,
+            type_vars: Vec::new(),
+        })
+    }
+
+    fn function(&mut self, function: &Function) -> Result<vir_legacy::Function> {
+        let (formal_args, return_type) = self.functions[&function.name].clone();
+        self.result_type = Some(return_type.clone());
+        let result = self.scoped(&formal_args, |lifting| {
+            Ok(vir_legacy::Function {
+                name: function.name.clone(),
+                formal_args: formal_args.clone(),
+                return_type,
+                pres: lifting.exprs(&function.pres)?,
+                posts: lifting.exprs(&function.posts)?,
+                body: function
+                    .body
+                    .as_ref()
+                    .map(|body| lifting.expr(body))
+                    .transpose()?,
+            })
+        });
+        self.result_type = None;
+        result
+    }
+
+    fn predicate(&mut self, predicate: &Predicate) -> Result<vir_legacy::Predicate> {
+        let [this] = predicate.parameters.as_slice() else {
+            return invalid(format!(
+                "the predicate {} does not have exactly one parameter",
+                predicate.name
+            ));
+        };
+        let this = variable(this)?;
+        let Some(body) = &predicate.body else {
+            return Ok(vir_legacy::Predicate::Bodyless(
+                predicate.name.clone(),
+                this,
+            ));
+        };
+        let body = self.scoped(&[this.clone()], |lifting| lifting.expr(body))?;
+        Ok(vir_legacy::Predicate::Struct(vir_legacy::StructPredicate {
+            name: predicate.name.clone(),
+            this,
+            body: Some(body),
+        }))
+    }
+
+    fn bodyless_method(&mut self, method: &Method) -> Result<vir_legacy::BodylessMethod> {
+        let formal_args = variables(&method.parameters)?;
+        let formal_returns = variables(&method.returns)?;
+        let pres = self.scoped(&formal_args, |lifting| lifting.exprs(&method.pres))?;
+        let mut scope = formal_args.clone();
+        scope.extend(formal_returns.iter().cloned());
+        let posts = self.scoped(&scope, |lifting| lifting.exprs(&method.posts))?;
+        Ok(vir_legacy::BodylessMethod {
+            name: method.name.clone(),
+            formal_args,
+            formal_returns,
+            pres,
+            posts,
+        })
+    }
+
+    fn cfg_method(&mut self, method: &Method, body: &[Stmt]) -> Result<CfgMethod> {
+        if !method.parameters.is_empty() || !method.pres.is_empty() || !method.posts.is_empty() {
+            return invalid(format!(
+                "the method {} with a control flow graph has parameters or a specification",
+                method.name
+            ));
+        }
+        let (locals, blocks) = split_blocks(body)?;
+        let formal_returns = variables(&method.returns)?;
+        let local_vars = locals
+            .into_iter()
+            .map(variable)
+            .collect::<Result<Vec<_>>>()?;
+        let mut cfg = CfgMethod::new(method.name.clone(), formal_returns, local_vars);
+        self.labels = cfg
+            .get_all_vars()
+            .into_iter()
+            .map(|variable| variable.name)
+            .collect();
+        let mut indices = HashMap::new();
+        for block in &blocks {
+            let is_label = block
+                .label
+                .starts_with(|c: char| c.is_alphabetic() || c == '_')
+                && block.label.chars().all(|c| c.is_alphanumeric() || c == '_');
+            if !is_label || !self.labels.insert(block.label.to_string()) {
+                return invalid(format!("the block label {} is not valid", block.label));
+            }
+            indices.insert(block.label, cfg.add_block(block.label, Vec::new()));
+        }
+        let scope = cfg.get_all_vars();
+        for block in &blocks {
+            let index = indices[block.label];
+            let (statements, successor) = self.scoped(&scope, |lifting| {
+                let statements = lifting.statements(block.statements.iter().copied())?;
+                Ok((statements, lifting.successor(block, &indices)?))
+            })?;
+            cfg.add_stmts(index, statements);
+            cfg.set_successor(index, successor);
+        }
+        Ok(cfg)
+    }
+
+    fn successor(
+        &mut self,
+        block: &Block,
+        indices: &HashMap<&str, vir_legacy::CfgBlockIndex>,
+    ) -> Result<Successor> {
+        let target = |label: &str| match indices.get(label) {
+            Some(index) => Ok(*index),
+            None => invalid(format!("the block {label} does not exist")),
+        };
+        let successor = match &block.successor {
+            BlockSuccessor::Return => Successor::Return,
+            BlockSuccessor::Goto(label) => Successor::Goto(target(label)?),
+            BlockSuccessor::Switch(targets, Some(default_target)) => {
+                let mut cases = Vec::new();
+                for (guard, label) in targets {
+                    cases.push((self.expr(guard)?, target(label)?));
+                }
+                Successor::GotoSwitch(cases, target(default_target)?)
+            }
+            BlockSuccessor::Switch(_, None) => {
+                return invalid(format!(
+                    "the conditional jumps of block {} have no default target",
+                    block.label
+                ))
+            }
+        };
+        Ok(successor)
+    }
+
+    fn statements<'a>(
+        &mut self,
+        statements: impl IntoIterator<Item = &'a Stmt>,
+    ) -> Result<Vec<vir_legacy::Stmt>> {
+        let mut result = Vec::new();
+        for statement in statements {
+            let statement = self.statement(statement, &mut result)?;
+            result.push(statement);
+        }
+        Ok(result)
+    }
+
+    /// Converts `statement`, which may consume the statements that the
+    /// printer emits before it.
+    fn statement(
+        &mut self,
+        statement: &Stmt,
+        previous: &mut Vec<vir_legacy::Stmt>,
+    ) -> Result<vir_legacy::Stmt> {
+        use vir_legacy::Stmt as S;
+        let position = position(&statement.annotation)?;
+        let statement = match &statement.kind {
+            StmtKind::Comment(comment) => S::Comment(comment.clone()),
+            StmtKind::Label(label) => {
+                if !self.labels.insert(label.clone()) {
+                    return invalid(format!("the label {label} is not fresh"));
+                }
+                S::Label(label.clone())
+            }
+            StmtKind::Inhale(expr) | StmtKind::Assume(expr) => S::Inhale(self.expr(expr)?),
+            StmtKind::Exhale(expr) => S::Exhale(self.expr(expr)?, position),
+            StmtKind::Assert(expr) => S::Assert(self.expr(expr)?, position),
+            StmtKind::Refute(expr) => S::Refute(self.expr(expr)?, position),
+            StmtKind::Fold(acc) => {
+                let (name, arguments, permission) = self.predicate_access(acc)?;
+                S::Fold(name, arguments, permission, None, position)
+            }
+            StmtKind::Unfold(acc) => {
+                let (name, arguments, permission) = self.predicate_access(acc)?;
+                S::Unfold(name, arguments, permission, None)
+            }
+            StmtKind::Apply(wand) => {
+                let wand = self.expr(wand)?;
+                let token_inhaled = matches!(
+                    (previous.last(), &wand),
+                    (
+                        Some(S::Inhale(vir_legacy::Expr::PredicateAccessPredicate(name, ..))),
+                        vir_legacy::Expr::MagicWand(_, _, Some(_), _),
+                    ) if name == DEAD_BORROW_TOKEN
+                );
+                if !token_inhaled {
+                    return invalid("applying a magic wand without inhaling its dead borrow token");
+                }
+                previous.pop();
+                S::ApplyMagicWand(wand, position)
+            }
+            StmtKind::Package(wand, body) => {
+                let wand = self.expr(wand)?;
+                let label = match previous.last() {
+                    Some(S::Label(label)) => label.clone(),
+                    _ => String::new(),
+                };
+                let mut variables = Vec::new();
+                let mut body = body.as_slice();
+                while let [Stmt {
+                    kind: StmtKind::Var(variable),
+                    ..
+                }, rest @ ..] = body
+                {
+                    variables.push(self::variable(variable)?);
+                    body = rest;
+                }
+                let body = self.scoped(&variables, |lifting| lifting.package_statements(body))?;
+                S::PackageMagicWand(wand, body, label, variables, position)
+            }
+            StmtKind::Assign(target, value) => {
+                let target = self.expr(target)?;
+                let kind = match target.get_maybe_type() {
+                    Some(vir_legacy::Type::Ref | vir_legacy::Type::TypedRef(_)) => AssignKind::Move,
+                    _ => AssignKind::Copy,
+                };
+                S::Assign(target, self.expr(value)?, kind)
+            }
+            StmtKind::Call(targets, method_name, arguments) => {
+                let targets = targets
+                    .iter()
+                    .map(|target| match self.expr(target)? {
+                        vir_legacy::Expr::Local(variable, _) => Ok(variable),
+                        _ => invalid(format!("the call target {target} is not a variable")),
+                    })
+                    .collect::<Result<_>>()?;
+                S::MethodCall(method_name.clone(), self.exprs(arguments)?, targets)
+            }
+            StmtKind::If(guard, then_branch, else_branch) => S::If(
+                self.expr(guard)?,
+                self.statements(then_branch)?,
+                self.statements(else_branch)?,
+            ),
+            StmtKind::Var(variable) => {
+                return invalid(format!(
+                    "the declaration of {} inside a block",
+                    variable.name
+                ))
+            }
+            StmtKind::Goto(label) => return invalid(format!("the jump to {label} inside a block")),
+        };
+        Ok(statement)
+    }
+
+    /// Converts the body of a package statement, dropping the footprint
+    /// assertions that the printer adds.
+    fn package_statements(&mut self, statements: &[Stmt]) -> Result<Vec<vir_legacy::Stmt>> {
+        let mut result = Vec::new();
+        for statement in statements {
+            match &statement.kind {
+                StmtKind::Assert(_) if statement.annotation.is_none() => {}
+                StmtKind::If(guard, then_branch, else_branch) => {
+                    let statement = vir_legacy::Stmt::If(
+                        self.expr(guard)?,
+                        self.package_statements(then_branch)?,
+                        self.package_statements(else_branch)?,
+                    );
+                    result.push(statement);
+                }
+                _ => {
+                    let statement = self.statement(statement, &mut result)?;
+                    result.push(statement);
+                }
+            }
+        }
+        Ok(result)
+    }
+
+    fn predicate_access(
+        &mut self,
+        acc: &Expr,
+    ) -> Result<(String, Vec<vir_legacy::Expr>, PermAmount)> {
+        if let Expr::Acc(location, permission) = acc {
+            if let Expr::Call(name, arguments) = &**location {
+                if self.predicates.contains(name) {
+                    return Ok((
+                        name.clone(),
+                        self.exprs(arguments)?,
+                        perm_amount(permission)?,
+                    ));
+                }
+            }
+        }
+        invalid(format!("{acc} is not a predicate access"))
+    }
+
+    fn exprs(&mut self, exprs: &[Expr]) -> Result<Vec<vir_legacy::Expr>> {
+        exprs.iter().map(|expr| self.expr(expr)).collect()
+    }
+
+    fn boxed(&mut self, expr: &Expr) -> Result<Box<vir_legacy::Expr>> {
+        self.expr(expr).map(Box::new)
+    }
+
+    fn local(&self, name: &str) -> Result<LocalVar> {
+        self.scopes
+            .iter()
+            .rev()
+            .flatten()
+            .find(|variable| variable.name == name)
+            .cloned()
+            .map_or_else(
+                || invalid(format!("the variable {name} is not declared")),
+                Ok,
+            )
+    }
+
+    fn expr(&mut self, expr: &Expr) -> Result<vir_legacy::Expr> {
+        use vir_legacy::Expr as E;
+        let pos = Position::default();
+        let result = match expr {
+            Expr::Local(name) => E::Local(self.local(name)?, pos),
+            Expr::Result => match &self.result_type {
+                Some(ty) => E::Local(LocalVar::new("__result", ty.clone()), pos),
+                None => return invalid("result outside of a function"),
+            },
+            Expr::Int(value) => match value.parse() {
+                Ok(value) => E::Const(Const::Int(value), pos),
+                Err(_) => E::Const(Const::BigInt(value.clone()), pos),
+            },
+            Expr::Bool(value) => E::Const(Const::Bool(*value), pos),
+            Expr::Null => E::Const(Const::FnPtr, pos),
+            Expr::NoPerm | Expr::FullPerm | Expr::Wildcard => {
+                return unsupported(format!("the permission {expr} outside of an access"))
+            }
+            Expr::Field(base, name) => {
+                let Some(typ) = self.fields.get(name) else {
+                    return invalid(format!("the field {name} is not declared"));
+                };
+                let field = Field::new(name, typ.clone());
+                E::Field(self.boxed(base)?, field, pos)
+            }
+            Expr::Old(Some(label), base) => E::LabelledOld(label.clone(), self.boxed(base)?, pos),
+            Expr::Old(None, _) => return unsupported("old expressions without a label"),
+            Expr::Unary(op, argument) => {
+                let op = match op {
+                    UnaryOp::Not => UnaryOpKind::Not,
+                    UnaryOp::Minus => UnaryOpKind::Minus,
+                };
+                E::UnaryOp(op, self.boxed(argument)?, pos)
+            }
+            Expr::Binary(BinaryOp::Wand, left, right) => {
+                let (borrow, left) = match &**left {
+                    Expr::Binary(BinaryOp::And, token, left) => match dead_borrow_token(token) {
+                        Some(borrow) => (borrow, left),
+                        None => return invalid("a magic wand without a dead borrow token"),
+                    },
+                    _ => return invalid("a magic wand without a dead borrow token"),
+                };
+                E::MagicWand(self.boxed(left)?, self.boxed(right)?, borrow, pos)
+            }
+            Expr::Binary(BinaryOp::Concat, left, right) => E::ContainerOp(
+                ContainerOpKind::SeqConcat,
+                self.boxed(left)?,
+                self.boxed(right)?,
+                pos,
+            ),
+            Expr::Binary(op, left, right) => {
+                let op = match op {
+                    BinaryOp::Implies => BinaryOpKind::Implies,
+                    BinaryOp::Or => BinaryOpKind::Or,
+                    BinaryOp::And => BinaryOpKind::And,
+                    BinaryOp::Eq => BinaryOpKind::EqCmp,
+                    BinaryOp::Ne => BinaryOpKind::NeCmp,
+                    BinaryOp::Lt => BinaryOpKind::LtCmp,
+                    BinaryOp::Le => BinaryOpKind::LeCmp,
+                    BinaryOp::Gt => BinaryOpKind::GtCmp,
+                    BinaryOp::Ge => BinaryOpKind::GeCmp,
+                    BinaryOp::Add => BinaryOpKind::Add,
+                    BinaryOp::Sub => BinaryOpKind::Sub,
+                    BinaryOp::Mul => BinaryOpKind::Mul,
+                    BinaryOp::Div => BinaryOpKind::Div,
+                    BinaryOp::Mod => BinaryOpKind::Mod,
+                    BinaryOp::Wand
+                    | BinaryOp::Concat
+                    | BinaryOp::In
+                    | BinaryOp::Subset
+                    | BinaryOp::Union
+                    | BinaryOp::Intersection
+                    | BinaryOp::Setminus => {
+                        return unsupported(format!("the operator {op} in legacy programs"))
+                    }
+                };
+                E::BinOp(op, self.boxed(left)?, self.boxed(right)?, pos)
+            }
+            Expr::Cond(guard, then_expr, else_expr) => E::Cond(
+                self.boxed(guard)?,
+                self.boxed(then_expr)?,
+                self.boxed(else_expr)?,
+                pos,
+            ),
+            Expr::Acc(location, permission) => {
+                let permission = perm_amount(permission)?;
+                match &**location {
+                    Expr::Call(name, arguments) if self.predicates.contains(name) => {
+                        let [argument] = arguments.as_slice() else {
+                            return invalid(format!(
+                                "the predicate {name} does not have exactly one argument"
+                            ));
+                        };
+                        E::PredicateAccessPredicate(
+                            name.clone(),
+                            self.boxed(argument)?,
+                            permission,
+                            pos,
+                        )
+                    }
+                    Expr::Field(..) => {
+                        E::FieldAccessPredicate(self.boxed(location)?, permission, pos)
+                    }
+                    _ => return invalid(format!("{location} is not a location")),
+                }
+            }
+            Expr::Call(name, arguments) => {
+                let arguments = self.exprs(arguments)?;
+                if let Some((formal_args, return_type)) = self.functions.get(name) {
+                    E::FuncApp(
+                        name.clone(),
+                        arguments,
+                        formal_args.clone(),
+                        return_type.clone(),
+                        pos,
+                    )
+                } else if let Some(function) = self.domain_functions.get(name) {
+                    E::DomainFuncApp(function.clone(), arguments, pos)
+                } else {
+                    return invalid(format!("the function {name} is not declared"));
+                }
+            }
+            Expr::Index(base, index) => E::ContainerOp(
+                ContainerOpKind::SeqIndex,
+                self.boxed(base)?,
+                self.boxed(index)?,
+                pos,
+            ),
+            Expr::Length(base) => E::ContainerOp(
+                ContainerOpKind::SeqLen,
+                self.boxed(base)?,
+                Box::new(E::Const(Const::Int(0), pos)),
+                pos,
+            ),
+            Expr::Update(..) => return unsupported("sequence and map updates in legacy programs"),
+            Expr::Unfolding(acc, body) => {
+                let (name, arguments, permission) = self.predicate_access(acc)?;
+                E::Unfolding(name, arguments, self.boxed(body)?, permission, None, pos)
+            }
+            Expr::Quantifier(quantifier, variables, triggers, body) => {
+                let variables = self::variables(variables)?;
+                self.scoped(&variables, |lifting| {
+                    let triggers = triggers
+                        .iter()
+                        .map(|trigger| Ok(Trigger::new(lifting.exprs(trigger)?)))
+                        .collect::<Result<_>>()?;
+                    let body = lifting.boxed(body)?;
+                    Ok(match quantifier {
+                        Quantifier::Forall => E::ForAll(variables.clone(), triggers, body, pos),
+                        Quantifier::Exists => E::Exists(variables.clone(), triggers, body, pos),
+                    })
+                })?
+            }
+            Expr::Let(name, def, body) => {
+                let def = self.expr(def)?;
+                let Some(typ) = def.get_maybe_type() else {
+                    return invalid(format!("the type of the definition of {name} is unknown"));
+                };
+                let variable = LocalVar::new(name, typ.clone());
+                let body = self.scoped(&[variable.clone()], |lifting| lifting.boxed(body))?;
+                E::LetExpr(variable, Box::new(def), body, pos)
+            }
+            Expr::InhaleExhale(inhale, exhale) => {
+                E::InhaleExhale(self.boxed(inhale)?, self.boxed(exhale)?, pos)
+            }
+            Expr::Empty(Collection::Seq, types) => match types.as_slice() {
+                [element_type] => E::Seq(seq_type(ty(element_type)?), Vec::new(), pos),
+                _ => return invalid("an empty sequence without an element type"),
+            },
+            Expr::Empty(Collection::Map, types) => match types.as_slice() {
+                [key_type, val_type] => E::Map(
+                    vir_legacy::Type::Map(Box::new(ty(key_type)?), Box::new(ty(val_type)?)),
+                    Vec::new(),
+                    pos,
+                ),
+                _ => return invalid("an empty map without key and value types"),
+            },
+            Expr::Explicit(Collection::Seq, elements) => {
+                let elements = self.exprs(elements)?;
+                let Some(element_type) = elements
+                    .first()
+                    .and_then(|element| element.get_maybe_type())
+                else {
+                    return invalid("a sequence literal whose element type is unknown");
+                };
+                E::Seq(seq_type(element_type.clone()), elements, pos)
+            }
+            Expr::Empty(..) | Expr::Explicit(..) => {
+                return unsupported(format!("the collection {expr} in legacy programs"))
+            }
+        };
+        Ok(result)
+    }
+}
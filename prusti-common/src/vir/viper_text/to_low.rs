@@ -0,0 +1,831 @@
+// © 2023, ETH Zurich
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! Conversion of a parsed program back into `vir::low`.
+
+use super::{
+    invalid,
+    syntax::{
+        find_annotation, has_blocks, parse_position, split_blocks, Annotation, BinaryOp,
+        BlockSuccessor, Collection, Expr, Program, Quantifier, Stmt, StmtKind, Type, UnaryOp,
+        VarDecl,
+    },
+    unsupported, Result,
+};
+use std::collections::HashMap;
+use vir::{
+    common::{check_mode::CheckMode, position::Position},
+    low::{
+        self as vir_low,
+        ast::{expression, statement, ty},
+        operations::ty::Typed,
+        ConstantValue, ContainerOpKind, Expression, FunctionKind, MethodKind, Statement,
+        VariableDecl,
+    },
+};
+
+pub(super) fn program(program: &Program) -> Result<vir_low::Program> {
+    if !program.fields.is_empty() {
+        return invalid("vir::low programs have no fields");
+    }
+    let mut lowering = Lowering {
+        functions: HashMap::new(),
+        domain_functions: HashMap::new(),
+        predicates: HashMap::new(),
+        scopes: Vec::new(),
+        result_type: None,
+    };
+    for function in &program.functions {
+        let signature = lowering.signature(&function.parameters, &function.return_type)?;
+        lowering.functions.insert(function.name.clone(), signature);
+    }
+    for domain in &program.domains {
+        if !domain.type_vars.is_empty() {
+            return invalid(format!("the domain {} has type parameters", domain.name));
+        }
+        for function in &domain.functions {
+            let signature = lowering.signature(&function.parameters, &function.return_type)?;
+            lowering
+                .domain_functions
+                .insert(function.name.clone(), (domain.name.clone(), signature));
+        }
+    }
+    for predicate in &program.predicates {
+        lowering
+            .predicates
+            .insert(predicate.name.clone(), variables(&predicate.parameters)?);
+    }
+    let check_mode = match find_annotation(&program.annotations, "check_mode") {
+        None | Some("CoreProof") => CheckMode::CoreProof,
+        Some("Specifications") => CheckMode::Specifications,
+        Some("Both") => CheckMode::Both,
+        Some(check_mode) => return invalid(format!("unknown check mode {check_mode}")),
+    };
+    let mut methods = Vec::new();
+    let mut procedures = Vec::new();
+    for method in &program.methods {
+        match &method.body {
+            Some(body) if has_blocks(body) => {
+                if !method.parameters.is_empty()
+                    || !method.returns.is_empty()
+                    || !method.pres.is_empty()
+                    || !method.posts.is_empty()
+                {
+                    return invalid(format!(
+                        "the procedure {} has a signature or a specification",
+                        method.name
+                    ));
+                }
+                procedures.push(lowering.procedure(&method.name, body)?);
+            }
+            _ => methods.push(lowering.method(method)?),
+        }
+    }
+    Ok(vir_low::Program {
+        name: find_annotation(&program.annotations, "program")
+            .unwrap_or("program")
+            .to_string(),
+        check_mode,
+        domains: program
+            .domains
+            .iter()
+            .map(|domain| lowering.domain(domain))
+            .collect::<Result<_>>()?,
+        predicates: program
+            .predicates
+            .iter()
+            .map(|predicate| {
+                let parameters = variables(&predicate.parameters)?;
+                lowering.scoped(&parameters, |lowering| {
+                    Ok(vir_low::PredicateDecl {
+                        name: predicate.name.clone(),
+                        parameters: parameters.clone(),
+                        body: predicate
+                            .body
+                            .as_ref()
+                            .map(|body| lowering.expr(body))
+                            .transpose()?,
+                    })
+                })
+            })
+            .collect::<Result<_>>()?,
+        functions: program
+            .functions
+            .iter()
+            .map(|function| lowering.function(function))
+            .collect::<Result<_>>()?,
+        methods,
+        procedures,
+    })
+}
+
+struct Signature {
+    parameters: Vec<VariableDecl>,
+    return_type: vir_low::Type,
+}
+
+struct Lowering {
+    functions: HashMap<String, Signature>,
+    /// The domain of each domain function and its signature.
+    domain_functions: HashMap<String, (String, Signature)>,
+    predicates: HashMap<String, Vec<VariableDecl>>,
+    scopes: Vec<Vec<VariableDecl>>,
+    /// The return type of the function whose body or specification is
+    /// converted.
+    result_type: Option<vir_low::Type>,
+}
+
+fn variables(variables: &[VarDecl]) -> Result<Vec<VariableDecl>> {
+    variables.iter().map(variable).collect()
+}
+
+fn variable(variable: &VarDecl) -> Result<VariableDecl> {
+    Ok(VariableDecl {
+        name: variable.name.clone(),
+        ty: ty(&variable.ty)?,
+    })
+}
+
+fn ty(ty: &Type) -> Result<vir_low::Type> {
+    let boxed = |element_type: &Type| self::ty(element_type).map(Box::new);
+    let ty = match ty {
+        Type::Int => vir_low::Type::Int,
+        Type::Bool => vir_low::Type::Bool,
+        Type::Perm => vir_low::Type::Perm,
+        Type::Ref => vir_low::Type::Ref,
+        Type::Seq(element_type) => vir_low::Type::Seq(ty::Seq {
+            element_type: boxed(element_type)?,
+        }),
+        Type::Set(element_type) => vir_low::Type::Set(ty::Set {
+            element_type: boxed(element_type)?,
+        }),
+        Type::Multiset(element_type) => vir_low::Type::MultiSet(ty::MultiSet {
+            element_type: boxed(element_type)?,
+        }),
+        Type::Map(key_type, val_type) => vir_low::Type::Map(ty::Map {
+            key_type: boxed(key_type)?,
+            val_type: boxed(val_type)?,
+        }),
+        Type::Domain(name) => vir_low::Type::Domain(ty::Domain { name: name.clone() }),
+    };
+    Ok(ty)
+}
+
+fn position(annotation: &Option<Annotation>) -> Result<Position> {
+    Ok(parse_position(annotation)?
+        .map(|(line, column, id)| Position::new(line, column, id))
+        .unwrap_or_default())
+}
+
+fn is_log_event(annotation: &Option<Annotation>) -> bool {
+    matches!(annotation, Some(annotation) if annotation.key == "log_event")
+}
+
+fn constant(value: ConstantValue, ty: vir_low::Type) -> Expression {
+    Expression::constant_no_pos(value, ty)
+}
+
+fn container_op(
+    kind: ContainerOpKind,
+    container_type: vir_low::Type,
+    operands: Vec<Expression>,
+) -> Expression {
+    Expression::ContainerOp(expression::ContainerOp {
+        kind,
+        container_type,
+        operands,
+        position: Position::default(),
+    })
+}
+
+impl Lowering {
+    fn signature(&self, parameters: &[VarDecl], return_type: &Type) -> Result<Signature> {
+        Ok(Signature {
+            parameters: variables(parameters)?,
+            return_type: ty(return_type)?,
+        })
+    }
+
+    fn scoped<T>(
+        &mut self,
+        variables: &[VariableDecl],
+        f: impl FnOnce(&mut Self) -> Result<T>,
+    ) -> Result<T> {
+        self.scopes.push(variables.to_vec());
+        let result = f(self);
+        self.scopes.pop();
+        result
+    }
+
+    fn lookup(&self, name: &str) -> Result<VariableDecl> {
+        self.scopes
+            .iter()
+            .rev()
+            .flat_map(|scope| scope.iter().rev())
+            .find(|variable| variable.name == name)
+            .cloned()
+            .map_or_else(|| invalid(format!("undeclared variable {name}")), Ok)
+    }
+
+    fn domain(&mut self, domain: &super::syntax::Domain) -> Result<vir_low::DomainDecl> {
+        let mut functions = Vec::new();
+        for function in &domain.functions {
+            functions.push(vir_low::DomainFunctionDecl {
+                name: function.name.clone(),
+                is_unique: function.unique,
+                parameters: variables(&function.parameters)?,
+                return_type: ty(&function.return_type)?,
+            });
+        }
+        let mut axioms = Vec::new();
+        for axiom in &domain.axioms {
+            axioms.push(vir_low::DomainAxiomDecl {
+                comment: axiom.comment.clone(),
+                name: axiom.name.clone(),
+                body: self.expr(&axiom.body)?,
+            });
+        }
+        Ok(vir_low::DomainDecl {
+            name: domain.name.clone(),
+            functions,
+            axioms,
+        })
+    }
+
+    fn function(&mut self, function: &super::syntax::Function) -> Result<vir_low::FunctionDecl> {
+        let kind = match find_annotation(&function.annotations, "kind") {
+            Some("MemoryBlockBytes") => FunctionKind::MemoryBlockBytes,
+            None | Some("CallerFor") => FunctionKind::CallerFor,
+            Some(kind) => return invalid(format!("unknown function kind {kind}")),
+        };
+        let parameters = variables(&function.parameters)?;
+        let return_type = ty(&function.return_type)?;
+        self.result_type = Some(return_type.clone());
+        let result = self.scoped(&parameters, |lowering| {
+            Ok(vir_low::FunctionDecl {
+                name: function.name.clone(),
+                kind,
+                parameters: parameters.clone(),
+                return_type,
+                pres: lowering.exprs(&function.pres)?,
+                posts: lowering.exprs(&function.posts)?,
+                body: function
+                    .body
+                    .as_ref()
+                    .map(|body| lowering.expr(body))
+                    .transpose()?,
+            })
+        });
+        self.result_type = None;
+        result
+    }
+
+    fn method(&mut self, method: &super::syntax::Method) -> Result<vir_low::MethodDecl> {
+        let kind = match find_annotation(&method.annotations, "kind") {
+            Some("LowMemoryOperation") => MethodKind::LowMemoryOperation,
+            None | Some("MirOperation") => MethodKind::MirOperation,
+            Some("Havoc") => MethodKind::Havoc,
+            Some(kind) => return invalid(format!("unknown method kind {kind}")),
+        };
+        let parameters = variables(&method.parameters)?;
+        let targets = variables(&method.returns)?;
+        let scope: Vec<_> = parameters.iter().chain(&targets).cloned().collect();
+        self.scoped(&scope, |lowering| {
+            Ok(vir_low::MethodDecl {
+                name: method.name.clone(),
+                kind,
+                parameters: parameters.clone(),
+                targets: targets.clone(),
+                pres: lowering.exprs(&method.pres)?,
+                posts: lowering.exprs(&method.posts)?,
+                body: method
+                    .body
+                    .as_deref()
+                    .map(|body| lowering.statements(body))
+                    .transpose()?,
+            })
+        })
+    }
+
+    fn procedure(&mut self, name: &str, body: &[Stmt]) -> Result<vir_low::ProcedureDecl> {
+        let (locals, blocks) = split_blocks(body)?;
+        let locals = locals
+            .into_iter()
+            .map(variable)
+            .collect::<Result<Vec<_>>>()?;
+        self.scoped(&locals, |lowering| {
+            let mut basic_blocks = Vec::new();
+            for block in blocks {
+                let label = |name: &str| vir_low::Label {
+                    name: name.to_string(),
+                };
+                let successor = match block.successor {
+                    BlockSuccessor::Return => vir_low::Successor::Return,
+                    BlockSuccessor::Goto(target) => vir_low::Successor::Goto(label(target)),
+                    BlockSuccessor::Switch(targets, None) => vir_low::Successor::GotoSwitch(
+                        targets
+                            .into_iter()
+                            .map(|(guard, target)| Ok((lowering.expr(guard)?, label(target))))
+                            .collect::<Result<_>>()?,
+                    ),
+                    BlockSuccessor::Switch(_, Some(_)) => {
+                        return invalid(format!(
+                            "the switch of block {} has a default target",
+                            block.label
+                        ))
+                    }
+                };
+                let statements = block
+                    .statements
+                    .into_iter()
+                    .map(|statement| lowering.statement(statement))
+                    .collect::<Result<_>>()?;
+                basic_blocks.push(vir_low::BasicBlock {
+                    label: label(block.label),
+                    statements,
+                    successor,
+                });
+            }
+            Ok(vir_low::ProcedureDecl {
+                name: name.to_string(),
+                locals: locals.clone(),
+                basic_blocks,
+            })
+        })
+    }
+
+    fn statements(&mut self, statements: &[Stmt]) -> Result<Vec<Statement>> {
+        statements
+            .iter()
+            .map(|statement| self.statement(statement))
+            .collect()
+    }
+
+    fn statement(&mut self, statement: &Stmt) -> Result<Statement> {
+        let position = position(&statement.annotation)?;
+        let statement = match &statement.kind {
+            StmtKind::Comment(comment) => Statement::Comment(statement::Comment {
+                comment: comment.clone(),
+            }),
+            StmtKind::Inhale(expression) if is_log_event(&statement.annotation) => {
+                Statement::LogEvent(statement::LogEvent {
+                    expression: self.expr(expression)?,
+                })
+            }
+            StmtKind::Inhale(expression) => Statement::Inhale(statement::Inhale {
+                expression: self.expr(expression)?,
+                position,
+            }),
+            StmtKind::Exhale(expression) => Statement::Exhale(statement::Exhale {
+                expression: self.expr(expression)?,
+                position,
+            }),
+            StmtKind::Assert(expression) => Statement::Assert(statement::Assert {
+                expression: self.expr(expression)?,
+                position,
+            }),
+            StmtKind::Assume(expression) => Statement::Assume(statement::Assume {
+                expression: self.expr(expression)?,
+                position,
+            }),
+            StmtKind::Fold(expression) => Statement::Fold(statement::Fold {
+                expression: self.expr(expression)?,
+                position,
+            }),
+            StmtKind::Unfold(expression) => Statement::Unfold(statement::Unfold {
+                expression: self.expr(expression)?,
+                position,
+            }),
+            StmtKind::Apply(expression) => Statement::ApplyMagicWand(statement::ApplyMagicWand {
+                expression: self.expr(expression)?,
+                position,
+            }),
+            StmtKind::Call(targets, method_name, arguments) => {
+                Statement::MethodCall(statement::MethodCall {
+                    method_name: method_name.clone(),
+                    arguments: self.exprs(arguments)?,
+                    targets: self.exprs(targets)?,
+                    position,
+                })
+            }
+            StmtKind::Assign(Expr::Local(target), value) => Statement::Assign(statement::Assign {
+                target: self.lookup(target)?,
+                value: self.expr(value)?,
+                position,
+            }),
+            StmtKind::If(guard, then_branch, else_branch) => {
+                Statement::Conditional(statement::Conditional {
+                    guard: self.expr(guard)?,
+                    then_branch: self.statements(then_branch)?,
+                    else_branch: self.statements(else_branch)?,
+                    position,
+                })
+            }
+            StmtKind::Var(_)
+            | StmtKind::Label(_)
+            | StmtKind::Goto(_)
+            | StmtKind::Refute(_)
+            | StmtKind::Package(..)
+            | StmtKind::Assign(..) => {
+                return invalid("a statement that vir::low does not have in this position")
+            }
+        };
+        Ok(statement)
+    }
+
+    fn exprs(&mut self, expressions: &[Expr]) -> Result<Vec<Expression>> {
+        expressions
+            .iter()
+            .map(|expression| self.expr(expression))
+            .collect()
+    }
+
+    fn expr(&mut self, expression: &Expr) -> Result<Expression> {
+        self.expr_in(expression, false)
+    }
+
+    fn predicate_access(
+        &mut self,
+        location: &Expr,
+        permission: Expression,
+        inside_trigger: bool,
+    ) -> Result<Expression> {
+        let Expr::Call(name, arguments) = location else {
+            return unsupported("field permissions in vir::low");
+        };
+        if !self.predicates.contains_key(name) {
+            return invalid(format!("unknown predicate {name}"));
+        }
+        let arguments = arguments
+            .iter()
+            .map(|argument| self.expr_in(argument, inside_trigger))
+            .collect::<Result<_>>()?;
+        Ok(Expression::PredicateAccessPredicate(
+            expression::PredicateAccessPredicate {
+                name: name.clone(),
+                arguments,
+                permission: Box::new(permission),
+                position: Position::default(),
+            },
+        ))
+    }
+
+    fn expr_in(&mut self, expression: &Expr, inside_trigger: bool) -> Result<Expression> {
+        let position = Position::default();
+        let expression = match expression {
+            Expr::Local(name) => Expression::Local(expression::Local {
+                variable: self.lookup(name)?,
+                position,
+            }),
+            Expr::Result => {
+                let Some(ty) = self.result_type.clone() else {
+                    return invalid("`result` outside of a function");
+                };
+                Expression::Local(expression::Local {
+                    variable: VariableDecl {
+                        name: "__result".to_string(),
+                        ty,
+                    },
+                    position,
+                })
+            }
+            Expr::Int(value) => match value.parse() {
+                Ok(value) => constant(ConstantValue::Int(value), vir_low::Type::Int),
+                Err(_) => constant(ConstantValue::BigInt(value.clone()), vir_low::Type::Int),
+            },
+            Expr::Bool(value) => constant(ConstantValue::Bool(*value), vir_low::Type::Bool),
+            Expr::NoPerm => constant(ConstantValue::Int(0), vir_low::Type::Perm),
+            Expr::FullPerm => constant(ConstantValue::Int(1), vir_low::Type::Perm),
+            Expr::Wildcard => constant(ConstantValue::Int(-1), vir_low::Type::Perm),
+            Expr::Null | Expr::Field(..) => return unsupported("references in vir::low"),
+            Expr::Old(label, base) => Expression::LabelledOld(expression::LabelledOld {
+                label: label.clone(),
+                base: Box::new(self.expr_in(base, inside_trigger)?),
+                position,
+            }),
+            Expr::Unary(op, argument) => Expression::UnaryOp(expression::UnaryOp {
+                op_kind: match op {
+                    UnaryOp::Not => expression::UnaryOpKind::Not,
+                    UnaryOp::Minus => expression::UnaryOpKind::Minus,
+                },
+                argument: Box::new(self.expr_in(argument, inside_trigger)?),
+                position,
+            }),
+            Expr::Binary(op, left, right) => {
+                let left = self.expr_in(left, inside_trigger)?;
+                let right = self.expr_in(right, inside_trigger)?;
+                binary(*op, left, right)?
+            }
+            Expr::Cond(guard, then_expr, else_expr) => {
+                Expression::Conditional(expression::Conditional {
+                    guard: Box::new(self.expr_in(guard, inside_trigger)?),
+                    then_expr: Box::new(self.expr_in(then_expr, inside_trigger)?),
+                    else_expr: Box::new(self.expr_in(else_expr, inside_trigger)?),
+                    position,
+                })
+            }
+            Expr::Acc(location, permission) => {
+                let permission = self.expr(permission)?;
+                self.predicate_access(location, permission, inside_trigger)?
+            }
+            Expr::Call(name, arguments) => {
+                let arguments = arguments
+                    .iter()
+                    .map(|argument| self.expr_in(argument, inside_trigger))
+                    .collect::<Result<Vec<_>>>()?;
+                if let Some(signature) = self.functions.get(name) {
+                    Expression::FuncApp(expression::FuncApp {
+                        function_name: name.clone(),
+                        arguments,
+                        parameters: signature.parameters.clone(),
+                        return_type: signature.return_type.clone(),
+                        position,
+                    })
+                } else if let Some((domain_name, signature)) = self.domain_functions.get(name) {
+                    Expression::DomainFuncApp(expression::DomainFuncApp {
+                        domain_name: domain_name.clone(),
+                        function_name: name.clone(),
+                        arguments,
+                        parameters: signature.parameters.clone(),
+                        return_type: signature.return_type.clone(),
+                        position,
+                    })
+                } else if inside_trigger && self.predicates.contains_key(name) {
+                    Expression::PredicateAccessPredicate(expression::PredicateAccessPredicate {
+                        name: name.clone(),
+                        arguments,
+                        permission: Box::new(constant(ConstantValue::Int(1), vir_low::Type::Perm)),
+                        position,
+                    })
+                } else {
+                    return invalid(format!("unknown function {name}"));
+                }
+            }
+            Expr::Index(base, index) => {
+                let base = self.expr_in(base, inside_trigger)?;
+                let index = self.expr_in(index, inside_trigger)?;
+                let kind = match base.get_type() {
+                    vir_low::Type::Seq(_) => ContainerOpKind::SeqIndex,
+                    vir_low::Type::Map(_) => ContainerOpKind::MapLookup,
+                    ty => return invalid(format!("indexing a value of type {ty}")),
+                };
+                container_op(kind, base.get_type().clone(), vec![base, index])
+            }
+            Expr::Update(base, key, value) => {
+                let base = self.expr_in(base, inside_trigger)?;
+                if !base.get_type().is_map() {
+                    return invalid(format!("updating a value of type {}", base.get_type()));
+                }
+                let key = self.expr_in(key, inside_trigger)?;
+                let value = self.expr_in(value, inside_trigger)?;
+                container_op(
+                    ContainerOpKind::MapUpdate,
+                    base.get_type().clone(),
+                    vec![base, key, value],
+                )
+            }
+            Expr::Length(base) => {
+                let base = self.expr_in(base, inside_trigger)?;
+                let kind = match base.get_type() {
+                    vir_low::Type::Seq(_) => ContainerOpKind::SeqLen,
+                    vir_low::Type::Map(_) => ContainerOpKind::MapLen,
+                    vir_low::Type::Set(_) => ContainerOpKind::SetCardinality,
+                    vir_low::Type::MultiSet(_) => ContainerOpKind::MultiSetCardinality,
+                    ty => return invalid(format!("the length of a value of type {ty}")),
+                };
+                container_op(kind, base.get_type().clone(), vec![base])
+            }
+            Expr::Unfolding(predicate, body) => {
+                let Expr::Acc(location, permission) = &**predicate else {
+                    return invalid("unfolding of a non-predicate");
+                };
+                let permission = self.expr(permission)?;
+                let Expression::PredicateAccessPredicate(predicate) =
+                    self.predicate_access(location, permission, inside_trigger)?
+                else {
+                    unreachable!();
+                };
+                Expression::Unfolding(expression::Unfolding {
+                    predicate: predicate.name,
+                    arguments: predicate.arguments,
+                    permission: predicate.permission,
+                    base: Box::new(self.expr_in(body, inside_trigger)?),
+                    position,
+                })
+            }
+            Expr::Quantifier(quantifier, variables, triggers, body) => {
+                let variables = self::variables(variables)?;
+                self.scoped(&variables, |lowering| {
+                    let triggers = triggers
+                        .iter()
+                        .map(|terms| {
+                            Ok(vir_low::Trigger {
+                                terms: terms
+                                    .iter()
+                                    .map(|term| lowering.expr_in(term, true))
+                                    .collect::<Result<_>>()?,
+                            })
+                        })
+                        .collect::<Result<_>>()?;
+                    Ok(Expression::Quantifier(expression::Quantifier {
+                        kind: match quantifier {
+                            Quantifier::Forall => expression::QuantifierKind::ForAll,
+                            Quantifier::Exists => expression::QuantifierKind::Exists,
+                        },
+                        variables: variables.clone(),
+                        triggers,
+                        body: Box::new(lowering.expr_in(body, inside_trigger)?),
+                        position,
+                    }))
+                })?
+            }
+            Expr::Let(name, def, body) => {
+                let def = self.expr_in(def, inside_trigger)?;
+                let variable = VariableDecl {
+                    name: name.clone(),
+                    ty: def.get_type().clone(),
+                };
+                let body = self.scoped(&[variable.clone()], |lowering| {
+                    lowering.expr_in(body, inside_trigger)
+                })?;
+                Expression::LetExpr(expression::LetExpr {
+                    variable,
+                    def: Box::new(def),
+                    body: Box::new(body),
+                    position,
+                })
+            }
+            Expr::InhaleExhale(inhale, exhale) => {
+                Expression::InhaleExhale(expression::InhaleExhale {
+                    inhale_expression: Box::new(self.expr_in(inhale, inside_trigger)?),
+                    exhale_expression: Box::new(self.expr_in(exhale, inside_trigger)?),
+                    position,
+                })
+            }
+            Expr::Empty(collection, types) => {
+                let types = types.iter().map(ty).collect::<Result<Vec<_>>>()?;
+                let element_type = |types: &[vir_low::Type]| match types {
+                    [element_type] => Ok(Box::new(element_type.clone())),
+                    _ => invalid(format!("wrong number of type arguments of {collection}")),
+                };
+                let (kind, container_type) = match collection {
+                    Collection::Seq => (
+                        ContainerOpKind::SeqEmpty,
+                        vir_low::Type::Seq(ty::Seq {
+                            element_type: element_type(&types)?,
+                        }),
+                    ),
+                    Collection::Set => (
+                        ContainerOpKind::SetEmpty,
+                        vir_low::Type::Set(ty::Set {
+                            element_type: element_type(&types)?,
+                        }),
+                    ),
+                    Collection::Multiset => (
+                        ContainerOpKind::MultiSetEmpty,
+                        vir_low::Type::MultiSet(ty::MultiSet {
+                            element_type: element_type(&types)?,
+                        }),
+                    ),
+                    Collection::Map => match types.as_slice() {
+                        [key_type, val_type] => (
+                            ContainerOpKind::MapEmpty,
+                            vir_low::Type::Map(ty::Map {
+                                key_type: Box::new(key_type.clone()),
+                                val_type: Box::new(val_type.clone()),
+                            }),
+                        ),
+                        _ => return invalid("wrong number of type arguments of Map"),
+                    },
+                };
+                container_op(kind, container_type, Vec::new())
+            }
+            Expr::Explicit(collection, elements) => {
+                let elements = elements
+                    .iter()
+                    .map(|element| self.expr_in(element, inside_trigger))
+                    .collect::<Result<Vec<_>>>()?;
+                let Some(first) = elements.first() else {
+                    return invalid(format!("an empty {collection} without a type"));
+                };
+                let element_type = Box::new(first.get_type().clone());
+                let (kind, container_type) = match collection {
+                    Collection::Seq => (
+                        ContainerOpKind::SeqConstructor,
+                        vir_low::Type::Seq(ty::Seq { element_type }),
+                    ),
+                    Collection::Set => (
+                        ContainerOpKind::SetConstructor,
+                        vir_low::Type::Set(ty::Set { element_type }),
+                    ),
+                    Collection::Multiset => (
+                        ContainerOpKind::MultiSetConstructor,
+                        vir_low::Type::MultiSet(ty::MultiSet { element_type }),
+                    ),
+                    Collection::Map => return unsupported("map literals in vir::low"),
+                };
+                container_op(kind, container_type, elements)
+            }
+        };
+        Ok(expression)
+    }
+}
+
+fn binary(op: BinaryOp, left: Expression, right: Expression) -> Result<Expression> {
+    let position = Position::default();
+    let left_type = left.get_type().clone();
+    let right_type = right.get_type().clone();
+    let op_kind = match op {
+        BinaryOp::Wand => {
+            return Ok(Expression::MagicWand(expression::MagicWand {
+                left: Box::new(left),
+                right: Box::new(right),
+                position,
+            }))
+        }
+        BinaryOp::Add | BinaryOp::Sub | BinaryOp::Mul | BinaryOp::Div
+            if left_type.is_perm() || right_type.is_perm() =>
+        {
+            let op_kind = match op {
+                BinaryOp::Add => expression::PermBinaryOpKind::Add,
+                BinaryOp::Sub => expression::PermBinaryOpKind::Sub,
+                BinaryOp::Mul => expression::PermBinaryOpKind::Mul,
+                _ => expression::PermBinaryOpKind::Div,
+            };
+            return Ok(Expression::PermBinaryOp(expression::PermBinaryOp {
+                op_kind,
+                left: Box::new(left),
+                right: Box::new(right),
+                position,
+            }));
+        }
+        BinaryOp::Implies => expression::BinaryOpKind::Implies,
+        BinaryOp::Or => expression::BinaryOpKind::Or,
+        BinaryOp::And => expression::BinaryOpKind::And,
+        BinaryOp::Eq => expression::BinaryOpKind::EqCmp,
+        BinaryOp::Ne => expression::BinaryOpKind::NeCmp,
+        BinaryOp::Lt => expression::BinaryOpKind::LtCmp,
+        BinaryOp::Le => expression::BinaryOpKind::LeCmp,
+        BinaryOp::Gt => expression::BinaryOpKind::GtCmp,
+        BinaryOp::Ge => expression::BinaryOpKind::GeCmp,
+        BinaryOp::Add => expression::BinaryOpKind::Add,
+        BinaryOp::Sub => expression::BinaryOpKind::Sub,
+        BinaryOp::Mul => expression::BinaryOpKind::Mul,
+        BinaryOp::Div => expression::BinaryOpKind::Div,
+        BinaryOp::Mod => expression::BinaryOpKind::Mod,
+        BinaryOp::In => {
+            let (kind, operands) = match &right_type {
+                vir_low::Type::Set(_) => (ContainerOpKind::SetContains, vec![left, right]),
+                vir_low::Type::MultiSet(_) => {
+                    (ContainerOpKind::MultiSetContains, vec![left, right])
+                }
+                vir_low::Type::Map(_) => (ContainerOpKind::MapContains, vec![right, left]),
+                ty => return invalid(format!("`in` on a value of type {ty}")),
+            };
+            return Ok(container_op(kind, right_type, operands));
+        }
+        BinaryOp::Concat
+        | BinaryOp::Union
+        | BinaryOp::Intersection
+        | BinaryOp::Setminus
+        | BinaryOp::Subset => {
+            let kind = match (op, &left_type) {
+                (BinaryOp::Concat, vir_low::Type::Seq(_)) => ContainerOpKind::SeqConcat,
+                (BinaryOp::Union, vir_low::Type::Set(_)) => ContainerOpKind::SetUnion,
+                (BinaryOp::Union, vir_low::Type::MultiSet(_)) => ContainerOpKind::MultiSetUnion,
+                (BinaryOp::Intersection, vir_low::Type::Set(_)) => ContainerOpKind::SetIntersection,
+                (BinaryOp::Intersection, vir_low::Type::MultiSet(_)) => {
+                    ContainerOpKind::MultiSetIntersection
+                }
+                (BinaryOp::Setminus, vir_low::Type::Set(_)) => ContainerOpKind::SetMinus,
+                (BinaryOp::Setminus, vir_low::Type::MultiSet(_)) => ContainerOpKind::MultiSetMinus,
+                (BinaryOp::Subset, vir_low::Type::Set(_)) => ContainerOpKind::SetSubset,
+                (BinaryOp::Subset, vir_low::Type::MultiSet(_)) => ContainerOpKind::MultiSetSubset,
+                (op, ty) => return invalid(format!("`{op}` on a value of type {ty}")),
+            };
+            return Ok(container_op(kind, left_type, vec![left, right]));
+        }
+    };
+    if matches!(
+        op_kind,
+        expression::BinaryOpKind::Add
+            | expression::BinaryOpKind::Sub
+            | expression::BinaryOpKind::Mul
+            | expression::BinaryOpKind::Div
+            | expression::BinaryOpKind::Mod
+    ) && left_type != right_type
+    {
+        return invalid(format!(
+            "arithmetic on values of types {left_type} and {right_type}"
+        ));
+    }
+    Ok(Expression::BinaryOp(expression::BinaryOp {
+        op_kind,
+        left: Box::new(left),
+        right: Box::new(right),
+        position,
+    }))
+}
@@ -16,3 +16,34 @@ impl<T, E: ::core::fmt::Debug> ::core::result::Result<T, E> {
     #[requires(matches!(self, Ok(_)))]
     fn unwrap(self) -> T;
 }
+
+#[extern_spec(core::ptr)]
+#[requires(own(src))]
+#[ensures(own(src))]
+unsafe fn read<T>(src: *const T) -> T;
+
+#[extern_spec(core::ptr)]
+#[requires(own(dst))]
+#[ensures(own(dst))]
+unsafe fn write<T>(dst: *mut T, src: T);
+
+// Pointer arithmetic does not access memory, so it does not need the ownership
+// of the memory behind the pointer and does not give the ownership of the
+// memory behind the resulting pointer.
+#[extern_spec]
+impl<T> *const T {
+    #[ensures(count == 0 ==> result == self)]
+    unsafe fn offset(self, count: isize) -> *const T;
+
+    #[ensures(count == 0 ==> result == self)]
+    unsafe fn add(self, count: usize) -> *const T;
+}
+
+#[extern_spec]
+impl<T> *mut T {
+    #[ensures(count == 0 ==> result == self)]
+    unsafe fn offset(self, count: isize) -> *mut T;
+
+    #[ensures(count == 0 ==> result == self)]
+    unsafe fn add(self, count: usize) -> *mut T;
+}
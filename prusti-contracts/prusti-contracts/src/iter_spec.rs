@@ -0,0 +1,216 @@
+//! Specifications of iterators. An iterator that implements `IteratorModel`
+//! describes the elements that it has produced and the ones that it has not
+//! produced yet, which gives a
+//! contract to its `next` method and lets loop invariants talk about the
+//! iterator of a `for` loop with `loop_iterator()`.
+
+use crate::*;
+use core::{
+    iter::{Enumerate, Zip},
+    ops::Range,
+    slice::Iter,
+};
+
+/// The model of an iterator: the sequence of the elements that the
+/// iterator has already produced and the sequence of the elements that it
+/// has not produced yet.
+pub trait IteratorModel: Iterator {
+    /// The number of elements that the iterator still produces.
+    #[pure]
+    fn remaining_len(&self) -> usize;
+
+    /// The element that the iterator produces after `index` further
+    /// elements.
+    #[pure]
+    #[requires(index < self.remaining_len())]
+    fn remaining_at(&self, index: usize) -> Self::Item;
+
+    /// The number of elements that the iterator has produced.
+    #[pure]
+    fn produced_len(&self) -> usize;
+
+    /// The element that the iterator produced after `index` other elements.
+    #[pure]
+    #[requires(index < self.produced_len())]
+    fn produced_at(&self, index: usize) -> Self::Item;
+}
+
+#[extern_spec(core::iter)]
+trait Iterator {
+    #[refine_spec(where Self: IteratorModel, [
+        ensures(old(self.remaining_len()) == 0 ==> (
+            matches!(result, None)
+                && self.remaining_len() == 0
+                && self.produced_len() == old(self.produced_len())
+        )),
+        ensures(old(self.remaining_len()) > 0 ==> (
+            self.remaining_len() == old(self.remaining_len()) - 1
+                && self.produced_len() == old(self.produced_len()) + 1
+                && result === Some(old(self.remaining_at(0)))
+                && self.produced_at(old(self.produced_len())) === old(self.remaining_at(0))
+        )),
+        ensures(forall(|i: usize| i < self.remaining_len() ==>
+            self.remaining_at(i) === old(self.remaining_at(i + 1)))),
+        ensures(forall(|i: usize| i < old(self.produced_len()) ==>
+            self.produced_at(i) === old(self.produced_at(i)))),
+    ])]
+    fn next(&mut self) -> Option<Self::Item>;
+
+    #[refine_spec(where Self: IteratorModel, [
+        ensures(result.remaining_len() == self.remaining_len()),
+        ensures(result.produced_len() == 0),
+        ensures(forall(|i: usize| i < result.remaining_len() ==>
+            result.remaining_at(i) === (i, self.remaining_at(i)))),
+    ])]
+    fn enumerate(self) -> Enumerate<Self>
+    where
+        Self: Sized;
+
+    #[refine_spec(where Self: IteratorModel, U: IteratorModel + IntoIterator<IntoIter = U>, [
+        ensures(self.remaining_len() <= other.remaining_len() ==>
+            result.remaining_len() == self.remaining_len()),
+        ensures(other.remaining_len() <= self.remaining_len() ==>
+            result.remaining_len() == other.remaining_len()),
+        ensures(forall(|i: usize| i < result.remaining_len() ==>
+            result.remaining_at(i) === (self.remaining_at(i), other.remaining_at(i)))),
+    ])]
+    fn zip<U>(self, other: U) -> Zip<Self, U::IntoIter>
+    where
+        Self: Sized,
+        U: IntoIterator;
+}
+
+#[extern_spec]
+impl<I: Iterator> IntoIterator for I {
+    #[ensures(result === self)]
+    fn into_iter(self) -> I;
+}
+
+impl IteratorModel for Range<usize> {
+    #[pure]
+    fn remaining_len(&self) -> usize {
+        if self.start < self.end {
+            self.end - self.start
+        } else {
+            0
+        }
+    }
+
+    #[pure]
+    fn remaining_at(&self, index: usize) -> usize {
+        self.start + index
+    }
+
+    // A range does not remember where it started, so it is modelled as if it
+    // started at 0. This is exact for the common `0..n`.
+
+    #[pure]
+    fn produced_len(&self) -> usize {
+        self.start
+    }
+
+    #[pure]
+    fn produced_at(&self, index: usize) -> usize {
+        index
+    }
+}
+
+#[extern_spec]
+impl<T> [T] {
+    #[ensures(result.as_slice() === self)]
+    fn iter(&self) -> Iter<'_, T>;
+}
+
+#[extern_spec]
+impl<'a, T> Iter<'a, T> {
+    #[pure]
+    fn as_slice(&self) -> &'a [T];
+}
+
+impl<'a, T> IteratorModel for Iter<'a, T> {
+    #[pure]
+    fn remaining_len(&self) -> usize {
+        self.as_slice().len()
+    }
+
+    #[pure]
+    fn remaining_at(&self, index: usize) -> &'a T {
+        &self.as_slice()[index]
+    }
+
+    // The elements that a slice iterator has produced are not part of its
+    // state, so they are only constrained by the specification of `next`.
+
+    #[pure]
+    #[trusted]
+    fn produced_len(&self) -> usize {
+        unimplemented!()
+    }
+
+    #[pure]
+    #[trusted]
+    fn produced_at(&self, _index: usize) -> &'a T {
+        unimplemented!()
+    }
+}
+
+// The fields of `Enumerate` and `Zip` are private, so their models are
+// abstract. They are constrained by the specifications of `enumerate`, `zip`
+// and `next`, and by the postconditions below, which follow from the
+// counter that `Enumerate` keeps.
+
+impl<I: IteratorModel> IteratorModel for Enumerate<I> {
+    #[pure]
+    #[trusted]
+    fn remaining_len(&self) -> usize {
+        unimplemented!()
+    }
+
+    #[pure]
+    #[trusted]
+    #[ensures(result.0 == self.produced_len() + index)]
+    #[allow(unused_variables)]
+    fn remaining_at(&self, index: usize) -> (usize, I::Item) {
+        unimplemented!()
+    }
+
+    #[pure]
+    #[trusted]
+    fn produced_len(&self) -> usize {
+        unimplemented!()
+    }
+
+    #[pure]
+    #[trusted]
+    #[ensures(result.0 == index)]
+    #[allow(unused_variables)]
+    fn produced_at(&self, index: usize) -> (usize, I::Item) {
+        unimplemented!()
+    }
+}
+
+impl<A: IteratorModel, B: IteratorModel> IteratorModel for Zip<A, B> {
+    #[pure]
+    #[trusted]
+    fn remaining_len(&self) -> usize {
+        unimplemented!()
+    }
+
+    #[pure]
+    #[trusted]
+    fn remaining_at(&self, _index: usize) -> (A::Item, B::Item) {
+        unimplemented!()
+    }
+
+    #[pure]
+    #[trusted]
+    fn produced_len(&self) -> usize {
+        unimplemented!()
+    }
+
+    #[pure]
+    #[trusted]
+    fn produced_at(&self, _index: usize) -> (A::Item, B::Item) {
+        unimplemented!()
+    }
+}
@@ -21,6 +21,9 @@ pub use prusti_contracts_proc_macros::trusted;
 /// A macro for marking a function as opted into verification.
 pub use prusti_contracts_proc_macros::verified;
 
+/// A macro for setting the verification timeout of a function, in seconds.
+pub use prusti_contracts_proc_macros::timeout;
+
 /// A macro for type invariants.
 pub use prusti_contracts_proc_macros::invariant;
 
@@ -124,6 +127,8 @@ mod private {
 #[cfg(feature = "prusti")]
 pub mod core_spec;
 
+pub mod iter_spec;
+
 #[cfg(feature = "prusti")]
 mod private {
     use core::{marker::PhantomData, ops::*};
@@ -353,6 +358,29 @@ pub fn exists<T, F>(_trigger_set: T, _closure: F) -> bool {
     true
 }
 
+/// The iterator of the `for` loop whose body contains the `body_invariant!`
+/// that calls this function. `I` is the type of the iterator, e.g.
+/// `loop_iterator::<Range<usize>>()` in `for i in 0..n`.
+pub fn loop_iterator<I>() -> I {
+    unimplemented!()
+}
+
+/// Calls through the function pointer `function` satisfy the contract of the
+/// function `spec`. This holds for the pointers created from `spec`, e.g.
+/// `satisfies_spec(f, increment)` after `let f: fn(u32) -> u32 = increment;`.
+pub fn satisfies_spec<F, S>(_function: F, _spec: S) -> bool {
+    true
+}
+
+/// Ownership of the memory that `pointer` points to. A precondition
+/// `own(p)` transfers the ownership of `*p` from the caller to the callee, a
+/// postcondition `own(p)` transfers it back. Only supported with
+/// `unsafe_core_proof` and only as a conjunct of a specification, not under
+/// an implication or a quantifier.
+pub fn own<T: ?Sized>(_pointer: *const T) -> bool {
+    true
+}
+
 /// Creates an owned copy of a reference. This should only be used from within
 /// ghost code, as it circumvents the borrow checker.
 pub fn snap<T>(_x: &T) -> T {
@@ -368,4 +396,5 @@ pub fn snapshot_equality<T>(_l: T, _r: T) -> bool {
     true
 }
 
+pub use iter_spec::IteratorModel;
 pub use private::*;
@@ -125,6 +125,8 @@ impl AstRewriter {
 
         spec_item.sig.generics = item.sig().generics.clone();
         spec_item.sig.inputs = item.sig().inputs.clone();
+        // Specifications of unsafe functions may dereference raw pointers.
+        spec_item.sig.unsafety = item.sig().unsafety;
         match spec_type {
             SpecItemType::Postcondition | SpecItemType::Pledge => {
                 let fn_arg = self.generate_result_arg(item);
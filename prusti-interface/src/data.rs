@@ -7,7 +7,9 @@
 //! This module defines data structures exchanged between a verifier and
 //! its environment.
 
+use crate::PrustiError;
 use prusti_rustc_interface::{hir::def_id::DefId, middle::ty};
+use rustc_hash::FxHashMap;
 
 /// A unique identifier of the Rust procedure.
 pub type ProcedureDefId = DefId;
@@ -21,12 +23,74 @@ pub struct VerificationTask<'tcx> {
     pub types: Vec<ty::Ty<'tcx>>,
 }
 
-/// Verification result returned by a verifier.
-#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
-pub enum VerificationResult {
+/// The verification result of a single procedure or type.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum ItemVerificationResult {
     /// Verification was successful.
-    Success,
-    /// Verification failed. Errors should have been already emitted by
-    /// the verifier.
-    Failure,
+    Verified,
+    /// Verification was successful in an earlier run and the result was taken
+    /// from the verification cache, or the item was not verified again because
+    /// it did not change since then.
+    Cached,
+    /// Verification failed with the given errors. The errors have already
+    /// been emitted by the verifier.
+    Failed(Vec<PrustiError>),
+    /// The item uses features that are not supported by Prusti. The errors
+    /// have already been emitted by the verifier; they are warnings if
+    /// unsupported features are skipped.
+    Unsupported(Vec<PrustiError>),
+    /// The verification of the item did not finish within its timeout. The
+    /// errors have already been emitted by the verifier; they are warnings if
+    /// timeouts are reported as warnings.
+    TimedOut(Vec<PrustiError>),
+    /// The item was not verified, e.g., because it is trusted.
+    Skipped,
+}
+
+impl ItemVerificationResult {
+    pub fn is_success(&self) -> bool {
+        match self {
+            ItemVerificationResult::Verified
+            | ItemVerificationResult::Cached
+            | ItemVerificationResult::Skipped => true,
+            ItemVerificationResult::Failed(_) => false,
+            ItemVerificationResult::Unsupported(errors)
+            | ItemVerificationResult::TimedOut(errors) => {
+                !errors.iter().any(|error| error.is_error())
+            }
+        }
+    }
+
+    /// The errors reported for the item.
+    pub fn errors(&self) -> &[PrustiError] {
+        match self {
+            ItemVerificationResult::Failed(errors)
+            | ItemVerificationResult::Unsupported(errors)
+            | ItemVerificationResult::TimedOut(errors) => errors,
+            _ => &[],
+        }
+    }
+}
+
+/// Verification result returned by a verifier.
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+pub struct VerificationResult<'tcx> {
+    /// The result of each verified procedure.
+    pub procedures: FxHashMap<ProcedureDefId, ItemVerificationResult>,
+    /// The result of each verified type.
+    pub types: FxHashMap<ty::Ty<'tcx>, ItemVerificationResult>,
+    /// Errors that could not be attributed to a single procedure or type.
+    /// The errors have already been emitted by the verifier.
+    pub other_errors: Vec<PrustiError>,
+}
+
+impl<'tcx> VerificationResult<'tcx> {
+    /// Whether all items were verified successfully.
+    pub fn is_success(&self) -> bool {
+        self.procedures
+            .values()
+            .chain(self.types.values())
+            .all(ItemVerificationResult::is_success)
+            && !self.other_errors.iter().any(|error| error.is_error())
+    }
 }
@@ -294,6 +294,28 @@ impl<'tcx> EnvBody<'tcx> {
         self.set_monomorphised(def_id, substs, Some(caller_def_id), body)
     }
 
+    /// Get the MIR body of a local pure function, predicate, spec or closure,
+    /// without any substitutions. Returns `None` for other items, including
+    /// impure functions.
+    pub fn try_get_local_body_identity(&self, def_id: LocalDefId) -> Option<MirBody<'tcx>> {
+        let preloaded = [
+            &self.pure_fns,
+            &self.predicates,
+            &self.specs,
+            &self.closures,
+        ];
+        if let Some(body) = preloaded
+            .into_iter()
+            .find_map(|bodies| bodies.local.get(&def_id))
+        {
+            return Some(body.clone());
+        }
+        if self.tcx.is_closure(def_id.to_def_id()) {
+            return Some(self.get_closure_body_identity(def_id.to_def_id()));
+        }
+        None
+    }
+
     /// Get Polonius facts of a local procedure.
     pub fn local_mir_borrowck_facts(&self, def_id: LocalDefId) -> Rc<BorrowckFacts> {
         self.try_get_local_mir_borrowck_facts(def_id).unwrap()
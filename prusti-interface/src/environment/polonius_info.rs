@@ -585,13 +585,18 @@ fn get_borrowed_places<'a, 'tcx: 'a>(
                     })
                     .collect()),
 
-                // slice creation involves an unsize pointer cast like &[i32; 3] -> &[i32]
+                // slice creation involves an unsize pointer cast like &[i32; 3] -> &[i32],
+                // trait object creation one like &mut T -> &mut dyn Trait
                 &mir::Rvalue::Cast(
                     mir::CastKind::PointerCoercion(ty::adjustment::PointerCoercion::Unsize),
                     ref operand,
                     ref cast_ty,
-                ) if cast_ty.is_slice_ref() => {
-                    trace!("slice: operand={:?}, ty={:?}", operand, cast_ty);
+                ) if cast_ty.is_slice_ref()
+                    || cast_ty
+                        .builtin_deref(true)
+                        .map_or(false, |pointee| pointee.ty.is_trait()) =>
+                {
+                    trace!("unsize: operand={:?}, ty={:?}", operand, cast_ty);
                     Ok(match operand {
                         mir::Operand::Copy(ref place) | mir::Operand::Move(ref place) => {
                             vec![place]
@@ -83,6 +83,15 @@ impl<'tcx> EnvQuery<'tcx> {
         crate::utils::has_prusti_attr(self.get_attributes(def_id.into_param()), name)
     }
 
+    /// Read the value of the procedure's `prusti::<name> = "..."` attribute.
+    pub fn get_prusti_attribute(
+        self,
+        def_id: impl IntoParam<ProcedureDefId>,
+        name: &str,
+    ) -> Option<String> {
+        crate::utils::read_prusti_attr(name, self.get_attributes(def_id.into_param()))
+    }
+
     /// Get the span of the given definition.
     pub fn get_def_span(self, def_id: impl IntoParam<DefId>) -> Span {
         self.tcx.def_span(def_id.into_param())
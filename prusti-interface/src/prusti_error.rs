@@ -86,6 +86,19 @@ impl PrustiError {
         error
     }
 
+    /// Report that the verification of the Rust code did not finish in time
+    pub fn timeout<S: ToString>(message: S, span: MultiSpan) -> Self {
+        check_message(message.to_string());
+        let mut error = PrustiError::new(
+            format!("[Prusti: verification timed out] {}", message.to_string()),
+            span,
+        );
+        if config::timeouts_as_warnings() {
+            error.set_warning();
+        }
+        error
+    }
+
     /// Report an unsupported feature of the verified Rust code (e.g. dereferencing raw pointers)
     pub fn unsupported<S: ToString>(message: S, span: MultiSpan) -> Self {
         check_message(message.to_string());
@@ -153,6 +166,14 @@ impl PrustiError {
         matches!(self.kind, PrustiErrorKind::Error)
     }
 
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    pub fn span(&self) -> &MultiSpan {
+        &self.span
+    }
+
     // FIXME: This flag is a temporary workaround for having duplicate errors
     // coming from verifying functions multiple times. We should verify each
     // function only once.
@@ -6,7 +6,11 @@
 #![feature(let_chains)]
 
 use prusti_utils::{config, launch};
-use std::{env, fs, io, path::PathBuf, process::Command};
+use std::{
+    env, fs, io,
+    path::{Path, PathBuf},
+    process::Command,
+};
 
 fn main() {
     if let Err(code) = process(env::args().skip(1)) {
@@ -30,7 +34,24 @@ where
     let args = args.skip_while(|arg| arg == "prusti");
     // Remove the "-- -Pflag" arguments since these won't apply to `cargo check`.
     // They have already been loaded (and the Category B flags are used below).
-    let args = args.take_while(|arg| arg != "--");
+    let mut args: Vec<_> = args.take_while(|arg| arg != "--").collect();
+    // `--verify-only <pattern>` and `--skip <pattern>` select the items to verify.
+    let item_filters = launch::take_item_filter_args(&mut args).map_err(|error| {
+        eprintln!("error: {error}");
+        eprintln!(
+            "usage: cargo prusti {} [<cargo arguments>...]",
+            launch::ITEM_FILTER_USAGE
+        );
+        2
+    })?;
+
+    let cargo_target = env::var("CARGO_TARGET_DIR").unwrap_or_else(|_| "target".to_string());
+    let cargo_target: PathBuf = [cargo_target, "verify".to_string()].into_iter().collect();
+
+    // `cargo prusti cache <action>` manages the verification cache of the crate.
+    if args.first().map(String::as_str) == Some("cache") {
+        return manage_cache(args.into_iter(), &cargo_target);
+    }
 
     // Category B flags (see dev-guide flags table):
     let cargo_path = config::cargo_path();
@@ -41,8 +62,6 @@ where
     } else {
         [].iter()
     };
-    let cargo_target = env::var("CARGO_TARGET_DIR").unwrap_or_else(|_| "target".to_string());
-    let cargo_target: PathBuf = [cargo_target, "verify".to_string()].into_iter().collect();
     let exit_status = Command::new(cargo_path)
         .arg(&command)
         .args(features)
@@ -52,6 +71,7 @@ where
         .env("RUSTC", prusti_rustc_path)
         .env("PRUSTI_CARGO", "")
         .env("CARGO_TARGET_DIR", &cargo_target)
+        .envs(item_filters)
         // Category B flags (update the docs if any more are added):
         .env("PRUSTI_BE_RUSTC", config::be_rustc().to_string())
         .env(
@@ -66,7 +86,11 @@ where
         .env("DEFAULT_PRUSTI_QUIET", "true")
         .env("DEFAULT_PRUSTI_FULL_COMPILATION", "true")
         .env("DEFAULT_PRUSTI_LOG_DIR", cargo_target.join("log"))
-        .env("DEFAULT_PRUSTI_CACHE_PATH", cargo_target.join("cache.bin"))
+        .env("DEFAULT_PRUSTI_CACHE_PATH", cargo_target.join("cache"))
+        .env(
+            "DEFAULT_PRUSTI_INCREMENTAL_DIR",
+            cargo_target.join("prusti-incremental"),
+        )
         .status()
         .expect("could not run cargo");
 
@@ -80,6 +104,35 @@ where
     }
 }
 
+/// Runs `prusti-server cache <action>` on the cache used by `cargo prusti`, or
+/// on the cache of the server at `SERVER_ADDRESS` if set.
+fn manage_cache<I>(mut args: I, cargo_target: &Path) -> Result<(), i32>
+where
+    I: Iterator<Item = String>,
+{
+    let mut prusti_server_path = env::current_exe()
+        .expect("current executable path invalid")
+        .with_file_name("prusti-server");
+    if cfg!(windows) {
+        prusti_server_path.set_extension("exe");
+    }
+    let mut cmd = Command::new(prusti_server_path);
+    cmd.args(args.next());
+    if let Some(server_address) = config::server_address() && server_address != "MOCK" {
+        cmd.arg("--server-address").arg(server_address);
+    }
+    cmd.args(args);
+    let exit_status = cmd
+        .env("DEFAULT_PRUSTI_CACHE_PATH", cargo_target.join("cache"))
+        .status()
+        .expect("could not run prusti-server");
+    if exit_status.success() {
+        Ok(())
+    } else {
+        Err(exit_status.code().unwrap_or(-1))
+    }
+}
+
 /// Copy specs from '{cargo_target}/*/deps/*.specs' to '{cargo_target}/*/*.specs'
 fn copy_exported_specs(cargo_target: PathBuf) -> io::Result<()> {
     for de in fs::read_dir(cargo_target)? {
@@ -0,0 +1,333 @@
+// © 2023, ETH Zurich
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! Conversion of the diagnostics emitted by the compiler into LSP diagnostics.
+
+use lsp_types::{
+    Diagnostic, DiagnosticRelatedInformation, DiagnosticSeverity, Location, Position, Range, Url,
+};
+use serde::Deserialize;
+use std::path::Path;
+
+/// A message printed by `cargo --message-format=json`.
+#[derive(Deserialize)]
+#[serde(tag = "reason", rename_all = "kebab-case")]
+pub enum CargoMessage {
+    CompilerMessage {
+        message: CompilerDiagnostic,
+    },
+    #[serde(other)]
+    Other,
+}
+
+/// A diagnostic in the JSON format of rustc.
+#[derive(Deserialize)]
+pub struct CompilerDiagnostic {
+    message: String,
+    level: String,
+    spans: Vec<DiagnosticSpan>,
+    children: Vec<CompilerDiagnostic>,
+}
+
+#[derive(Deserialize)]
+struct DiagnosticSpan {
+    file_name: String,
+    line_start: u32,
+    line_end: u32,
+    column_start: u32,
+    column_end: u32,
+    is_primary: bool,
+}
+
+impl DiagnosticSpan {
+    fn location(&self, root: &Path) -> Option<Location> {
+        Some(Location {
+            uri: file_url(root, &self.file_name)?,
+            range: range(
+                self.line_start,
+                self.column_start,
+                self.line_end,
+                self.column_end,
+            ),
+        })
+    }
+}
+
+/// A counterexample value reported in a note of a Prusti error.
+pub struct Counterexample {
+    /// The location of the variable.
+    pub range: Range,
+    /// The note, e.g. `counterexample for "x"\n  final value: 5`.
+    pub text: String,
+}
+
+impl Counterexample {
+    pub fn contains(&self, position: Position) -> bool {
+        self.range.start <= position && position <= self.range.end
+    }
+
+    /// A one-line rendering of the counterexample, e.g. `x: 4 → 5`.
+    pub fn summary(&self) -> String {
+        let mut lines = self.text.lines();
+        let name = lines
+            .next()
+            .and_then(|line| line.strip_prefix("counterexample for "))
+            .unwrap_or_default()
+            .trim_matches('"');
+        let mut values = vec![];
+        let mut value = String::new();
+        for line in lines {
+            let line = line.trim();
+            let start = line
+                .strip_prefix("initial value:")
+                .or_else(|| line.strip_prefix("final value:"));
+            if let Some(start) = start {
+                if !value.is_empty() {
+                    values.push(std::mem::take(&mut value));
+                }
+                value.push_str(start.trim());
+            } else {
+                // The value spans multiple lines.
+                if !value.ends_with(['(', '{', '[']) && !line.starts_with([')', '}', ']']) {
+                    value.push(' ');
+                }
+                value.push_str(line);
+            }
+        }
+        values.push(value);
+        format!("{name}: {}", values.join(" → "))
+    }
+}
+
+/// A Prusti error converted to an LSP diagnostic.
+pub struct PrustiDiagnostic {
+    pub location: Location,
+    pub diagnostic: Diagnostic,
+    pub counterexamples: Vec<Counterexample>,
+}
+
+impl CompilerDiagnostic {
+    /// Whether the diagnostic was reported by Prusti rather than by the
+    /// compiler.
+    pub fn is_prusti_error(&self) -> bool {
+        self.message.starts_with("[Prusti")
+    }
+
+    /// The message with its notes and help texts, for diagnostics that cannot
+    /// be located in a source file.
+    pub fn full_message(&self) -> String {
+        let mut message = self.message.clone();
+        for child in &self.children {
+            message.push_str(&format!("\n{}: {}", child.level, child.message));
+        }
+        message
+    }
+
+    /// Converts the diagnostic, whose file paths are relative to `root`. Notes
+    /// and help texts without a location are appended to the message, the
+    /// others become related information.
+    pub fn to_lsp(&self, root: &Path) -> Option<PrustiDiagnostic> {
+        let primary_span = self
+            .spans
+            .iter()
+            .find(|span| span.is_primary)
+            .or_else(|| self.spans.first())?;
+        let location = primary_span.location(root)?;
+        let mut message = self.message.clone();
+        let mut related_information = vec![];
+        let mut counterexamples = vec![];
+        for child in &self.children {
+            let child_location = child.spans.first().and_then(|span| span.location(root));
+            if let Some(child_location) = child_location {
+                if child.message.starts_with("counterexample for ")
+                    && child_location.uri == location.uri
+                {
+                    counterexamples.push(Counterexample {
+                        range: child_location.range,
+                        text: child.message.clone(),
+                    });
+                }
+                related_information.push(DiagnosticRelatedInformation {
+                    location: child_location,
+                    message: child.message.clone(),
+                });
+            } else {
+                message.push_str(&format!("\n{}: {}", child.level, child.message));
+            }
+        }
+        let diagnostic = Diagnostic {
+            range: location.range,
+            severity: Some(severity(&self.level)),
+            source: Some("prusti".to_string()),
+            message,
+            related_information: (!related_information.is_empty()).then_some(related_information),
+            ..Default::default()
+        };
+        Some(PrustiDiagnostic {
+            location,
+            diagnostic,
+            counterexamples,
+        })
+    }
+}
+
+/// The LSP severity of a rustc diagnostic level.
+fn severity(level: &str) -> DiagnosticSeverity {
+    match level {
+        "error" | "error: internal compiler error" => DiagnosticSeverity::ERROR,
+        "warning" => DiagnosticSeverity::WARNING,
+        "note" => DiagnosticSeverity::INFORMATION,
+        _ => DiagnosticSeverity::HINT,
+    }
+}
+
+/// The URL of `file`, a path relative to `root` or an absolute one.
+pub fn file_url(root: &Path, file: &str) -> Option<Url> {
+    Url::from_file_path(root.join(file)).ok()
+}
+
+/// The range between two 1-based positions.
+pub fn range(start_line: u32, start_column: u32, end_line: u32, end_column: u32) -> Range {
+    let position =
+        |line: u32, column: u32| Position::new(line.saturating_sub(1), column.saturating_sub(1));
+    Range::new(
+        position(start_line, start_column),
+        position(end_line, end_column),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn span(file_name: &str, line: u32, is_primary: bool) -> DiagnosticSpan {
+        DiagnosticSpan {
+            file_name: file_name.to_string(),
+            line_start: line,
+            line_end: line,
+            column_start: 5,
+            column_end: 10,
+            is_primary,
+        }
+    }
+
+    fn diagnostic(
+        level: &str,
+        message: &str,
+        spans: Vec<DiagnosticSpan>,
+        children: Vec<CompilerDiagnostic>,
+    ) -> CompilerDiagnostic {
+        CompilerDiagnostic {
+            message: message.to_string(),
+            level: level.to_string(),
+            spans,
+            children,
+        }
+    }
+
+    #[test]
+    fn range_is_zero_based() {
+        assert_eq!(
+            range(3, 5, 4, 10),
+            Range::new(Position::new(2, 4), Position::new(3, 9))
+        );
+        // Positions before the start of the file are clamped.
+        assert_eq!(
+            range(0, 0, 1, 1),
+            Range::new(Position::new(0, 0), Position::new(0, 0))
+        );
+    }
+
+    #[test]
+    fn maps_levels_to_severities() {
+        assert_eq!(severity("error"), DiagnosticSeverity::ERROR);
+        assert_eq!(
+            severity("error: internal compiler error"),
+            DiagnosticSeverity::ERROR
+        );
+        assert_eq!(severity("warning"), DiagnosticSeverity::WARNING);
+        assert_eq!(severity("note"), DiagnosticSeverity::INFORMATION);
+        assert_eq!(severity("help"), DiagnosticSeverity::HINT);
+    }
+
+    #[test]
+    fn locates_the_primary_span() {
+        let root = Path::new("/project");
+        let error = diagnostic(
+            "error",
+            "[Prusti: verification error] postcondition might not hold.",
+            vec![span("src/lib.rs", 1, false), span("src/lib.rs", 7, true)],
+            vec![],
+        );
+        let converted = error.to_lsp(root).unwrap();
+        assert_eq!(
+            converted.location.uri,
+            Url::from_file_path("/project/src/lib.rs").unwrap()
+        );
+        assert_eq!(converted.location.range, range(7, 5, 7, 10));
+        assert_eq!(converted.diagnostic.range, converted.location.range);
+        assert_eq!(
+            converted.diagnostic.severity,
+            Some(DiagnosticSeverity::ERROR)
+        );
+        assert!(diagnostic("error", "no span", vec![], vec![])
+            .to_lsp(root)
+            .is_none());
+    }
+
+    #[test]
+    fn groups_the_notes_by_file() {
+        let root = Path::new("/project");
+        let error = diagnostic(
+            "error",
+            "[Prusti: verification error] assertion might fail.",
+            vec![span("src/lib.rs", 7, true)],
+            vec![
+                diagnostic(
+                    "note",
+                    "counterexample for \"x\"\n  final value: 5",
+                    vec![span("src/lib.rs", 2, true)],
+                    vec![],
+                ),
+                diagnostic(
+                    "note",
+                    "counterexample for \"y\"\n  final value: 6",
+                    vec![span("src/other.rs", 3, true)],
+                    vec![],
+                ),
+                diagnostic("help", "try a loop invariant", vec![], vec![]),
+            ],
+        );
+        let converted = error.to_lsp(root).unwrap();
+        assert_eq!(
+            converted.diagnostic.message,
+            "[Prusti: verification error] assertion might fail.\nhelp: try a loop invariant"
+        );
+        let related = converted.diagnostic.related_information.unwrap();
+        assert_eq!(related.len(), 2);
+        assert_eq!(
+            related[1].location.uri,
+            Url::from_file_path("/project/src/other.rs").unwrap()
+        );
+        // Only the counterexamples in the file of the error are shown there.
+        assert_eq!(converted.counterexamples.len(), 1);
+        assert_eq!(converted.counterexamples[0].range, range(2, 5, 2, 10));
+        assert_eq!(converted.counterexamples[0].summary(), "x: 5");
+    }
+
+    #[test]
+    fn summarizes_counterexamples() {
+        let counterexample = Counterexample {
+            range: range(1, 1, 1, 2),
+            text: "counterexample for \"v\"\n  initial value: Point {\n    x: 1\n  }\n  \
+                   final value: 2"
+                .to_string(),
+        };
+        assert_eq!(counterexample.summary(), "v: Point {x: 1} → 2");
+        assert!(counterexample.contains(Position::new(0, 1)));
+        assert!(!counterexample.contains(Position::new(1, 0)));
+    }
+}
@@ -0,0 +1,346 @@
+// © 2023, ETH Zurich
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! A Language Server Protocol frontend for Prusti.
+//!
+//! The crate of a Rust file is verified when the file is opened for the first
+//! time and whenever it is saved, by running `cargo-prusti` against a shared
+//! Prusti server. Prusti errors are published as diagnostics, verified
+//! functions get a code lens to verify them again, and counterexamples are
+//! shown as hovers and inlay hints.
+
+#![feature(let_chains)]
+
+mod diagnostics;
+mod verification;
+
+use lsp_server::{Connection, ExtractError, Message, Notification, Request, RequestId, Response};
+use lsp_types::{
+    notification::{DidOpenTextDocument, DidSaveTextDocument, Notification as _},
+    request::{CodeLensRequest, ExecuteCommand, HoverRequest, InlayHintRequest, Request as _},
+    CodeLens, CodeLensOptions, Command, ExecuteCommandOptions, Hover, HoverContents,
+    HoverProviderCapability, InitializeParams, InlayHint, InlayHintLabel, InlayHintTooltip,
+    MarkupContent, MarkupKind, OneOf, ServerCapabilities, TextDocumentSyncCapability,
+    TextDocumentSyncOptions, TextDocumentSyncSaveOptions,
+};
+use prusti_utils::{config, launch};
+use std::{
+    collections::HashSet,
+    error::Error,
+    io::{BufRead, BufReader},
+    path::{Path, PathBuf},
+    process::{Child, Command as Process, Stdio},
+    sync::{mpsc, Arc, Mutex},
+    thread,
+};
+use verification::{Results, VerificationTask, Verifier};
+
+/// The command of the "verify this function" code lens. Its arguments are the
+/// definition path of the function and the directory of its crate.
+const VERIFY_FUNCTION_COMMAND: &str = "prusti.verifyFunction";
+
+type LspResult<T> = Result<T, Box<dyn Error + Sync + Send>>;
+
+fn main() -> LspResult<()> {
+    let (connection, io_threads) = Connection::stdio();
+    let params: InitializeParams =
+        serde_json::from_value(connection.initialize(serde_json::to_value(capabilities())?)?)?;
+
+    // The server keeps the JVM running between verifications.
+    let server = if let Some(address) = config::server_address() {
+        ServerProcess::Remote(address)
+    } else {
+        ServerProcess::start().unwrap_or(ServerProcess::None)
+    };
+    let results = Arc::new(Mutex::new(Results::default()));
+    let tasks = Verifier::spawn(
+        connection.sender.clone(),
+        results.clone(),
+        server.address(),
+        &params.capabilities,
+    );
+    main_loop(&connection, &tasks, &results)?;
+
+    drop(tasks);
+    drop(server);
+    io_threads.join()?;
+    Ok(())
+}
+
+fn capabilities() -> ServerCapabilities {
+    ServerCapabilities {
+        text_document_sync: Some(TextDocumentSyncCapability::Options(
+            TextDocumentSyncOptions {
+                open_close: Some(true),
+                save: Some(TextDocumentSyncSaveOptions::Supported(true)),
+                ..Default::default()
+            },
+        )),
+        hover_provider: Some(HoverProviderCapability::Simple(true)),
+        code_lens_provider: Some(CodeLensOptions {
+            resolve_provider: Some(false),
+        }),
+        execute_command_provider: Some(ExecuteCommandOptions {
+            commands: vec![VERIFY_FUNCTION_COMMAND.to_string()],
+            ..Default::default()
+        }),
+        inlay_hint_provider: Some(OneOf::Left(true)),
+        ..Default::default()
+    }
+}
+
+fn main_loop(
+    connection: &Connection,
+    tasks: &mpsc::Sender<VerificationTask>,
+    results: &Mutex<Results>,
+) -> LspResult<()> {
+    // The crates verified so far, to verify each one when its first file is
+    // opened.
+    let mut verified_crates = HashSet::new();
+    for message in &connection.receiver {
+        match message {
+            Message::Request(request) => {
+                if connection.handle_shutdown(&request)? {
+                    return Ok(());
+                }
+                let response = handle_request(request, tasks, &results.lock().unwrap())?;
+                connection.sender.send(Message::Response(response))?;
+            }
+            Message::Notification(notification) => {
+                let (uri, is_save) = match notification.method.as_str() {
+                    DidSaveTextDocument::METHOD => (
+                        extract_notification::<DidSaveTextDocument>(notification)?
+                            .text_document
+                            .uri,
+                        true,
+                    ),
+                    DidOpenTextDocument::METHOD => (
+                        extract_notification::<DidOpenTextDocument>(notification)?
+                            .text_document
+                            .uri,
+                        false,
+                    ),
+                    _ => continue,
+                };
+                let Some(crate_dir) = uri.to_file_path().ok().and_then(|path| crate_dir(&path))
+                else {
+                    continue;
+                };
+                if is_save || verified_crates.insert(crate_dir.clone()) {
+                    tasks.send(VerificationTask {
+                        crate_dir,
+                        function: None,
+                    })?;
+                }
+            }
+            // Responses to the refresh requests sent by the verifier.
+            Message::Response(_) => {}
+        }
+    }
+    Ok(())
+}
+
+fn handle_request(
+    request: Request,
+    tasks: &mpsc::Sender<VerificationTask>,
+    results: &Results,
+) -> LspResult<Response> {
+    let id = request.id.clone();
+    let response = match request.method.as_str() {
+        HoverRequest::METHOD => {
+            let (_, params) = extract_request::<HoverRequest>(request)?;
+            let position = params.text_document_position_params;
+            let hover = results
+                .file(&position.text_document.uri)
+                .and_then(|file| {
+                    file.counterexamples
+                        .iter()
+                        .find(|counterexample| counterexample.contains(position.position))
+                })
+                .map(|counterexample| Hover {
+                    contents: HoverContents::Markup(MarkupContent {
+                        kind: MarkupKind::Markdown,
+                        value: format!("```text\n{}\n```", counterexample.text),
+                    }),
+                    range: Some(counterexample.range),
+                });
+            Response::new_ok(id, hover)
+        }
+        InlayHintRequest::METHOD => {
+            let (_, params) = extract_request::<InlayHintRequest>(request)?;
+            let hints: Vec<_> = results
+                .file(&params.text_document.uri)
+                .into_iter()
+                .flat_map(|file| &file.counterexamples)
+                .filter(|counterexample| {
+                    params.range.start <= counterexample.range.end
+                        && counterexample.range.end <= params.range.end
+                })
+                .map(|counterexample| InlayHint {
+                    position: counterexample.range.end,
+                    label: InlayHintLabel::String(counterexample.summary()),
+                    kind: None,
+                    text_edits: None,
+                    tooltip: Some(InlayHintTooltip::String(counterexample.text.clone())),
+                    padding_left: Some(true),
+                    padding_right: None,
+                    data: None,
+                })
+                .collect();
+            Response::new_ok(id, hints)
+        }
+        CodeLensRequest::METHOD => {
+            let (_, params) = extract_request::<CodeLensRequest>(request)?;
+            let lenses: Vec<_> = results
+                .crate_of(&params.text_document.uri)
+                .into_iter()
+                .flat_map(|(crate_dir, file)| {
+                    file.items.iter().map(move |item| CodeLens {
+                        range: item.range,
+                        command: Some(Command {
+                            title: format!("Prusti: {} — verify this function", item.status),
+                            command: VERIFY_FUNCTION_COMMAND.to_string(),
+                            arguments: Some(vec![
+                                item.def_path.clone().into(),
+                                crate_dir.to_string_lossy().into(),
+                            ]),
+                        }),
+                        data: None,
+                    })
+                })
+                .collect();
+            Response::new_ok(id, lenses)
+        }
+        ExecuteCommand::METHOD => {
+            let (_, params) = extract_request::<ExecuteCommand>(request)?;
+            if let [function, crate_dir] = params.arguments.as_slice()
+                && params.command == VERIFY_FUNCTION_COMMAND
+            {
+                tasks.send(VerificationTask {
+                    crate_dir: serde_json::from_value(crate_dir.clone())?,
+                    function: Some(serde_json::from_value(function.clone())?),
+                })?;
+            }
+            Response::new_ok(id, serde_json::Value::Null)
+        }
+        method => Response::new_err(
+            id,
+            lsp_server::ErrorCode::MethodNotFound as i32,
+            format!("unsupported request: {method}"),
+        ),
+    };
+    Ok(response)
+}
+
+fn extract_request<R: lsp_types::request::Request>(
+    request: Request,
+) -> LspResult<(RequestId, R::Params)> {
+    request.extract(R::METHOD).map_err(|error| {
+        match error {
+            ExtractError::JsonError { method, error } => {
+                format!("invalid {method} request: {error}")
+            }
+            ExtractError::MethodMismatch(request) => {
+                format!("unexpected {} request", request.method)
+            }
+        }
+        .into()
+    })
+}
+
+fn extract_notification<N: lsp_types::notification::Notification>(
+    notification: Notification,
+) -> LspResult<N::Params> {
+    notification.extract(N::METHOD).map_err(|error| {
+        match error {
+            ExtractError::JsonError { method, error } => {
+                format!("invalid {method} notification: {error}")
+            }
+            ExtractError::MethodMismatch(notification) => {
+                format!("unexpected {} notification", notification.method)
+            }
+        }
+        .into()
+    })
+}
+
+/// The directory of the crate containing `file`, i.e., the closest ancestor
+/// containing a `Cargo.toml`.
+fn crate_dir(file: &Path) -> Option<PathBuf> {
+    file.ancestors()
+        .skip(1)
+        .find(|dir| dir.join("Cargo.toml").is_file())
+        .map(Path::to_path_buf)
+}
+
+/// The path of the Prusti executable `name`, which is installed next to this
+/// one.
+pub(crate) fn sibling_executable(name: &str) -> PathBuf {
+    let mut path = launch::get_current_executable_dir().join(name);
+    if cfg!(windows) {
+        path.set_extension("exe");
+    }
+    path
+}
+
+/// The Prusti server used for verification.
+enum ServerProcess {
+    /// A server started by the language server, which stops it on exit.
+    Local { process: Child, address: String },
+    /// The server at `SERVER_ADDRESS`.
+    Remote(String),
+    /// Every verification starts its own JVM.
+    None,
+}
+
+impl ServerProcess {
+    fn start() -> Option<Self> {
+        let mut process = Process::new(sibling_executable("prusti-server"))
+            .args(["--port", "0"])
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            // The standard output of the language server is used by the protocol.
+            .stderr(Stdio::inherit())
+            .spawn()
+            .ok()?;
+        let mut stdout = BufReader::new(process.stdout.take()?);
+        let mut line = String::new();
+        let port = loop {
+            line.clear();
+            if stdout.read_line(&mut line).ok()? == 0 {
+                let _ = process.kill();
+                return None;
+            }
+            if let Some(port) = line.trim().strip_prefix("port: ") {
+                break port.to_string();
+            }
+        };
+        // Keep draining the output so that the server never blocks on it.
+        thread::spawn(move || std::io::copy(&mut stdout, &mut std::io::sink()));
+        Some(ServerProcess::Local {
+            process,
+            address: format!("127.0.0.1:{port}"),
+        })
+    }
+
+    fn address(&self) -> Option<String> {
+        match self {
+            ServerProcess::Local { address, .. } | ServerProcess::Remote(address) => {
+                Some(address.clone())
+            }
+            ServerProcess::None => None,
+        }
+    }
+}
+
+impl Drop for ServerProcess {
+    fn drop(&mut self) {
+        if let ServerProcess::Local { process, .. } = self {
+            let _ = process.kill();
+            let _ = process.wait();
+        }
+    }
+}
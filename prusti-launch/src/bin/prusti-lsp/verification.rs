@@ -0,0 +1,412 @@
+// © 2023, ETH Zurich
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! Verification of crates with `cargo-prusti` in a background thread.
+
+use crate::{
+    diagnostics::{file_url, range, CargoMessage, Counterexample, PrustiDiagnostic},
+    sibling_executable,
+};
+use crossbeam_channel::Sender;
+use lsp_server::{Message, Notification, Request, RequestId};
+use lsp_types::{
+    notification::{LogMessage, PublishDiagnostics, ShowMessage},
+    request::{CodeLensRefresh, InlayHintRefreshRequest},
+    ClientCapabilities, Diagnostic, LogMessageParams, MessageType, PublishDiagnosticsParams, Range,
+    ShowMessageParams, Url,
+};
+use prusti_utils::config;
+use serde::Deserialize;
+use std::{
+    collections::HashMap,
+    env, fs,
+    path::{Path, PathBuf},
+    process::{Command, Stdio},
+    sync::{mpsc, Arc, Mutex},
+    thread,
+};
+
+/// A request to verify a crate.
+#[derive(PartialEq, Eq)]
+pub struct VerificationTask {
+    pub crate_dir: PathBuf,
+    /// The definition path of the only function to verify, whose outcome is
+    /// shown to the user.
+    pub function: Option<String>,
+}
+
+/// The outcome of the last verification of each crate.
+#[derive(Default)]
+pub struct Results {
+    crates: HashMap<PathBuf, CrateResults>,
+}
+
+impl Results {
+    /// The results of the file at `uri`, and the directory of the crate that
+    /// reported them.
+    pub fn crate_of(&self, uri: &Url) -> Option<(&Path, &FileResults)> {
+        self.crates
+            .iter()
+            .find_map(|(crate_dir, results)| Some((crate_dir.as_path(), results.files.get(uri)?)))
+    }
+
+    pub fn file(&self, uri: &Url) -> Option<&FileResults> {
+        self.crate_of(uri).map(|(_, file)| file)
+    }
+}
+
+#[derive(Default)]
+struct CrateResults {
+    files: HashMap<Url, FileResults>,
+}
+
+impl CrateResults {
+    /// Adds the diagnostic to the results of the file it is located in.
+    fn add_diagnostic(&mut self, diagnostic: PrustiDiagnostic) {
+        let file = self.files.entry(diagnostic.location.uri).or_default();
+        file.diagnostics.push(diagnostic.diagnostic);
+        file.counterexamples.extend(diagnostic.counterexamples);
+    }
+}
+
+/// The outcome of verifying the items of a file.
+#[derive(Default)]
+pub struct FileResults {
+    pub diagnostics: Vec<Diagnostic>,
+    pub counterexamples: Vec<Counterexample>,
+    pub items: Vec<VerifiedItem>,
+}
+
+/// A procedure listed in the verification report of a crate.
+#[derive(Clone)]
+pub struct VerifiedItem {
+    pub def_path: String,
+    /// The location of the signature of the procedure.
+    pub range: Range,
+    /// The verification status, e.g. `verified` or `failed`.
+    pub status: String,
+}
+
+/// The parts of the verification report (see `REPORT_DIR`) that are shown
+/// to the user.
+#[derive(Deserialize)]
+struct CrateReport {
+    items: Vec<ItemReport>,
+}
+
+#[derive(Deserialize)]
+struct ItemReport {
+    def_path: String,
+    location: Option<SourceLocation>,
+    status: String,
+}
+
+#[derive(Deserialize)]
+struct SourceLocation {
+    file: String,
+    start_line: u32,
+    start_column: u32,
+    end_line: u32,
+    end_column: u32,
+}
+
+pub struct Verifier {
+    sender: Sender<Message>,
+    results: Arc<Mutex<Results>>,
+    server_address: Option<String>,
+    refresh_code_lenses: bool,
+    refresh_inlay_hints: bool,
+    next_request_id: i32,
+}
+
+impl Verifier {
+    /// Starts a thread verifying the crates of the tasks sent to the returned
+    /// channel. The outcome is stored in `results` and published through
+    /// `sender`.
+    pub fn spawn(
+        sender: Sender<Message>,
+        results: Arc<Mutex<Results>>,
+        server_address: Option<String>,
+        capabilities: &ClientCapabilities,
+    ) -> mpsc::Sender<VerificationTask> {
+        let workspace = capabilities.workspace.as_ref();
+        let mut verifier = Verifier {
+            sender,
+            results,
+            server_address,
+            refresh_code_lenses: workspace
+                .and_then(|workspace| workspace.code_lens.as_ref()?.refresh_support)
+                .unwrap_or(false),
+            refresh_inlay_hints: workspace
+                .and_then(|workspace| workspace.inlay_hint.as_ref()?.refresh_support)
+                .unwrap_or(false),
+            next_request_id: 0,
+        };
+        let (tasks, receiver) = mpsc::channel::<VerificationTask>();
+        thread::spawn(move || {
+            while let Ok(task) = receiver.recv() {
+                // Duplicate tasks queued during the last verification are
+                // only run once.
+                let mut pending: Vec<VerificationTask> = vec![task];
+                for task in receiver.try_iter() {
+                    if !pending.contains(&task) {
+                        pending.push(task);
+                    }
+                }
+                for task in pending {
+                    verifier.verify(task);
+                }
+            }
+        });
+        tasks
+    }
+
+    fn verify(&mut self, task: VerificationTask) {
+        self.log(format!("Verifying {}", task.crate_dir.display()));
+        let root = workspace_root(&task.crate_dir);
+        // The reports are not removed between runs: Cargo does not run Prusti
+        // again on crates that did not change since the last run.
+        let report_dir = target_dir(&task.crate_dir).join("lsp-report");
+
+        let mut command = Command::new(sibling_executable("cargo-prusti"));
+        command
+            .arg("--message-format=json")
+            .current_dir(&task.crate_dir)
+            .env("PRUSTI_REPORT_DIR", &report_dir)
+            .stdin(Stdio::null());
+        if let Some(function) = &task.function {
+            command.env("PRUSTI_VERIFY_ONLY", function);
+        }
+        if let Some(server_address) = &self.server_address {
+            command.env("PRUSTI_SERVER_ADDRESS", server_address);
+        }
+        let output = match command.output() {
+            Ok(output) => output,
+            Err(error) => {
+                self.show(
+                    MessageType::ERROR,
+                    format!("Could not run cargo-prusti: {error}"),
+                );
+                return;
+            }
+        };
+
+        let mut results = CrateResults::default();
+        let mut reported_errors = false;
+        for line in String::from_utf8_lossy(&output.stdout).lines() {
+            let Ok(CargoMessage::CompilerMessage { message }) = serde_json::from_str(line) else {
+                continue;
+            };
+            if !message.is_prusti_error() {
+                continue;
+            }
+            reported_errors = true;
+            match message.to_lsp(&root) {
+                Some(diagnostic) => results.add_diagnostic(diagnostic),
+                None => self.show(MessageType::ERROR, message.full_message()),
+            }
+        }
+        for report in read_reports(&report_dir) {
+            for item in report.items {
+                let Some(location) = item.location else {
+                    continue;
+                };
+                let Some(uri) = file_url(&root, &location.file) else {
+                    continue;
+                };
+                results
+                    .files
+                    .entry(uri)
+                    .or_default()
+                    .items
+                    .push(VerifiedItem {
+                        def_path: item.def_path,
+                        range: range(
+                            location.start_line,
+                            location.start_column,
+                            location.end_line,
+                            location.end_column,
+                        ),
+                        status: item.status,
+                    });
+            }
+        }
+        if !output.status.success() && !reported_errors {
+            self.show(
+                MessageType::ERROR,
+                format!(
+                    "Verification of {} failed:\n{}",
+                    task.crate_dir.display(),
+                    String::from_utf8_lossy(&output.stderr)
+                ),
+            );
+        }
+
+        if let Some(function) = &task.function {
+            let status = results
+                .files
+                .values()
+                .flat_map(|file| &file.items)
+                .find(|item| &item.def_path == function)
+                .map_or("not found", |item| item.status.as_str());
+            self.show(MessageType::INFO, format!("Prusti: {function}: {status}"));
+            // Only the function was verified; keep the code lenses of the
+            // others. Their diagnostics are dropped until the next
+            // verification of the whole crate.
+            if let Some(old_results) = self.results.lock().unwrap().crates.get(&task.crate_dir) {
+                for (uri, old_file) in &old_results.files {
+                    let file = results.files.entry(uri.clone()).or_default();
+                    for item in &old_file.items {
+                        if &item.def_path != function {
+                            file.items.push(item.clone());
+                        }
+                    }
+                }
+            }
+        }
+        self.publish(task.crate_dir, results);
+    }
+
+    /// Replaces the results of the crate, and notifies the client.
+    fn publish(&mut self, crate_dir: PathBuf, results: CrateResults) {
+        let mut notifications: Vec<_> = results
+            .files
+            .iter()
+            .map(|(uri, file)| (uri.clone(), file.diagnostics.clone()))
+            .collect();
+        let old_results = self
+            .results
+            .lock()
+            .unwrap()
+            .crates
+            .insert(crate_dir, results);
+        // Clear the diagnostics of files without errors.
+        for uri in old_results
+            .into_iter()
+            .flat_map(|results| results.files.into_keys())
+        {
+            if !notifications.iter().any(|(notified, _)| *notified == uri) {
+                notifications.push((uri, vec![]));
+            }
+        }
+        for (uri, diagnostics) in notifications {
+            self.notify::<PublishDiagnostics>(PublishDiagnosticsParams::new(
+                uri,
+                diagnostics,
+                None,
+            ));
+        }
+        if self.refresh_code_lenses {
+            self.request::<CodeLensRefresh>(());
+        }
+        if self.refresh_inlay_hints {
+            self.request::<InlayHintRefreshRequest>(());
+        }
+    }
+
+    fn log(&self, message: String) {
+        self.notify::<LogMessage>(LogMessageParams {
+            typ: MessageType::LOG,
+            message,
+        });
+    }
+
+    fn show(&self, typ: MessageType, message: String) {
+        self.notify::<ShowMessage>(ShowMessageParams { typ, message });
+    }
+
+    fn notify<N: lsp_types::notification::Notification>(&self, params: N::Params) {
+        // Sending only fails once the client disconnected.
+        let _ = self.sender.send(Message::Notification(Notification::new(
+            N::METHOD.to_string(),
+            params,
+        )));
+    }
+
+    fn request<R: lsp_types::request::Request>(&mut self, params: R::Params) {
+        let id = RequestId::from(format!("prusti-{}", self.next_request_id));
+        self.next_request_id += 1;
+        let _ = self.sender.send(Message::Request(Request::new(
+            id,
+            R::METHOD.to_string(),
+            params,
+        )));
+    }
+}
+
+/// The reports written to `report_dir`, one per verified crate.
+fn read_reports(report_dir: &Path) -> Vec<CrateReport> {
+    let Ok(entries) = fs::read_dir(report_dir) else {
+        return vec![];
+    };
+    entries
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| {
+            entry
+                .file_name()
+                .to_string_lossy()
+                .ends_with(".prusti.json")
+        })
+        .filter_map(|entry| serde_json::from_slice(&fs::read(entry.path()).ok()?).ok())
+        .collect()
+}
+
+/// The root of the workspace of the crate, relative to which the compiler
+/// reports file paths.
+fn workspace_root(crate_dir: &Path) -> PathBuf {
+    Command::new(config::cargo_path())
+        .args(["locate-project", "--workspace", "--message-format", "plain"])
+        .current_dir(crate_dir)
+        .stderr(Stdio::null())
+        .output()
+        .ok()
+        .filter(|output| output.status.success())
+        .and_then(|output| {
+            let manifest = PathBuf::from(String::from_utf8(output.stdout).ok()?.trim());
+            Some(manifest.parent()?.to_path_buf())
+        })
+        .unwrap_or_else(|| crate_dir.to_path_buf())
+}
+
+/// The target directory used by `cargo-prusti` for the crate.
+fn target_dir(crate_dir: &Path) -> PathBuf {
+    let target = env::var("CARGO_TARGET_DIR").unwrap_or_else(|_| "target".to_string());
+    crate_dir.join(target).join("verify")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use lsp_types::Location;
+
+    fn diagnostic(file: &str, line: u32, message: &str) -> PrustiDiagnostic {
+        let location = Location::new(Url::from_file_path(file).unwrap(), range(line, 1, line, 2));
+        PrustiDiagnostic {
+            diagnostic: Diagnostic::new_simple(location.range, message.to_string()),
+            counterexamples: vec![Counterexample {
+                range: location.range,
+                text: format!("counterexample for \"{message}\"\n  final value: 1"),
+            }],
+            location,
+        }
+    }
+
+    #[test]
+    fn groups_diagnostics_by_file() {
+        let mut results = CrateResults::default();
+        results.add_diagnostic(diagnostic("/project/src/lib.rs", 1, "a"));
+        results.add_diagnostic(diagnostic("/project/src/main.rs", 2, "b"));
+        results.add_diagnostic(diagnostic("/project/src/lib.rs", 3, "c"));
+
+        assert_eq!(results.files.len(), 2);
+        let lib = &results.files[&Url::from_file_path("/project/src/lib.rs").unwrap()];
+        let messages: Vec<_> = lib.diagnostics.iter().map(|d| d.message.as_str()).collect();
+        assert_eq!(messages, ["a", "c"]);
+        assert_eq!(lib.counterexamples.len(), 2);
+        let main = &results.files[&Url::from_file_path("/project/src/main.rs").unwrap()];
+        assert_eq!(main.diagnostics[0].range, range(2, 1, 2, 2));
+    }
+}
@@ -39,6 +39,15 @@ fn process(mut args: Vec<String>) -> Result<(), i32> {
 
     let mut cmd = Command::new(&prusti_driver_path);
     cmd.arg("--cfg=prusti");
+    let item_filters = launch::take_item_filter_args(&mut args).map_err(|error| {
+        eprintln!("error: {error}");
+        eprintln!(
+            "usage: prusti-rustc {} [<rustc arguments>...]",
+            launch::ITEM_FILTER_USAGE
+        );
+        2
+    })?;
+    cmd.envs(item_filters);
 
     launch::add_to_loader_path(vec![compiler_lib, compiler_bin, libjvm_path], &mut cmd);
 
@@ -0,0 +1,13 @@
+use std::process::Command;
+
+fn main() {
+    // Used to invalidate the verification cache when Prusti changes.
+    if let Some(commit_hash) = Command::new("git")
+        .args(["rev-parse", "--short", "HEAD"])
+        .output()
+        .ok()
+        .and_then(|output| String::from_utf8(output.stdout).ok())
+    {
+        println!("cargo:rustc-env=COMMIT_HASH={}", commit_hash.trim());
+    }
+}
@@ -1,17 +1,65 @@
-use crate::dump_viper_program;
+use crate::{dump_viper_program, SmtStopHandle, SmtVerifier};
+use log::info;
 use prusti_common::{
     config,
+    report::log::report,
     vir::{LoweringContext, ToViper},
     Stopwatch,
 };
-use viper::{VerificationContext, VerificationResult};
+use viper::{QuantifierStatistics, StopHandle, VerificationContext, VerificationResultKind};
 
 pub enum Backend<'a> {
     Viper(viper::Verifier<'a>, &'a VerificationContext<'a>),
+    Smt(SmtVerifier),
+}
+
+/// Allows stopping the verification of a [`Backend`] from another thread.
+#[derive(Clone)]
+pub enum BackendStopHandle {
+    Viper(StopHandle),
+    Smt(SmtStopHandle),
+}
+
+impl BackendStopHandle {
+    pub fn stop(&self) {
+        match self {
+            BackendStopHandle::Viper(stop_handle) => stop_handle.stop(),
+            BackendStopHandle::Smt(stop_handle) => stop_handle.stop(),
+        }
+    }
 }
 
 impl<'a> Backend<'a> {
-    pub fn verify(&mut self, program: &prusti_common::vir::program::Program) -> VerificationResult {
+    /// A handle to stop the verification from another thread.
+    pub fn stop_handle(&self) -> BackendStopHandle {
+        match self {
+            Backend::Viper(viper, _) => BackendStopHandle::Viper(viper.stop_handle()),
+            Backend::Smt(smt) => BackendStopHandle::Smt(smt.stop_handle()),
+        }
+    }
+
+    /// The number of quantifier instantiations of the last verification, if
+    /// it was recorded.
+    pub fn quantifier_instantiations(&self) -> Option<u64> {
+        match self {
+            Backend::Viper(viper, _) => viper.quantifier_instantiations(),
+            Backend::Smt(_) => None,
+        }
+    }
+
+    /// The instantiated quantifiers of the last verification, if they were
+    /// recorded.
+    pub fn quantifier_statistics(&self) -> Vec<QuantifierStatistics> {
+        match self {
+            Backend::Viper(viper, _) => viper.quantifier_statistics(),
+            Backend::Smt(_) => Vec::new(),
+        }
+    }
+
+    pub fn verify(
+        &mut self,
+        program: &prusti_common::vir::program::Program,
+    ) -> VerificationResultKind {
         match self {
             Backend::Viper(viper, context) => {
                 let mut stopwatch =
@@ -25,17 +73,22 @@ impl<'a> Backend<'a> {
 
                     if config::dump_viper_program() {
                         stopwatch.start_next("dumping viper program");
-                        dump_viper_program(
-                            &ast_utils,
-                            viper_program,
-                            &program.get_name_with_check_mode(),
-                        );
+                        dump_viper_program(program, || ast_utils.pretty_print(viper_program));
                     }
 
                     stopwatch.start_next("viper verification");
                     viper.verify(viper_program)
                 })
             }
+            Backend::Smt(smt) => {
+                if config::dump_viper_program() {
+                    let filename = format!("{}.smt2", program.get_name_with_check_mode());
+                    info!("Dumping SMT program to 'smt_program/{}'", filename);
+                    report("smt_program", filename, smt.smt_script());
+                }
+                let _stopwatch = Stopwatch::start("prusti-server backend", "smt verification");
+                smt.verify()
+            }
         }
     }
 }
@@ -0,0 +1,151 @@
+// © 2023, ETH Zurich
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+use crate::PrustiClient;
+use prusti_common::config;
+use rustc_hash::FxHasher;
+use std::{
+    fs,
+    hash::{Hash, Hasher},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+use tokio::runtime::Builder;
+use viper::{CacheEntryInfo, CacheLimits, PersistentCache};
+
+/// An operation on the persistent verification cache.
+#[derive(clap::Subcommand, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CacheAction {
+    /// Lists the cached verification results, the most recently used first.
+    List,
+    /// Evicts the entries exceeding `CACHE_MAX_SIZE` or `CACHE_MAX_AGE`.
+    Prune,
+    /// Removes all entries.
+    Clear,
+}
+
+/// Opens the cache at `CACHE_PATH`, with the limits set by `CACHE_MAX_SIZE`
+/// and `CACHE_MAX_AGE`.
+pub fn load_persistent_cache() -> PersistentCache {
+    let max_age = config::cache_max_age();
+    PersistentCache::load_cache(
+        config::cache_path(),
+        cache_version_stamp(),
+        CacheLimits {
+            max_size: config::cache_max_size(),
+            max_age: (max_age > 0).then(|| Duration::from_secs(max_age)),
+        },
+    )
+}
+
+/// Identifies the versions of Prusti and of the Viper JARs. Cached results are
+/// discarded when either changes.
+pub fn cache_version_stamp() -> String {
+    let mut jars: Vec<_> = fs::read_dir(config::viper_home())
+        .map(|entries| entries.filter_map(|entry| entry.ok()).collect())
+        .unwrap_or_default();
+    jars.sort_by_key(|entry| entry.file_name());
+    let mut hasher = FxHasher::default();
+    for jar in jars {
+        jar.file_name().hash(&mut hasher);
+        if let Ok(metadata) = jar.metadata() {
+            metadata.len().hash(&mut hasher);
+            metadata
+                .modified()
+                .ok()
+                .and_then(|modified| modified.duration_since(UNIX_EPOCH).ok())
+                .hash(&mut hasher);
+        }
+    }
+    format!(
+        "prusti {}, commit {}, viper {:016x}",
+        env!("CARGO_PKG_VERSION"),
+        option_env!("COMMIT_HASH").unwrap_or("<unknown>"),
+        hasher.finish()
+    )
+}
+
+/// Performs `action` on the cache of the server at `server_address`, or on the
+/// cache directory at `CACHE_PATH` if no address is given. The outcome is printed
+/// to stdout.
+pub fn manage_cache(action: CacheAction, server_address: Option<String>) -> Result<(), String> {
+    let outcome = if let Some(server_address) = server_address {
+        let client = PrustiClient::new(&server_address).map_err(|error| {
+            format!("Could not parse server address ({server_address}) due to {error:?}")
+        })?;
+        let runtime = Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .expect("failed to construct Tokio runtime");
+        runtime
+            .block_on(async {
+                match action {
+                    CacheAction::List => client.list_cache().await.map(CacheOutcome::Entries),
+                    CacheAction::Prune => client.prune_cache().await.map(CacheOutcome::Evicted),
+                    CacheAction::Clear => client.clear_cache().await.map(CacheOutcome::Removed),
+                }
+            })
+            .map_err(|error| format!("Cache request to {server_address} failed: {error}"))?
+    } else {
+        if config::cache_path().as_os_str().is_empty() {
+            return Err(
+                "No cache directory is configured. Set `CACHE_PATH` or pass a server \
+                address to manage the cache of a running server."
+                    .to_string(),
+            );
+        }
+        let mut cache = load_persistent_cache();
+        match action {
+            CacheAction::List => CacheOutcome::Entries(cache.entries()),
+            CacheAction::Prune => CacheOutcome::Evicted(cache.prune()),
+            CacheAction::Clear => CacheOutcome::Removed(cache.clear()),
+        }
+    };
+    outcome.print();
+    Ok(())
+}
+
+enum CacheOutcome {
+    Entries(Vec<CacheEntryInfo>),
+    Evicted(usize),
+    Removed(usize),
+}
+
+impl CacheOutcome {
+    fn print(&self) {
+        match self {
+            CacheOutcome::Entries(entries) => {
+                let now = SystemTime::now()
+                    .duration_since(UNIX_EPOCH)
+                    .map(|duration| duration.as_secs())
+                    .unwrap_or_default();
+                for entry in entries {
+                    println!(
+                        "{:016x}  {:<7}  {:>10} B  created {:>8} ago  used {:>8} ago  {}",
+                        entry.hash,
+                        if entry.success { "success" } else { "failure" },
+                        entry.size,
+                        format_age(now.saturating_sub(entry.created)),
+                        format_age(now.saturating_sub(entry.last_used)),
+                        entry.item_name,
+                    );
+                }
+                let total_size: u64 = entries.iter().map(|entry| entry.size).sum();
+                println!("{} entries, {total_size} B in total", entries.len());
+            }
+            CacheOutcome::Evicted(count) => println!("Evicted {count} entries"),
+            CacheOutcome::Removed(count) => println!("Removed {count} entries"),
+        }
+    }
+}
+
+fn format_age(secs: u64) -> String {
+    match secs {
+        0..=59 => format!("{secs}s"),
+        60..=3599 => format!("{}m", secs / 60),
+        3600..=86399 => format!("{}h", secs / 3600),
+        _ => format!("{}d", secs / 86400),
+    }
+}
@@ -4,11 +4,18 @@
 // License, v. 2.0. If a copy of the MPL was not distributed with this
 // file, You can obtain one at http://mozilla.org/MPL/2.0/.
 
-use crate::VerificationRequest;
+use crate::{JobId, JobInfo, VerificationRequest};
 use prusti_common::config;
-use reqwest::Client;
+use reqwest::{
+    header::{HeaderMap, HeaderValue},
+    Client,
+};
 use url::{ParseError, Url};
-use viper::VerificationResult;
+use viper::{CacheEntryInfo, VerificationResult};
+
+/// The header identifying the client of a request. The server uses it to share
+/// its workers fairly between clients.
+pub const CLIENT_HEADER: &str = "prusti-client";
 
 pub struct PrustiClient {
     client: Client,
@@ -21,8 +28,15 @@ impl PrustiClient {
         if !address.starts_with("http") {
             address = format!("http://{address}");
         }
+        let mut headers = HeaderMap::new();
+        if let Ok(name) = HeaderValue::from_str(&client_name()) {
+            headers.insert(CLIENT_HEADER, name);
+        }
         Ok(Self {
-            client: Client::new(),
+            client: Client::builder()
+                .default_headers(headers)
+                .build()
+                .expect("failed to construct HTTP client"),
             server_url: Url::parse(address.as_str())?,
         })
     }
@@ -58,4 +72,98 @@ impl PrustiClient {
         };
         Ok(response)
     }
+
+    /// Queues the verification of `request` without waiting for its result.
+    pub async fn submit_job(&self, request: &VerificationRequest) -> reqwest::Result<JobId> {
+        self.client
+            .post(self.server_url.join("jobs").unwrap())
+            .json(request)
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await
+    }
+
+    pub async fn job(&self, id: JobId) -> reqwest::Result<JobInfo> {
+        self.client
+            .get(self.server_url.join(&format!("jobs/{id}")).unwrap())
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await
+    }
+
+    pub async fn list_jobs(&self) -> reqwest::Result<Vec<JobInfo>> {
+        self.client
+            .get(self.server_url.join("jobs").unwrap())
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await
+    }
+
+    /// Cancels the job if it has not finished yet, or makes the server forget
+    /// it otherwise. Returns the status of the job before the call.
+    pub async fn cancel_job(&self, id: JobId) -> reqwest::Result<JobInfo> {
+        self.client
+            .delete(self.server_url.join(&format!("jobs/{id}")).unwrap())
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await
+    }
+
+    pub async fn list_cache(&self) -> reqwest::Result<Vec<CacheEntryInfo>> {
+        self.client
+            .get(self.server_url.join("cache/list").unwrap())
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await
+    }
+
+    /// Returns the number of evicted entries.
+    pub async fn prune_cache(&self) -> reqwest::Result<usize> {
+        self.client
+            .post(self.server_url.join("cache/prune").unwrap())
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await
+    }
+
+    /// Returns the number of removed entries.
+    pub async fn clear_cache(&self) -> reqwest::Result<usize> {
+        self.client
+            .post(self.server_url.join("cache/clear").unwrap())
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await
+    }
+
+    /// The metrics of the server, in the Prometheus text format.
+    pub async fn metrics(&self) -> reqwest::Result<String> {
+        self.client
+            .get(self.server_url.join("metrics").unwrap())
+            .send()
+            .await?
+            .error_for_status()?
+            .text()
+            .await
+    }
+}
+
+/// The name of the user running the client.
+fn client_name() -> String {
+    std::env::var("USER")
+        .or_else(|_| std::env::var("USERNAME"))
+        .unwrap_or_else(|_| "unknown".to_string())
 }
@@ -4,7 +4,8 @@
 // License, v. 2.0. If a copy of the MPL was not distributed with this
 // file, You can obtain one at http://mozilla.org/MPL/2.0/.
 
-use clap::Parser;
+use clap::{Parser, Subcommand};
+use prusti_server::CacheAction;
 use prusti_utils::config;
 
 /// A verification server to handle Prusti verification requests.
@@ -15,6 +16,22 @@ struct Args {
     /// Pass 0 to get a free one assigned by the OS.
     #[clap(short, long, value_name = "PORT", default_value_t = 0)]
     port: u16,
+
+    #[clap(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Inspects or modifies the persistent verification cache at `CACHE_PATH`.
+    Cache {
+        /// Manages the cache of the server running at this address instead.
+        #[clap(long, value_name = "ADDRESS")]
+        server_address: Option<String>,
+
+        #[clap(subcommand)]
+        action: CacheAction,
+    },
 }
 
 fn main() {
@@ -26,5 +43,16 @@ fn main() {
 
     let args = Args::parse();
 
-    prusti_server::start_server_on_port(args.port);
+    match args.command {
+        Some(Command::Cache {
+            server_address,
+            action,
+        }) => {
+            if let Err(error) = prusti_server::manage_cache(action, server_address) {
+                eprintln!("{error}");
+                std::process::exit(1);
+            }
+        }
+        None => prusti_server::start_server_on_port(args.port),
+    }
 }
@@ -0,0 +1,148 @@
+// © 2023, ETH Zurich
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+use crate::BackendStopHandle;
+use log::info;
+use std::{
+    sync::{Arc, Condvar, Mutex},
+    thread,
+    time::{Duration, Instant},
+};
+
+/// How often a stopped verifier is told again to stop, in case the first
+/// request arrived while it could not be interrupted.
+const STOP_RETRY_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Why a verification was interrupted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Interruption {
+    /// The verification exceeded its deadline.
+    TimedOut,
+    /// The verification was cancelled through its [`Interrupt`].
+    Cancelled,
+}
+
+#[derive(Default)]
+struct InterruptState {
+    cancelled: bool,
+    verification_finished: bool,
+    /// The interrupts that are cancelled together with this one.
+    children: Vec<Interrupt>,
+}
+
+/// Allows cancelling a verification request from another thread.
+#[derive(Clone, Default)]
+pub struct Interrupt {
+    state: Arc<(Mutex<InterruptState>, Condvar)>,
+}
+
+impl Interrupt {
+    /// Cancels the verification. If it is running, the verifier is stopped.
+    pub fn cancel(&self) {
+        let (state, condvar) = &*self.state;
+        let children = {
+            let mut state = state.lock().unwrap();
+            state.cancelled = true;
+            std::mem::take(&mut state.children)
+        };
+        condvar.notify_all();
+        for child in children {
+            child.cancel();
+        }
+    }
+
+    /// An interrupt that is cancelled when this one is, but that can also be
+    /// cancelled alone, e.g. to stop one of several verifiers of a request.
+    pub(crate) fn child(&self) -> Interrupt {
+        let child = Interrupt::default();
+        let mut state = self.state.0.lock().unwrap();
+        if state.cancelled {
+            child.cancel();
+        } else {
+            state.children.push(child.clone());
+        }
+        child
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.state.0.lock().unwrap().cancelled
+    }
+
+    /// Stops the verifier of `stop_handle` when the verification is cancelled
+    /// or exceeds `deadline`, until [`Watchdog::finish`] is called.
+    pub(crate) fn watch(
+        &self,
+        stop_handle: BackendStopHandle,
+        deadline: Option<Duration>,
+    ) -> Watchdog {
+        self.state.0.lock().unwrap().verification_finished = false;
+        let interrupt = self.clone();
+        let deadline = deadline.map(|deadline| Instant::now() + deadline);
+        let thread = thread::Builder::new()
+            .name("prusti-server-watchdog".to_string())
+            .spawn(move || interrupt.run_watchdog(stop_handle, deadline))
+            .expect("failed to spawn the verification watchdog");
+        Watchdog {
+            interrupt: self.clone(),
+            thread,
+        }
+    }
+
+    fn run_watchdog(
+        &self,
+        stop_handle: BackendStopHandle,
+        deadline: Option<Instant>,
+    ) -> Option<Interruption> {
+        let (state, condvar) = &*self.state;
+        let mut state = state.lock().unwrap();
+        let mut interruption = None;
+        loop {
+            if state.verification_finished {
+                return interruption;
+            }
+            if interruption.is_none() {
+                if state.cancelled {
+                    interruption = Some(Interruption::Cancelled);
+                } else if deadline.map_or(false, |deadline| Instant::now() >= deadline) {
+                    interruption = Some(Interruption::TimedOut);
+                }
+            }
+            let timeout = if let Some(interruption) = interruption {
+                info!("Stopping the verifier: {interruption:?}");
+                // Stopping the backend, e.g. calling into the JVM, might take
+                // a while.
+                drop(state);
+                stop_handle.stop();
+                state = self.state.0.lock().unwrap();
+                STOP_RETRY_INTERVAL
+            } else if let Some(deadline) = deadline {
+                deadline.saturating_duration_since(Instant::now())
+            } else {
+                Duration::MAX
+            };
+            if !state.verification_finished {
+                state = condvar.wait_timeout(state, timeout).unwrap().0;
+            }
+        }
+    }
+}
+
+/// Watches a running verification; see [`Interrupt::watch`].
+pub(crate) struct Watchdog {
+    interrupt: Interrupt,
+    thread: thread::JoinHandle<Option<Interruption>>,
+}
+
+impl Watchdog {
+    /// Stops watching the verification, which must have returned. Returns why
+    /// the verifier was stopped, if it was.
+    pub(crate) fn finish(self) -> Option<Interruption> {
+        let (state, condvar) = &*self.interrupt.state;
+        state.lock().unwrap().verification_finished = true;
+        condvar.notify_all();
+        self.thread.join().unwrap()
+    }
+}
@@ -0,0 +1,286 @@
+// © 2023, ETH Zurich
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! The queue of verification jobs processed by the server.
+//!
+//! Every verification request, whether submitted through the job API or
+//! through the blocking `verify` endpoints, becomes a job. Jobs are processed
+//! by a pool of verification workers, while clients observe their status
+//! through a [`watch`] channel.
+//!
+//! To share one server fairly, the queue alternates between clients: the next
+//! job is the oldest one of the client that was least recently served. The jobs
+//! of each client are processed in submission order.
+
+use crate::{Interrupt, VerificationRequest};
+use rustc_hash::FxHashMap;
+use std::{
+    collections::VecDeque,
+    sync::{Condvar, Mutex},
+};
+use tokio::sync::watch;
+use viper::VerificationResult;
+
+pub type JobId = u64;
+
+/// How many finished jobs are remembered before the oldest ones are dropped.
+const MAX_FINISHED_JOBS: usize = 1000;
+
+/// The status of a verification job.
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum JobStatus {
+    /// The job waits for `position` other jobs to be processed first.
+    Queued { position: usize },
+    /// The job is being verified.
+    Running,
+    /// The job was verified.
+    Finished { result: VerificationResult },
+    /// The job was cancelled before it finished.
+    Cancelled,
+}
+
+impl JobStatus {
+    /// Whether the status of the job can still change.
+    pub fn is_final(&self) -> bool {
+        matches!(self, JobStatus::Finished { .. } | JobStatus::Cancelled)
+    }
+}
+
+/// The public description of a job.
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct JobInfo {
+    pub id: JobId,
+    /// The client that submitted the job.
+    pub client: String,
+    /// The name of the program to verify.
+    pub program_name: String,
+    #[serde(flatten)]
+    pub status: JobStatus,
+}
+
+struct Job {
+    client: String,
+    program_name: String,
+    /// The request, until a worker takes the job.
+    request: Option<VerificationRequest>,
+    status: watch::Sender<JobStatus>,
+    /// Stops the verification of the job when it is cancelled.
+    interrupt: Interrupt,
+}
+
+impl Job {
+    fn info(&self, id: JobId) -> JobInfo {
+        JobInfo {
+            id,
+            client: self.client.clone(),
+            program_name: self.program_name.clone(),
+            status: self.status.borrow().clone(),
+        }
+    }
+}
+
+#[derive(Default)]
+struct JobQueueState {
+    next_id: JobId,
+    jobs: FxHashMap<JobId, Job>,
+    /// The jobs waiting for a worker, in processing order.
+    queued: VecDeque<JobId>,
+    /// The jobs that reached a final status, the oldest first.
+    finished: VecDeque<JobId>,
+    /// The number of jobs taken by workers so far.
+    served: u64,
+    /// The value of `served` when a job of the client was last taken.
+    last_served: FxHashMap<String, u64>,
+}
+
+impl JobQueueState {
+    /// Sorts the queued jobs in processing order, and updates their positions.
+    fn reorder_queue(&mut self) {
+        let mut queued: Vec<_> = self.queued.iter().copied().collect();
+        queued.sort_unstable();
+        let mut clients: Vec<(&str, VecDeque<JobId>)> = vec![];
+        for id in queued {
+            let client = self.jobs[&id].client.as_str();
+            match clients.iter_mut().find(|(name, _)| *name == client) {
+                Some((_, jobs)) => jobs.push_back(id),
+                None => clients.push((client, VecDeque::from([id]))),
+            }
+        }
+        // Clients are sorted by their oldest job, so ties keep that order.
+        clients.sort_by_key(|(client, _)| self.last_served.get(*client).copied().unwrap_or(0));
+        let mut order = VecDeque::with_capacity(self.queued.len());
+        while !clients.is_empty() {
+            for (_, jobs) in &mut clients {
+                order.extend(jobs.pop_front());
+            }
+            clients.retain(|(_, jobs)| !jobs.is_empty());
+        }
+        self.queued = order;
+        self.update_queue_positions();
+    }
+
+    fn update_queue_positions(&self) {
+        for (position, id) in self.queued.iter().enumerate() {
+            self.jobs[id].status.send_if_modified(|status| {
+                let new_status = JobStatus::Queued { position };
+                let modified = *status != new_status;
+                *status = new_status;
+                modified
+            });
+        }
+    }
+
+    fn set_final_status(&mut self, id: JobId, status: JobStatus) {
+        self.jobs[&id].status.send_replace(status);
+        self.finished.push_back(id);
+        while self.finished.len() > MAX_FINISHED_JOBS {
+            let oldest = self.finished.pop_front().unwrap();
+            self.jobs.remove(&oldest);
+        }
+    }
+}
+
+/// The verification jobs known to the server.
+#[derive(Default)]
+pub struct JobQueue {
+    state: Mutex<JobQueueState>,
+    /// Notified when a job is queued.
+    job_queued: Condvar,
+}
+
+impl JobQueue {
+    /// Queues the verification of `request`, submitted by `client`.
+    pub fn submit(&self, request: VerificationRequest, client: String) -> JobId {
+        let mut state = self.state.lock().unwrap();
+        let id = state.next_id;
+        state.next_id += 1;
+        let position = state.queued.len();
+        let job = Job {
+            client,
+            program_name: request.program.get_name().to_string(),
+            request: Some(request),
+            status: watch::channel(JobStatus::Queued { position }).0,
+            interrupt: Interrupt::default(),
+        };
+        state.jobs.insert(id, job);
+        state.queued.push_back(id);
+        state.reorder_queue();
+        self.job_queued.notify_one();
+        id
+    }
+
+    pub fn info(&self, id: JobId) -> Option<JobInfo> {
+        let state = self.state.lock().unwrap();
+        state.jobs.get(&id).map(|job| job.info(id))
+    }
+
+    /// All known jobs, ordered by their submission.
+    pub fn list(&self) -> Vec<JobInfo> {
+        let state = self.state.lock().unwrap();
+        let mut jobs: Vec<_> = state.jobs.iter().map(|(&id, job)| job.info(id)).collect();
+        jobs.sort_by_key(|job| job.id);
+        jobs
+    }
+
+    /// The number of queued jobs.
+    pub fn queued_count(&self) -> usize {
+        self.state.lock().unwrap().queued.len()
+    }
+
+    /// The number of jobs being verified.
+    pub fn running_count(&self) -> usize {
+        let state = self.state.lock().unwrap();
+        state
+            .jobs
+            .values()
+            .filter(|job| *job.status.borrow() == JobStatus::Running)
+            .count()
+    }
+
+    /// A receiver of the status updates of the job.
+    pub fn subscribe(&self, id: JobId) -> Option<watch::Receiver<JobStatus>> {
+        let state = self.state.lock().unwrap();
+        state.jobs.get(&id).map(|job| job.status.subscribe())
+    }
+
+    /// Cancels the job if it has not finished yet, or forgets it otherwise.
+    /// Returns the status of the job before the call.
+    ///
+    /// Cancelling a running job stops its verification.
+    pub fn cancel(&self, id: JobId) -> Option<JobInfo> {
+        let mut state = self.state.lock().unwrap();
+        let info = state.jobs.get(&id)?.info(id);
+        match info.status {
+            JobStatus::Queued { .. } => {
+                state.queued.retain(|&queued_id| queued_id != id);
+                state.jobs.get_mut(&id).unwrap().request = None;
+                state.set_final_status(id, JobStatus::Cancelled);
+                state.update_queue_positions();
+            }
+            JobStatus::Running => {
+                state.jobs[&id].interrupt.cancel();
+                state.set_final_status(id, JobStatus::Cancelled);
+            }
+            JobStatus::Finished { .. } | JobStatus::Cancelled => {
+                state.finished.retain(|&finished_id| finished_id != id);
+                state.jobs.remove(&id);
+            }
+        }
+        Some(info)
+    }
+
+    /// Waits until the job reaches a final status, and forgets it. Returns the
+    /// result, or `None` if the job was cancelled.
+    pub async fn wait_for_result(&self, id: JobId) -> Option<VerificationResult> {
+        let mut receiver = self.subscribe(id)?;
+        let status = receiver
+            .wait_for(JobStatus::is_final)
+            .await
+            .ok()
+            .map(|status| status.clone());
+        let mut state = self.state.lock().unwrap();
+        state.finished.retain(|&finished_id| finished_id != id);
+        state.jobs.remove(&id);
+        match status? {
+            JobStatus::Finished { result } => Some(result),
+            _ => None,
+        }
+    }
+
+    /// Blocks until a job is queued, and marks it as running. The returned
+    /// interrupt is cancelled when the job is.
+    pub fn take_next(&self) -> (JobId, VerificationRequest, Interrupt) {
+        let mut state = self.state.lock().unwrap();
+        loop {
+            if let Some(id) = state.queued.pop_front() {
+                let job = state.jobs.get_mut(&id).unwrap();
+                let request = job.request.take().unwrap();
+                let interrupt = job.interrupt.clone();
+                job.status.send_replace(JobStatus::Running);
+                let client = job.client.clone();
+                state.served += 1;
+                let served = state.served;
+                state.last_served.insert(client, served);
+                state.reorder_queue();
+                return (id, request, interrupt);
+            }
+            state = self.job_queued.wait(state).unwrap();
+        }
+    }
+
+    /// Records the result of a job taken with [`JobQueue::take_next`].
+    pub fn finish(&self, id: JobId, result: VerificationResult) {
+        let mut state = self.state.lock().unwrap();
+        let is_running = state
+            .jobs
+            .get(&id)
+            .map_or(false, |job| *job.status.borrow() == JobStatus::Running);
+        if is_running {
+            state.set_final_status(id, JobStatus::Finished { result });
+        }
+    }
+}
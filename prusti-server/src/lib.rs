@@ -6,16 +6,26 @@
 
 #![warn(clippy::disallowed_types)]
 
+mod cache;
 mod client;
+mod interrupt;
+mod jobs;
+mod metrics;
 mod process_verification;
 mod server;
+mod smt;
 mod verification_request;
 mod backend;
 
 pub use backend::*;
+pub use cache::*;
 pub use client::*;
+pub use interrupt::*;
+pub use jobs::*;
+pub use metrics::*;
 pub use process_verification::*;
 pub use server::*;
+pub use smt::*;
 pub use verification_request::*;
 
 // Futures returned by `Client` need to be executed in a compatible tokio runtime.
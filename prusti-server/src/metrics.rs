@@ -0,0 +1,190 @@
+// © 2023, ETH Zurich
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! Server metrics, exported in the [Prometheus text format](https://prometheus.io/docs/instrumenting/exposition_formats/).
+
+use crate::JobQueue;
+use std::{
+    collections::BTreeMap,
+    fmt::Write,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Mutex,
+    },
+    time::Duration,
+};
+use viper::VerificationBackend;
+
+/// The upper bounds (in seconds) of the buckets of the duration histograms.
+const DURATION_BUCKETS: &[f64] = &[
+    0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0, 60.0, 120.0, 300.0, 600.0,
+];
+
+#[derive(Default)]
+struct Histogram {
+    /// The number of observations in each of the `DURATION_BUCKETS`.
+    bucket_counts: [u64; DURATION_BUCKETS.len()],
+    count: u64,
+    sum: f64,
+}
+
+impl Histogram {
+    fn observe(&mut self, duration: Duration) {
+        let secs = duration.as_secs_f64();
+        if let Some(bucket) = DURATION_BUCKETS.iter().position(|&bound| secs <= bound) {
+            self.bucket_counts[bucket] += 1;
+        }
+        self.count += 1;
+        self.sum += secs;
+    }
+
+    /// Writes the samples of the histogram, whose labels (if any) are `labels`.
+    fn write(&self, out: &mut String, name: &str, labels: &str) {
+        let separator = if labels.is_empty() { "" } else { "," };
+        let mut cumulative_count = 0;
+        for (bound, count) in DURATION_BUCKETS.iter().zip(self.bucket_counts) {
+            cumulative_count += count;
+            writeln!(
+                out,
+                "{name}_bucket{{{labels}{separator}le=\"{bound}\"}} {cumulative_count}"
+            )
+            .unwrap();
+        }
+        writeln!(
+            out,
+            "{name}_bucket{{{labels}{separator}le=\"+Inf\"}} {}",
+            self.count
+        )
+        .unwrap();
+        let labels = if labels.is_empty() {
+            String::new()
+        } else {
+            format!("{{{labels}}}")
+        };
+        writeln!(out, "{name}_sum{labels} {}", self.sum).unwrap();
+        writeln!(out, "{name}_count{labels} {}", self.count).unwrap();
+    }
+}
+
+/// Measurements of the server, shared by its workers.
+#[derive(Default)]
+pub struct Metrics {
+    cache_hits: AtomicU64,
+    cache_misses: AtomicU64,
+    jvm_attach_durations: Mutex<Histogram>,
+    verification_durations: Mutex<BTreeMap<String, Histogram>>,
+}
+
+impl Metrics {
+    /// Records whether the result of a request was found in the cache.
+    pub fn record_cache_lookup(&self, hit: bool) {
+        let counter = if hit {
+            &self.cache_hits
+        } else {
+            &self.cache_misses
+        };
+        counter.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Records how long it took a worker to attach to the JVM.
+    pub fn record_jvm_attach(&self, duration: Duration) {
+        self.jvm_attach_durations.lock().unwrap().observe(duration);
+    }
+
+    /// Records the duration of a verification by `backend`.
+    pub fn record_verification(&self, backend: VerificationBackend, duration: Duration) {
+        self.verification_durations
+            .lock()
+            .unwrap()
+            .entry(backend.to_string().to_lowercase())
+            .or_default()
+            .observe(duration);
+    }
+
+    /// The metrics in the Prometheus text format.
+    pub fn render(&self, jobs: &JobQueue, workers: usize) -> String {
+        let mut out = String::new();
+        let mut gauge = |name: &str, help: &str, value: usize| {
+            writeln!(out, "# HELP {name} {help}").unwrap();
+            writeln!(out, "# TYPE {name} gauge").unwrap();
+            writeln!(out, "{name} {value}").unwrap();
+        };
+        gauge(
+            "prusti_server_queued_jobs",
+            "Number of jobs waiting for a worker.",
+            jobs.queued_count(),
+        );
+        gauge(
+            "prusti_server_running_jobs",
+            "Number of jobs being verified.",
+            jobs.running_count(),
+        );
+        gauge(
+            "prusti_server_workers",
+            "Maximum number of concurrent verifications.",
+            workers,
+        );
+
+        let cache_hits = self.cache_hits.load(Ordering::Relaxed);
+        let cache_misses = self.cache_misses.load(Ordering::Relaxed);
+        let mut counter = |name: &str, help: &str, value: u64| {
+            writeln!(out, "# HELP {name} {help}").unwrap();
+            writeln!(out, "# TYPE {name} counter").unwrap();
+            writeln!(out, "{name} {value}").unwrap();
+        };
+        counter(
+            "prusti_server_cache_hits_total",
+            "Number of requests answered from the verification cache.",
+            cache_hits,
+        );
+        counter(
+            "prusti_server_cache_misses_total",
+            "Number of requests not found in the verification cache.",
+            cache_misses,
+        );
+        let lookups = cache_hits + cache_misses;
+        writeln!(
+            out,
+            "# HELP prusti_server_cache_hit_ratio Fraction of requests answered from the verification cache."
+        )
+        .unwrap();
+        writeln!(out, "# TYPE prusti_server_cache_hit_ratio gauge").unwrap();
+        writeln!(
+            out,
+            "prusti_server_cache_hit_ratio {}",
+            if lookups == 0 {
+                0.0
+            } else {
+                cache_hits as f64 / lookups as f64
+            }
+        )
+        .unwrap();
+
+        let name = "prusti_server_jvm_attach_duration_seconds";
+        writeln!(
+            out,
+            "# HELP {name} Time taken by workers to attach to the JVM."
+        )
+        .unwrap();
+        writeln!(out, "# TYPE {name} histogram").unwrap();
+        self.jvm_attach_durations
+            .lock()
+            .unwrap()
+            .write(&mut out, name, "");
+
+        let name = "prusti_server_verification_duration_seconds";
+        writeln!(
+            out,
+            "# HELP {name} Duration of the verifications that were not cached, per backend."
+        )
+        .unwrap();
+        writeln!(out, "# TYPE {name} histogram").unwrap();
+        for (backend, histogram) in self.verification_durations.lock().unwrap().iter() {
+            histogram.write(&mut out, name, &format!("backend=\"{backend}\""));
+        }
+        out
+    }
+}
@@ -4,27 +4,53 @@
 // License, v. 2.0. If a copy of the MPL was not distributed with this
 // file, You can obtain one at http://mozilla.org/MPL/2.0/.
 
-use crate::{Backend, VerificationRequest, ViperBackendConfig};
-use log::info;
+use crate::{
+    add_boogie_option, Backend, Interrupt, Interruption, SmtScript, SmtVerifier,
+    VerificationRequest, ViperBackendConfig,
+};
+use log::{info, warn};
 use once_cell::sync::Lazy;
 use prusti_common::{
     config,
-    report::log::{report, to_legal_file_name},
-    vir::{program_normalization::NormalizationInfo, ToViper},
+    report::log::{build_writer, report, to_legal_file_name},
+    vir::{program::Program, program_normalization::NormalizationInfo, viper_text, ToViper},
     Stopwatch,
 };
-use std::{fs::create_dir_all, path::PathBuf};
+use std::{
+    fs::create_dir_all,
+    io::Write,
+    path::PathBuf,
+    sync::Mutex,
+    thread,
+    time::{Duration, Instant},
+};
 use viper::{
-    smt_manager::SmtManager, Cache, VerificationBackend, VerificationContext, VerificationResult,
+    smt_manager::SmtManager, Cache, QuantifierStatistics, VerificationBackend, VerificationContext,
+    VerificationResult, VerificationResultKind,
 };
 
 #[tracing::instrument(level = "debug", skip_all, fields(program = %request.program.get_name()))]
 pub fn process_verification_request<'v, 't: 'v>(
     verification_context: &'v Lazy<VerificationContext<'t>, impl Fn() -> VerificationContext<'t>>,
     mut request: VerificationRequest,
-    cache: impl Cache,
+    mut cache: impl Cache,
+    interrupt: &Interrupt,
 ) -> viper::VerificationResult {
-    let ast_utils = verification_context.new_ast_utils();
+    if interrupt.is_cancelled() {
+        return VerificationResult {
+            item_name: request.program.get_name().to_string(),
+            kind: VerificationResultKind::Cancelled,
+            cached: false,
+            time_ms: 0,
+            backend: None,
+            quantifier_instantiations: None,
+            quantifiers: Vec::new(),
+        };
+    }
+
+    if config::dump_verification_requests() {
+        dump_verification_request(&request);
+    }
 
     // Only for testing: Check that the normalization is reversible.
     if config::print_hash() {
@@ -46,23 +72,21 @@ pub fn process_verification_request<'v, 't: 'v>(
         request.program.get_name()
     );
 
-    let build_or_dump_viper_program = || {
-        let mut stopwatch = Stopwatch::start("prusti-server", "construction of JVM objects");
-        let ast_factory = verification_context.new_ast_factory();
-        let viper_program = request
-            .program
-            .to_viper(prusti_common::vir::LoweringContext::default(), &ast_factory);
-
-        if config::dump_viper_program() {
-            stopwatch.start_next("dumping viper program");
-            dump_viper_program(
-                &ast_utils,
-                viper_program,
-                &request.program.get_name_with_check_mode(),
-            );
-        }
-
-        viper_program
+    // Building the Viper program starts the JVM, which is only needed when
+    // the program cannot be printed without it.
+    let dump_request_program = || {
+        dump_viper_program(&request.program, || {
+            let mut stopwatch = Stopwatch::start("prusti-server", "construction of JVM objects");
+            let ast_utils = verification_context.new_ast_utils();
+            ast_utils.with_local_frame(16, || {
+                let ast_factory = verification_context.new_ast_factory();
+                let viper_program = request
+                    .program
+                    .to_viper(prusti_common::vir::LoweringContext::default(), &ast_factory);
+                stopwatch.start_next("dumping viper program");
+                ast_utils.pretty_print(viper_program)
+            })
+        });
     };
 
     // Only for testing: Print the hash and skip verification.
@@ -74,11 +98,17 @@ pub fn process_verification_request<'v, 't: 'v>(
         println!("Hash of the request is: {hash}");
         // Some tests need the dump to report a diff of the Viper programs.
         if config::dump_viper_program() {
-            ast_utils.with_local_frame(16, || {
-                let _ = build_or_dump_viper_program();
-            });
+            dump_request_program();
         }
-        return viper::VerificationResult::Success;
+        return VerificationResult {
+            item_name: request.program.get_name().to_string(),
+            kind: VerificationResultKind::Success,
+            cached: false,
+            time_ms: 0,
+            backend: None,
+            quantifier_instantiations: None,
+            quantifiers: Vec::new(),
+        };
     }
 
     // Early return in case of cache hit
@@ -90,35 +120,64 @@ pub fn process_verification_request<'v, 't: 'v>(
                 request.program.get_name()
             );
             if config::dump_viper_program() {
-                ast_utils.with_local_frame(16, || {
-                    let _ = build_or_dump_viper_program();
-                });
+                dump_request_program();
             }
+            result.cached = true;
             normalization_info.denormalize_result(&mut result);
             return result;
         }
     };
 
-    let mut stopwatch = Stopwatch::start("prusti-server", "verifier startup");
-
-    // Create a new verifier each time.
-    // Workaround for https://github.com/viperproject/prusti-dev/issues/744
-    let mut backend = match request.backend_config.backend {
-        VerificationBackend::Carbon | VerificationBackend::Silicon => Backend::Viper(
-            new_viper_verifier(
-                request.program.get_name(),
-                verification_context,
-                request.backend_config,
-            ),
+    let start = Instant::now();
+    let deadline = request
+        .deadline
+        .map(|deadline| start + Duration::from_secs(deadline));
+    if deadline.map_or(false, |deadline| deadline <= Instant::now()) {
+        // The deadline already passed, e.g. for a timeout of zero seconds.
+        // Starting the verifier anyway would let it verify small programs
+        // before it is stopped.
+        return VerificationResult {
+            item_name: request.program.get_name().to_string(),
+            kind: VerificationResultKind::TimedOut,
+            cached: false,
+            time_ms: 0,
+            backend: None,
+            quantifier_instantiations: None,
+            quantifiers: Vec::new(),
+        };
+    }
+    let outcome = match request.backend_config.backend {
+        VerificationBackend::Portfolio => verify_with_portfolio(
+            &request.program,
+            &request.backend_config,
             verification_context,
+            interrupt,
+            deadline,
+        ),
+        _ => verify_with_backend(
+            &request.program,
+            request.backend_config,
+            verification_context,
+            interrupt,
+            deadline,
         ),
     };
+    let mut result = VerificationResult {
+        item_name: request.program.get_name().to_string(),
+        kind: outcome.kind,
+        cached: false,
+        time_ms: start.elapsed().as_millis(),
+        backend: Some(outcome.backend),
+        quantifier_instantiations: outcome.quantifier_instantiations,
+        quantifiers: outcome.quantifiers,
+    };
 
-    stopwatch.start_next("backend verification");
-    let mut result = backend.verify(&request.program);
-
-    // Don't cache Java exceptions, which might be due to misconfigured paths.
-    if config::enable_cache() && !matches!(result, VerificationResult::JavaException(_)) {
+    // Don't cache Java exceptions, which might be due to misconfigured paths,
+    // nor the results of interrupted verifications.
+    if config::enable_cache()
+        && !result.kind.is_interrupted()
+        && !matches!(result.kind, VerificationResultKind::JavaException(_))
+    {
         info!(
             "Storing new cached result {:?} for program {}",
             &result,
@@ -131,22 +190,234 @@ pub fn process_verification_request<'v, 't: 'v>(
     result
 }
 
-pub fn dump_viper_program(
-    ast_utils: &viper::AstUtils,
-    program: viper::Program,
-    program_name: &str,
-) {
+/// The outcome of verifying a program with a single backend.
+struct BackendOutcome {
+    kind: VerificationResultKind,
+    /// The backend that produced the outcome, which differs from the
+    /// requested one if the SMT backend falls back to Silicon.
+    backend: VerificationBackend,
+    quantifier_instantiations: Option<u64>,
+    quantifiers: Vec<QuantifierStatistics>,
+}
+
+/// Verifies `program` with the backend of `backend_config`, stopping at
+/// `deadline`.
+fn verify_with_backend(
+    program: &Program,
+    backend_config: ViperBackendConfig,
+    verification_context: &VerificationContext,
+    interrupt: &Interrupt,
+    deadline: Option<Instant>,
+) -> BackendOutcome {
+    let mut stopwatch = Stopwatch::start("prusti-server", "verifier startup");
+
+    // Create a new verifier each time.
+    // Workaround for https://github.com/viperproject/prusti-dev/issues/744
+    let mut used_backend = backend_config.backend;
+    let mut backend_config = backend_config;
+    if let (VerificationBackend::Carbon, None, Some(deadline)) =
+        (backend_config.backend, config::check_timeout(), deadline)
+    {
+        // Let Boogie stop each procedure by itself at the deadline.
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        add_boogie_option(
+            &mut backend_config.verifier_args,
+            format!("/timeLimit:{}", remaining.as_secs().max(1)),
+        );
+    }
+    let mut backend = match backend_config.backend {
+        VerificationBackend::Carbon | VerificationBackend::Silicon => Backend::Viper(
+            new_viper_verifier(program.get_name(), verification_context, backend_config),
+            verification_context,
+        ),
+        VerificationBackend::Smt => match SmtScript::from_program(program) {
+            Ok(script) => Backend::Smt(SmtVerifier::new(script)),
+            Err(reason) => {
+                info!(
+                    "Verifying program {} with Silicon: {reason}",
+                    program.get_name()
+                );
+                used_backend = VerificationBackend::Silicon;
+                Backend::Viper(
+                    new_viper_verifier(
+                        program.get_name(),
+                        verification_context,
+                        backend_config.with_backend(VerificationBackend::Silicon),
+                    ),
+                    verification_context,
+                )
+            }
+        },
+        VerificationBackend::Portfolio => {
+            unreachable!("the portfolio verifies programs with the other backends")
+        }
+    };
+
+    stopwatch.start_next("backend verification");
+    let watchdog = interrupt.watch(
+        backend.stop_handle(),
+        deadline.map(|deadline| deadline.saturating_duration_since(Instant::now())),
+    );
+    let mut kind = backend.verify(program);
+    // The verifier reports any stop as a cancellation.
+    if watchdog.finish() == Some(Interruption::TimedOut)
+        && kind == VerificationResultKind::Cancelled
+    {
+        kind = VerificationResultKind::TimedOut;
+    }
+    BackendOutcome {
+        kind,
+        backend: used_backend,
+        quantifier_instantiations: backend.quantifier_instantiations(),
+        quantifiers: backend.quantifier_statistics(),
+    }
+}
+
+/// Verifies `program` with Silicon and Carbon concurrently. The first backend
+/// that verifies the program stops the other one. If neither does, the errors
+/// reported by Silicon are kept, because only Silicon provides
+/// counterexamples, unless Silicon timed out.
+fn verify_with_portfolio(
+    program: &Program,
+    backend_config: &ViperBackendConfig,
+    verification_context: &VerificationContext,
+    interrupt: &Interrupt,
+    deadline: Option<Instant>,
+) -> BackendOutcome {
+    let silicon_config = backend_config.with_backend(VerificationBackend::Silicon);
+    if config::boogie_path().is_none() {
+        info!(
+            "Verifying program {} only with Silicon: BOOGIE_PATH is not set",
+            program.get_name()
+        );
+        return verify_with_backend(
+            program,
+            silicon_config,
+            verification_context,
+            interrupt,
+            deadline,
+        );
+    }
+    let silicon_interrupt = interrupt.child();
+    let carbon_interrupt = interrupt.child();
+    let java_vm = verification_context.java_vm();
+    thread::scope(|scope| {
+        let carbon = thread::Builder::new()
+            .name("prusti-server-carbon".to_string())
+            .spawn_scoped(scope, || {
+                let carbon_context = VerificationContext::new(
+                    java_vm
+                        .attach_current_thread()
+                        .expect("failed to attach the Carbon thread to the JVM"),
+                );
+                let carbon = verify_with_backend(
+                    program,
+                    backend_config.with_backend(VerificationBackend::Carbon),
+                    &carbon_context,
+                    &carbon_interrupt,
+                    deadline,
+                );
+                if carbon.kind.is_success() {
+                    silicon_interrupt.cancel();
+                }
+                carbon
+            })
+            .expect("failed to spawn the Carbon verification");
+        let silicon = verify_with_backend(
+            program,
+            silicon_config,
+            verification_context,
+            &silicon_interrupt,
+            deadline,
+        );
+        if silicon.kind.is_success() {
+            carbon_interrupt.cancel();
+        }
+        let carbon = carbon.join().unwrap();
+        if silicon.kind.is_success()
+            || (!carbon.kind.is_success() && silicon.kind != VerificationResultKind::TimedOut)
+        {
+            silicon
+        } else {
+            carbon
+        }
+    })
+}
+
+/// Dumps the Viper encoding of `program`, see [`viper_program_text`].
+pub fn dump_viper_program(program: &Program, pretty_print_with_viper: impl FnOnce() -> String) {
     let namespace = "viper_program";
-    let filename = format!("{program_name}.vpr");
+    let filename = format!("{}.vpr", program.get_name_with_check_mode());
     info!("Dumping Viper program to '{}/{}'", namespace, filename);
-    report(namespace, filename, ast_utils.pretty_print(program));
+    report(
+        namespace,
+        filename,
+        viper_program_text(program, pretty_print_with_viper),
+    );
+}
+
+/// The Viper encoding of `program`. The program is printed without the JVM if
+/// possible; `pretty_print_with_viper` is used for the constructs that the
+/// Rust printer does not support.
+pub fn viper_program_text(
+    program: &Program,
+    pretty_print_with_viper: impl FnOnce() -> String,
+) -> String {
+    viper_text::print_program(program).unwrap_or_else(|error| {
+        info!("Printing the Viper program with Viper: {}", error);
+        pretty_print_with_viper()
+    })
+}
+
+/// Saves `request` for `prusti-replay`, together with a JSON copy that can be
+/// inspected without it. Failing to save the request is not an error of the
+/// verification, so it is only logged.
+pub fn dump_verification_request(request: &VerificationRequest) {
+    let namespace = "verification_request";
+    let name = request.program.get_name_with_check_mode();
+    info!(
+        "Dumping verification request to '{}/{}.bincode'",
+        namespace, name
+    );
+    dump_with_writer(namespace, format!("{name}.bincode"), |writer| {
+        bincode::serialize_into(writer, request).map_err(|error| error.to_string())
+    });
+    dump_with_writer(namespace, format!("{name}.json"), |writer| {
+        serde_json::to_writer_pretty(writer, request).map_err(|error| error.to_string())
+    });
 }
 
+fn dump_with_writer(
+    namespace: &str,
+    filename: String,
+    write: impl FnOnce(&mut Box<dyn Write>) -> Result<(), String>,
+) {
+    let result = build_writer(namespace, &filename)
+        .map_err(|error| error.to_string())
+        .and_then(|mut writer| {
+            write(&mut writer)?;
+            writer.flush().map_err(|error| error.to_string())
+        });
+    if let Err(error) = result {
+        warn!("Failed to dump '{}/{}': {}", namespace, filename, error);
+    }
+}
+
+/// Serializes the creation of Viper verifiers, which configures the SMT
+/// solver wrapper through process-global environment variables. Without it,
+/// the verifiers of a portfolio could start their solvers with the settings of
+/// each other.
+static VIPER_VERIFIER_SETUP: Mutex<()> = Mutex::new(());
+
 fn new_viper_verifier<'v, 't: 'v>(
     program_name: &str,
     verification_context: &'v viper::VerificationContext<'t>,
     backend_config: ViperBackendConfig,
 ) -> viper::Verifier<'v> {
+    // A panic while holding the lock leaves no inconsistent state behind.
+    let _setup = VIPER_VERIFIER_SETUP
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner());
     let mut verifier_args: Vec<String> = backend_config.verifier_args;
     let report_path: Option<PathBuf>;
     if config::dump_debug_info() {
@@ -165,11 +436,16 @@ fn new_viper_verifier<'v, 't: 'v>(
                     //"--printTranslatedProgram".to_string(),
                 ])
             }
-            VerificationBackend::Carbon => verifier_args.extend(vec![
-                "--boogieOpt".to_string(),
-                format!("/logPrefix {log_dir_str}"),
+            VerificationBackend::Carbon => {
+                add_boogie_option(&mut verifier_args, format!("/logPrefix {log_dir_str}"));
                 //"--print".to_string(), "./log/boogie_program/program.bpl".to_string(),
-            ]),
+            }
+            VerificationBackend::Smt | VerificationBackend::Portfolio => {
+                unreachable!(
+                    "the {} backend does not use a single Viper verifier",
+                    backend_config.backend
+                )
+            }
         }
     } else {
         report_path = None;
@@ -0,0 +1,166 @@
+// © 2023, ETH Zurich
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+use clap::Parser;
+use once_cell::sync::Lazy;
+use prusti_common::vir::{program::Program, viper_text};
+use prusti_server::{
+    load_persistent_cache, process_verification_request, Interrupt, VerificationRequest,
+    ViperBackendConfig,
+};
+use prusti_utils::config;
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    process::exit,
+};
+use viper::{VerificationBackend, VerificationResultKind, Viper};
+use vir::common::check_mode::CheckMode;
+
+/// Verifies a single verification request, as saved by Prusti with
+/// `DUMP_VERIFICATION_REQUESTS`.
+#[derive(Parser, Debug)]
+#[clap(version, about, long_about = None)]
+struct Args {
+    /// The request to verify: a `.bincode` file saved by Prusti, a `.json`
+    /// file, or a `.vpr` file dumped with `DUMP_VIPER_PROGRAM`.
+    #[clap(value_name = "FILE")]
+    request: PathBuf,
+
+    /// Verifies the request with this backend (Silicon, Carbon, Smt or
+    /// Portfolio). The arguments of the verifier are then taken from the
+    /// Prusti flags.
+    #[clap(long, value_parser = parse_backend)]
+    backend: Option<VerificationBackend>,
+
+    /// Appends an argument to the arguments of the verifier.
+    #[clap(long = "verifier-arg", value_name = "ARG", allow_hyphen_values = true)]
+    verifier_args: Vec<String>,
+
+    /// Drops the arguments of the verifier saved in the request.
+    #[clap(long)]
+    clear_verifier_args: bool,
+
+    /// Stops the verification after this many seconds.
+    #[clap(long, value_name = "SECONDS")]
+    timeout: Option<u64>,
+
+    /// Looks the request up in the verification cache first.
+    #[clap(long)]
+    use_cache: bool,
+}
+
+fn parse_backend(backend: &str) -> Result<VerificationBackend, String> {
+    backend.parse().map_err(|error| format!("{error}"))
+}
+
+fn load_request(path: &Path) -> Result<VerificationRequest, String> {
+    let extension = path.extension().and_then(|extension| extension.to_str());
+    let error = |error: &dyn std::fmt::Display| format!("{}: {error}", path.display());
+    if extension == Some("vpr") {
+        let text = fs::read_to_string(path).map_err(|e| error(&e))?;
+        let program = viper_text::parse_program(&text).map_err(|e| error(&e))?;
+        return Ok(request_for_program(program));
+    }
+    let bytes = fs::read(path).map_err(|e| error(&e))?;
+    if extension == Some("json") {
+        serde_json::from_slice(&bytes).map_err(|e| error(&e))
+    } else {
+        bincode::deserialize(&bytes).map_err(|e| error(&e))
+    }
+}
+
+/// The request that Prusti would send to verify `program`.
+fn request_for_program(program: Program) -> VerificationRequest {
+    let backend = if program.get_check_mode() == CheckMode::Specifications {
+        config::verify_specifications_backend()
+    } else {
+        config::viper_backend()
+    }
+    .parse()
+    .unwrap();
+    VerificationRequest {
+        program,
+        backend_config: ViperBackendConfig::new(backend),
+        deadline: None,
+    }
+}
+
+fn main() {
+    env_logger::init_from_env(
+        env_logger::Env::new()
+            .filter_or("PRUSTI_LOG", config::log())
+            .write_style_or("PRUSTI_LOG_STYLE", config::log_style()),
+    );
+
+    let args = Args::parse();
+    let mut request = load_request(&args.request).unwrap_or_else(|error| {
+        eprintln!("{error}");
+        exit(2);
+    });
+    if let Some(backend) = args.backend {
+        request.backend_config = ViperBackendConfig::new(backend);
+    }
+    if args.clear_verifier_args {
+        request.backend_config.verifier_args.clear();
+    }
+    request
+        .backend_config
+        .verifier_args
+        .extend(args.verifier_args);
+    if args.timeout.is_some() {
+        request.deadline = args.timeout;
+    }
+    config::set_enable_cache(args.use_cache);
+
+    println!("Program: {}", request.program.get_name());
+    println!("Backend: {}", request.backend_config.backend);
+    if !request.backend_config.verifier_args.is_empty() {
+        println!(
+            "Verifier arguments: {}",
+            request.backend_config.verifier_args.join(" ")
+        );
+    }
+
+    let viper = Lazy::new(|| Viper::new_with_args(&config::viper_home(), config::extra_jvm_args()));
+    let verification_context = Lazy::new(|| viper.attach_current_thread());
+    let result = process_verification_request(
+        &verification_context,
+        request,
+        &mut load_persistent_cache(),
+        &Interrupt::default(),
+    );
+
+    let cached = if result.cached { " (cached)" } else { "" };
+    println!("Time: {} ms{cached}", result.time_ms);
+    if let Some(backend) = result.backend {
+        println!("Verified with: {backend}");
+    }
+    match &result.kind {
+        VerificationResultKind::Success => println!("Result: success"),
+        VerificationResultKind::Failure(errors) => {
+            println!("Result: failure");
+            for error in errors {
+                println!("  {}: {}", error.full_id, error.message);
+            }
+        }
+        VerificationResultKind::ConsistencyErrors(errors) => {
+            println!("Result: consistency errors");
+            for error in errors {
+                println!("  {error}");
+            }
+        }
+        VerificationResultKind::JavaException(exception) => {
+            println!("Result: Java exception");
+            println!("  {exception}");
+        }
+        VerificationResultKind::TimedOut => println!("Result: timed out"),
+        VerificationResultKind::Cancelled => println!("Result: cancelled"),
+    }
+    if !result.is_success() {
+        exit(1);
+    }
+}
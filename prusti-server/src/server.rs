@@ -4,23 +4,33 @@
 // License, v. 2.0. If a copy of the MPL was not distributed with this
 // file, You can obtain one at http://mozilla.org/MPL/2.0/.
 
-use crate::{process_verification_request, VerificationRequest};
+use crate::{
+    load_persistent_cache, process_verification_request, JobId, JobQueue, JobStatus, Metrics,
+    VerificationRequest, CLIENT_HEADER,
+};
+use futures::{stream, Stream};
 use log::info;
 use once_cell::sync::Lazy;
 use prusti_common::{config, Stopwatch};
 use std::{
+    convert::Infallible,
     net::{Ipv4Addr, SocketAddr},
     sync::{mpsc, Arc, Mutex},
     thread,
+    time::{Duration, Instant},
 };
-use tokio::runtime::Builder;
-use viper::{PersistentCache, Viper};
-use warp::Filter;
+use tokio::{runtime::Builder, sync::watch};
+use viper::{VerificationResult, Viper};
+use warp::{sse, Filter};
 
 #[derive(Debug)]
 struct BincodeReject(bincode::Error);
 impl warp::reject::Reject for BincodeReject {}
 
+#[derive(Debug)]
+struct JobCancelled(JobId);
+impl warp::reject::Reject for JobCancelled {}
+
 pub fn start_server_on_port(port: u16) {
     listen_on_port_with_address_callback(port, move |address| {
         if port == 0 {
@@ -53,23 +63,64 @@ where
 
     stopwatch.finish();
 
-    let cache_data = PersistentCache::load_cache(config::cache_path());
+    let cache_data = load_persistent_cache();
     let cache = Arc::new(Mutex::new(cache_data));
-    let build_verification_request_handler = |viper_arc: Arc<Lazy<Viper, _>>, cache| {
-        move |request: VerificationRequest| {
-            let stopwatch = Stopwatch::start("prusti-server", "attach thread to JVM");
-            let viper_thread = Lazy::new(|| viper_arc.attach_current_thread());
-            stopwatch.finish();
-            process_verification_request(&viper_thread, request, &cache)
-        }
+
+    // Verification happens in a single worker thread by default because
+    // 1. Viper is not thread safe yet (Silicon issue #578), and
+    // 2. By default Silicon already uses as many cores as possible.
+    // More workers, each verifying one request at a time, are used only when
+    // explicitly requested.
+    let workers = config::server_max_concurrency().unwrap_or(1).max(1);
+    let jobs = Arc::new(JobQueue::default());
+    let metrics = Arc::new(Metrics::default());
+    for worker in 0..workers {
+        let viper = viper.clone();
+        let jobs = jobs.clone();
+        let metrics = metrics.clone();
+        let cache = cache.clone();
+        thread::Builder::new()
+            .name(format!("prusti-server-worker-{worker}"))
+            .spawn(move || {
+                let attach_metrics = metrics.clone();
+                let viper_thread = Lazy::new(move || {
+                    let stopwatch = Stopwatch::start("prusti-server", "attach thread to JVM");
+                    let start = Instant::now();
+                    let viper_thread = viper.attach_current_thread();
+                    attach_metrics.record_jvm_attach(start.elapsed());
+                    stopwatch.finish();
+                    viper_thread
+                });
+                loop {
+                    let (id, request, interrupt) = jobs.take_next();
+                    info!("Processing job {id} in worker {worker}");
+                    let backend = request.backend_config.backend;
+                    let result =
+                        process_verification_request(&viper_thread, request, &cache, &interrupt);
+                    if config::enable_cache() {
+                        metrics.record_cache_lookup(result.cached);
+                    }
+                    if !result.cached {
+                        metrics.record_verification(
+                            backend,
+                            Duration::from_millis(result.time_ms as u64),
+                        );
+                    }
+                    jobs.finish(id, result);
+                }
+            })
+            .expect("failed to spawn a verification worker");
+    }
+    let with_jobs = move || {
+        let jobs = jobs.clone();
+        warp::any().map(move || jobs.clone())
     };
 
     let json_verify = warp::path!("json" / "verify")
         .and(warp::body::json())
-        .map(build_verification_request_handler(
-            viper.clone(),
-            cache.clone(),
-        ))
+        .and(client_name())
+        .and(with_jobs())
+        .and_then(verify)
         .map(|response| warp::reply::json(&response));
 
     let bincode_verify = warp::path!("bincode" / "verify")
@@ -80,13 +131,100 @@ where
                 warp::reject::custom(BincodeReject(err))
             })
         })
-        .map(build_verification_request_handler(viper, cache.clone()))
+        .and(client_name())
+        .and(with_jobs())
+        .and_then(verify)
         .map(|result| {
             warp::http::Response::new(
                 bincode::serialize(&result).expect("could not encode verification result"),
             )
         });
 
+    let submit_job = warp::post()
+        .and(warp::path!("jobs"))
+        .and(warp::body::json())
+        .and(client_name())
+        .and(with_jobs())
+        .map(
+            |request: VerificationRequest, client, jobs: Arc<JobQueue>| {
+                warp::reply::json(&jobs.submit(request, client))
+            },
+        );
+
+    let list_jobs = warp::get()
+        .and(warp::path!("jobs"))
+        .and(with_jobs())
+        .map(|jobs: Arc<JobQueue>| warp::reply::json(&jobs.list()));
+
+    let job_status = warp::get()
+        .and(warp::path!("jobs" / JobId))
+        .and(with_jobs())
+        .and_then(|id, jobs: Arc<JobQueue>| async move {
+            jobs.info(id)
+                .map(|info| warp::reply::json(&info))
+                .ok_or_else(warp::reject::not_found)
+        });
+
+    let job_events = warp::get()
+        .and(warp::path!("jobs" / JobId / "events"))
+        .and(with_jobs())
+        .and_then(|id, jobs: Arc<JobQueue>| async move {
+            let receiver = jobs.subscribe(id).ok_or_else(warp::reject::not_found)?;
+            let events = warp::sse::keep_alive().stream(job_status_events(receiver));
+            Ok::<_, warp::Rejection>(warp::sse::reply(events))
+        });
+
+    let cancel_job = warp::delete()
+        .and(warp::path!("jobs" / JobId))
+        .and(with_jobs())
+        .and_then(|id, jobs: Arc<JobQueue>| async move {
+            jobs.cancel(id)
+                .map(|info| warp::reply::json(&info))
+                .ok_or_else(warp::reject::not_found)
+        });
+
+    let get_metrics = warp::get()
+        .and(warp::path!("metrics"))
+        .and(with_jobs())
+        .map(move |jobs: Arc<JobQueue>| {
+            warp::reply::with_header(
+                metrics.render(&jobs, workers),
+                "content-type",
+                "text/plain; version=0.0.4",
+            )
+        });
+
+    let list_cache = {
+        let cache = cache.clone();
+        warp::get()
+            .and(warp::path!("cache" / "list"))
+            .map(move || warp::reply::json(&cache.lock().unwrap().entries()))
+    };
+
+    let prune_cache = {
+        let cache = cache.clone();
+        warp::post()
+            .and(warp::path!("cache" / "prune"))
+            .map(move || {
+                let mut cache = cache.lock().unwrap();
+                let evicted = cache.prune();
+                cache.save();
+                warp::reply::json(&evicted)
+            })
+    };
+
+    let clear_cache = {
+        let cache = cache.clone();
+        warp::post()
+            .and(warp::path!("cache" / "clear"))
+            .map(move || {
+                let mut cache = cache.lock().unwrap();
+                let removed = cache.clear();
+                cache.save();
+                warp::reply::json(&removed)
+            })
+    };
+
     let save_cache = warp::post()
         .and(warp::path("save"))
         .and(warp::path::end())
@@ -95,11 +233,19 @@ where
             warp::reply::html("Saved")
         });
 
-    let endpoints = json_verify.or(bincode_verify).or(save_cache);
+    let endpoints = json_verify
+        .or(bincode_verify)
+        .or(submit_job)
+        .or(list_jobs)
+        .or(job_status)
+        .or(job_events)
+        .or(cancel_job)
+        .or(get_metrics)
+        .or(list_cache)
+        .or(prune_cache)
+        .or(clear_cache)
+        .or(save_cache);
 
-    // Here we use a single thread because
-    // 1. Viper is not thread safe yet (Silicon issue #578), and
-    // 2. By default Silicon already uses as many cores as possible.
     let runtime = Builder::new_current_thread()
         .thread_name("prusti-server")
         .enable_all()
@@ -120,3 +266,46 @@ where
 
     unreachable!("The server unexpectedly stopped.");
 }
+
+/// Identifies the client sending a request, to share the workers fairly. The
+/// name given by the client is used, or its IP address otherwise.
+fn client_name() -> impl Filter<Extract = (String,), Error = warp::Rejection> + Clone {
+    warp::header::optional(CLIENT_HEADER)
+        .and(warp::addr::remote())
+        .map(|name: Option<String>, address: Option<SocketAddr>| {
+            name.or_else(|| address.map(|address| address.ip().to_string()))
+                .unwrap_or_default()
+        })
+}
+
+/// Verifies `request` in a worker thread and waits for the result.
+async fn verify(
+    request: VerificationRequest,
+    client: String,
+    jobs: Arc<JobQueue>,
+) -> Result<VerificationResult, warp::Rejection> {
+    let id = jobs.submit(request, client);
+    jobs.wait_for_result(id)
+        .await
+        .ok_or_else(|| warp::reject::custom(JobCancelled(id)))
+}
+
+/// A stream with one server-sent event per status change of a job, ending
+/// with its final status.
+fn job_status_events(
+    receiver: watch::Receiver<JobStatus>,
+) -> impl Stream<Item = Result<sse::Event, Infallible>> {
+    stream::unfold(Some((receiver, true)), |state| async move {
+        let (mut receiver, is_first) = state?;
+        if !is_first {
+            receiver.changed().await.ok()?;
+        }
+        let status = receiver.borrow_and_update().clone();
+        let event = sse::Event::default()
+            .event("status")
+            .json_data(&status)
+            .expect("could not encode job status");
+        let next_state = (!status.is_final()).then_some((receiver, false));
+        Some((Ok(event), next_state))
+    })
+}
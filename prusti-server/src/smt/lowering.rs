@@ -0,0 +1,1253 @@
+// © 2023, ETH Zurich
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! Lowering of `vir::low` programs to SMT-LIB.
+//!
+//! Every verification condition becomes a `check-sat` of its negation, which
+//! is expected to be `unsat`. The acyclic control flow graph of a procedure is
+//! converted to SSA form: each assignment declares a fresh constant, and each
+//! program point has a Boolean constant that holds iff the point is reached.
+//! Programs that need permissions, fields or predicates are not supported.
+
+use prusti_common::vir::program::Program;
+use rustc_hash::FxHashMap;
+use std::fmt::{self, Write};
+use viper::VerificationError;
+use vir::{
+    common::position::{Position, Positioned},
+    low::{
+        ast::statement,
+        expression::{self, QuantifierKind},
+        BasicBlock, BinaryOpKind, ConstantValue, ContainerOpKind, DomainDecl, Expression,
+        FunctionDecl, MethodDecl, ProcedureDecl, Statement, Successor, Type, UnaryOpKind,
+    },
+};
+
+/// The sort of Viper references.
+const REF_SORT: &str = "|$Ref|";
+
+/// The name of the result variable in the postconditions of functions.
+const RESULT: &str = "__result";
+
+/// The reason why a program cannot be verified by the SMT backend.
+#[derive(Debug)]
+pub struct SmtUnsupported(String);
+
+impl fmt::Display for SmtUnsupported {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "the SMT backend does not support {}", self.0)
+    }
+}
+
+fn unsupported<T>(feature: impl fmt::Display) -> LoweringResult<T> {
+    Err(SmtUnsupported(feature.to_string()))
+}
+
+type LoweringResult<T> = Result<T, SmtUnsupported>;
+
+/// An SMT-LIB script checking the verification conditions of a program.
+pub struct SmtScript {
+    pub(super) commands: String,
+    /// The error reported for each `check-sat` of the script, in order, if
+    /// its answer is not `unsat`.
+    pub(super) checks: Vec<VerificationError>,
+}
+
+impl SmtScript {
+    pub fn from_program(program: &Program) -> Result<Self, SmtUnsupported> {
+        let program = match program {
+            Program::Low(program) => program,
+            Program::Legacy(_) => return unsupported("legacy VIR programs"),
+        };
+        let functions = program
+            .functions
+            .iter()
+            .map(|function| (function.name.as_str(), function))
+            .collect();
+        let mut lowerer = Lowerer {
+            program,
+            functions: &functions,
+            methods: program
+                .methods
+                .iter()
+                .map(|method| (method.name.as_str(), method))
+                .collect(),
+            sorts: FxHashMap::default(),
+            out: String::new(),
+            checks: Vec::new(),
+            fresh: 0,
+        };
+        lowerer.program()?;
+        Ok(SmtScript {
+            commands: lowerer.out,
+            checks: lowerer.checks,
+        })
+    }
+}
+
+/// The SMT term of each program variable.
+type Variables = FxHashMap<String, String>;
+
+/// The SSA state at a program point.
+#[derive(Clone)]
+struct State {
+    variables: Variables,
+    /// A Boolean constant that holds iff the program point is reached.
+    path_condition: String,
+}
+
+/// The states referred to by old expressions in a procedure or method.
+struct Frame {
+    /// The initial state.
+    old: Variables,
+    /// The state at each label that was reached.
+    labels: FxHashMap<String, Variables>,
+}
+
+/// The statement whose verification conditions are checked, which determines
+/// the error reported when they fail.
+#[derive(Clone, Copy)]
+struct Context {
+    error_id: &'static str,
+    message: &'static str,
+    position: Position,
+}
+
+impl Context {
+    fn new(error_id: &'static str, message: &'static str, position: Position) -> Self {
+        Context {
+            error_id,
+            message,
+            position,
+        }
+    }
+}
+
+enum ObligationKind {
+    Precondition,
+    DivisionByZero,
+    SeqIndexNegative,
+    SeqIndexLength,
+}
+
+/// A condition for an expression to be well-defined.
+struct Obligation {
+    kind: ObligationKind,
+    condition: String,
+    /// The position of the partial operation.
+    position: Position,
+    /// The position of the condition that might not hold.
+    reason_position: Position,
+    message: String,
+}
+
+impl Obligation {
+    fn error(&self, context: Context) -> VerificationError {
+        let reason = match self.kind {
+            ObligationKind::Precondition => {
+                return error(
+                    "application.precondition:assertion.false".to_string(),
+                    self.position,
+                    self.reason_position,
+                    self.message.clone(),
+                );
+            }
+            ObligationKind::DivisionByZero => "division.by.zero",
+            ObligationKind::SeqIndexNegative => "seq.index.negative",
+            ObligationKind::SeqIndexLength => "seq.index.length",
+        };
+        error(
+            format!("{}:{reason}", context.error_id),
+            context.position,
+            self.reason_position,
+            format!("{} {}", context.message, self.message),
+        )
+    }
+}
+
+fn error(
+    full_id: String,
+    position: Position,
+    reason_position: Position,
+    message: String,
+) -> VerificationError {
+    VerificationError::new(
+        full_id,
+        Some(position.id.to_string()),
+        Some(position.id.to_string()),
+        Some(reason_position.id.to_string()),
+        message,
+        None,
+    )
+}
+
+/// Quotes `name` as an SMT-LIB symbol.
+fn symbol(name: &str) -> String {
+    format!("|{}|", name.replace(['|', '\\'], "_"))
+}
+
+fn sort(ty: &Type) -> LoweringResult<String> {
+    match ty {
+        Type::Int => Ok("Int".to_string()),
+        Type::Bool => Ok("Bool".to_string()),
+        Type::Ref => Ok(REF_SORT.to_string()),
+        Type::Domain(domain) => Ok(symbol(&domain.name)),
+        Type::Seq(seq) => Ok(format!("(Seq {})", sort(&seq.element_type)?)),
+        _ => unsupported(format_args!("values of type {ty}")),
+    }
+}
+
+fn apply(function: &str, arguments: &[String]) -> String {
+    if arguments.is_empty() {
+        function.to_string()
+    } else {
+        format!("({function} {})", arguments.join(" "))
+    }
+}
+
+fn and(terms: &[String]) -> String {
+    match terms {
+        [] => "true".to_string(),
+        [term] => term.clone(),
+        _ => format!("(and {})", terms.join(" ")),
+    }
+}
+
+/// Quantifies `body` over the `(name, sort)` pairs of `variables`, triggered
+/// by `trigger`.
+fn forall(variables: &[(String, String)], trigger: &str, body: String) -> String {
+    if variables.is_empty() {
+        return body;
+    }
+    let variables: Vec<_> = variables
+        .iter()
+        .map(|(name, sort)| format!("({name} {sort})"))
+        .collect();
+    format!(
+        "(forall ({}) (! {body} :pattern ({trigger})))",
+        variables.join(" ")
+    )
+}
+
+fn constant(constant: &expression::Constant) -> LoweringResult<String> {
+    match (&constant.ty, &constant.value) {
+        (Type::Bool, ConstantValue::Bool(value)) => Ok(value.to_string()),
+        (Type::Int, ConstantValue::Int(value)) if *value < 0 => {
+            Ok(format!("(- {})", value.unsigned_abs()))
+        }
+        (Type::Int, ConstantValue::Int(value)) => Ok(value.to_string()),
+        (Type::Int, ConstantValue::BigInt(value)) => Ok(match value.strip_prefix('-') {
+            Some(absolute) => format!("(- {absolute})"),
+            None => value.clone(),
+        }),
+        _ => unsupported(format_args!("constant {constant} of type {}", constant.ty)),
+    }
+}
+
+/// The variables in which an expression is evaluated.
+#[derive(Clone)]
+struct Env<'a> {
+    functions: &'a FxHashMap<&'a str, &'a FunctionDecl>,
+    variables: &'a Variables,
+    /// The state referred to by `old(..)`.
+    old: &'a Variables,
+    /// The states referred to by `old[label](..)`.
+    labels: &'a FxHashMap<String, Variables>,
+    /// The names and SMT terms of the variables bound by enclosing quantifiers
+    /// and let expressions.
+    bound: Vec<(String, String)>,
+    /// The conditions under which the expression is evaluated.
+    guards: Vec<String>,
+    /// Whether to collect the well-definedness obligations of the expression.
+    check_definedness: bool,
+}
+
+impl<'a> Env<'a> {
+    fn new(
+        functions: &'a FxHashMap<&'a str, &'a FunctionDecl>,
+        variables: &'a Variables,
+        old: &'a Variables,
+        labels: &'a FxHashMap<String, Variables>,
+        check_definedness: bool,
+    ) -> Self {
+        Env {
+            functions,
+            variables,
+            old,
+            labels,
+            bound: Vec::new(),
+            guards: Vec::new(),
+            check_definedness,
+        }
+    }
+
+    fn guarded(&self, guard: String) -> Self {
+        let mut env = self.clone();
+        env.guards.push(guard);
+        env
+    }
+
+    fn binding(&self, variables: impl IntoIterator<Item = (String, String)>) -> Self {
+        let mut env = self.clone();
+        env.bound.extend(variables);
+        env
+    }
+
+    fn variable(&self, name: &str) -> LoweringResult<String> {
+        self.bound
+            .iter()
+            .rev()
+            .find(|(bound, _)| bound == name)
+            .map(|(_, term)| term)
+            .or_else(|| self.variables.get(name))
+            .cloned()
+            .ok_or_else(|| SmtUnsupported(format!("the undeclared variable {name}")))
+    }
+
+    fn obligation(
+        &self,
+        obligations: &mut Vec<Obligation>,
+        kind: ObligationKind,
+        condition: String,
+        position: Position,
+        reason_position: Position,
+        message: String,
+    ) -> LoweringResult<()> {
+        if !self.check_definedness {
+            return Ok(());
+        }
+        if !self.bound.is_empty() {
+            return unsupported("partial operations inside quantifiers and let expressions");
+        }
+        let condition = if self.guards.is_empty() {
+            condition
+        } else {
+            format!("(=> {} {condition})", and(&self.guards))
+        };
+        obligations.push(Obligation {
+            kind,
+            condition,
+            position,
+            reason_position,
+            message,
+        });
+        Ok(())
+    }
+
+    fn lower_all(
+        &self,
+        expressions: &[Expression],
+        obligations: &mut Vec<Obligation>,
+    ) -> LoweringResult<Vec<String>> {
+        expressions
+            .iter()
+            .map(|expression| self.lower(expression, obligations))
+            .collect()
+    }
+
+    /// Lowers `expression`, adding the conditions for it to be well-defined to
+    /// `obligations`.
+    fn lower(
+        &self,
+        expression: &Expression,
+        obligations: &mut Vec<Obligation>,
+    ) -> LoweringResult<String> {
+        match expression {
+            Expression::Local(local) => self.variable(&local.variable.name),
+            Expression::LabelledOld(old) => {
+                let variables = match &old.label {
+                    Some(label) => self.labels.get(label).ok_or_else(|| {
+                        SmtUnsupported(format!("the label {label} before it is reached"))
+                    })?,
+                    None => self.old,
+                };
+                Env {
+                    variables,
+                    ..self.clone()
+                }
+                .lower(&old.base, obligations)
+            }
+            Expression::Constant(expression) => constant(expression),
+            Expression::UnaryOp(op) => {
+                let argument = self.lower(&op.argument, obligations)?;
+                Ok(match op.op_kind {
+                    UnaryOpKind::Not => format!("(not {argument})"),
+                    UnaryOpKind::Minus => format!("(- {argument})"),
+                })
+            }
+            Expression::BinaryOp(op) => self.binary_op(op, obligations),
+            Expression::ContainerOp(op) => self.container_op(op, obligations),
+            Expression::Conditional(conditional) => {
+                let guard = self.lower(&conditional.guard, obligations)?;
+                let then_expr = self
+                    .guarded(guard.clone())
+                    .lower(&conditional.then_expr, obligations)?;
+                let else_expr = self
+                    .guarded(format!("(not {guard})"))
+                    .lower(&conditional.else_expr, obligations)?;
+                Ok(format!("(ite {guard} {then_expr} {else_expr})"))
+            }
+            Expression::Quantifier(quantifier) => self.quantifier(quantifier, obligations),
+            Expression::LetExpr(let_expr) => {
+                let def = self.lower(&let_expr.def, obligations)?;
+                let variable = symbol(&let_expr.variable.name);
+                let body = self
+                    .binding([(let_expr.variable.name.clone(), variable.clone())])
+                    .lower(&let_expr.body, obligations)?;
+                Ok(format!("(let (({variable} {def})) {body})"))
+            }
+            Expression::FuncApp(app) => self.func_app(app, obligations),
+            Expression::DomainFuncApp(app) => {
+                let arguments = self.lower_all(&app.arguments, obligations)?;
+                Ok(apply(&symbol(&app.function_name), &arguments))
+            }
+            Expression::Field(_)
+            | Expression::MagicWand(_)
+            | Expression::PredicateAccessPredicate(_)
+            | Expression::FieldAccessPredicate(_)
+            | Expression::Unfolding(_)
+            | Expression::PermBinaryOp(_)
+            | Expression::InhaleExhale(_) => {
+                unsupported(format_args!("the expression {expression}"))
+            }
+        }
+    }
+
+    fn binary_op(
+        &self,
+        op: &expression::BinaryOp,
+        obligations: &mut Vec<Obligation>,
+    ) -> LoweringResult<String> {
+        let left = self.lower(&op.left, obligations)?;
+        let right = match op.op_kind {
+            BinaryOpKind::And | BinaryOpKind::Implies => {
+                self.guarded(left.clone()).lower(&op.right, obligations)?
+            }
+            BinaryOpKind::Or => self
+                .guarded(format!("(not {left})"))
+                .lower(&op.right, obligations)?,
+            _ => self.lower(&op.right, obligations)?,
+        };
+        let operator = match op.op_kind {
+            BinaryOpKind::EqCmp => "=",
+            BinaryOpKind::NeCmp => "distinct",
+            BinaryOpKind::GtCmp => ">",
+            BinaryOpKind::GeCmp => ">=",
+            BinaryOpKind::LtCmp => "<",
+            BinaryOpKind::LeCmp => "<=",
+            BinaryOpKind::Add => "+",
+            BinaryOpKind::Sub => "-",
+            BinaryOpKind::Mul => "*",
+            BinaryOpKind::Div | BinaryOpKind::Mod => {
+                self.obligation(
+                    obligations,
+                    ObligationKind::DivisionByZero,
+                    format!("(not (= {right} 0))"),
+                    op.position,
+                    op.right.position(),
+                    format!("Divisor {} might be zero.", op.right),
+                )?;
+                if op.op_kind == BinaryOpKind::Div {
+                    "div"
+                } else {
+                    "mod"
+                }
+            }
+            BinaryOpKind::And => "and",
+            BinaryOpKind::Or => "or",
+            BinaryOpKind::Implies => "=>",
+        };
+        Ok(format!("({operator} {left} {right})"))
+    }
+
+    fn container_op(
+        &self,
+        op: &expression::ContainerOp,
+        obligations: &mut Vec<Obligation>,
+    ) -> LoweringResult<String> {
+        let operands = self.lower_all(&op.operands, obligations)?;
+        match (&op.kind, operands.as_slice()) {
+            (ContainerOpKind::SeqEmpty, []) | (ContainerOpKind::SeqConstructor, []) => {
+                Ok(format!("(as seq.empty {})", sort(&op.container_type)?))
+            }
+            (ContainerOpKind::SeqConstructor, [element]) => Ok(format!("(seq.unit {element})")),
+            (ContainerOpKind::SeqConstructor, elements) => {
+                let units: Vec<_> = elements
+                    .iter()
+                    .map(|element| format!("(seq.unit {element})"))
+                    .collect();
+                Ok(format!("(seq.++ {})", units.join(" ")))
+            }
+            (ContainerOpKind::SeqIndex, [seq, index]) => {
+                let (seq_expression, index_expression) = (&op.operands[0], &op.operands[1]);
+                self.obligation(
+                    obligations,
+                    ObligationKind::SeqIndexNegative,
+                    format!("(>= {index} 0)"),
+                    op.position,
+                    index_expression.position(),
+                    format!("Index {index_expression} into {seq_expression} might be negative."),
+                )?;
+                self.obligation(
+                    obligations,
+                    ObligationKind::SeqIndexLength,
+                    format!("(< {index} (seq.len {seq}))"),
+                    op.position,
+                    index_expression.position(),
+                    format!(
+                        "Index {index_expression} into {seq_expression} might exceed sequence length."
+                    ),
+                )?;
+                Ok(format!("(seq.nth {seq} {index})"))
+            }
+            (ContainerOpKind::SeqConcat, [left, right]) => Ok(format!("(seq.++ {left} {right})")),
+            (ContainerOpKind::SeqLen, [seq]) => Ok(format!("(seq.len {seq})")),
+            _ => unsupported(format_args!("the container operation {}", op.kind)),
+        }
+    }
+
+    fn quantifier(
+        &self,
+        quantifier: &expression::Quantifier,
+        obligations: &mut Vec<Obligation>,
+    ) -> LoweringResult<String> {
+        let mut variables = Vec::new();
+        for variable in &quantifier.variables {
+            variables.push(format!(
+                "({} {})",
+                symbol(&variable.name),
+                sort(&variable.ty)?
+            ));
+        }
+        let env = self.binding(
+            quantifier
+                .variables
+                .iter()
+                .map(|variable| (variable.name.clone(), symbol(&variable.name))),
+        );
+        let body = env.lower(&quantifier.body, obligations)?;
+        if variables.is_empty() {
+            return Ok(body);
+        }
+        let trigger_env = Env {
+            check_definedness: false,
+            ..env
+        };
+        let mut patterns = String::new();
+        for trigger in &quantifier.triggers {
+            let terms = trigger_env.lower_all(&trigger.terms, &mut Vec::new())?;
+            write!(patterns, " :pattern ({})", terms.join(" ")).unwrap();
+        }
+        let kind = match quantifier.kind {
+            QuantifierKind::ForAll => "forall",
+            QuantifierKind::Exists => "exists",
+        };
+        let variables = variables.join(" ");
+        Ok(if patterns.is_empty() {
+            format!("({kind} ({variables}) {body})")
+        } else {
+            format!("({kind} ({variables}) (! {body}{patterns}))")
+        })
+    }
+
+    fn func_app(
+        &self,
+        app: &expression::FuncApp,
+        obligations: &mut Vec<Obligation>,
+    ) -> LoweringResult<String> {
+        let arguments = self.lower_all(&app.arguments, obligations)?;
+        let Some(function) = self.functions.get(app.function_name.as_str()) else {
+            return unsupported(format_args!(
+                "the undeclared function {}",
+                app.function_name
+            ));
+        };
+        if self.check_definedness && !function.pres.is_empty() {
+            let parameters: Variables = function
+                .parameters
+                .iter()
+                .map(|parameter| parameter.name.clone())
+                .zip(arguments.iter().cloned())
+                .collect();
+            let no_labels = FxHashMap::default();
+            let callee = Env::new(self.functions, &parameters, &parameters, &no_labels, false);
+            for pre in &function.pres {
+                let condition = callee.lower(pre, &mut Vec::new())?;
+                self.obligation(
+                    obligations,
+                    ObligationKind::Precondition,
+                    condition,
+                    app.position,
+                    pre.position(),
+                    format!(
+                        "Precondition of function {} might not hold. Assertion {pre} might not hold.",
+                        function.name
+                    ),
+                )?;
+            }
+        }
+        Ok(apply(&symbol(&app.function_name), &arguments))
+    }
+}
+
+struct Lowerer<'p> {
+    program: &'p vir::low::Program,
+    functions: &'p FxHashMap<&'p str, &'p FunctionDecl>,
+    methods: FxHashMap<&'p str, &'p MethodDecl>,
+    /// The sort of each variable of the procedure or method being lowered.
+    sorts: FxHashMap<String, String>,
+    out: String,
+    checks: Vec<VerificationError>,
+    /// The number of fresh constants declared so far.
+    fresh: usize,
+}
+
+impl<'p> Lowerer<'p> {
+    fn command(&mut self, command: impl fmt::Display) {
+        writeln!(self.out, "{command}").unwrap();
+    }
+
+    fn comment(&mut self, text: &str) {
+        for line in text.lines() {
+            writeln!(self.out, "; {line}").unwrap();
+        }
+    }
+
+    fn fresh_constant(&mut self, name: &str, sort: &str) -> String {
+        self.fresh += 1;
+        let constant = symbol(&format!("{name}@{}", self.fresh));
+        self.command(format_args!("(declare-const {constant} {sort})"));
+        constant
+    }
+
+    /// Declares a fresh constant for the variable `name`, which becomes its
+    /// value in `variables`.
+    fn havoc(
+        &mut self,
+        variables: &mut Variables,
+        name: &str,
+        ty: &Type,
+    ) -> LoweringResult<String> {
+        let sort = sort(ty)?;
+        let constant = self.fresh_constant(name, &sort);
+        self.sorts.insert(name.to_string(), sort);
+        variables.insert(name.to_string(), constant.clone());
+        Ok(constant)
+    }
+
+    fn assume(&mut self, state: &mut State, condition: String) {
+        if condition == "true" {
+            return;
+        }
+        let path_condition = self.fresh_constant("pc", "Bool");
+        self.command(format_args!(
+            "(assert (= {path_condition} (and {} {condition})))",
+            state.path_condition
+        ));
+        state.path_condition = path_condition;
+    }
+
+    /// Checks that `condition` holds whenever `state` is reached.
+    fn check(&mut self, state: &State, condition: &str, error: VerificationError) {
+        if condition == "true" {
+            return;
+        }
+        self.command(format_args!(
+            "(push 1)\n(assert {})\n(assert (not {condition}))\n(check-sat)\n(pop 1)",
+            state.path_condition
+        ));
+        self.checks.push(error);
+    }
+
+    /// Lowers the pure `expression`, checking that it is well-defined in
+    /// `state`.
+    fn pure(
+        &mut self,
+        frame: &Frame,
+        state: &State,
+        expression: &Expression,
+        context: Context,
+    ) -> LoweringResult<String> {
+        let env = Env::new(
+            self.functions,
+            &state.variables,
+            &frame.old,
+            &frame.labels,
+            true,
+        );
+        let mut obligations = Vec::new();
+        let term = env.lower(expression, &mut obligations)?;
+        for obligation in obligations {
+            self.check(state, &obligation.condition, obligation.error(context));
+        }
+        Ok(term)
+    }
+
+    /// The state after the control flow of `states` merges.
+    fn join(&mut self, mut states: Vec<State>) -> LoweringResult<State> {
+        if states.len() == 1 {
+            return Ok(states.pop().unwrap());
+        }
+        let path_conditions: Vec<_> = states
+            .iter()
+            .map(|state| state.path_condition.as_str())
+            .collect();
+        let path_condition = self.fresh_constant("pc", "Bool");
+        self.command(format_args!(
+            "(assert (= {path_condition} (or {})))",
+            path_conditions.join(" ")
+        ));
+        let mut names: Vec<_> = states
+            .iter()
+            .flat_map(|state| state.variables.keys().cloned())
+            .collect();
+        names.sort();
+        names.dedup();
+        let mut variables = Variables::default();
+        for name in names {
+            let first = states[0].variables.get(&name);
+            if first.is_some()
+                && states
+                    .iter()
+                    .all(|state| state.variables.get(&name) == first)
+            {
+                variables.insert(name, first.unwrap().clone());
+                continue;
+            }
+            let constant = self.fresh_constant(&name, &self.sorts[&name].clone());
+            for state in &states {
+                if let Some(value) = state.variables.get(&name) {
+                    self.command(format_args!(
+                        "(assert (=> {} (= {constant} {value})))",
+                        state.path_condition
+                    ));
+                }
+            }
+            variables.insert(name, constant);
+        }
+        Ok(State {
+            variables,
+            path_condition,
+        })
+    }
+
+    fn program(&mut self) -> LoweringResult<()> {
+        let program = self.program;
+        if let Some(predicate) = program.predicates.first() {
+            return unsupported(format_args!("the predicate {}", predicate.name));
+        }
+        self.command(format_args!("(declare-sort {REF_SORT} 0)"));
+        for domain in &program.domains {
+            self.command(format_args!("(declare-sort {} 0)", symbol(&domain.name)));
+        }
+        for domain in &program.domains {
+            self.domain_functions(domain)?;
+        }
+        for function in &program.functions {
+            let parameters = function
+                .parameters
+                .iter()
+                .map(|parameter| sort(&parameter.ty))
+                .collect::<LoweringResult<Vec<_>>>()?;
+            self.command(format_args!(
+                "(declare-fun {} ({}) {})",
+                symbol(&function.name),
+                parameters.join(" "),
+                sort(&function.return_type)?
+            ));
+        }
+        let no_variables = Variables::default();
+        let no_labels = FxHashMap::default();
+        let env = Env::new(
+            self.functions,
+            &no_variables,
+            &no_variables,
+            &no_labels,
+            false,
+        );
+        for domain in &program.domains {
+            for axiom in &domain.axioms {
+                let body = env.lower(&axiom.body, &mut Vec::new())?;
+                self.comment(axiom.comment.as_deref().unwrap_or(&axiom.name));
+                self.command(format_args!("(assert {body})"));
+            }
+        }
+        for function in &program.functions {
+            self.function_axioms(function)?;
+        }
+        for function in &program.functions {
+            self.function(function)?;
+        }
+        for method in &program.methods {
+            self.method(method)?;
+        }
+        for procedure in &program.procedures {
+            self.procedure(procedure)?;
+        }
+        Ok(())
+    }
+
+    fn domain_functions(&mut self, domain: &DomainDecl) -> LoweringResult<()> {
+        let mut unique: FxHashMap<String, Vec<String>> = FxHashMap::default();
+        for function in &domain.functions {
+            let parameters = function
+                .parameters
+                .iter()
+                .map(|parameter| sort(&parameter.ty))
+                .collect::<LoweringResult<Vec<_>>>()?;
+            let return_sort = sort(&function.return_type)?;
+            let name = symbol(&function.name);
+            self.command(format_args!(
+                "(declare-fun {name} ({}) {return_sort})",
+                parameters.join(" ")
+            ));
+            if function.is_unique {
+                unique.entry(return_sort).or_default().push(name);
+            }
+        }
+        let mut unique: Vec<_> = unique
+            .into_values()
+            .filter(|names| names.len() > 1)
+            .collect();
+        unique.sort();
+        for names in unique {
+            self.command(format_args!("(assert (distinct {}))", names.join(" ")));
+        }
+        Ok(())
+    }
+
+    /// Asserts the definition and the postconditions of `function`.
+    fn function_axioms(&mut self, function: &FunctionDecl) -> LoweringResult<()> {
+        let mut parameters = Vec::new();
+        for parameter in &function.parameters {
+            parameters.push((symbol(&parameter.name), sort(&parameter.ty)?));
+        }
+        let arguments: Vec<_> = parameters.iter().map(|(name, _)| name.clone()).collect();
+        let application = apply(&symbol(&function.name), &arguments);
+        let no_variables = Variables::default();
+        let no_labels = FxHashMap::default();
+        let env = Env::new(
+            self.functions,
+            &no_variables,
+            &no_variables,
+            &no_labels,
+            false,
+        )
+        .binding(
+            function
+                .parameters
+                .iter()
+                .map(|parameter| parameter.name.clone())
+                .zip(arguments),
+        );
+        let pres = env.lower_all(&function.pres, &mut Vec::new())?;
+        let pres = and(&pres);
+        if let Some(body) = &function.body {
+            let body = env.lower(body, &mut Vec::new())?;
+            self.command(format_args!(
+                "(assert {})",
+                forall(
+                    &parameters,
+                    &application,
+                    format!("(=> {pres} (= {application} {body}))")
+                )
+            ));
+        }
+        let env = env.binding([(RESULT.to_string(), application.clone())]);
+        for post in &function.posts {
+            let post = env.lower(post, &mut Vec::new())?;
+            self.command(format_args!(
+                "(assert {})",
+                forall(&parameters, &application, format!("(=> {pres} {post})"))
+            ));
+        }
+        Ok(())
+    }
+
+    /// Checks that `function` is well-defined and satisfies its
+    /// postconditions.
+    fn function(&mut self, function: &FunctionDecl) -> LoweringResult<()> {
+        if function.pres.is_empty() && function.posts.is_empty() && function.body.is_none() {
+            return Ok(());
+        }
+        self.comment(&format!("function {}", function.name));
+        self.command("(push 1)");
+        self.sorts.clear();
+        let mut variables = Variables::default();
+        for parameter in &function.parameters {
+            self.havoc(&mut variables, &parameter.name, &parameter.ty)?;
+        }
+        let frame = Frame {
+            old: variables.clone(),
+            labels: FxHashMap::default(),
+        };
+        let mut state = State {
+            variables,
+            path_condition: "true".to_string(),
+        };
+        let context = Context::new(
+            "function.not.wellformed",
+            "Function might not be well-formed.",
+            Position::default(),
+        );
+        for pre in &function.pres {
+            let pre = self.pure(&frame, &state, pre, context)?;
+            self.assume(&mut state, pre);
+        }
+        let body = match &function.body {
+            Some(body) => Some(self.pure(&frame, &state, body, context)?),
+            None => None,
+        };
+        let result = self.havoc(&mut state.variables, RESULT, &function.return_type)?;
+        if let Some(body) = body {
+            self.command(format_args!("(assert (= {result} {body}))"));
+        }
+        for post in &function.posts {
+            let condition = self.pure(&frame, &state, post, context)?;
+            if function.body.is_some() {
+                self.check(
+                    &state,
+                    &condition,
+                    error(
+                        "postcondition.violated:assertion.false".to_string(),
+                        post.position(),
+                        post.position(),
+                        format!(
+                            "Postcondition of {} might not hold. Assertion {post} might not hold.",
+                            function.name
+                        ),
+                    ),
+                );
+            }
+            self.assume(&mut state, condition);
+        }
+        self.command("(pop 1)");
+        Ok(())
+    }
+
+    /// Checks that the body of `method`, if any, satisfies its
+    /// postconditions.
+    fn method(&mut self, method: &MethodDecl) -> LoweringResult<()> {
+        let Some(statements) = &method.body else {
+            return Ok(());
+        };
+        self.comment(&format!("method {}", method.name));
+        self.command("(push 1)");
+        self.sorts.clear();
+        let mut variables = Variables::default();
+        for variable in method.parameters.iter().chain(&method.targets) {
+            self.havoc(&mut variables, &variable.name, &variable.ty)?;
+        }
+        let frame = Frame {
+            old: variables.clone(),
+            labels: FxHashMap::default(),
+        };
+        let mut state = State {
+            variables,
+            path_condition: "true".to_string(),
+        };
+        let context = Context::new(
+            "contract.not.wellformed",
+            "Contract might not be well-formed.",
+            Position::default(),
+        );
+        for pre in &method.pres {
+            let pre = self.pure(&frame, &state, pre, context)?;
+            self.assume(&mut state, pre);
+        }
+        self.statements(&frame, &mut state, statements)?;
+        for post in &method.posts {
+            let condition = self.pure(&frame, &state, post, context)?;
+            self.check(
+                &state,
+                &condition,
+                error(
+                    "postcondition.violated:assertion.false".to_string(),
+                    post.position(),
+                    post.position(),
+                    format!(
+                        "Postcondition of {} might not hold. Assertion {post} might not hold.",
+                        method.name
+                    ),
+                ),
+            );
+        }
+        self.command("(pop 1)");
+        Ok(())
+    }
+
+    fn procedure(&mut self, procedure: &ProcedureDecl) -> LoweringResult<()> {
+        self.comment(&format!("procedure {}", procedure.name));
+        self.command("(push 1)");
+        self.sorts.clear();
+        let mut variables = Variables::default();
+        for local in &procedure.locals {
+            self.havoc(&mut variables, &local.name, &local.ty)?;
+        }
+        let mut frame = Frame {
+            old: variables.clone(),
+            labels: FxHashMap::default(),
+        };
+        let blocks = topological_order(procedure)?;
+        let mut incoming: FxHashMap<&str, Vec<State>> = FxHashMap::default();
+        if let Some(entry) = blocks.first() {
+            incoming.insert(
+                &entry.label.name,
+                vec![State {
+                    variables,
+                    path_condition: "true".to_string(),
+                }],
+            );
+        }
+        for block in blocks {
+            let states = incoming.remove(block.label.name.as_str()).unwrap();
+            let mut state = self.join(states)?;
+            self.comment(&format!("block {}", block.label.name));
+            frame
+                .labels
+                .insert(block.label.name.clone(), state.variables.clone());
+            self.statements(&frame, &mut state, &block.statements)?;
+            match &block.successor {
+                Successor::Return => {}
+                Successor::Goto(target) => incoming.entry(&target.name).or_default().push(state),
+                Successor::GotoSwitch(targets) => {
+                    for (test, target) in targets {
+                        let context = Context::new(
+                            "if.failed",
+                            "Conditional statement might fail.",
+                            test.position(),
+                        );
+                        let test = self.pure(&frame, &state, test, context)?;
+                        let mut taken = state.clone();
+                        self.assume(&mut taken, test.clone());
+                        incoming.entry(&target.name).or_default().push(taken);
+                        self.assume(&mut state, format!("(not {test})"));
+                    }
+                    // Viper encodes the fall-through case as `assert false`.
+                    let position = Position::default();
+                    self.check(
+                        &state,
+                        "false",
+                        error(
+                            "assert.failed:assertion.false".to_string(),
+                            position,
+                            position,
+                            "Assert might fail. Assertion false might not hold.".to_string(),
+                        ),
+                    );
+                }
+            }
+        }
+        self.command("(pop 1)");
+        Ok(())
+    }
+
+    fn statements(
+        &mut self,
+        frame: &Frame,
+        state: &mut State,
+        statements: &[Statement],
+    ) -> LoweringResult<()> {
+        for statement in statements {
+            self.statement(frame, state, statement)?;
+        }
+        Ok(())
+    }
+
+    fn statement(
+        &mut self,
+        frame: &Frame,
+        state: &mut State,
+        statement: &Statement,
+    ) -> LoweringResult<()> {
+        match statement {
+            Statement::Comment(comment) => self.comment(&comment.comment),
+            Statement::LogEvent(log_event) => {
+                let context =
+                    Context::new("inhale.failed", "Inhale might fail.", Position::default());
+                let event = self.pure(frame, state, &log_event.expression, context)?;
+                self.assume(state, event);
+            }
+            Statement::Assume(assume) => {
+                let context = Context::new("inhale.failed", "Inhale might fail.", assume.position);
+                let condition = self.pure(frame, state, &assume.expression, context)?;
+                self.assume(state, condition);
+            }
+            Statement::Inhale(inhale) => {
+                let context = Context::new("inhale.failed", "Inhale might fail.", inhale.position);
+                let condition = self.pure(frame, state, &inhale.expression, context)?;
+                self.assume(state, condition);
+            }
+            Statement::Assert(assert) => {
+                let context = Context::new("assert.failed", "Assert might fail.", assert.position);
+                self.assert(frame, state, &assert.expression, context)?;
+            }
+            Statement::Exhale(exhale) => {
+                let context = Context::new("exhale.failed", "Exhale might fail.", exhale.position);
+                self.assert(frame, state, &exhale.expression, context)?;
+            }
+            Statement::Fold(_) | Statement::Unfold(_) | Statement::ApplyMagicWand(_) => {
+                return unsupported(format_args!("the statement {statement}"));
+            }
+            Statement::MethodCall(call) => self.method_call(frame, state, call)?,
+            Statement::Assign(assign) => {
+                let context = Context::new(
+                    "assignment.failed",
+                    "Assignment might fail.",
+                    assign.position,
+                );
+                let value = self.pure(frame, state, &assign.value, context)?;
+                let target =
+                    self.havoc(&mut state.variables, &assign.target.name, &assign.target.ty)?;
+                self.command(format_args!("(assert (= {target} {value}))"));
+            }
+            Statement::Conditional(conditional) => {
+                let context = Context::new(
+                    "if.failed",
+                    "Conditional statement might fail.",
+                    conditional.position,
+                );
+                let guard = self.pure(frame, state, &conditional.guard, context)?;
+                let mut then_state = state.clone();
+                self.assume(&mut then_state, guard.clone());
+                self.statements(frame, &mut then_state, &conditional.then_branch)?;
+                let mut else_state = state.clone();
+                self.assume(&mut else_state, format!("(not {guard})"));
+                self.statements(frame, &mut else_state, &conditional.else_branch)?;
+                *state = self.join(vec![then_state, else_state])?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Checks `expression` and assumes it afterwards, as Viper continues
+    /// after a failed assertion.
+    fn assert(
+        &mut self,
+        frame: &Frame,
+        state: &mut State,
+        expression: &Expression,
+        context: Context,
+    ) -> LoweringResult<()> {
+        let condition = self.pure(frame, state, expression, context)?;
+        self.check(
+            state,
+            &condition,
+            error(
+                format!("{}:assertion.false", context.error_id),
+                context.position,
+                expression.position(),
+                format!("{} Assertion {expression} might not hold.", context.message),
+            ),
+        );
+        self.assume(state, condition);
+        Ok(())
+    }
+
+    fn method_call(
+        &mut self,
+        frame: &Frame,
+        state: &mut State,
+        call: &statement::MethodCall,
+    ) -> LoweringResult<()> {
+        let Some(method) = self.methods.get(call.method_name.as_str()).copied() else {
+            return unsupported(format_args!("the undeclared method {}", call.method_name));
+        };
+        let context = Context::new("call.failed", "Method call might fail.", call.position);
+        let mut callee = Variables::default();
+        for (parameter, argument) in method.parameters.iter().zip(&call.arguments) {
+            let argument = self.pure(frame, state, argument, context)?;
+            callee.insert(parameter.name.clone(), argument);
+        }
+        let no_labels = FxHashMap::default();
+        for pre in &method.pres {
+            let condition = Env::new(self.functions, &callee, &callee, &no_labels, false)
+                .lower(pre, &mut Vec::new())?;
+            self.check(
+                state,
+                &condition,
+                error(
+                    "call.precondition:assertion.false".to_string(),
+                    call.position,
+                    pre.position(),
+                    format!(
+                        "The precondition of method {} might not hold. Assertion {pre} might not hold.",
+                        method.name
+                    ),
+                ),
+            );
+        }
+        let old = callee.clone();
+        for (target, declaration) in call.targets.iter().zip(&method.targets) {
+            let Expression::Local(local) = target else {
+                return unsupported(format_args!("the call target {target}"));
+            };
+            let value = self.havoc(
+                &mut state.variables,
+                &local.variable.name,
+                &local.variable.ty,
+            )?;
+            callee.insert(declaration.name.clone(), value);
+        }
+        for post in &method.posts {
+            let condition = Env::new(self.functions, &callee, &old, &no_labels, false)
+                .lower(post, &mut Vec::new())?;
+            self.assume(state, condition);
+        }
+        Ok(())
+    }
+}
+
+fn successors(block: &BasicBlock) -> Vec<&str> {
+    match &block.successor {
+        Successor::Return => Vec::new(),
+        Successor::Goto(target) => vec![&target.name],
+        Successor::GotoSwitch(targets) => targets
+            .iter()
+            .map(|(_, target)| target.name.as_str())
+            .collect(),
+    }
+}
+
+/// The blocks reachable from the entry block of `procedure`, in topological
+/// order.
+fn topological_order(procedure: &ProcedureDecl) -> LoweringResult<Vec<&BasicBlock>> {
+    let blocks: FxHashMap<&str, &BasicBlock> = procedure
+        .basic_blocks
+        .iter()
+        .map(|block| (block.label.name.as_str(), block))
+        .collect();
+    let Some(entry) = procedure.basic_blocks.first() else {
+        return Ok(Vec::new());
+    };
+    // `false` while the block is on the stack of the depth-first search.
+    let mut finished: FxHashMap<&str, bool> = FxHashMap::default();
+    let mut post_order = Vec::new();
+    finished.insert(&entry.label.name, false);
+    let mut stack = vec![(entry, successors(entry))];
+    while let Some((block, successors_left)) = stack.last_mut() {
+        let block = *block;
+        let Some(successor) = successors_left.pop() else {
+            finished.insert(&block.label.name, true);
+            post_order.push(block);
+            stack.pop();
+            continue;
+        };
+        match finished.get(successor) {
+            Some(false) => return unsupported("loops"),
+            Some(true) => {}
+            None => {
+                let Some(&successor_block) = blocks.get(successor) else {
+                    return unsupported(format_args!("the undeclared label {successor}"));
+                };
+                finished.insert(successor, false);
+                stack.push((successor_block, successors(successor_block)));
+            }
+        }
+    }
+    post_order.reverse();
+    Ok(post_order)
+}
@@ -0,0 +1,164 @@
+// © 2023, ETH Zurich
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! A verification backend that checks `vir::low` programs with the SMT solver
+//! directly, without starting a JVM.
+
+mod lowering;
+
+pub use lowering::{SmtScript, SmtUnsupported};
+use prusti_common::config;
+use std::{
+    io::{self, Read, Write},
+    process::{Child, Command, Stdio},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+    thread,
+};
+use viper::VerificationResultKind;
+
+/// Checks the verification conditions of an [`SmtScript`] with the solver at
+/// `SMT_SOLVER_PATH`.
+pub struct SmtVerifier {
+    script: SmtScript,
+    stop_handle: SmtStopHandle,
+}
+
+/// Allows stopping a running [`SmtVerifier`] from another thread.
+#[derive(Clone, Default)]
+pub struct SmtStopHandle {
+    solver: Arc<Mutex<Option<Child>>>,
+    stopped: Arc<AtomicBool>,
+}
+
+impl SmtStopHandle {
+    /// Stops the verification by killing the solver. The verification then
+    /// returns [`VerificationResultKind::Cancelled`].
+    pub fn stop(&self) {
+        self.stopped.store(true, Ordering::SeqCst);
+        if let Some(solver) = self.solver.lock().unwrap().as_mut() {
+            let _ = solver.kill();
+        }
+    }
+
+    fn is_stopped(&self) -> bool {
+        self.stopped.load(Ordering::SeqCst)
+    }
+}
+
+impl SmtVerifier {
+    pub fn new(script: SmtScript) -> Self {
+        SmtVerifier {
+            script,
+            stop_handle: SmtStopHandle::default(),
+        }
+    }
+
+    pub fn stop_handle(&self) -> SmtStopHandle {
+        self.stop_handle.clone()
+    }
+
+    /// The input of the solver.
+    pub fn smt_script(&self) -> String {
+        let mut script = String::new();
+        if config::assert_timeout() > 0 {
+            script.push_str(&format!(
+                "(set-option :timeout {})\n",
+                config::assert_timeout()
+            ));
+        }
+        script.push_str(&format!(
+            "(set-option :smt.qi.eager_threshold {})\n",
+            config::smt_qi_eager_threshold()
+        ));
+        script.push_str(&self.script.commands);
+        script.push_str("(exit)\n");
+        script
+    }
+
+    pub fn verify(&mut self) -> VerificationResultKind {
+        if self.script.checks.is_empty() {
+            return VerificationResultKind::Success;
+        }
+        let output = self.run_solver(self.smt_script());
+        if self.stop_handle.is_stopped() {
+            return VerificationResultKind::Cancelled;
+        }
+        let output = match output {
+            Ok(output) => output,
+            Err(error) => {
+                return VerificationResultKind::ConsistencyErrors(vec![format!(
+                    "Failed to run the SMT solver: {error}"
+                )]);
+            }
+        };
+        let solver_errors: Vec<_> = output
+            .lines()
+            .filter(|line| line.starts_with("(error"))
+            .map(|line| line.to_string())
+            .collect();
+        if !solver_errors.is_empty() {
+            return VerificationResultKind::ConsistencyErrors(solver_errors);
+        }
+        let answers: Vec<_> = output
+            .lines()
+            .filter(|line| matches!(*line, "sat" | "unsat" | "unknown"))
+            .collect();
+        if answers.len() != self.script.checks.len() {
+            return VerificationResultKind::ConsistencyErrors(vec![format!(
+                "The SMT solver answered {} of {} checks",
+                answers.len(),
+                self.script.checks.len()
+            )]);
+        }
+        // An `unknown` answer, e.g. due to a timeout, is reported as a failure
+        // like Viper does.
+        let errors: Vec<_> = answers
+            .into_iter()
+            .zip(&self.script.checks)
+            .filter(|(answer, _)| *answer != "unsat")
+            .map(|(_, error)| error.clone())
+            .collect();
+        if errors.is_empty() {
+            VerificationResultKind::Success
+        } else {
+            VerificationResultKind::Failure(errors)
+        }
+    }
+
+    fn run_solver(&self, input: String) -> io::Result<String> {
+        let mut solver = Command::new(config::smt_solver_path())
+            .args(["-smt2", "-in"])
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()?;
+        let mut stdin = solver.stdin.take().unwrap();
+        let mut stdout = solver.stdout.take().unwrap();
+        {
+            let mut running = self.stop_handle.solver.lock().unwrap();
+            if self.stop_handle.is_stopped() {
+                let _ = solver.kill();
+            }
+            *running = Some(solver);
+        }
+        // The solver answers while it reads the script, so the script is
+        // written from another thread.
+        let writer = thread::spawn(move || stdin.write_all(input.as_bytes()));
+        let mut output = String::new();
+        let read = stdout.read_to_string(&mut output);
+        let written = writer.join().unwrap();
+        let solver = self.stop_handle.solver.lock().unwrap().take();
+        if let Some(mut solver) = solver {
+            solver.wait()?;
+        }
+        read?;
+        written?;
+        Ok(output)
+    }
+}
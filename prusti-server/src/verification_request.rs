@@ -9,22 +9,29 @@ use rustc_hash::FxHasher;
 use std::hash::{Hash, Hasher};
 use viper::{self, VerificationBackend};
 
-#[derive(Debug, Clone, serde::Serialize, serde::Deserialize, Hash)]
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub struct VerificationRequest {
     pub program: Program,
     pub backend_config: ViperBackendConfig,
+    /// The number of seconds after which the verification is stopped and
+    /// reported as timed out.
+    #[serde(default)]
+    pub deadline: Option<u64>,
 }
 
 impl VerificationRequest {
+    /// The key of the request in the verification cache. The deadline does not
+    /// affect the outcome of a completed verification, so it is not hashed.
     pub(crate) fn get_hash(&self) -> u64 {
         let mut hasher = FxHasher::default();
-        self.hash(&mut hasher);
+        self.program.hash(&mut hasher);
+        self.backend_config.hash(&mut hasher);
         hasher.finish()
     }
 }
 
 /// The configuration for the viper backend, (i.e. verifier).
-/// Expresses which backend (silicon or carbon) should be used, and provides command-line arguments
+/// Expresses which backend (silicon, carbon or smt) should be used, and provides command-line arguments
 /// to the viper verifier.
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize, Eq, PartialEq, Hash)]
 pub struct ViperBackendConfig {
@@ -35,52 +42,102 @@ pub struct ViperBackendConfig {
 impl ViperBackendConfig {
     pub fn new(backend: VerificationBackend) -> Self {
         let mut verifier_args = config::extra_verifier_args();
-        match backend {
-            VerificationBackend::Silicon => {
-                verifier_args.push(format!(
-                    "--numberOfErrorsToReport={}",
-                    config::num_errors_per_function()
-                ));
-                if config::use_more_complete_exhale() {
-                    verifier_args.push("--enableMoreCompleteExhale".to_string());
-                }
-                if config::counterexample() {
-                    verifier_args.push("--counterexample".to_string());
-                    verifier_args.push("mapped".to_string());
-                }
-                if let Some(number) = config::number_of_parallel_verifiers() {
-                    verifier_args.push("--numberOfParallelVerifiers".to_string());
-                    verifier_args.push(number.to_string());
-                }
+        add_backend_verifier_args(backend, &mut verifier_args);
+        Self {
+            backend,
+            verifier_args,
+        }
+    }
+
+    /// The configuration for verifying the request with `backend` instead of
+    /// the SMT backend or the portfolio, whose arguments are not specific to a
+    /// backend. The arguments of the request are kept and followed by the ones
+    /// that Prusti passes to `backend`.
+    pub(crate) fn with_backend(&self, backend: VerificationBackend) -> Self {
+        let mut verifier_args = self.verifier_args.clone();
+        add_backend_verifier_args(backend, &mut verifier_args);
+        Self {
+            backend,
+            verifier_args,
+        }
+    }
+}
 
-                verifier_args.extend(vec![
-                    "--disableTerminationPlugin".to_string(),
-                    "--assertTimeout".to_string(),
-                    config::assert_timeout().to_string(),
-                    "--proverConfigArgs".to_string(),
-                    // model.partial changes the default case of functions in counterexamples
-                    // to #unspecified
-                    format!(
-                        "smt.qi.eager_threshold={} model.partial={}",
-                        config::smt_qi_eager_threshold(),
-                        config::counterexample()
-                    ),
-                    "--logLevel".to_string(),
-                    "ERROR".to_string(),
-                ]);
+/// Adds the arguments that Prusti passes to `backend`.
+fn add_backend_verifier_args(backend: VerificationBackend, verifier_args: &mut Vec<String>) {
+    match backend {
+        VerificationBackend::Silicon => {
+            verifier_args.push(format!(
+                "--numberOfErrorsToReport={}",
+                config::num_errors_per_function()
+            ));
+            if config::use_more_complete_exhale() {
+                verifier_args.push("--enableMoreCompleteExhale".to_string());
+            }
+            if config::counterexample() {
+                verifier_args.push("--counterexample".to_string());
+                verifier_args.push("mapped".to_string());
+            }
+            if let Some(number) = config::number_of_parallel_verifiers() {
+                verifier_args.push("--numberOfParallelVerifiers".to_string());
+                verifier_args.push(number.to_string());
+            }
 
-                if let Some(check_timeout) = config::check_timeout() {
-                    verifier_args.push("--checkTimeout".to_string());
-                    verifier_args.push(check_timeout.to_string());
-                }
+            verifier_args.extend(vec![
+                "--disableTerminationPlugin".to_string(),
+                "--assertTimeout".to_string(),
+                config::assert_timeout().to_string(),
+                "--proverConfigArgs".to_string(),
+                // model.partial changes the default case of functions in counterexamples
+                // to #unspecified
+                format!(
+                    "smt.qi.eager_threshold={} model.partial={}",
+                    config::smt_qi_eager_threshold(),
+                    config::counterexample()
+                ),
+                "--logLevel".to_string(),
+                "ERROR".to_string(),
+            ]);
+
+            if let Some(check_timeout) = config::check_timeout() {
+                verifier_args.push("--checkTimeout".to_string());
+                verifier_args.push(check_timeout.to_string());
+            }
+        }
+        VerificationBackend::Carbon => {
+            verifier_args.extend(vec!["--disableAllocEncoding".to_string()]);
+            add_boogie_option(
+                verifier_args,
+                format!(
+                    "/proverOpt:O:smt.qi.eager_threshold={}",
+                    config::smt_qi_eager_threshold()
+                ),
+            );
+            // Boogie has no timeout per assertion, only per procedure, which
+            // corresponds to the timeout per check of Silicon. Without it, the
+            // deadline of the request is used, see `verify_with_backend`.
+            if let Some(check_timeout) = config::check_timeout() {
+                add_boogie_option(verifier_args, format!("/timeLimit:{check_timeout}"));
             }
-            VerificationBackend::Carbon => {
-                verifier_args.extend(vec!["--disableAllocEncoding".to_string()]);
+            if let Some(number) = config::number_of_parallel_verifiers() {
+                add_boogie_option(verifier_args, format!("/vcsCores:{number}"));
             }
         }
-        Self {
-            backend,
-            verifier_args,
+        // The SMT backend is configured directly from the Prusti flags, and
+        // the portfolio configures the backends it runs.
+        VerificationBackend::Smt | VerificationBackend::Portfolio => {}
+    }
+}
+
+/// Adds `option` to the options that Carbon passes to Boogie. Carbon takes
+/// them from a single `--boogieOpt` argument, separated by spaces.
+pub(crate) fn add_boogie_option(verifier_args: &mut Vec<String>, option: String) {
+    let position = verifier_args.iter().position(|arg| arg == "--boogieOpt");
+    match position.and_then(|position| verifier_args.get_mut(position + 1)) {
+        Some(options) => {
+            options.push(' ');
+            options.push_str(&option);
         }
+        None => verifier_args.extend(["--boogieOpt".to_string(), option]),
     }
 }
@@ -1,10 +1,12 @@
 use lazy_static::lazy_static;
 use prusti_common::vir::*;
 use prusti_server::{
-    spawn_server_thread, tokio::runtime::Builder, PrustiClient, VerificationRequest,
+    spawn_server_thread, tokio::runtime::Builder, JobStatus, PrustiClient, VerificationRequest,
     ViperBackendConfig,
 };
-use viper::VerificationResult;
+use std::{thread, time::Duration};
+use viper::{VerificationBackend, VerificationResult, VerificationResultKind};
+use vir::{common::check_mode::CheckMode, low as vir_low};
 
 lazy_static! {
     // only start the jvm & server once
@@ -20,8 +22,8 @@ fn consistency_error() {
         });
     });
 
-    match result {
-        VerificationResult::ConsistencyErrors(errors) => assert_eq!(errors.len(), 1),
+    match result.kind {
+        VerificationResultKind::ConsistencyErrors(errors) => assert_eq!(errors.len(), 1),
         other => panic!("consistency errors not identified, instead found {other:?}"),
     }
 }
@@ -30,18 +32,163 @@ fn consistency_error() {
 fn empty_program() {
     let result = process_program(|_| ());
 
-    match result {
-        VerificationResult::Success => {}
+    match result.kind {
+        VerificationResultKind::Success => {}
         other => panic!("empty program not verified successfully, instead found {other:?}"),
     }
 }
 
+#[test]
+fn job_api() {
+    let client = PrustiClient::new(SERVER_ADDRESS.clone()).expect("Could not connect to server!");
+    let runtime = Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .expect("failed to construct Tokio runtime");
+    runtime.block_on(async {
+        let id = client
+            .submit_job(&verification_request(|_| ()))
+            .await
+            .expect("Job submission failed");
+        let result = loop {
+            let job = client.job(id).await.expect("Job status request failed");
+            match job.status {
+                JobStatus::Finished { result } => break result,
+                JobStatus::Cancelled => panic!("job was unexpectedly cancelled"),
+                JobStatus::Queued { .. } | JobStatus::Running => {
+                    thread::sleep(Duration::from_millis(100))
+                }
+            }
+        };
+        assert!(result.is_success());
+
+        let jobs = client.list_jobs().await.expect("Job list request failed");
+        assert!(jobs.iter().any(|job| job.id == id));
+
+        // Cancelling a finished job forgets it.
+        let job = client
+            .cancel_job(id)
+            .await
+            .expect("Job cancellation failed");
+        assert!(matches!(job.status, JobStatus::Finished { .. }));
+        assert!(client.job(id).await.is_err());
+    });
+}
+
+#[test]
+fn metrics() {
+    process_program(|_| ());
+    let client = PrustiClient::new(SERVER_ADDRESS.clone()).expect("Could not connect to server!");
+    let metrics = Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .expect("failed to construct Tokio runtime")
+        .block_on(client.metrics())
+        .expect("Metrics request failed");
+    for name in [
+        "prusti_server_queued_jobs",
+        "prusti_server_workers",
+        "prusti_server_cache_hit_ratio",
+        "prusti_server_jvm_attach_duration_seconds_count",
+        "prusti_server_verification_duration_seconds_bucket",
+    ] {
+        assert!(
+            metrics.contains(name),
+            "missing metric {name} in:\n{metrics}"
+        );
+    }
+}
+
+#[test]
+fn smt_backend() {
+    let result = process_low_assertion(true);
+    assert!(
+        result.is_success(),
+        "valid assertion not verified, instead found {:?}",
+        result.kind
+    );
+
+    let result = process_low_assertion(false);
+    match result.kind {
+        VerificationResultKind::Failure(errors) => assert_eq!(errors.len(), 1),
+        other => panic!("failing assertion not identified, instead found {other:?}"),
+    }
+}
+
+#[test]
+fn viper_program_text_fallback() {
+    let request = verification_request(|_| ());
+    let text = prusti_server::viper_program_text(&request.program, || {
+        panic!("a supported program is printed with Viper")
+    });
+    assert!(text.starts_with("//@ vir legacy\n//@ program dummy\n"));
+
+    // Floats are not supported by the Rust printer.
+    let request = verification_request(|program| {
+        program.fields.push(Field {
+            name: "value".to_string(),
+            typ: Type::Float(Float::F32),
+        });
+    });
+    let text =
+        prusti_server::viper_program_text(&request.program, || "printed by Viper".to_string());
+    assert_eq!(text, "printed by Viper");
+}
+
+/// Verifies a procedure asserting `value` with the SMT backend.
+fn process_low_assertion(value: bool) -> VerificationResult {
+    let procedure = vir_low::ProcedureDecl {
+        name: "assertion".to_string(),
+        locals: vec![],
+        basic_blocks: vec![vir_low::BasicBlock {
+            label: vir_low::Label {
+                name: "start".to_string(),
+            },
+            statements: vec![vir_low::Statement::assert_no_pos(value.into())],
+            successor: vir_low::Successor::Return,
+        }],
+    };
+    let request = VerificationRequest {
+        program: prusti_common::vir::program::Program::Low(vir_low::Program {
+            name: "smt".to_string(),
+            check_mode: CheckMode::Both,
+            domains: vec![],
+            predicates: vec![],
+            functions: vec![],
+            methods: vec![],
+            procedures: vec![procedure],
+        }),
+        backend_config: ViperBackendConfig::new(VerificationBackend::Smt),
+        deadline: None,
+    };
+    let client = PrustiClient::new(SERVER_ADDRESS.clone()).expect("Could not connect to server!");
+    Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .expect("failed to construct Tokio runtime")
+        .block_on(client.verify(request))
+        .expect("Verification request failed")
+}
+
 fn process_program<F>(configure: F) -> VerificationResult
 where
     F: FnOnce(&mut Program),
 {
     let client = PrustiClient::new(SERVER_ADDRESS.clone()).expect("Could not connect to server!");
+    let request = verification_request(configure);
 
+    Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .expect("failed to construct Tokio runtime")
+        .block_on(client.verify(request))
+        .expect("Verification request failed")
+}
+
+fn verification_request<F>(configure: F) -> VerificationRequest
+where
+    F: FnOnce(&mut Program),
+{
     let mut program = Program {
         name: "dummy".to_string(),
         backend_types: vec![],
@@ -54,17 +201,11 @@ where
     };
     configure(&mut program);
 
-    let request = VerificationRequest {
+    VerificationRequest {
         program: prusti_common::vir::program::Program::Legacy(program),
         backend_config: ViperBackendConfig::new(
             prusti_common::config::viper_backend().parse().unwrap(),
         ),
-    };
-
-    Builder::new_current_thread()
-        .enable_all()
-        .build()
-        .expect("failed to construct Tokio runtime")
-        .block_on(client.verify(request))
-        .expect("Verification request failed")
+        deadline: None,
+    }
 }
@@ -0,0 +1,98 @@
+use prusti_common::vir::*;
+use prusti_server::{JobId, JobQueue, JobStatus, VerificationRequest, ViperBackendConfig};
+use viper::VerificationBackend;
+
+#[test]
+fn alternates_between_clients() {
+    let jobs = JobQueue::default();
+    submit(&jobs, "a", &["a1", "a2", "a3"]);
+    submit(&jobs, "b", &["b1", "b2"]);
+    submit(&jobs, "c", &["c1"]);
+
+    assert_eq!(take_all(&jobs), vec!["a1", "b1", "c1", "a2", "b2", "a3"]);
+}
+
+#[test]
+fn serves_a_new_client_before_the_last_served_one() {
+    let jobs = JobQueue::default();
+    submit(&jobs, "a", &["a1", "a2", "a3"]);
+    assert_eq!(take(&jobs), "a1");
+    submit(&jobs, "b", &["b1", "b2"]);
+
+    assert_eq!(take_all(&jobs), vec!["b1", "a2", "b2", "a3"]);
+}
+
+#[test]
+fn keeps_the_submission_order_of_a_client() {
+    let jobs = JobQueue::default();
+    submit(&jobs, "a", &["a1"]);
+    submit(&jobs, "b", &["b1"]);
+    submit(&jobs, "a", &["a2"]);
+    submit(&jobs, "b", &["b2"]);
+    submit(&jobs, "a", &["a3"]);
+
+    assert_eq!(take_all(&jobs), vec!["a1", "b1", "a2", "b2", "a3"]);
+}
+
+#[test]
+fn reports_the_queue_positions() {
+    let jobs = JobQueue::default();
+    let a = submit(&jobs, "a", &["a1", "a2"]);
+    let b = submit(&jobs, "b", &["b1"]);
+
+    assert_eq!(position(&jobs, a[0]), 0);
+    assert_eq!(position(&jobs, b[0]), 1);
+    assert_eq!(position(&jobs, a[1]), 2);
+
+    jobs.cancel(b[0]);
+    assert_eq!(jobs.info(b[0]).unwrap().status, JobStatus::Cancelled);
+    assert_eq!(position(&jobs, a[1]), 1);
+    assert_eq!(take_all(&jobs), vec!["a1", "a2"]);
+}
+
+fn submit(jobs: &JobQueue, client: &str, names: &[&str]) -> Vec<JobId> {
+    names
+        .iter()
+        .map(|name| jobs.submit(verification_request(name), client.to_string()))
+        .collect()
+}
+
+/// Takes the next job, which must exist, and returns the name of its program.
+fn take(jobs: &JobQueue) -> String {
+    let (_, request, _) = jobs.take_next();
+    request.program.get_name().to_string()
+}
+
+/// Takes all queued jobs, in processing order.
+fn take_all(jobs: &JobQueue) -> Vec<String> {
+    let mut names = vec![];
+    while jobs.queued_count() > 0 {
+        names.push(take(jobs));
+    }
+    names
+}
+
+fn position(jobs: &JobQueue, id: JobId) -> usize {
+    match jobs.info(id).unwrap().status {
+        JobStatus::Queued { position } => position,
+        status => panic!("job {id} is not queued: {status:?}"),
+    }
+}
+
+fn verification_request(name: &str) -> VerificationRequest {
+    let program = Program {
+        name: name.to_string(),
+        backend_types: vec![],
+        domains: vec![],
+        fields: vec![],
+        builtin_methods: vec![],
+        methods: vec![],
+        functions: vec![],
+        viper_predicates: vec![],
+    };
+    VerificationRequest {
+        program: prusti_common::vir::program::Program::Legacy(program),
+        backend_config: ViperBackendConfig::new(VerificationBackend::Silicon),
+        deadline: None,
+    }
+}
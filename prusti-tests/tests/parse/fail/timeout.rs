@@ -0,0 +1,9 @@
+use prusti_contracts::*;
+
+#[timeout(thirty)] //~ ERROR the `#[timeout]` attribute expects the timeout in seconds
+fn wrong() {}
+
+#[timeout(30)]
+fn right() {}
+
+fn main() {}
@@ -0,0 +1,12 @@
+// compile-flags: -Pcounterexample=true
+
+use prusti_contracts::*;
+
+fn check(a: [i32; 2]) { //~ NOTE counterexample for "a"
+    //~^ final value:   [
+    //~| 3,
+    //~| 4,
+    assert!(a[0] != 3 || a[1] != 4); //~ ERROR the asserted expression might not hold
+}
+
+fn main() {}
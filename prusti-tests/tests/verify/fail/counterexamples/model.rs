@@ -0,0 +1,19 @@
+// compile-flags: -Pcounterexample=true
+
+use prusti_contracts::*;
+
+use std::vec::Vec;
+
+#[model]
+struct Vec<#[concrete] i32> {
+    len: usize,
+}
+
+// The counterexample shows the model instead of the fields of the vector.
+#[requires(v.model().len == 3)]
+fn check(v: Vec<i32>) { //~ NOTE counterexample for "v"
+    //~^ len: 3
+    prusti_assert!(v.model().len != 3); //~ ERROR the asserted expression might not hold
+}
+
+fn main() {}
@@ -0,0 +1,12 @@
+// compile-flags: -Pcounterexample=true
+
+use prusti_contracts::*;
+
+fn reset(x: &mut i32) { //~ NOTE counterexample for "x"
+    //~^ initial value: ref(5)
+    //~| final value:   ref(5)
+    assert!(*x != 5); //~ ERROR the asserted expression might not hold
+    *x = 0;
+}
+
+fn main() {}
@@ -0,0 +1,13 @@
+// compile-flags: -Pcounterexample=true
+
+use prusti_contracts::*;
+
+#[requires(s.len() == 2)]
+fn check(s: &[i32]) { //~ NOTE counterexample for "s"
+    //~^ final value:   ref([
+    //~| 3,
+    //~| 4,
+    assert!(s[0] != 3 || s[1] != 4); //~ ERROR the asserted expression might not hold
+}
+
+fn main() {}
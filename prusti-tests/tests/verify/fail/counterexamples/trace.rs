@@ -0,0 +1,13 @@
+// compile-flags: -Pcounterexample=true -Pcounterexample_trace=true
+
+use prusti_contracts::*;
+
+fn check(x: i32) { //~ NOTE counterexample for "x"
+    if x > 5 {
+        assert!(x != 7); //~ ERROR the asserted expression might not hold
+        //~^ NOTE counterexample trace:
+        //~| bb0 (line 6): x = 7
+    }
+}
+
+fn main() {}
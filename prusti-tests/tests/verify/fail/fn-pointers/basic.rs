@@ -0,0 +1,47 @@
+use prusti_contracts::*;
+
+#[requires(x < 100)]
+#[ensures(result == x + 1)]
+fn increment(x: u32) -> u32 {
+    x + 1
+}
+
+#[requires(x < 100)]
+#[ensures(result > x)]
+fn increase(x: u32) -> u32 {
+    x + 2
+}
+
+#[requires(satisfies_spec(f, increase))]
+#[ensures(result > 10)]
+fn apply(f: fn(u32) -> u32) -> u32 {
+    f(10)
+}
+
+#[requires(satisfies_spec(f, increase))]
+fn apply_unchecked(f: fn(u32) -> u32, x: u32) -> u32 {
+    f(x) //~ ERROR precondition might not hold
+}
+
+struct Handler {
+    callback: fn(u32) -> u32,
+}
+
+#[requires(satisfies_spec(handler.callback, increase))]
+fn dispatch(handler: &Handler) -> u32 {
+    let result = (handler.callback)(5);
+    assert!(result > 5);
+    result
+}
+
+fn main() {
+    let f: fn(u32) -> u32 = increment;
+    let y = f(3);
+    assert!(y == 4);
+    assert!(y == 5); //~ ERROR the asserted expression might not hold
+
+    let g: fn(u32) -> u32 = increase;
+    apply(g);
+    apply(f); //~ ERROR precondition might not hold
+    dispatch(&Handler { callback: g });
+}
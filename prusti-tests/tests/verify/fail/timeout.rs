@@ -0,0 +1,10 @@
+use prusti_contracts::*;
+
+// A timeout of zero seconds is always exceeded.
+#[timeout(0)]
+fn no_time() {} //~ ERROR verification timed out
+
+#[timeout(30)]
+fn enough_time() {}
+
+fn main() {}
@@ -0,0 +1,13 @@
+// compile-flags: -Ptimeouts_as_warnings=true
+#![allow(dead_code)]
+use prusti_contracts::*;
+
+// A timeout of zero seconds is always exceeded.
+#[timeout(0)]
+fn no_time() {} //~ WARNING verification timed out
+
+// Other verification errors are still reported as errors.
+#[ensures(false)] //~ ERROR postcondition
+fn wrong() {}
+
+fn main() {}
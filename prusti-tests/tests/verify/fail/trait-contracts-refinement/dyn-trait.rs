@@ -0,0 +1,58 @@
+use prusti_contracts::*;
+
+trait Counter {
+    #[pure]
+    fn value(&self) -> usize;
+
+    #[requires(self.value() < 100)]
+    #[ensures(self.value() == old(self.value()) + 1)]
+    fn increment(&mut self);
+}
+
+struct Simple {
+    value: usize,
+}
+
+impl Counter for Simple {
+    #[pure]
+    fn value(&self) -> usize {
+        self.value
+    }
+
+    fn increment(&mut self) {
+        self.value += 1;
+    }
+}
+
+#[requires(counter.value() < 99)]
+#[ensures(counter.value() == old(counter.value()) + 2)]
+fn increment_twice(counter: &mut dyn Counter) {
+    counter.increment();
+    counter.increment();
+}
+
+fn increment_unchecked(counter: &mut dyn Counter) {
+    counter.increment(); //~ ERROR precondition might not hold
+}
+
+#[requires(counter.value() == 5)]
+fn read(counter: &dyn Counter) {
+    assert!(counter.value() == 5);
+    assert!(counter.value() == 6); //~ ERROR the asserted expression might not hold
+}
+
+#[requires(counter.value() < 100)]
+fn increment_boxed(mut counter: Box<dyn Counter>) {
+    counter.increment();
+    assert!(counter.value() > 0);
+}
+
+fn main() {
+    let simple = Simple { value: 5 };
+    let counter: &dyn Counter = &simple;
+    assert!(counter.value() == 6); //~ ERROR the asserted expression might not hold
+    let mut simple = Simple { value: 5 };
+    let counter: &mut dyn Counter = &mut simple;
+    counter.increment();
+    assert!(simple.value() == 5); //~ ERROR the asserted expression might not hold
+}
@@ -0,0 +1,47 @@
+use prusti_contracts::*;
+
+union Bits {
+    signed: i32,
+    unsigned: u32,
+}
+
+fn read_active() {
+    let bits = Bits { unsigned: 1 };
+    let value = unsafe { bits.unsigned };
+    assert!(value == 1);
+}
+
+fn read_inactive() {
+    let bits = Bits { unsigned: 1 };
+    let _value = unsafe { bits.signed }; //~ ERROR the accessed field of the union might not be its active field.
+}
+
+fn write_activates() {
+    let mut bits = Bits { unsigned: 1 };
+    bits.signed = -1;
+    let value = unsafe { bits.signed };
+    assert!(value == -1);
+    let _value = unsafe { bits.unsigned }; //~ ERROR the accessed field of the union might not be its active field.
+}
+
+pub union MaybeUninit {
+    uninit: (),
+    value: (i32, u32),
+}
+
+fn set_active_field() {
+    let mut maybe = MaybeUninit { uninit: () };
+    #[allow(unused_must_use, unused_variables)]
+    if false {
+        #[prusti::spec_only]
+        || -> bool { true };
+        unsafe { prusti_set_union_active_field(std::ptr::addr_of!(maybe.value)) };
+    }
+    maybe.value.0 = 1;
+    maybe.value.1 = 2;
+    let value = unsafe { maybe.value };
+    assert!(value.0 == 1);
+    assert!(value.1 == 2);
+}
+
+fn main() {}
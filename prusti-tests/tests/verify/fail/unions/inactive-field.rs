@@ -0,0 +1,24 @@
+use prusti_contracts::*;
+
+union Foo {
+    a: [i32; 1],
+    b: [i32; 1],
+}
+
+fn read_inactive_array() {
+    let foo = Foo { a: [0] };
+    let _value = unsafe { foo.b }; //~ ERROR the accessed field of the union might not be its active field.
+}
+
+#[derive(Clone, Copy)]
+union Bits {
+    signed: i32,
+    unsigned: u32,
+}
+
+#[pure]
+fn read_signed(bits: Bits) -> i32 {
+    unsafe { bits.signed } //~ ERROR the accessed field of the union might not be its active field.
+}
+
+fn main() {}
@@ -0,0 +1,53 @@
+use prusti_contracts::*;
+
+trait Counter {
+    #[pure]
+    fn value(&self) -> usize;
+
+    #[requires(self.value() < 100)]
+    #[ensures(self.value() == old(self.value()) + 1)]
+    fn increment(&mut self);
+}
+
+struct Simple {
+    value: usize,
+}
+
+impl Counter for Simple {
+    #[pure]
+    fn value(&self) -> usize {
+        self.value
+    }
+
+    fn increment(&mut self) {
+        self.value += 1;
+    }
+}
+
+#[requires(counter.value() < 99)]
+#[ensures(counter.value() == old(counter.value()) + 2)]
+fn increment_twice(counter: &mut dyn Counter) {
+    counter.increment();
+    counter.increment();
+}
+
+#[requires(counter.value() == 5)]
+fn read(counter: &dyn Counter) {
+    assert!(counter.value() == 5);
+}
+
+fn main() {
+    let simple = Simple { value: 5 };
+    let counter: &dyn Counter = &simple;
+    assert!(counter.value() == 5);
+    read(counter);
+    assert!(simple.value == 5);
+
+    let mut simple = Simple { value: 5 };
+    let counter: &mut dyn Counter = &mut simple;
+    increment_twice(counter);
+    assert!(simple.value() == 7);
+
+    let boxed: Box<dyn Counter> = Box::new(Simple { value: 5 });
+    assert!(boxed.value() == 5);
+}
@@ -0,0 +1,15 @@
+// compile-flags: -Pprint_collected_verification_items=true
+// rustc-env:PRUSTI_VERIFY_ONLY=selected
+// rustc-env:PRUSTI_SKIP_VERIFICATION=*::skipped
+
+use prusti_contracts::*;
+
+mod selected {
+    pub fn verified() {}
+    pub fn skipped() {}
+}
+
+#[ensures(false)]
+fn not_selected() {}
+
+fn main() {}
@@ -0,0 +1,73 @@
+// compile-flags: -Punsafe_core_proof=true
+
+use prusti_contracts::*;
+
+#[requires(own(p))]
+#[ensures(own(p))]
+unsafe fn reset(p: *mut u32) {
+    *p = 0;
+}
+
+#[requires(own(p))]
+unsafe fn consume(p: *mut u32) {
+    *p = 1;
+}
+
+#[requires(own(p) && own(q))]
+#[ensures(own(p) && own(q))]
+unsafe fn reset_both(p: *mut u32, q: *mut u32) {
+    reset(p);
+    reset(q);
+}
+
+#[requires(own(p))]
+#[ensures(own(p))]
+unsafe fn read_write(p: *mut u32) {
+    let value = std::ptr::read(p);
+    std::ptr::write(p, value);
+}
+
+#[requires(own(p))]
+#[ensures(own(p))]
+unsafe fn give_away(p: *mut u32) {  //~ ERROR: postcondition might not hold.
+    consume(p);
+}
+
+unsafe fn without_ownership(p: *mut u32) {
+    reset(p);   //~ ERROR: precondition might not hold.
+}
+
+#[requires(own(p))]
+#[ensures(own(p))]
+unsafe fn stale_after_write(p: *mut u32) {
+    *p = 5;
+    *p = 0;
+    assert!(*p == 5);   //~ ERROR: the asserted expression might not hold
+}
+
+#[requires(own(p))]
+#[ensures(own(p))]
+unsafe fn stale_after_call(p: *mut u32) {
+    *p = 5;
+    reset(p);
+    assert!(*p == 5);   //~ ERROR: the asserted expression might not hold
+}
+
+#[requires(own(p))]
+#[ensures(own(p) && *p == 5)]
+unsafe fn wrong_value(p: *mut u32) {  //~ ERROR: postcondition might not hold.
+    *p = 4;
+}
+
+#[requires(own(p))]
+#[ensures(own(p))]
+unsafe fn reassign(mut p: *mut u32, q: *mut u32) {  //~ ERROR: only if the function does not assign to its pointer arguments
+    p = q;
+}
+
+unsafe fn add_one(p: *mut u32) {
+    let q = p.add(1);
+    assert!(q == p);    //~ ERROR: the asserted expression might not hold
+}
+
+fn main() {}
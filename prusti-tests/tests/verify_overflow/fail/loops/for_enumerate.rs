@@ -0,0 +1,11 @@
+use prusti_contracts::*;
+use std::{iter::Enumerate, slice::Iter};
+
+fn first_index<T>(s: &[T]) {
+    for (i, _) in s.iter().enumerate() {
+        body_invariant!(loop_iterator::<Enumerate<Iter<'_, T>>>().produced_len() == i + 1);
+        assert!(i == 0); //~ ERROR the asserted expression might not hold
+    }
+}
+
+fn main() {}
@@ -0,0 +1,23 @@
+use prusti_contracts::*;
+use std::slice::Iter;
+
+#[ensures(result == s.len() + 1)] //~ ERROR postcondition might not hold.
+fn count<T>(s: &[T]) -> usize {
+    let mut count = 0;
+    for _ in s.iter() {
+        body_invariant!(count + 1 + loop_iterator::<Iter<'_, T>>().remaining_len() == s.len());
+        count += 1;
+    }
+    count
+}
+
+#[requires(forall(|i: usize| i < s.len() ==> s[i] >= 0))]
+fn all_positive(s: &[i32]) {
+    for x in s.iter() {
+        body_invariant!(forall(|j: usize| j < loop_iterator::<Iter<'_, i32>>().remaining_len()
+            ==> *loop_iterator::<Iter<'_, i32>>().remaining_at(j) >= 0));
+        assert!(*x > 0); //~ ERROR the asserted expression might not hold
+    }
+}
+
+fn main() {}
@@ -0,0 +1,24 @@
+use prusti_contracts::*;
+use std::{iter::Zip, slice::Iter};
+
+type Pairs<'a> = Zip<Iter<'a, u32>, Iter<'a, u32>>;
+
+#[requires(b.len() <= a.len())]
+#[ensures(result == a.len())] //~ ERROR postcondition might not hold.
+fn count(a: &[u32], b: &[u32]) -> usize {
+    let mut count = 0;
+    for _ in a.iter().zip(b.iter()) {
+        body_invariant!(count + 1 + loop_iterator::<Pairs<'_>>().remaining_len() == b.len());
+        count += 1;
+    }
+    count
+}
+
+#[requires(a.len() == b.len())]
+fn pairwise_le(a: &[u32], b: &[u32]) {
+    for (x, y) in a.iter().zip(b.iter()) {
+        assert!(*x <= *y); //~ ERROR the asserted expression might not hold
+    }
+}
+
+fn main() {}
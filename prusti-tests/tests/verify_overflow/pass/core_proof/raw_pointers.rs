@@ -0,0 +1,40 @@
+// compile-flags: -Punsafe_core_proof=true
+
+use prusti_contracts::*;
+
+#[requires(own(p))]
+#[ensures(own(p) && *p == 0)]
+unsafe fn reset(p: *mut u32) {
+    *p = 0;
+}
+
+#[requires(own(p))]
+#[ensures(own(p) && *p == 1)]
+unsafe fn reset_and_increment(p: *mut u32) {
+    reset(p);
+    assert!(*p == 0);
+    *p += 1;
+}
+
+#[requires(own(p) && *p == 3)]
+#[ensures(own(p) && *p == 3)]
+unsafe fn read_only(p: *mut u32) {
+    let value = *p;
+    assert!(value == 3);
+}
+
+#[requires(own(p))]
+#[ensures(own(p) && *p == 3)]
+unsafe fn call_read_only(p: *mut u32) {
+    *p = 3;
+    read_only(p);
+}
+
+unsafe fn add_zero(p: *mut u32) {
+    let q = p.add(0);
+    assert!(q == p);
+    let r = p.offset(0);
+    assert!(r == p);
+}
+
+fn main() {}
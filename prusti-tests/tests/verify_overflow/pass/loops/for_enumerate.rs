@@ -0,0 +1,22 @@
+use prusti_contracts::*;
+use std::{iter::Enumerate, slice::Iter};
+
+#[ensures(result == s.len())]
+fn count<T>(s: &[T]) -> usize {
+    let mut count = 0;
+    for (i, _) in s.iter().enumerate() {
+        body_invariant!(i == count);
+        body_invariant!(loop_iterator::<Enumerate<Iter<'_, T>>>().produced_len() == i + 1);
+        body_invariant!(
+            i + 1 + loop_iterator::<Enumerate<Iter<'_, T>>>().remaining_len() == s.len()
+        );
+        assert!(i < s.len());
+        count += 1;
+    }
+    count
+}
+
+fn main() {
+    let s = [1, 2, 3];
+    assert!(count(&s) == 3);
+}
@@ -0,0 +1,18 @@
+use prusti_contracts::*;
+use std::ops::Range;
+
+#[ensures(result == n)]
+fn count(n: usize) -> usize {
+    let mut count = 0;
+    for i in 0..n {
+        body_invariant!(i < n && count == i);
+        body_invariant!(loop_iterator::<Range<usize>>().start == i + 1);
+        body_invariant!(loop_iterator::<Range<usize>>().end == n);
+        count += 1;
+    }
+    count
+}
+
+fn main() {
+    assert!(count(10) == 10);
+}
@@ -0,0 +1,34 @@
+use prusti_contracts::*;
+use std::{iter::Zip, slice::Iter};
+
+type Pairs<'a> = Zip<Iter<'a, u32>, Iter<'a, u32>>;
+
+#[requires(a.len() <= b.len())]
+#[ensures(result == a.len())]
+fn count(a: &[u32], b: &[u32]) -> usize {
+    let mut count = 0;
+    for _ in a.iter().zip(b.iter()) {
+        body_invariant!(count + 1 + loop_iterator::<Pairs<'_>>().remaining_len() == a.len());
+        count += 1;
+    }
+    count
+}
+
+#[requires(a.len() == b.len())]
+#[requires(forall(|i: usize| i < a.len() ==> a[i] <= b[i]))]
+fn pairwise_le(a: &[u32], b: &[u32]) {
+    for (x, y) in a.iter().zip(b.iter()) {
+        body_invariant!(*x <= *y);
+        body_invariant!(forall(|j: usize| j < loop_iterator::<Pairs<'_>>().remaining_len()
+            ==> *loop_iterator::<Pairs<'_>>().remaining_at(j).0
+                <= *loop_iterator::<Pairs<'_>>().remaining_at(j).1));
+        assert!(*x <= *y);
+    }
+}
+
+fn main() {
+    let a = [1, 2];
+    let b = [1, 2, 3];
+    assert!(count(&a, &b) == 2);
+    pairwise_le(&a, &b[..2]);
+}
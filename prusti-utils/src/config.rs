@@ -77,6 +77,8 @@ lazy_static::lazy_static! {
         settings.set_default::<Option<String>>("java_home", None).unwrap();
 
         settings.set_default::<Option<u32>>("check_timeout", None).unwrap();
+        settings.set_default::<Option<u64>>("verification_timeout", None).unwrap();
+        settings.set_default("timeouts_as_warnings", false).unwrap();
         settings.set_default("check_foldunfold_state", false).unwrap();
         settings.set_default("check_overflows", true).unwrap();
         settings.set_default("check_panics", true).unwrap();
@@ -88,6 +90,8 @@ lazy_static::lazy_static! {
         settings.set_default("log_dir", "log").unwrap();
         settings.set_default("log_tracing", false).unwrap();
         settings.set_default("cache_path", "").unwrap();
+        settings.set_default("cache_max_size", 0).unwrap();
+        settings.set_default("cache_max_age", 0).unwrap();
         settings.set_default("dump_debug_info", false).unwrap();
         settings.set_default("dump_debug_info_during_fold", false).unwrap();
         settings.set_default("dump_nll_facts", false).unwrap();
@@ -97,6 +101,7 @@ lazy_static::lazy_static! {
         settings.set_default("dump_reborrowing_dag_in_debug_info", false).unwrap();
         settings.set_default("dump_borrowck_info", false).unwrap();
         settings.set_default("dump_viper_program", false).unwrap();
+        settings.set_default("dump_verification_requests", false).unwrap();
         settings.set_default("foldunfold_state_filter", "").unwrap();
         settings.set_default::<Vec<String>>("extra_jvm_args", vec![]).unwrap();
         settings.set_default::<Vec<String>>("extra_verifier_args", vec![]).unwrap();
@@ -110,6 +115,8 @@ lazy_static::lazy_static! {
         settings.set_default("no_verify", false).unwrap();
         settings.set_default("no_verify_deps", false).unwrap();
         settings.set_default("opt_in_verification", false).unwrap();
+        settings.set_default::<Vec<String>>("verify_only", vec![]).unwrap();
+        settings.set_default::<Vec<String>>("skip_verification", vec![]).unwrap();
         settings.set_default("full_compilation", false).unwrap();
         settings.set_default("json_communication", false).unwrap();
         settings.set_default("optimizations", "all").unwrap();
@@ -138,11 +145,17 @@ lazy_static::lazy_static! {
         settings.set_default("hide_uuids", false).unwrap();
         settings.set_default("counterexample", false).unwrap();
         settings.set_default("print_counterexample_if_model_is_present", false).unwrap();
+        settings.set_default::<Option<String>>("counterexample_test_dir", None).unwrap();
+        settings.set_default("counterexample_trace", false).unwrap();
         settings.set_default::<Option<String>>("save_failing_trace_to_file", None).unwrap();
         settings.set_default::<Option<String>>("execute_only_failing_trace", None).unwrap();
         settings.set_default::<Option<String>>("dump_fold_unfold_state_of_blocks", None).unwrap();
         settings.set_default("print_hash", false).unwrap();
         settings.set_default("enable_cache", true).unwrap();
+        settings.set_default::<Option<String>>("report_dir", None).unwrap();
+        settings.set_default("dump_profile", false).unwrap();
+        settings.set_default("incremental_verification", false).unwrap();
+        settings.set_default("incremental_dir", "").unwrap();
 
         settings.set_default("cargo_path", "cargo").unwrap();
         settings.set_default("cargo_command", "check").unwrap();
@@ -161,6 +174,7 @@ lazy_static::lazy_static! {
         // Flags for debugging performance.
         settings.set_default("preserve_smt_trace_files", false).unwrap();
         settings.set_default("write_smt_statistics", false).unwrap();
+        settings.set_default("dump_quantifier_hot_spots", false).unwrap();
         settings.set_default("log_smt_wrapper_interaction", false).unwrap();
 
         // Flags for debugging Prusti that can change verification results.
@@ -212,6 +226,8 @@ lazy_static::lazy_static! {
                 .with_list_parse_key("extra_jvm_args")
                 .with_list_parse_key("extra_verifier_args")
                 .with_list_parse_key("verify_only_basic_block_path")
+                .with_list_parse_key("verify_only")
+                .with_list_parse_key("skip_verification")
                 .list_separator(" ")
         ).unwrap();
         check_keys(&settings, &allowed_keys, "the `PRUSTI_*` environment variables");
@@ -312,6 +328,9 @@ pub fn check_foldunfold_state() -> bool {
 ///   [Carbon](https://github.com/viperproject/carbon).
 /// - `Silicon` - symbolic-execution-based backend
 ///   [Silicon](https://github.com/viperproject/silicon/).
+/// - `Smt` - lowers the program to SMT-LIB and checks it with the solver at
+///   `smt_solver_path`, without starting a JVM. Programs that the backend
+///   does not support are verified with Silicon.
 pub fn viper_backend() -> String {
     read_setting::<String>("viper_backend")
         .to_lowercase()
@@ -422,6 +441,12 @@ pub fn dump_viper_program() -> bool {
     read_setting("dump_viper_program")
 }
 
+/// When enabled, every verification request is saved, so that it can be
+/// verified again with `prusti-replay`.
+pub fn dump_verification_requests() -> bool {
+    read_setting("dump_verification_requests")
+}
+
 /// Filter for `fold`/`unfold` nodes when debug info is dumped.
 pub fn foldunfold_state_filter() -> String {
     read_setting("foldunfold_state_filter")
@@ -447,14 +472,27 @@ pub fn log_tracing() -> bool {
     read_setting("log_tracing")
 }
 
-/// Path to a cache file, where verification cache will be loaded from and
+/// Path to a cache directory, where verification results are loaded from and
 /// saved to. The default empty string disables saving any cache to disk.
-/// A path to a file which does not yet exist will result in using an empty
-/// cache, but then creating and saving to that location on exit.
+/// The directory can be shared by concurrent Prusti processes and servers.
 pub fn cache_path() -> PathBuf {
     PathBuf::from(read_setting::<String>("cache_path"))
 }
 
+/// Maximal total size (in bytes) of the entries of the verification cache.
+/// When exceeded, the least recently used entries are evicted. The default
+/// `0` means unlimited.
+pub fn cache_max_size() -> u64 {
+    read_setting("cache_max_size")
+}
+
+/// Maximal time (in seconds) since the last use of an entry of the
+/// verification cache. Entries that were not used for longer are evicted. The
+/// default `0` means unlimited.
+pub fn cache_max_age() -> u64 {
+    read_setting("cache_max_age")
+}
+
 /// When enabled, binary operations and numeric casts will be checked for
 /// overflows.
 pub fn check_overflows() -> bool {
@@ -511,6 +549,19 @@ pub fn check_timeout() -> Option<u32> {
     read_setting("check_timeout")
 }
 
+/// Maximum time (in seconds) for the verifier to spend on a single function
+/// or type. Functions annotated with `#[timeout(seconds)]` use their own
+/// timeout instead.
+pub fn verification_timeout() -> Option<u64> {
+    read_setting("verification_timeout")
+}
+
+/// When enabled, functions whose verification timed out are reported with a
+/// warning instead of an error.
+pub fn timeouts_as_warnings() -> bool {
+    read_setting("timeouts_as_warnings")
+}
+
 /// When enabled, a more complete `exhale` version is used in the verifier.
 /// See [`consolidate`](https://github.com/viperproject/silicon/blob/f48de7f6e2d90d9020812869c713a5d3e2035995/src/main/scala/rules/StateConsolidator.scala#L29-L46).
 /// Equivalent to the verifier command-line argument
@@ -552,6 +603,41 @@ pub fn print_counterexample_if_model_is_present() -> bool {
     read_setting("print_counterexample_if_model_is_present")
 }
 
+/// When enabled, counterexamples include the basic blocks executed on the
+/// failing path together with the values of the variables at each of them.
+/// The trace is printed as a note and added to the errors of the verification
+/// report (see `report_dir`).
+///
+/// Note: This requires the `counterexample` option to be enabled.
+pub fn counterexample_trace() -> bool {
+    let value = read_setting("counterexample_trace");
+    if value {
+        assert!(
+            counterexample(),
+            "Counterexamples need to be enabled to trace counterexamples"
+        );
+    }
+    value
+}
+
+/// If this is set to a path, Prusti will write a `#[test]` function for each
+/// procedure with a counterexample to `<crate>_counterexamples.rs` in that
+/// directory. The test calls the procedure with the initial values of its
+/// arguments in the counterexample.
+///
+/// Note: This requires the `counterexample` option to be enabled, and is not
+/// supported with `unsafe_core_proof`.
+pub fn counterexample_test_dir() -> Option<PathBuf> {
+    let value: Option<String> = read_setting("counterexample_test_dir");
+    if value.is_some() {
+        assert!(
+            counterexample(),
+            "Counterexamples need to be enabled to generate counterexample tests"
+        );
+    }
+    value.map(PathBuf::from)
+}
+
 /// If this is set to a path, Prusti will extract the information about the
 /// trace that led to the error and save it to the given file.
 ///
@@ -624,6 +710,42 @@ pub fn enable_cache() -> bool {
     read_setting("enable_cache")
 }
 
+/// Enables or disables the cache of verification requests, see
+/// [`enable_cache`].
+pub fn set_enable_cache(value: bool) {
+    write_setting("enable_cache", value);
+}
+
+/// When enabled, procedures that were verified successfully are fingerprinted
+/// and are neither encoded nor verified again by later runs, as long as
+/// neither their MIR, nor the contracts of their callees, nor the types they
+/// depend on change. The fingerprints are saved in `INCREMENTAL_DIR`.
+pub fn incremental_verification() -> bool {
+    read_setting("incremental_verification")
+}
+
+/// Path to a directory where the fingerprints used by incremental verification
+/// are loaded from and saved to. The default empty string disables saving any
+/// fingerprints to disk.
+pub fn incremental_dir() -> PathBuf {
+    PathBuf::from(read_setting::<String>("incremental_dir"))
+}
+
+/// When set to a path, Prusti will write a machine-readable report of the
+/// verification outcome of each crate into that directory. The report is
+/// written both as JSON (`<crate>.prusti.json`) and in the SARIF format
+/// (`<crate>.sarif`).
+pub fn report_dir() -> Option<PathBuf> {
+    read_setting::<Option<String>>("report_dir").map(PathBuf::from)
+}
+
+/// When enabled, Prusti writes a profile of the verification of each crate to
+/// `profile/<crate>.csv` in the log directory. The profile lists the verified functions with
+/// the time spent on them, slowest first.
+pub fn dump_profile() -> bool {
+    read_setting("dump_profile")
+}
+
 /// Maximum amount of instantiated Viper verifiers the server will keep around
 /// for reuse. If not set, defaults to
 /// `SERVER_MAX_CONCURRENT_VERIFICATION_OPERATIONS`. It also doesn't make much
@@ -641,8 +763,8 @@ pub fn server_max_stored_verifiers() -> Option<usize> {
 }
 
 /// Maximum amount of verification requests the server will work on
-/// concurrently. If not set, defaults to the number of (logical) cores on
-/// the system.
+/// concurrently. If not set, defaults to 1 because Viper is not thread safe
+/// and Silicon already uses all cores for a single verification.
 pub fn server_max_concurrency() -> Option<usize> {
     read_optional_setting("server_max_concurrency")
 }
@@ -860,6 +982,14 @@ pub fn write_smt_statistics() -> bool {
     read_smt_wrapper_dependent_bool("write_smt_statistics")
 }
 
+/// When enabled, Prusti writes the quantifiers instantiated by the SMT solver
+/// to `quantifier_hot_spots/<crate>.csv` in the log directory, the most
+/// instantiated first, and warns about the quantifiers of specifications that
+/// look like matching loops.
+pub fn dump_quantifier_hot_spots() -> bool {
+    read_smt_wrapper_dependent_bool("dump_quantifier_hot_spots")
+}
+
 /// Log communication of Silicon with Z3.
 pub fn log_smt_wrapper_interaction() -> bool {
     read_smt_wrapper_dependent_bool("log_smt_wrapper_interaction")
@@ -945,7 +1075,8 @@ pub fn use_new_encoder() -> bool {
     read_setting("use_new_encoder")
 }
 
-/// How many parallel verifiers Silicon should use.
+/// How many parallel verifiers Silicon should use. With Carbon, this is the
+/// number of cores Boogie uses.
 pub fn number_of_parallel_verifiers() -> Option<u8> {
     read_setting("number_of_parallel_verifiers")
 }
@@ -997,6 +1128,21 @@ pub fn opt_in_verification() -> bool {
     read_setting("opt_in_verification")
 }
 
+/// Space-separated list of glob patterns. When not empty, only the items
+/// whose definition path (e.g. `my_crate::module::function`) matches one of
+/// the patterns are verified. A pattern also matches the items nested in the
+/// items it matches, and may omit the crate name. `*` matches any sequence of
+/// characters and `?` matches any single character.
+pub fn verify_only() -> Vec<String> {
+    read_setting("verify_only")
+}
+
+/// Space-separated list of glob patterns, written as for `VERIFY_ONLY`. The
+/// items whose definition path matches one of the patterns are not verified.
+pub fn skip_verification() -> Vec<String> {
+    read_setting("skip_verification")
+}
+
 /// When enabled, compilation will continue and a binary will be generated
 /// after Prusti terminates.
 pub fn full_compilation() -> bool {
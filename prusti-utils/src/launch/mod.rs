@@ -243,6 +243,60 @@ pub fn find_z3_exe(base_dir: &Path) -> Option<PathBuf> {
     candidates.into_iter().find(|candidate| candidate.is_file())
 }
 
+/// The usage of the arguments handled by [`take_item_filter_args`].
+pub const ITEM_FILTER_USAGE: &str = "[--verify-only <pattern>]... [--skip <pattern>]...";
+
+/// Removes the `--verify-only <pattern>` and `--skip <pattern>` arguments
+/// preceding any `--` from `args`. Returns the `PRUSTI_VERIFY_ONLY` and
+/// `PRUSTI_SKIP_VERIFICATION` environment variables to set, which extend the
+/// patterns of the current environment with the given ones, or an error if a
+/// flag is not followed by a pattern.
+pub fn take_item_filter_args(
+    args: &mut Vec<String>,
+) -> Result<Vec<(&'static str, String)>, String> {
+    let mut filters = [
+        ("PRUSTI_VERIFY_ONLY", "--verify-only", vec![]),
+        ("PRUSTI_SKIP_VERIFICATION", "--skip", vec![]),
+    ];
+    let mut remaining_args = vec![];
+    let mut args_iter = std::mem::take(args).into_iter();
+    while let Some(arg) = args_iter.next() {
+        if arg == "--" {
+            remaining_args.push(arg);
+            remaining_args.extend(args_iter.by_ref());
+            break;
+        }
+        let Some((_, flag, patterns)) = filters
+            .iter_mut()
+            .find(|(_, flag, _)| arg == *flag || arg.starts_with(&format!("{flag}=")))
+        else {
+            remaining_args.push(arg);
+            continue;
+        };
+        let pattern = if arg == *flag {
+            args_iter
+                .next()
+                .ok_or_else(|| format!("missing pattern after `{flag}`"))?
+        } else {
+            arg[flag.len() + 1..].to_string()
+        };
+        patterns.push(pattern);
+    }
+    *args = remaining_args;
+    Ok(filters
+        .into_iter()
+        .filter(|(_, _, patterns)| !patterns.is_empty())
+        .map(|(var, _, patterns)| {
+            let value = env::var(var)
+                .into_iter()
+                .chain(patterns)
+                .collect::<Vec<_>>()
+                .join(" ");
+            (var, value)
+        })
+        .collect())
+}
+
 pub fn set_environment_settings(
     cmd: &mut Command,
     current_executable_dir: &Path,
@@ -44,16 +44,15 @@ impl<Level: LogLevel> Stopwatch<Level> {
     }
 
     /// Finishes up the current section, logging the time taken, and starts timing the next one.
-    pub fn start_next<S: ToString>(&mut self, section_name: S) {
+    /// Returns the time taken by the finished section.
+    pub fn start_next<S: ToString>(&mut self, section_name: S) -> Duration {
         let now = Instant::now();
-        Level::log_finish(
-            &self.prefix,
-            &self.section_name,
-            now.duration_since(self.start_time),
-        );
+        let duration = now.duration_since(self.start_time);
+        Level::log_finish(&self.prefix, &self.section_name, duration);
         self.section_name = section_name.to_string();
         Level::log_start(&self.prefix, &self.section_name);
         self.start_time = now;
+        duration
     }
 
     /// Finishes up the current section, logging the time taken.
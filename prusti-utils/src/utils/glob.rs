@@ -0,0 +1,107 @@
+// © 2023, ETH Zurich
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+/// Whether `text` matches the glob `pattern`, in which `*` matches any
+/// sequence of characters and `?` matches any single character.
+pub fn matches(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let text: Vec<char> = text.chars().collect();
+    let (mut p, mut t) = (0, 0);
+    // The position of the last `*` in the pattern, and of the text it was
+    // matched against, to backtrack to if the rest does not match.
+    let mut backtrack = None;
+    while t < text.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                backtrack = Some((p, t));
+                p += 1;
+            }
+            Some(&c) if c == '?' || c == text[t] => {
+                p += 1;
+                t += 1;
+            }
+            _ => match backtrack {
+                Some((star, star_t)) => {
+                    // Let the `*` match one more character.
+                    p = star + 1;
+                    t = star_t + 1;
+                    backtrack = Some((star, star_t + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+/// Whether the item at `def_path` (e.g. `my_crate::module::function`) is
+/// selected by `pattern`: the pattern matches the path or the path of an
+/// ancestor of the item, with or without the crate name.
+pub fn matches_def_path(pattern: &str, def_path: &str) -> bool {
+    let without_crate = def_path.split_once("::").map(|(_, path)| path);
+    [Some(def_path), without_crate]
+        .into_iter()
+        .flatten()
+        .any(|path| matches(pattern, path) || matches(&format!("{pattern}::*"), path))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_matches_literal() {
+        assert!(matches("list::push", "list::push"));
+        assert!(!matches("list::push", "list::pop"));
+        assert!(!matches("list::push", "list::push_back"));
+    }
+
+    #[test]
+    fn test_matches_star() {
+        assert!(matches("*", ""));
+        assert!(matches("*", "list::push"));
+        assert!(matches("list::*", "list::push"));
+        assert!(matches("*push*", "list::push_back"));
+        assert!(matches("a*b*c", "abbcbc"));
+        assert!(!matches("a*b*c", "abcb"));
+        assert!(!matches("list::*", "lists::push"));
+    }
+
+    #[test]
+    fn test_matches_question_mark() {
+        assert!(matches("f?o", "foo"));
+        assert!(matches("???", "abc"));
+        assert!(!matches("???", "ab"));
+        assert!(!matches("???", "abcd"));
+        assert!(!matches("?", ""));
+    }
+
+    #[test]
+    fn test_matches_empty() {
+        assert!(matches("", ""));
+        assert!(!matches("", "a"));
+        assert!(!matches("a", ""));
+        assert!(matches("**", ""));
+    }
+
+    #[test]
+    fn test_matches_multibyte() {
+        assert!(matches("grüß?", "grüße"));
+        assert!(matches("?", "é"));
+        assert!(matches("*ß*", "straße"));
+        assert!(!matches("??", "é"));
+        assert!(matches("λ*::*λ", "λx::fλ"));
+    }
+
+    #[test]
+    fn test_matches_def_path() {
+        assert!(matches_def_path("list::push", "my_crate::list::push"));
+        assert!(matches_def_path("my_crate::list", "my_crate::list::push"));
+        assert!(matches_def_path("*::push", "my_crate::list::push"));
+        assert!(!matches_def_path("push", "my_crate::list::push"));
+        assert!(!matches_def_path("list::pu", "my_crate::list::push"));
+    }
+}
@@ -52,6 +52,12 @@ pub enum BuiltinFunctionKind {
         slice_pred_type: vir::Type,
         elem_pred_type: vir::Type,
     },
+    /// abstract function that holds for the function pointers whose calls
+    /// satisfy the contract of a function
+    FnPointerSpec {
+        spec_name: String,
+        pointer_snapshot_type: vir::Type,
+    },
 }
 
 #[derive(Copy, Clone, Debug, Hash, Eq, PartialEq)]
@@ -230,6 +236,18 @@ impl<'p, 'v: 'p, 'tcx: 'v> BuiltinEncoder<'p, 'v, 'tcx> {
                     body: None,
                 }
             }
+            BuiltinFunctionKind::FnPointerSpec {
+                pointer_snapshot_type,
+                ..
+            } => vir::Function {
+                name: fn_name,
+                type_arguments,
+                formal_args: vec![vir::LocalVar::new("self", pointer_snapshot_type)],
+                return_type: vir::Type::Bool,
+                pres: vec![],
+                posts: vec![],
+                body: None,
+            },
         }
     }
 
@@ -1,6 +1,10 @@
+use super::{
+    counterexample_test::CounterexampleTest, custom_print::write_custom_print,
+    trace::CounterexampleTrace,
+};
 use prusti_interface::PrustiError;
 use prusti_rustc_interface::span::Span;
-use std::fmt;
+use std::{fmt, iter};
 
 /// Counterexample information for a single variable.
 pub struct CounterexampleEntry {
@@ -68,17 +72,40 @@ impl fmt::Display for CounterexampleEntry {
 
 /// A concrete counterexample containing mapped values of arguments and locals
 /// (the latter only for impure functions), as well as the result (if any).
-pub struct Counterexample(Vec<CounterexampleEntry>);
+pub struct Counterexample {
+    entries: Vec<CounterexampleEntry>,
+    /// A test reproducing the counterexample, if `COUNTEREXAMPLE_TEST_DIR` is
+    /// set.
+    test: Option<CounterexampleTest>,
+    /// The path to the failure, if `COUNTEREXAMPLE_TRACE` is enabled.
+    trace: Option<CounterexampleTrace>,
+}
 
 impl Counterexample {
-    pub fn new(entries: Vec<CounterexampleEntry>) -> Self {
-        Self(entries)
+    pub fn new(
+        entries: Vec<CounterexampleEntry>,
+        test: Option<CounterexampleTest>,
+        trace: Option<CounterexampleTrace>,
+    ) -> Self {
+        Self {
+            entries,
+            test,
+            trace,
+        }
+    }
+
+    pub fn take_test(&mut self) -> Option<CounterexampleTest> {
+        self.test.take()
+    }
+
+    pub fn take_trace(&mut self) -> Option<CounterexampleTrace> {
+        self.trace.take()
     }
 
     /// Annotates a Prusti error with notes for any variable present in the
     /// mapped counterexample.
     pub fn annotate_error(&self, mut prusti_error: PrustiError) -> PrustiError {
-        for entry in &self.0 {
+        for entry in &self.entries {
             prusti_error = prusti_error.add_note(format!("{entry}"), Some(entry.span));
         }
         prusti_error
@@ -99,6 +126,8 @@ pub enum Entry {
     Struct {
         name: String,
         field_entries: Vec<(String, Entry)>,
+        //this vec stores how a sruct should be formated to the user
+        custom_print_option: Option<Vec<String>>,
     },
     Enum {
         super_name: String,
@@ -106,8 +135,13 @@ pub enum Entry {
         field_entries: Vec<(String, Entry)>,
         //note: if fields are not named, their order is important!
         //that is why no FxHashMap is used
+        //this vec stores how a enum should be formated to the user
+        custom_print_option: Option<Vec<String>>,
     },
+    Array(Vec<Entry>),
     Tuple(Vec<Entry>),
+    /// The abstract value of a collection, e.g. the `Seq` of a model.
+    Seq(Vec<Entry>),
     #[default]
     Unknown,
 }
@@ -119,25 +153,34 @@ impl Entry {
             _ => false,
         }
     }
+    /// Completes the unknown parts of this entry with `other`. Entries
+    /// showing the model of a type are preferred.
     pub fn merge(&self, other: &Entry) -> Entry {
         match (self, other) {
             (Entry::Int(x), _) => Entry::Int(x.clone()),
             (Entry::Float(x), _) => Entry::Float(x.clone()),
             (Entry::Bool(x), _) => Entry::Bool(*x),
             (Entry::Char(x), _) => Entry::Char(*x),
-            (Entry::Ref(entry), _) => Entry::Ref(Box::new(entry.merge(other))),
-            (Entry::Box(entry), _) => Entry::Box(Box::new(entry.merge(other))),
+            (Entry::Ref(entry1), Entry::Ref(entry2)) => Entry::Ref(Box::new(entry1.merge(entry2))),
+            (Entry::Box(entry1), Entry::Box(entry2)) => Entry::Box(Box::new(entry1.merge(entry2))),
+            (Entry::Unknown, _) => other.clone(),
             (
                 Entry::Struct {
                     name: name1,
                     field_entries: field_entries1,
+                    custom_print_option,
                 },
                 Entry::Struct {
                     name: name2,
                     field_entries: field_entries2,
+                    ..
                 },
             ) => {
-                if *name1 == *name2 && field_entries1.len() == field_entries2.len() {
+                if *name1 != *name2 {
+                    // only the other entry shows the model of the type
+                    return other.clone();
+                }
+                if field_entries1.len() == field_entries2.len() {
                     let mut other_iter = field_entries2.iter();
                     let new_field_entries = field_entries1
                         .iter()
@@ -149,6 +192,7 @@ impl Entry {
                     return Entry::Struct {
                         name: name1.to_string(),
                         field_entries: new_field_entries,
+                        custom_print_option: custom_print_option.clone(),
                     };
                 }
                 self.clone()
@@ -158,11 +202,13 @@ impl Entry {
                     super_name: super_name1,
                     name: name1,
                     field_entries: field_entries1,
+                    custom_print_option,
                 },
                 Entry::Enum {
                     super_name: super_name2,
                     name: name2,
                     field_entries: field_entries2,
+                    ..
                 },
             ) => {
                 if *super_name1 == *super_name2 {
@@ -183,6 +229,7 @@ impl Entry {
                             super_name: super_name1.to_string(),
                             name: name1.to_string(),
                             field_entries: new_field_entries,
+                            custom_print_option: custom_print_option.clone(),
                         };
                     }
                 }
@@ -202,6 +249,15 @@ impl Entry {
                 }
                 self.clone()
             }
+            (Entry::Array(entries1), Entry::Array(entries2)) => {
+                if entries1.len() == entries2.len() {
+                    let new_entries = iter::zip(entries1, entries2)
+                        .map(|(x, y)| x.merge(y))
+                        .collect();
+                    return Entry::Array(new_entries);
+                }
+                self.clone()
+            }
             _ => self.clone(),
         }
     }
@@ -223,10 +279,21 @@ impl fmt::Debug for Entry {
             }
             Entry::Ref(el) => write!(f, "ref({el:#?})"),
             Entry::Box(el) => write!(f, "box({el:#?})"),
+            Entry::Enum {
+                field_entries,
+                custom_print_option: Some(custom_print),
+                ..
+            }
+            | Entry::Struct {
+                field_entries,
+                custom_print_option: Some(custom_print),
+                ..
+            } => write_custom_print(f, custom_print, field_entries),
             Entry::Enum {
                 super_name,
                 name,
                 field_entries,
+                custom_print_option: None,
             } => {
                 let named_fields =
                     !field_entries.is_empty() && field_entries[0].0.parse::<usize>().is_err();
@@ -248,6 +315,7 @@ impl fmt::Debug for Entry {
             Entry::Struct {
                 name,
                 field_entries,
+                custom_print_option: None,
             } => {
                 let mut f1 = f.debug_struct(name);
                 for (fieldname, entry) in field_entries {
@@ -266,6 +334,18 @@ impl fmt::Debug for Entry {
                     f1.finish()
                 }
             }
+            Entry::Array(elements) => {
+                let mut f1 = f.debug_list();
+                f1.entries(elements);
+                f1.finish()
+            }
+            Entry::Seq(elements) => {
+                let mut f1 = f.debug_tuple("Seq");
+                for entry in elements {
+                    f1.field(entry);
+                }
+                f1.finish()
+            }
             Entry::Unknown => write!(f, "?"),
         }
     }
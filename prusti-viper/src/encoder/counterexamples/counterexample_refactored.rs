@@ -1,3 +1,4 @@
+use super::{custom_print::write_custom_print, trace::CounterexampleTrace};
 use prusti_interface::PrustiError;
 use prusti_rustc_interface::errors::MultiSpan;
 use std::fmt;
@@ -47,17 +48,25 @@ impl fmt::Display for CounterexampleEntry {
 }
 
 /// A concrete counterexample containing mapped values of arguments and locals
-pub struct Counterexample(Vec<CounterexampleEntry>);
+pub struct Counterexample {
+    entries: Vec<CounterexampleEntry>,
+    /// The path to the failure, if `COUNTEREXAMPLE_TRACE` is enabled.
+    trace: Option<CounterexampleTrace>,
+}
 
 impl Counterexample {
-    pub fn new(entries: Vec<CounterexampleEntry>) -> Self {
-        Self(entries)
+    pub fn new(entries: Vec<CounterexampleEntry>, trace: Option<CounterexampleTrace>) -> Self {
+        Self { entries, trace }
+    }
+
+    pub fn take_trace(&mut self) -> Option<CounterexampleTrace> {
+        self.trace.take()
     }
 
     /// Annotates a Prusti error with notes for any variable present in the
     /// mapped counterexample.
     pub fn annotate_error(&self, mut prusti_error: PrustiError) -> PrustiError {
-        for counterexample_entry in &self.0 {
+        for counterexample_entry in &self.entries {
             let messages = counterexample_entry.history_to_string();
             let mut iter = messages.iter();
             for (_, span) in &counterexample_entry.history {
@@ -137,21 +146,7 @@ impl fmt::Debug for Entry {
                 custom_print_option,
             } => {
                 if let Some(custom_print) = custom_print_option {
-                    let mut custom_print_iter = custom_print.iter();
-                    let text = custom_print_iter.next().unwrap(); //safe because custom_print has at least one element
-                    let mut text_iter = text.split("{}");
-                    let mut output = text_iter.next().unwrap().to_string(); //safe because text_iter has at least one element
-                    for next in text_iter {
-                        let fieldname = custom_print_iter.next().unwrap(); //safe because of encoding (checked by compiler)
-                        let field_entry = &field_entries
-                            .iter()
-                            .find(|(name, _)| fieldname == name)
-                            .unwrap()
-                            .1; //safe because of encoding (checked by compiler)
-                        output.push_str(&format!("{field_entry:#?}"));
-                        output.push_str(next);
-                    }
-                    write!(f, "{output}")
+                    write_custom_print(f, custom_print, field_entries)
                 } else {
                     let named_fields =
                         !field_entries.is_empty() && field_entries[0].0.parse::<usize>().is_err();
@@ -177,21 +172,7 @@ impl fmt::Debug for Entry {
                 custom_print_option,
             } => {
                 if let Some(custom_print) = custom_print_option {
-                    let mut custom_print_iter = custom_print.iter();
-                    let text = custom_print_iter.next().unwrap(); //safe because custom_print has at least one element
-                    let mut text_iter = text.split("{}");
-                    let mut output = text_iter.next().unwrap().to_string(); //safe because text_iter has at least one element
-                    for next in text_iter {
-                        let fieldname = custom_print_iter.next().unwrap(); //safe because of encoding (checked by compiler)
-                        let field_entry = &field_entries
-                            .iter()
-                            .find(|(name, _)| fieldname == name)
-                            .unwrap()
-                            .1; //safe because of encoding (checked by compiler)
-                        output.push_str(&format!("{field_entry:#?}"));
-                        output.push_str(next);
-                    }
-                    write!(f, "{output}")
+                    write_custom_print(f, custom_print, field_entries)
                 } else {
                     let mut f1 = f.debug_struct(name);
                     for (fieldname, entry) in field_entries {
@@ -0,0 +1,211 @@
+// © 2023, ETH Zurich
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! Generation of Rust tests that reproduce counterexamples.
+//!
+//! For every procedure with a counterexample, a `#[test]` function calls the
+//! procedure with the initial values of its arguments. The tests of a crate
+//! are written to a single file, which is meant to be included as a module of
+//! the crate root:
+//!
+//! ```ignore
+//! #[cfg(test)]
+//! #[path = "../target/counterexamples/my_crate_counterexamples.rs"]
+//! mod prusti_counterexamples;
+//! ```
+
+use log::{error, info};
+use rustc_hash::FxHashSet;
+use std::{fmt::Write, fs, path::Path};
+
+/// A call of a procedure with the argument values of a counterexample.
+pub struct CounterexampleTest {
+    /// The path of the procedure, relative to the crate root.
+    function: String,
+    /// The arguments as Rust expressions, or the reason why they cannot be
+    /// constructed.
+    arguments: Result<Vec<String>, String>,
+    /// The first line of the error of the counterexample.
+    error: String,
+}
+
+impl CounterexampleTest {
+    pub fn new(function: String, arguments: Result<Vec<String>, String>) -> Self {
+        CounterexampleTest {
+            function,
+            arguments,
+            error: String::new(),
+        }
+    }
+
+    pub fn function(&self) -> &str {
+        &self.function
+    }
+
+    /// Sets the error message shown in the documentation of the test.
+    pub fn set_error(&mut self, message: &str) {
+        self.error = message.lines().next().unwrap_or_default().to_string();
+    }
+
+    /// Renders the test, whose function is called `name`.
+    fn write(&self, out: &mut String, name: &str) {
+        let arguments = match &self.arguments {
+            Ok(arguments) => arguments,
+            Err(reason) => {
+                writeln!(out, "// No test for `{}`: {reason}.", self.function).unwrap();
+                return;
+            }
+        };
+        writeln!(out, "/// {}", self.error).unwrap();
+        writeln!(out, "#[test]").unwrap();
+        writeln!(out, "fn {name}() {{").unwrap();
+        writeln!(
+            out,
+            "    let _ = {}({});",
+            self.function,
+            arguments.join(", ")
+        )
+        .unwrap();
+        writeln!(out, "}}").unwrap();
+    }
+}
+
+/// The name of the test of `function`, made of the lowercase alphanumeric
+/// characters of its path.
+fn test_name(function: &str) -> String {
+    format!("counterexample_{function}")
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() {
+                c.to_ascii_lowercase()
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
+/// Renders the file with the tests of the crate. Tests whose names collide
+/// are numbered.
+fn render_tests(crate_name: &str, tests: &[CounterexampleTest]) -> String {
+    let mut out = format!(
+        "// Tests reproducing the counterexamples found by Prusti in `{crate_name}`.\n\
+         // This file is generated; it is overwritten whenever the crate is verified.\n\n\
+         #[allow(unused_imports)]\n\
+         use super::*;\n"
+    );
+    let mut names = FxHashSet::default();
+    for test in tests {
+        let base_name = test_name(&test.function);
+        let mut name = base_name.clone();
+        let mut index = 1;
+        while !names.insert(name.clone()) {
+            index += 1;
+            name = format!("{base_name}_{index}");
+        }
+        out.push('\n');
+        test.write(&mut out, &name);
+    }
+    out
+}
+
+/// Writes the tests of the crate to `<dir>/<crate>_counterexamples.rs`,
+/// replacing the tests of previous runs.
+pub fn write_tests_to_dir(dir: &Path, crate_name: &str, tests: &[CounterexampleTest]) {
+    let out = render_tests(crate_name, tests);
+    let path = dir.join(format!("{crate_name}_counterexamples.rs"));
+    match fs::create_dir_all(dir).and_then(|()| fs::write(&path, out)) {
+        Ok(()) => info!("Saved counterexample tests to \"{}\"", path.display()),
+        Err(e) => error!(
+            "Failed to save counterexample tests to \"{}\": {e}",
+            path.display()
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test(function: &str, arguments: Result<Vec<&str>, &str>) -> CounterexampleTest {
+        let arguments = arguments
+            .map(|arguments| arguments.into_iter().map(String::from).collect())
+            .map_err(String::from);
+        let mut test = CounterexampleTest::new(function.to_string(), arguments);
+        test.set_error("[Prusti: verification error] the asserted expression might not hold\nmore");
+        test
+    }
+
+    #[test]
+    fn test_names_are_sanitized() {
+        assert_eq!(test_name("check"), "counterexample_check");
+        assert_eq!(
+            test_name("Stack::<T>::pop"),
+            "counterexample_stack___t___pop"
+        );
+        assert_eq!(test_name("m::Été"), "counterexample_m___t_");
+    }
+
+    #[test]
+    fn test_file_layout() {
+        let rendered = render_tests("my_crate", &[test("check", Ok(vec!["5_i32", "true"]))]);
+        assert_eq!(
+            rendered,
+            "// Tests reproducing the counterexamples found by Prusti in `my_crate`.\n\
+             // This file is generated; it is overwritten whenever the crate is verified.\n\
+             \n\
+             #[allow(unused_imports)]\n\
+             use super::*;\n\
+             \n\
+             /// [Prusti: verification error] the asserted expression might not hold\n\
+             #[test]\n\
+             fn counterexample_check() {\n\
+             \x20   let _ = check(5_i32, true);\n\
+             }\n"
+        );
+    }
+
+    #[test]
+    fn test_colliding_names_are_numbered() {
+        let rendered = render_tests(
+            "my_crate",
+            &[
+                test("m::f", Ok(vec![])),
+                test("m__f", Ok(vec![])),
+                test("M::f", Ok(vec![])),
+            ],
+        );
+        assert!(rendered.contains("fn counterexample_m__f() {\n    let _ = m::f();"));
+        assert!(rendered.contains("fn counterexample_m__f_2() {\n    let _ = m__f();"));
+        assert!(rendered.contains("fn counterexample_m__f_3() {\n    let _ = M::f();"));
+    }
+
+    #[test]
+    fn test_unconstructible_arguments_are_explained() {
+        let rendered = render_tests(
+            "my_crate",
+            &[test("generic", Err("the procedure is generic"))],
+        );
+        assert!(rendered.ends_with("\n// No test for `generic`: the procedure is generic.\n"));
+        assert!(!rendered.contains("#[test]"));
+    }
+
+    #[test]
+    fn test_file_is_replaced() {
+        let dir = std::env::temp_dir().join(format!(
+            "prusti-counterexample-tests-{}",
+            std::process::id()
+        ));
+        write_tests_to_dir(&dir, "my_crate", &[test("first", Ok(vec![]))]);
+        write_tests_to_dir(&dir, "my_crate", &[test("second", Ok(vec![]))]);
+        let written = fs::read_to_string(dir.join("my_crate_counterexamples.rs")).unwrap();
+        assert_eq!(
+            written,
+            render_tests("my_crate", &[test("second", Ok(vec![]))])
+        );
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -1,17 +1,28 @@
-use super::{super::high::types::HighTypeEncoderInterface, counterexample::*};
+use super::{
+    super::high::types::HighTypeEncoderInterface, counterexample::*,
+    counterexample_test::CounterexampleTest, custom_print::custom_print,
+    trace::CounterexampleTrace,
+};
 use crate::encoder::{
     counterexamples::*,
+    mir::specifications::SpecificationsInterface,
     places::{Local, LocalVariableManager},
     Encoder,
 };
+use prusti_common::config;
 use prusti_interface::{
     data::ProcedureDefId,
     environment::{body::MirBody, EnvQuery},
 };
 use prusti_rustc_interface::{
+    hir::{
+        def::CtorKind,
+        def_id::{DefId, LocalDefId},
+        Mutability,
+    },
     middle::{
         mir::{self, VarDebugInfo},
-        ty::{self, Ty, TyCtxt},
+        ty::{self, print::with_no_trimmed_paths, Ty, TyCtxt},
     },
     span::Span,
 };
@@ -26,6 +37,7 @@ pub fn backtranslate(
     silicon_counterexample: &SiliconCounterexample,
 ) -> Counterexample {
     let translator = CounterexampleTranslator::new(encoder, def_id, silicon_counterexample);
+    let trace_labels = encoder.trace_labels(def_id);
 
     // TODO: ideally we would use the "main" counterexample from Silicon, the
     // one not associated with any label, because it contains the values of the
@@ -34,7 +46,9 @@ pub fn backtranslate(
     // Instead, we use the last *labelled* counterexample.
     let last_label: Option<&str> = silicon_counterexample
         .label_order
-        .last()
+        .iter()
+        .rev()
+        .find(|label| !trace_labels.contains_key(*label))
         .map(|label| label.as_str());
 
     let old_impure_label = if silicon_counterexample
@@ -56,12 +70,17 @@ pub fn backtranslate(
 
     // to be processed
     let entries_to_process = translator.entries_to_process(encoder);
+    let trace =
+        (!trace_labels.is_empty()).then(|| translator.trace(&trace_labels, &entries_to_process));
     let (result_sil_name, result_span, result_typ, result_encoded_typ) =
         translator.result_to_process(encoder);
 
     // map those needed
     let mut entries = FxHashMap::default();
     let mut args = FxHashMap::default();
+    // The initial values of arguments whose type has a model, shown instead of
+    // the values in `args`, from which tests are generated.
+    let mut arg_models = FxHashMap::default();
 
     for (rust_name, span, vir_name, typ, encoded_typ, is_arg) in entries_to_process {
         if !translator.is_pure {
@@ -89,6 +108,15 @@ pub fn backtranslate(
             let arg_entry = translator
                 .translate_silicon_entry(typ, opt_sil_entry, vir_name.clone(), silicon_model)
                 .unwrap_or_default();
+            if translator.has_model(typ) {
+                let arg_entry_snapshot = translator
+                    .translate_silicon_entry_with_snapshot(typ, opt_sil_entry, Some(encoded_typ))
+                    .unwrap_or_default();
+                arg_models.insert(
+                    (rust_name.clone(), span),
+                    arg_entry.merge(&arg_entry_snapshot),
+                );
+            }
             args.insert((rust_name, span), arg_entry);
         }
     }
@@ -117,7 +145,8 @@ pub fn backtranslate(
     // add counterexample notes for arguments and locals
     for (place, entry) in sorted_entries.into_iter() {
         // place is a tuple (Name of the variable, Option<Span>)
-        ce_entries.push(if let Some(entry_arg) = args.get(&place) {
+        let entry_arg = arg_models.get(&place).or_else(|| args.get(&place));
+        ce_entries.push(if let Some(entry_arg) = entry_arg {
             CounterexampleEntry::with_two_values(place.1, Some(place.0), entry_arg.clone(), entry)
         } else {
             CounterexampleEntry::with_one_value(place.1, Some(place.0), entry)
@@ -141,10 +170,14 @@ pub fn backtranslate(
         ));
     }
 
-    Counterexample::new(ce_entries)
+    let test = config::counterexample_test_dir()
+        .is_some()
+        .then(|| translator.counterexample_test(&args));
+    Counterexample::new(ce_entries, test, trace)
 }
 
-pub struct CounterexampleTranslator<'ce, 'tcx> {
+pub struct CounterexampleTranslator<'ce, 'tcx, 'v> {
+    encoder: &'ce Encoder<'v, 'tcx>,
     env_query: EnvQuery<'tcx>,
     tcx: TyCtxt<'tcx>,
     mir: MirBody<'tcx>,
@@ -156,9 +189,9 @@ pub struct CounterexampleTranslator<'ce, 'tcx> {
     local_variable_manager: LocalVariableManager<'tcx>,
 }
 
-impl<'ce, 'tcx> CounterexampleTranslator<'ce, 'tcx> {
+impl<'ce, 'tcx, 'v> CounterexampleTranslator<'ce, 'tcx, 'v> {
     pub fn new(
-        encoder: &Encoder<'_, 'tcx>,
+        encoder: &'ce Encoder<'v, 'tcx>,
         def_id: ProcedureDefId,
         silicon_counterexample: &'ce SiliconCounterexample,
     ) -> Self {
@@ -169,6 +202,7 @@ impl<'ce, 'tcx> CounterexampleTranslator<'ce, 'tcx> {
         let var_debug_info = mir.var_debug_info.clone();
         let local_variable_manager = LocalVariableManager::new(&mir.local_decls);
         Self {
+            encoder,
             env_query: encoder.env().query,
             tcx: encoder.env().tcx(),
             mir,
@@ -269,6 +303,11 @@ impl<'ce, 'tcx> CounterexampleTranslator<'ce, 'tcx> {
         vir_name: String,
         silicon_ce_entries: &FxHashMap<String, ModelEntry>,
     ) -> Option<Entry> {
+        if let Some(ModelEntry::RecursiveRef(ref_name)) = sil_entry {
+            // follow the reference to the actual entry
+            let real_ref_entry = silicon_ce_entries.get(ref_name);
+            return self.translate_silicon_entry(typ, real_ref_entry, vir_name, silicon_ce_entries);
+        }
         Some(match (typ.kind(), sil_entry) {
             (ty::TyKind::Bool, Some(ModelEntry::LitBool(value))) => Entry::Bool(*value),
             (ty::TyKind::Bool, Some(ModelEntry::Ref(_, map))) => {
@@ -348,6 +387,7 @@ impl<'ce, 'tcx> CounterexampleTranslator<'ce, 'tcx> {
                 Entry::Struct {
                     name: struct_name,
                     field_entries,
+                    custom_print_option: self.custom_print_option(adt_def.did(), None),
                 }
             }
             (ty::TyKind::Adt(adt_def, subst), Some(ModelEntry::Ref(_, map)))
@@ -356,6 +396,7 @@ impl<'ce, 'tcx> CounterexampleTranslator<'ce, 'tcx> {
                 let super_name = format!("{adt_def:?}");
                 let mut variant_name = "?".to_string();
                 let mut field_entries = vec![];
+                let mut custom_print_option = None;
 
                 let mut variant = None;
                 let mut opt_discriminant = self.translate_int(map.get("discriminant"));
@@ -397,23 +438,207 @@ impl<'ce, 'tcx> CounterexampleTranslator<'ce, 'tcx> {
                         subst,
                         silicon_ce_entries,
                     );
+                    custom_print_option =
+                        self.custom_print_option(adt_def.did(), Some(variant_name.as_str()));
                 }
 
                 Entry::Enum {
                     super_name,
                     name: variant_name,
                     field_entries,
+                    custom_print_option,
                 }
             }
             (ty::TyKind::Adt(adt_def, _), _) if adt_def.is_enum() => Entry::Enum {
                 super_name: format!("{adt_def:?}"),
                 name: "?".to_string(),
                 field_entries: vec![],
+                custom_print_option: None,
             },
             _ => Entry::Unknown,
         })
     }
 
+    /// The basic blocks on the failing path, with the values of the variables
+    /// that changed since the previous block. The values are those at the
+    /// start of each block, i.e., at its trace label.
+    fn trace(
+        &self,
+        trace_labels: &FxHashMap<String, mir::BasicBlock>,
+        entries_to_process: &[(String, Span, String, Ty<'tcx>, String, bool)],
+    ) -> CounterexampleTrace {
+        let mut trace = CounterexampleTrace::default();
+        let mut last_values: FxHashMap<&str, String> = FxHashMap::default();
+        for label in &self.silicon_counterexample.label_order {
+            let Some(&block) = trace_labels.get(label) else {
+                continue;
+            };
+            let mut values = vec![];
+            for (rust_name, _, vir_name, typ, _, _) in entries_to_process {
+                let (silicon_model, opt_sil_entry) =
+                    self.get_silicon_at_label(Some(label), vir_name);
+                let entry = self
+                    .translate_silicon_entry(*typ, opt_sil_entry, vir_name.clone(), silicon_model)
+                    .unwrap_or_default();
+                if matches!(entry, Entry::Unknown) {
+                    continue;
+                }
+                let value = format!("{entry:?}");
+                if last_values.get(rust_name.as_str()) != Some(&value) {
+                    last_values.insert(rust_name.as_str(), value.clone());
+                    values.push((rust_name.clone(), value));
+                }
+            }
+            let span = self.mir.basic_blocks[block].terminator().source_info.span;
+            trace.push_step(format!("{block:?}"), span.into(), values);
+        }
+        trace
+    }
+
+    /// A test calling the procedure with the initial values `args` of its
+    /// arguments.
+    fn counterexample_test(&self, args: &FxHashMap<(String, Span), Entry>) -> CounterexampleTest {
+        let function = with_no_trimmed_paths!(self.tcx.def_path_str(self.def_id));
+        let arguments = if self
+            .tcx
+            .generics_of(self.def_id)
+            .requires_monomorphization(self.tcx)
+        {
+            Err("the procedure is generic".to_string())
+        } else {
+            (1..=self.mir.arg_count)
+                .map(|index| {
+                    let local = mir::Local::from_usize(index);
+                    let (name, entry) = self
+                        .var_debug_info
+                        .iter()
+                        .find_map(|vdi| {
+                            let mir::VarDebugInfoContents::Place(place) = vdi.value else {
+                                return None;
+                            };
+                            if place.as_local() != Some(local) {
+                                return None;
+                            }
+                            let name = vdi.name.to_ident_string();
+                            let entry = args.get(&(name.clone(), vdi.source_info.span))?;
+                            Some((name, entry))
+                        })
+                        .ok_or_else(|| {
+                            format!("the counterexample does not contain argument {index}")
+                        })?;
+                    self.rust_expression(self.mir.local_decls[local].ty, entry)
+                        .map_err(|reason| format!("{reason} in argument `{name}`"))
+                })
+                .collect()
+        };
+        CounterexampleTest::new(function, arguments)
+    }
+
+    /// A Rust expression constructing the value `entry` of type `typ`.
+    fn rust_expression(&self, typ: Ty<'tcx>, entry: &Entry) -> Result<String, String> {
+        Ok(match (typ.kind(), entry) {
+            (ty::TyKind::Bool, Entry::Bool(value)) => value.to_string(),
+            (ty::TyKind::Int(_) | ty::TyKind::Uint(_), Entry::Int(value)) => {
+                format!("{value}_{typ}")
+            }
+            (ty::TyKind::Float(_), Entry::Float(value))
+                if value.parse::<f64>().map_or(false, f64::is_finite) =>
+            {
+                format!("{value}_{typ}")
+            }
+            (ty::TyKind::Char, Entry::Char(value)) => format!("{value:?}"),
+            (ty::TyKind::Ref(_, typ, mutability), Entry::Ref(entry)) => {
+                let prefix = match mutability {
+                    Mutability::Mut => "&mut ",
+                    Mutability::Not => "&",
+                };
+                format!("{prefix}{}", self.rust_expression(*typ, entry)?)
+            }
+            (ty::TyKind::Tuple(types), Entry::Tuple(entries)) if types.len() == entries.len() => {
+                let fields = iter::zip(types.iter(), entries)
+                    .map(|(typ, entry)| self.rust_expression(typ, entry))
+                    .collect::<Result<Vec<_>, _>>()?;
+                if fields.len() == 1 {
+                    format!("({},)", fields[0])
+                } else {
+                    format!("({})", fields.join(", "))
+                }
+            }
+            (ty::TyKind::Array(typ, _) | ty::TyKind::Slice(typ), Entry::Array(entries)) => {
+                let elements = entries
+                    .iter()
+                    .map(|entry| self.rust_expression(*typ, entry))
+                    .collect::<Result<Vec<_>, _>>()?;
+                format!("[{}]", elements.join(", "))
+            }
+            (ty::TyKind::Adt(adt_def, subst), Entry::Box(entry)) if adt_def.is_box() => {
+                format!(
+                    "Box::new({})",
+                    self.rust_expression(subst.type_at(0), entry)?
+                )
+            }
+            (ty::TyKind::Adt(adt_def, subst), Entry::Struct { field_entries, .. })
+                if adt_def.is_struct() =>
+            {
+                self.rust_constructor(
+                    with_no_trimmed_paths!(self.tcx.def_path_str(adt_def.did())),
+                    adt_def.non_enum_variant(),
+                    subst,
+                    field_entries,
+                )?
+            }
+            (
+                ty::TyKind::Adt(adt_def, subst),
+                Entry::Enum {
+                    name,
+                    field_entries,
+                    ..
+                },
+            ) if adt_def.is_enum() => {
+                let variant = adt_def
+                    .variants()
+                    .iter()
+                    .find(|variant| variant.ident(self.tcx).name.as_str() == name)
+                    .ok_or_else(|| format!("the variant of the `{typ}` value is unknown"))?;
+                self.rust_constructor(
+                    with_no_trimmed_paths!(self.tcx.def_path_str(variant.def_id)),
+                    variant,
+                    subst,
+                    field_entries,
+                )?
+            }
+            (_, Entry::Unknown) => return Err(format!("the `{typ}` value is unknown")),
+            _ => return Err(format!("`{typ}` values cannot be constructed")),
+        })
+    }
+
+    /// A Rust expression constructing the struct or enum variant `variant`,
+    /// whose path is `path`, from the values of its fields.
+    fn rust_constructor(
+        &self,
+        path: String,
+        variant: &ty::VariantDef,
+        subst: ty::GenericArgsRef<'tcx>,
+        field_entries: &[(String, Entry)],
+    ) -> Result<String, String> {
+        if variant.fields.len() != field_entries.len() {
+            return Err(format!("the fields of the `{path}` value are unknown"));
+        }
+        let fields = iter::zip(&variant.fields, field_entries)
+            .map(|(field, (_, entry))| self.rust_expression(field.ty(self.tcx, subst), entry))
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(match variant.ctor_kind() {
+            Some(CtorKind::Const) => path,
+            Some(CtorKind::Fn) => format!("{path}({})", fields.join(", ")),
+            None => {
+                let fields: Vec<_> = iter::zip(&variant.fields, fields)
+                    .map(|(field, value)| format!("{}: {value}", field.ident(self.tcx)))
+                    .collect();
+                format!("{path} {{ {} }}", fields.join(", "))
+            }
+        })
+    }
+
     fn translate_vardef(
         &self,
         variant: &ty::VariantDef,
@@ -570,7 +795,7 @@ impl<'ce, 'tcx> CounterexampleTranslator<'ce, 'tcx> {
                 self.translate_silicon_entry_with_snapshot(typ, real_ref_entry, encoded_typ_option)
                     .unwrap_or_default()
             }
-            Some(ModelEntry::DomainValue(_, _)) => {
+            Some(ModelEntry::DomainValue(_, _) | ModelEntry::Seq(_, _)) => {
                 self.translate_snapshot_entry(typ, sil_entry, encoded_typ_option)
             }
             _ => Entry::Unknown,
@@ -583,6 +808,7 @@ impl<'ce, 'tcx> CounterexampleTranslator<'ce, 'tcx> {
         snapshot_var: Option<&ModelEntry>,
         encoded_typ_option: Option<String>,
     ) -> Entry {
+        let typ_with_ref = typ;
         match typ.kind() {
             ty::TyKind::Ref(_, typ, _) => {
                 match snapshot_var {
@@ -602,9 +828,26 @@ impl<'ce, 'tcx> CounterexampleTranslator<'ce, 'tcx> {
                             Entry::Ref(Box::new(Entry::Unknown))
                         }
                     }
+                    Some(ModelEntry::RecursiveRef(ref_name)) => {
+                        let real_ref_entry =
+                            self.silicon_counterexample.model.entries.get(ref_name);
+                        self.translate_snapshot_entry(
+                            typ_with_ref,
+                            real_ref_entry,
+                            encoded_typ_option,
+                        )
+                    }
                     _ => Entry::Ref(Box::new(Entry::Unknown)),
                 }
             }
+            ty::TyKind::Array(elem_typ, _) | ty::TyKind::Slice(elem_typ) => {
+                match self.sequence_of_snapshot(snapshot_var) {
+                    Some(elements) => {
+                        Entry::Array(self.translate_snapshot_elements(*elem_typ, elements))
+                    }
+                    None => Entry::Unknown,
+                }
+            }
             ty::TyKind::Tuple(subst) => match snapshot_var {
                 Some(ModelEntry::DomainValue(domain, _)) => {
                     let sil_domain = self
@@ -633,6 +876,15 @@ impl<'ce, 'tcx> CounterexampleTranslator<'ce, 'tcx> {
                 }
                 _ => Entry::Tuple(vec![]),
             },
+            //collections whose snapshot is a sequence, e.g. the fields of models
+            ty::TyKind::Adt(_, subst) if matches!(snapshot_var, Some(ModelEntry::Seq(..))) => {
+                match (snapshot_var, subst.types().next()) {
+                    (Some(ModelEntry::Seq(_, elements)), Some(elem_typ)) => {
+                        Entry::Seq(self.translate_snapshot_elements(elem_typ, elements))
+                    }
+                    _ => Entry::Unknown,
+                }
+            }
             ty::TyKind::Adt(adt_def, subst) if adt_def.is_box() => {
                 let new_typ = subst.type_at(0);
                 let entry = self
@@ -647,6 +899,19 @@ impl<'ce, 'tcx> CounterexampleTranslator<'ce, 'tcx> {
             ty::TyKind::Adt(adt_def, subst) if adt_def.is_struct() => {
                 let variant = adt_def.variants().iter().next().unwrap();
                 let struct_name = variant.ident(self.tcx).name.to_ident_string();
+                //a type with a model is shown as its model
+                if let Some(Entry::Struct {
+                    field_entries,
+                    custom_print_option,
+                    ..
+                }) = self.translate_model(adt_def.did(), snapshot_var)
+                {
+                    return Entry::Struct {
+                        name: format!("{struct_name}_model"),
+                        field_entries,
+                        custom_print_option,
+                    };
+                }
                 let field_entries = self.translate_snapshot_adt_fields(
                     variant,
                     snapshot_var,
@@ -656,6 +921,7 @@ impl<'ce, 'tcx> CounterexampleTranslator<'ce, 'tcx> {
                 Entry::Struct {
                     name: struct_name,
                     field_entries,
+                    custom_print_option: self.custom_print_option(adt_def.did(), None),
                 }
             }
             ty::TyKind::Adt(adt_def, subst) if adt_def.is_enum() => {
@@ -691,10 +957,15 @@ impl<'ce, 'tcx> CounterexampleTranslator<'ce, 'tcx> {
                                         encoded_typ,
                                         subst,
                                     );
+                                    let custom_print_option = self.custom_print_option(
+                                        adt_def.did(),
+                                        Some(variant_name.as_str()),
+                                    );
                                     return Entry::Enum {
                                         super_name,
                                         name: variant_name,
                                         field_entries,
+                                        custom_print_option,
                                     };
                                 }
                             }
@@ -705,12 +976,130 @@ impl<'ce, 'tcx> CounterexampleTranslator<'ce, 'tcx> {
                     super_name: format!("{adt_def:?}"),
                     name: "?".to_string(),
                     field_entries: vec![],
+                    custom_print_option: None,
                 }
             }
             _ => Entry::Unknown,
         }
     }
 
+    /// The elements of the snapshot of an array or slice, i.e., of the
+    /// sequence it was constructed from.
+    fn sequence_of_snapshot<'a>(
+        &'a self,
+        snapshot_var: Option<&'a ModelEntry>,
+    ) -> Option<&'a [ModelEntry]> {
+        let sequence = match snapshot_var? {
+            ModelEntry::DomainValue(domain, _) => {
+                //this should never fail since a DomainValue can only exist if the corresponding domain exists
+                let sil_domain = self
+                    .silicon_counterexample
+                    .domains
+                    .entries
+                    .get(domain)
+                    .unwrap();
+                let uncons_fn_name = format!("uncons${domain}$");
+                let sil_fn_param = vec![snapshot_var.cloned()];
+                sil_domain
+                    .functions
+                    .entries
+                    .get(&uncons_fn_name)?
+                    .get_function_value(&sil_fn_param)
+                    .as_ref()?
+            }
+            sequence => sequence,
+        };
+        match sequence {
+            ModelEntry::Seq(_, elements) => Some(elements),
+            _ => None,
+        }
+    }
+
+    /// Translates the elements of a sequence, all of type `elem_typ`.
+    fn translate_snapshot_elements(
+        &self,
+        elem_typ: Ty<'tcx>,
+        elements: &[ModelEntry],
+    ) -> Vec<Entry> {
+        elements
+            .iter()
+            .map(|element| {
+                let encoded_typ = match element {
+                    ModelEntry::DomainValue(domain, _) => Some(domain.clone()),
+                    _ => None,
+                };
+                self.translate_silicon_entry_with_snapshot(elem_typ, Some(element), encoded_typ)
+                    .unwrap_or_default()
+            })
+            .collect()
+    }
+
+    /// The format of the `#[print_counterexample]` annotation of the type
+    /// `adt_did`, or of its variant `variant` for enums.
+    fn custom_print_option(&self, adt_did: DefId, variant: Option<&str>) -> Option<Vec<String>> {
+        self.encoder
+            .get_type_specs(adt_did)
+            .and_then(|specs| custom_print(self.tcx, &specs.counterexample_print, variant))
+    }
+
+    /// Whether `typ` is a struct annotated with `#[model]`.
+    fn has_model(&self, typ: Ty<'tcx>) -> bool {
+        match typ.kind() {
+            ty::TyKind::Adt(adt_def, _) if adt_def.is_struct() => self
+                .encoder
+                .get_type_specs(adt_def.did())
+                .and_then(|specs| specs.model)
+                .is_some(),
+            _ => false,
+        }
+    }
+
+    /// The model of the struct `adt_did` with snapshot `snapshot_var`, if the
+    /// struct has a model and the model function is part of the
+    /// counterexample.
+    fn translate_model(&self, adt_did: DefId, snapshot_var: Option<&ModelEntry>) -> Option<Entry> {
+        let (to_model, model_id) = self.encoder.get_type_specs(adt_did)?.model?;
+        let to_model_fn = self.to_model_fn(&to_model, adt_did)?;
+        //the Viper function of the pure `model` method, with any type arguments
+        let sil_fn_prefix = format!(
+            "{}__$TY$__",
+            self.encoder.encode_pure_item_name(to_model_fn)
+        );
+        let (_, sil_to_model_fn) = self
+            .silicon_counterexample
+            .functions
+            .entries
+            .iter()
+            .find(|(name, _)| name.starts_with(&sil_fn_prefix))?;
+        let sil_model = sil_to_model_fn.get_function_value(&vec![snapshot_var.cloned()]);
+        let encoded_typ = match sil_model {
+            Some(ModelEntry::DomainValue(domain, _)) => Some(domain.clone()),
+            _ => None,
+        };
+        let model_typ = self.tcx.type_of(model_id).instantiate_identity();
+        Some(self.translate_snapshot_entry(model_typ, sil_model.as_ref(), encoded_typ))
+    }
+
+    /// The `model` method of the struct `adt_did`, which implements the trait
+    /// called `to_model` generated by `#[model]`.
+    fn to_model_fn(&self, to_model: &str, adt_did: DefId) -> Option<DefId> {
+        let (_, impls) = self
+            .tcx
+            .all_local_trait_impls(())
+            .iter()
+            .find(|(trait_id, _)| self.tcx.item_name(**trait_id).as_str() == to_model)?;
+        let impl_id: &LocalDefId = impls.iter().find(|impl_id| {
+            matches!(
+                self.tcx.type_of(**impl_id).instantiate_identity().kind(),
+                ty::TyKind::Adt(adt_def, _) if adt_def.did() == adt_did
+            )
+        })?;
+        self.tcx
+            .associated_item_def_ids(impl_id.to_def_id())
+            .first()
+            .copied()
+    }
+
     fn translate_snapshot_adt_fields(
         &self,
         variant: &ty::VariantDef,
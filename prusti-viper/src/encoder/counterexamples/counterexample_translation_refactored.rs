@@ -1,4 +1,7 @@
-use super::{counterexample_refactored::*, VarMapping, VarMappingInterface};
+use super::{
+    counterexample_refactored::*, custom_print::custom_print, trace::CounterexampleTrace,
+    VarMapping, VarMappingInterface,
+};
 use crate::encoder::{
     counterexamples::mapping::PureFunction,
     errors::PositionManager,
@@ -9,14 +12,12 @@ use crate::encoder::{
 use prusti_common::config;
 use prusti_interface::data::ProcedureDefId;
 use prusti_rustc_interface::{
-    ast::LitKind,
     errors::MultiSpan,
-    hir::{def_id::LocalDefId, Block, Expr, ExprKind, Path, QPath, StmtKind},
+    hir::def_id::LocalDefId,
     middle::{
         mir::{self, VarDebugInfo},
         ty::{self, Ty, TyCtxt},
     },
-    span::source_map::Spanned,
 };
 use rustc_hash::FxHashMap;
 use std::{iter, vec};
@@ -40,8 +41,10 @@ pub fn backtranslate(
     }
 
     let counterexample_entry_vec = translator.process_entries(position_manager, &label_markers);
+    let trace =
+        config::counterexample_trace().then(|| translator.trace(position_manager, &label_markers));
 
-    Counterexample::new(counterexample_entry_vec)
+    Counterexample::new(counterexample_entry_vec, trace)
 }
 
 pub struct CounterexampleTranslator<'ce, 'tcx, 'v> {
@@ -124,6 +127,60 @@ impl<'ce, 'tcx, 'v> CounterexampleTranslator<'ce, 'tcx, 'v> {
         snapshot_var_vec
     }
 
+    /// The basic blocks on the failing path, with the values of the variables
+    /// assigned in each of them.
+    fn trace(
+        &self,
+        position_manager: &PositionManager,
+        label_markers: &FxHashMap<String, bool>,
+    ) -> CounterexampleTrace {
+        let mut variables = vec![];
+        for vdi in &self.var_debug_info {
+            if let mir::VarDebugInfoContents::Place(place) = vdi.value {
+                if let Some(local) = place.as_local() {
+                    variables.push((vdi.name.to_ident_string(), Local::from(local)));
+                }
+            }
+        }
+        variables.push(("result".to_string(), Local::from(mir::Local::from_usize(0))));
+
+        let mut trace = CounterexampleTrace::default();
+        let mut label = "start_label".to_string();
+        loop {
+            let mut span = None;
+            let mut values = vec![];
+            for (rust_name, local) in &variables {
+                let vir_name = self.local_variable_manager.get_name(*local);
+                let snapshot_var = self
+                    .var_mapping
+                    .var_snaphot_mapping
+                    .get(&vir_name)
+                    .and_then(|label_snapshot_mapping| label_snapshot_mapping.get(&label))
+                    .and_then(|snapshot_vars| snapshot_vars.last());
+                if let Some(snapshot_var) = snapshot_var {
+                    let typ = self.local_variable_manager.get_type(*local);
+                    let model_entry = self
+                        .silicon_counterexample
+                        .model
+                        .entries
+                        .get(&snapshot_var.name);
+                    let entry = self.translate_snapshot_entry(model_entry, Some(typ), true);
+                    span.get_or_insert_with(|| {
+                        self.get_span(position_manager, &snapshot_var.position)
+                    });
+                    values.push((rust_name.clone(), format!("{entry:?}")));
+                }
+            }
+            trace.push_step(label.clone(), span.unwrap_or_else(MultiSpan::new), values);
+            if let Some(next) = self.get_successor(&label, label_markers) {
+                label = next.to_string();
+            } else {
+                break;
+            }
+        }
+        trace
+    }
+
     fn process_entries(
         &self,
         position_manager: &PositionManager,
@@ -254,78 +311,6 @@ impl<'ce, 'tcx, 'v> CounterexampleTranslator<'ce, 'tcx, 'v> {
         entries
     }
 
-    fn custom_print(
-        &self,
-        prusti_counterexample_print: Vec<(Option<String>, LocalDefId)>,
-        variant_option: Option<String>,
-    ) -> Option<Vec<String>> {
-        let def_id_option = match variant_option {
-            Some(_) => prusti_counterexample_print
-                .iter()
-                .find(|x| x.0 == variant_option),
-            None => prusti_counterexample_print.first(),
-        };
-
-        if let Some(def_id) = def_id_option {
-            let expr = &self
-                .tcx
-                .hir()
-                .body(self.tcx.hir().body_owned_by(def_id.1))
-                .value;
-            if let ExprKind::Block(
-                Block {
-                    expr:
-                        Some(Expr {
-                            kind: ExprKind::If(_, expr, _),
-                            ..
-                        }),
-                    ..
-                },
-                _,
-            ) = expr.kind
-            {
-                if let ExprKind::Block(block, _) = expr.kind {
-                    let stmts = block.stmts;
-                    let args = stmts
-                        .iter()
-                        .filter_map(|stmt| {
-                            match stmt.kind {
-                                StmtKind::Semi(Expr {
-                                    kind:
-                                        ExprKind::Lit(Spanned {
-                                            node: LitKind::Str(symbol, _),
-                                            ..
-                                        }),
-                                    ..
-                                }) => Some(symbol.to_ident_string()), //first arg
-                                StmtKind::Semi(Expr {
-                                    kind:
-                                        ExprKind::Lit(Spanned {
-                                            node: LitKind::Int(int, _),
-                                            ..
-                                        }),
-                                    ..
-                                }) => Some(int.to_string()), //unnamed fields
-                                StmtKind::Semi(Expr {
-                                    kind: ExprKind::Path(QPath::Resolved(_, Path { segments, .. })),
-                                    ..
-                                }) => {
-                                    //named fields
-                                    segments.first().map(|path_segment| {
-                                        path_segment.ident.name.to_ident_string()
-                                    })
-                                }
-                                _ => None,
-                            }
-                        })
-                        .collect::<Vec<String>>();
-                    return Some(args);
-                }
-            }
-        }
-        None
-    }
-
     fn translate_snapshot_entry(
         &self,
         model_entry: Option<&ModelEntry>,
@@ -465,7 +450,7 @@ impl<'ce, 'tcx, 'v> CounterexampleTranslator<'ce, 'tcx, 'v> {
                 let custom_print_option = self
                     .encoder
                     .get_type_specs(adt_def.did())
-                    .and_then(|p| self.custom_print(p.counterexample_print, None));
+                    .and_then(|p| custom_print(self.tcx, &p.counterexample_print, None));
                 Entry::Struct {
                     name: struct_name,
                     field_entries,
@@ -576,9 +561,10 @@ impl<'ce, 'tcx, 'v> CounterexampleTranslator<'ce, 'tcx, 'v> {
                                 );
                                 let custom_print_option =
                                     self.encoder.get_type_specs(adt_def.did()).and_then(|p| {
-                                        self.custom_print(
-                                            p.counterexample_print,
-                                            Some(variant_name.clone()),
+                                        custom_print(
+                                            self.tcx,
+                                            &p.counterexample_print,
+                                            Some(variant_name.as_str()),
                                         )
                                     });
                                 return Entry::Enum {
@@ -607,7 +593,10 @@ impl<'ce, 'tcx, 'v> CounterexampleTranslator<'ce, 'tcx, 'v> {
 
                 Entry::Seq(entries)
             }
-            (Some(ModelEntry::Seq(_, model_entries)), Some(ty::TyKind::Array(typ, _))) => {
+            (
+                Some(ModelEntry::Seq(_, model_entries)),
+                Some(ty::TyKind::Array(typ, _) | ty::TyKind::Slice(typ)),
+            ) => {
                 let entries = model_entries
                     .iter()
                     .map(|entry| self.translate_snapshot_entry(Some(entry), Some(*typ), model))
@@ -0,0 +1,110 @@
+// © 2023, ETH Zurich
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! Formatting of counterexamples for types annotated with
+//! `#[print_counterexample]`, shared by both counterexample translators.
+
+use prusti_rustc_interface::{
+    ast::LitKind,
+    hir::{def_id::LocalDefId, Block, Expr, ExprKind, Path, QPath, StmtKind},
+    middle::ty::TyCtxt,
+    span::source_map::Spanned,
+};
+use std::fmt;
+
+/// The format string and the fields printed by the `#[print_counterexample]`
+/// annotation of a type, or of its variant `variant_option` for enums.
+pub fn custom_print(
+    tcx: TyCtxt<'_>,
+    prusti_counterexample_print: &[(Option<String>, LocalDefId)],
+    variant_option: Option<&str>,
+) -> Option<Vec<String>> {
+    let def_id_option = match variant_option {
+        Some(variant) => prusti_counterexample_print
+            .iter()
+            .find(|x| x.0.as_deref() == Some(variant)),
+        None => prusti_counterexample_print.first(),
+    };
+
+    if let Some(def_id) = def_id_option {
+        let expr = &tcx.hir().body(tcx.hir().body_owned_by(def_id.1)).value;
+        if let ExprKind::Block(
+            Block {
+                expr:
+                    Some(Expr {
+                        kind: ExprKind::If(_, expr, _),
+                        ..
+                    }),
+                ..
+            },
+            _,
+        ) = expr.kind
+        {
+            if let ExprKind::Block(block, _) = expr.kind {
+                let stmts = block.stmts;
+                let args = stmts
+                    .iter()
+                    .filter_map(|stmt| {
+                        match stmt.kind {
+                            StmtKind::Semi(Expr {
+                                kind:
+                                    ExprKind::Lit(Spanned {
+                                        node: LitKind::Str(symbol, _),
+                                        ..
+                                    }),
+                                ..
+                            }) => Some(symbol.to_ident_string()), //first arg
+                            StmtKind::Semi(Expr {
+                                kind:
+                                    ExprKind::Lit(Spanned {
+                                        node: LitKind::Int(int, _),
+                                        ..
+                                    }),
+                                ..
+                            }) => Some(int.to_string()), //unnamed fields
+                            StmtKind::Semi(Expr {
+                                kind: ExprKind::Path(QPath::Resolved(_, Path { segments, .. })),
+                                ..
+                            }) => {
+                                //named fields
+                                segments
+                                    .first()
+                                    .map(|path_segment| path_segment.ident.name.to_ident_string())
+                            }
+                            _ => None,
+                        }
+                    })
+                    .collect::<Vec<String>>();
+                return Some(args);
+            }
+        }
+    }
+    None
+}
+
+/// Writes the fields `field_entries` of a struct or enum variant in the
+/// format `custom_print` obtained from [`custom_print`].
+pub fn write_custom_print<E: fmt::Debug>(
+    f: &mut fmt::Formatter<'_>,
+    custom_print: &[String],
+    field_entries: &[(String, E)],
+) -> fmt::Result {
+    let mut custom_print_iter = custom_print.iter();
+    let text = custom_print_iter.next().unwrap(); //safe because custom_print has at least one element
+    let mut text_iter = text.split("{}");
+    let mut output = text_iter.next().unwrap().to_string(); //safe because text_iter has at least one element
+    for next in text_iter {
+        let fieldname = custom_print_iter.next().unwrap(); //safe because of encoding (checked by compiler)
+        let field_entry = &field_entries
+            .iter()
+            .find(|(name, _)| fieldname == name)
+            .unwrap()
+            .1; //safe because of encoding (checked by compiler)
+        output.push_str(&format!("{field_entry:#?}"));
+        output.push_str(next);
+    }
+    write!(f, "{output}")
+}
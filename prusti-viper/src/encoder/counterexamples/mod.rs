@@ -1,14 +1,19 @@
+mod custom_print;
 mod discriminants_interface;
 mod interface;
 mod mapping;
+mod trace_labels_interface;
 
 pub(crate) use self::{
     discriminants_interface::{DiscriminantsState, DiscriminantsStateInterface},
     interface::{MirProcedureMapping, MirProcedureMappingInterface},
     mapping::{VarMapping, VarMappingInterface},
+    trace_labels_interface::{TraceLabelsState, TraceLabelsStateInterface},
 };
 
 pub mod counterexample;
 pub mod counterexample_refactored;
+pub mod counterexample_test;
 pub mod counterexample_translation;
 pub mod counterexample_translation_refactored;
+pub mod trace;
@@ -0,0 +1,85 @@
+// © 2023, ETH Zurich
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+use crate::verification_report::{SourceLocation, TraceStepReport, TraceValueReport};
+use prusti_interface::PrustiError;
+use prusti_rustc_interface::{errors::MultiSpan, span::source_map::SourceMap};
+use std::fmt::Write;
+
+/// The execution of a basic block on the failing path of a counterexample.
+struct TraceStep {
+    /// The name of the block, e.g. `bb3`.
+    block: String,
+    span: MultiSpan,
+    /// The variables that changed, with their new value.
+    values: Vec<(String, String)>,
+}
+
+/// The basic blocks executed on the failing path of a counterexample, in
+/// execution order.
+#[derive(Default)]
+pub struct CounterexampleTrace {
+    steps: Vec<TraceStep>,
+}
+
+impl CounterexampleTrace {
+    /// Appends the execution of `block`. The values are rendered on a single
+    /// line.
+    pub fn push_step(&mut self, block: String, span: MultiSpan, values: Vec<(String, String)>) {
+        let values = values
+            .into_iter()
+            .map(|(name, value)| (name, value.split_whitespace().collect::<Vec<_>>().join(" ")))
+            .collect();
+        self.steps.push(TraceStep {
+            block,
+            span,
+            values,
+        });
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.steps.is_empty()
+    }
+
+    /// Annotates a Prusti error with a note listing the steps of the trace.
+    pub fn annotate_error(&self, source_map: &SourceMap, prusti_error: PrustiError) -> PrustiError {
+        let mut note = "counterexample trace:".to_string();
+        for step in &self.steps {
+            write!(note, "\n  {}", step.block).unwrap();
+            if let Some(location) = SourceLocation::from_span(source_map, &step.span) {
+                write!(note, " (line {})", location.start_line).unwrap();
+            }
+            let values: Vec<_> = step
+                .values
+                .iter()
+                .map(|(name, value)| format!("{name} = {value}"))
+                .collect();
+            if !values.is_empty() {
+                write!(note, ": {}", values.join(", ")).unwrap();
+            }
+        }
+        prusti_error.add_note(note, None)
+    }
+
+    /// The trace in the format of the verification report.
+    pub fn to_report(&self, source_map: &SourceMap) -> Vec<TraceStepReport> {
+        self.steps
+            .iter()
+            .map(|step| TraceStepReport {
+                block: step.block.clone(),
+                location: SourceLocation::from_span(source_map, &step.span),
+                values: step
+                    .values
+                    .iter()
+                    .map(|(name, value)| TraceValueReport {
+                        name: name.clone(),
+                        value: value.clone(),
+                    })
+                    .collect(),
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,36 @@
+use prusti_interface::data::ProcedureDefId;
+use prusti_rustc_interface::middle::mir;
+use rustc_hash::FxHashMap;
+use std::cell::RefCell;
+
+/// The labels placed at the start of each encoded basic block when
+/// `counterexample_trace` is enabled. The states of the labels on the failing
+/// path are part of the counterexample.
+#[derive(Default)]
+pub(crate) struct TraceLabelsState {
+    trace_labels: RefCell<FxHashMap<ProcedureDefId, FxHashMap<String, mir::BasicBlock>>>,
+}
+
+pub(crate) trait TraceLabelsStateInterface {
+    /// Returns a fresh label marking the start of `block`.
+    fn add_trace_label(&self, proc_def_id: ProcedureDefId, block: mir::BasicBlock) -> String;
+    fn trace_labels(&self, proc_def_id: ProcedureDefId) -> FxHashMap<String, mir::BasicBlock>;
+}
+
+impl<'v, 'tcx: 'v> TraceLabelsStateInterface for super::super::Encoder<'v, 'tcx> {
+    fn add_trace_label(&self, proc_def_id: ProcedureDefId, block: mir::BasicBlock) -> String {
+        let mut trace_labels = self.trace_labels_state.trace_labels.borrow_mut();
+        let labels = trace_labels.entry(proc_def_id).or_default();
+        let label = format!("trace{}_{block:?}", labels.len());
+        labels.insert(label.clone(), block);
+        label
+    }
+    fn trace_labels(&self, proc_def_id: ProcedureDefId) -> FxHashMap<String, mir::BasicBlock> {
+        self.trace_labels_state
+            .trace_labels
+            .borrow()
+            .get(&proc_def_id)
+            .cloned()
+            .unwrap_or_default()
+    }
+}
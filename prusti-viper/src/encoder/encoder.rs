@@ -6,7 +6,9 @@
 
 use super::{
     builtin_encoder::BuiltinDomainKind,
-    counterexamples::{DiscriminantsState, MirProcedureMapping, MirProcedureMappingInterface},
+    counterexamples::{
+        DiscriminantsState, MirProcedureMapping, MirProcedureMappingInterface, TraceLabelsState,
+    },
     high::{
         builtin_functions::HighBuiltinFunctionEncoderState,
         to_typed::types::HighToTypedTypeEncoderState,
@@ -42,7 +44,7 @@ use crate::{
 };
 use ::log::{debug, info};
 use prusti_common::{
-    config, report::log, utils::identifiers::encode_identifier, vir_expr, vir_local,
+    config, report::log, utils::identifiers::encode_identifier, vir_expr, vir_local, Stopwatch,
 };
 use prusti_interface::{
     data::ProcedureDefId,
@@ -61,6 +63,7 @@ use std::{
     io::Write,
     ops::AddAssign,
     rc::Rc,
+    time::Duration,
 };
 use vir_crate::{
     common::{check_mode::CheckMode, identifier::WithIdentifier},
@@ -97,9 +100,26 @@ pub struct Encoder<'v, 'tcx: 'v> {
     vir_program_before_foldunfold_writer: Option<RefCell<Box<dyn Write>>>,
     vir_program_before_viper_writer: Option<RefCell<Box<dyn Write>>>,
     encoding_errors_counter: RefCell<usize>,
+    /// The procedure that is currently taken from the encoding queue, if any.
+    current_procedure: Cell<Option<ProcedureDefId>>,
+    /// The encoding errors reported while encoding a procedure.
+    procedure_encoding_errors: RefCell<FxHashMap<ProcedureDefId, Vec<SpannedEncodingError>>>,
+    /// The encoding errors reported while encoding a type.
+    type_encoding_errors: RefCell<FxHashMap<ty::Ty<'tcx>, Vec<SpannedEncodingError>>>,
+    /// The encoding errors reported outside of the encoding of a queued
+    /// procedure or type.
+    other_encoding_errors: RefCell<Vec<SpannedEncodingError>>,
+    /// The names of the core proof programs that check types.
+    type_program_names: RefCell<FxHashMap<ty::Ty<'tcx>, String>>,
+    /// The time spent on encoding each procedure.
+    procedure_encoding_times: RefCell<FxHashMap<ProcedureDefId, Duration>>,
+    /// The part of the encoding time of each procedure spent on adding
+    /// fold/unfold statements.
+    procedure_fold_unfold_times: RefCell<FxHashMap<ProcedureDefId, Duration>>,
     name_interner: RefCell<NameInterner>,
     /// Maps locals to the local of their discriminant.
     pub(super) discriminants_state: DiscriminantsState,
+    pub(super) trace_labels_state: TraceLabelsState,
     pub(super) mir_procedure_mapping: MirProcedureMapping,
     /// Whether the current pure expression that's being encoded sits inside a trigger closure.
     /// Viper limits the type of expressions that are allowed in quantifier triggers and
@@ -174,11 +194,19 @@ impl<'v, 'tcx> Encoder<'v, 'tcx> {
             snapshot_encoder_state: Default::default(),
             mirror_encoder: RefCell::new(MirrorEncoder::new()),
             encoding_errors_counter: RefCell::new(0),
+            current_procedure: Cell::new(None),
+            procedure_encoding_errors: RefCell::new(FxHashMap::default()),
+            type_encoding_errors: RefCell::new(FxHashMap::default()),
+            other_encoding_errors: RefCell::new(Vec::new()),
+            type_program_names: RefCell::new(FxHashMap::default()),
+            procedure_encoding_times: RefCell::new(FxHashMap::default()),
+            procedure_fold_unfold_times: RefCell::new(FxHashMap::default()),
             name_interner: RefCell::new(NameInterner::new()),
             is_encoding_trigger: Cell::new(false),
             specifications_state: SpecificationsState::new(def_spec),
             mir_procedure_mapping: Default::default(),
             discriminants_state: Default::default(),
+            trace_labels_state: Default::default(),
         }
     }
 
@@ -266,6 +294,30 @@ impl<'v, 'tcx> Encoder<'v, 'tcx> {
 
     #[tracing::instrument(level = "debug", skip(self))]
     pub(in crate::encoder) fn register_encoding_error(&self, encoding_error: SpannedEncodingError) {
+        if let Some(proc_def_id) = self.current_procedure.get() {
+            self.procedure_encoding_errors
+                .borrow_mut()
+                .entry(proc_def_id)
+                .or_default()
+                .push(encoding_error.clone());
+        } else {
+            self.other_encoding_errors
+                .borrow_mut()
+                .push(encoding_error.clone());
+        }
+        self.emit_encoding_error(encoding_error);
+    }
+
+    fn register_type_encoding_error(&self, ty: ty::Ty<'tcx>, encoding_error: SpannedEncodingError) {
+        self.type_encoding_errors
+            .borrow_mut()
+            .entry(ty)
+            .or_default()
+            .push(encoding_error.clone());
+        self.emit_encoding_error(encoding_error);
+    }
+
+    fn emit_encoding_error(&self, encoding_error: SpannedEncodingError) {
         let prusti_error: PrustiError = encoding_error.into();
         if prusti_error.is_error() {
             self.encoding_errors_counter.borrow_mut().add_assign(1);
@@ -277,6 +329,67 @@ impl<'v, 'tcx> Encoder<'v, 'tcx> {
         *self.encoding_errors_counter.borrow()
     }
 
+    /// The encoding errors reported while encoding the given procedure.
+    pub fn get_procedure_encoding_errors(
+        &self,
+        proc_def_id: ProcedureDefId,
+    ) -> Vec<SpannedEncodingError> {
+        self.procedure_encoding_errors
+            .borrow()
+            .get(&proc_def_id)
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    /// The encoding errors reported while encoding the given type.
+    pub fn get_type_encoding_errors(&self, ty: ty::Ty<'tcx>) -> Vec<SpannedEncodingError> {
+        self.type_encoding_errors
+            .borrow()
+            .get(&ty)
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    /// The encoding errors that are not related to a queued procedure or type.
+    pub fn get_other_encoding_errors(&self) -> Vec<SpannedEncodingError> {
+        self.other_encoding_errors.borrow().clone()
+    }
+
+    /// The name of the core proof program that checks the given type, if it
+    /// was encoded.
+    pub fn get_type_program_name(&self, ty: ty::Ty<'tcx>) -> Option<String> {
+        self.type_program_names.borrow().get(&ty).cloned()
+    }
+
+    /// The time spent on encoding the given procedure, if it was encoded.
+    pub fn get_procedure_encoding_time(&self, proc_def_id: ProcedureDefId) -> Option<Duration> {
+        self.procedure_encoding_times
+            .borrow()
+            .get(&proc_def_id)
+            .copied()
+    }
+
+    /// The time spent on adding fold/unfold statements to the given procedure,
+    /// if it was encoded.
+    pub fn get_procedure_fold_unfold_time(&self, proc_def_id: ProcedureDefId) -> Option<Duration> {
+        self.procedure_fold_unfold_times
+            .borrow()
+            .get(&proc_def_id)
+            .copied()
+    }
+
+    pub(in crate::encoder) fn add_procedure_fold_unfold_time(
+        &self,
+        proc_def_id: ProcedureDefId,
+        duration: Duration,
+    ) {
+        *self
+            .procedure_fold_unfold_times
+            .borrow_mut()
+            .entry(proc_def_id)
+            .or_default() += duration;
+    }
+
     pub(super) fn get_mirror_domain(&self) -> Option<vir::Domain> {
         self.mirror_encoder.borrow().get_domain().cloned()
     }
@@ -373,7 +486,8 @@ impl<'v, 'tcx> Encoder<'v, 'tcx> {
             | ty::TyKind::Closure(_, _)
             | ty::TyKind::Array(..)
             | ty::TyKind::Tuple(_)
-            | ty::TyKind::Param(_) => {
+            | ty::TyKind::Param(_)
+            | ty::TyKind::FnPtr(_) => {
                 Ok(base) // don't use a field for tuples and ADTs
             }
             _ => {
@@ -800,116 +914,128 @@ impl<'v, 'tcx> Encoder<'v, 'tcx> {
                     def_id: proc_def_id,
                     substs,
                 } => {
-                    let proc_name = self.env.name.get_unique_item_name(proc_def_id);
-                    let proc_def_path = self.env.name.get_item_def_path(proc_def_id);
-                    info!("Encoding: {} ({})", proc_name, proc_def_path);
                     assert!(substs.is_empty());
-
-                    if config::unsafe_core_proof() {
-                        if self.env.query.is_unsafe_function(proc_def_id) {
-                            if let Err(error) =
-                                self.encode_lifetimes_core_proof(proc_def_id, CheckMode::Both)
-                            {
-                                self.register_encoding_error(error);
-                                debug!(
-                                    "Error encoding function: {:?} {}",
-                                    proc_def_id,
-                                    CheckMode::Both
-                                );
-                            }
-                        } else {
-                            if config::verify_core_proof() {
-                                if let Err(error) = self
-                                    .encode_lifetimes_core_proof(proc_def_id, CheckMode::CoreProof)
-                                {
-                                    self.register_encoding_error(error);
-                                    debug!(
-                                        "Error encoding function: {:?} {}",
-                                        proc_def_id,
-                                        CheckMode::CoreProof
-                                    );
-                                }
+                    let proc_def_path = self.env.name.get_item_def_path(proc_def_id);
+                    let stopwatch = Stopwatch::start_debug(
+                        "prusti-viper",
+                        format!("encoding of {proc_def_path}"),
+                    );
+                    self.current_procedure.set(Some(proc_def_id));
+                    self.encode_procedure_task(proc_def_id);
+                    self.current_procedure.set(None);
+                    let duration = stopwatch.finish();
+                    self.procedure_encoding_times
+                        .borrow_mut()
+                        .insert(proc_def_id, duration);
+                }
+                EncodingTask::Type { ty } => {
+                    if config::unsafe_core_proof()
+                        && config::verify_core_proof()
+                        && config::verify_types()
+                    {
+                        match self.encode_core_proof_for_type(ty, CheckMode::CoreProof) {
+                            Ok(program_name) => {
+                                self.type_program_names
+                                    .borrow_mut()
+                                    .insert(ty, program_name);
                             }
-                            if config::verify_specifications() {
-                                let check_mode = if config::verify_specifications_with_core_proof()
-                                {
-                                    CheckMode::Both
-                                } else {
-                                    CheckMode::Specifications
-                                };
-                                if let Err(error) =
-                                    self.encode_lifetimes_core_proof(proc_def_id, check_mode)
-                                {
-                                    self.register_encoding_error(error);
-                                    debug!(
-                                        "Error encoding function: {:?} {}",
-                                        proc_def_id, check_mode
-                                    );
-                                }
+                            Err(error) => {
+                                self.register_type_encoding_error(ty, error);
+                                debug!("Error encoding type: {:?} {}", ty, CheckMode::CoreProof);
                             }
                         }
-                        continue;
                     }
+                }
+            }
+        }
+    }
 
-                    let proc_kind = self.get_proc_kind(proc_def_id, None);
-
-                    if matches!(proc_kind, ProcedureSpecificationKind::Pure) {
-                        // Check that the pure Rust function satisfies the basic
-                        // requirements by trying to encode it as a Viper function,
-                        // which will automatically run the validity checks.
+    fn encode_procedure_task(&mut self, proc_def_id: ProcedureDefId) {
+        let proc_name = self.env.name.get_unique_item_name(proc_def_id);
+        let proc_def_path = self.env.name.get_item_def_path(proc_def_id);
+        info!("Encoding: {} ({})", proc_name, proc_def_path);
 
-                        // TODO: Make sure that this encoded function does not end up in
-                        // the Viper file because that would be unsound.
-                        let identity_substs = self.env.query.identity_substs(proc_def_id);
-                        if let Err(error) =
-                            self.encode_pure_function_def(proc_def_id, proc_def_id, identity_substs)
-                        {
-                            self.register_encoding_error(error);
-                            debug!("Error encoding function: {:?}", proc_def_id);
-                            // Skip encoding the function as a method.
-                            continue;
-                        }
+        if config::unsafe_core_proof() {
+            if self.env.query.is_unsafe_function(proc_def_id) {
+                if let Err(error) = self.encode_lifetimes_core_proof(proc_def_id, CheckMode::Both) {
+                    self.register_encoding_error(error);
+                    debug!(
+                        "Error encoding function: {:?} {}",
+                        proc_def_id,
+                        CheckMode::Both
+                    );
+                }
+            } else {
+                if config::verify_core_proof() {
+                    if let Err(error) =
+                        self.encode_lifetimes_core_proof(proc_def_id, CheckMode::CoreProof)
+                    {
+                        self.register_encoding_error(error);
+                        debug!(
+                            "Error encoding function: {:?} {}",
+                            proc_def_id,
+                            CheckMode::CoreProof
+                        );
                     }
-
-                    match proc_kind {
-                        _ if self.is_trusted(proc_def_id, None) => {
-                            debug!(
-                                "Trusted procedure will not be encoded or verified: {:?}",
-                                proc_def_id
-                            );
-                        }
-                        ProcedureSpecificationKind::Predicate(_) => {
-                            debug!(
-                                "Predicates will not be encoded or verified: {:?}",
-                                proc_def_id
-                            );
-                        }
-                        ProcedureSpecificationKind::Pure | ProcedureSpecificationKind::Impure => {
-                            if let Err(error) = self.encode_procedure(proc_def_id) {
-                                self.register_encoding_error(error);
-                                debug!("Error encoding function: {:?}", proc_def_id);
-                            } else {
-                                match self.finalize_viper_program(proc_name, proc_def_id) {
-                                    Ok(program) => self.programs.push(program),
-                                    Err(error) => {
-                                        self.register_encoding_error(error);
-                                        debug!("Error finalizing program: {:?}", proc_def_id);
-                                    }
-                                }
-                            }
-                        }
+                }
+                if config::verify_specifications() {
+                    let check_mode = if config::verify_specifications_with_core_proof() {
+                        CheckMode::Both
+                    } else {
+                        CheckMode::Specifications
+                    };
+                    if let Err(error) = self.encode_lifetimes_core_proof(proc_def_id, check_mode) {
+                        self.register_encoding_error(error);
+                        debug!("Error encoding function: {:?} {}", proc_def_id, check_mode);
                     }
                 }
-                EncodingTask::Type { ty } => {
-                    if config::unsafe_core_proof()
-                        && config::verify_core_proof()
-                        && config::verify_types()
-                    {
-                        if let Err(error) =
-                            self.encode_core_proof_for_type(ty, CheckMode::CoreProof)
-                        {
+            }
+            return;
+        }
+
+        let proc_kind = self.get_proc_kind(proc_def_id, None);
+
+        if matches!(proc_kind, ProcedureSpecificationKind::Pure) {
+            // Check that the pure Rust function satisfies the basic
+            // requirements by trying to encode it as a Viper function,
+            // which will automatically run the validity checks.
+
+            // TODO: Make sure that this encoded function does not end up in
+            // the Viper file because that would be unsound.
+            let identity_substs = self.env.query.identity_substs(proc_def_id);
+            if let Err(error) =
+                self.encode_pure_function_def(proc_def_id, proc_def_id, identity_substs)
+            {
+                self.register_encoding_error(error);
+                debug!("Error encoding function: {:?}", proc_def_id);
+                // Skip encoding the function as a method.
+                return;
+            }
+        }
+
+        match proc_kind {
+            _ if self.is_trusted(proc_def_id, None) => {
+                debug!(
+                    "Trusted procedure will not be encoded or verified: {:?}",
+                    proc_def_id
+                );
+            }
+            ProcedureSpecificationKind::Predicate(_) => {
+                debug!(
+                    "Predicates will not be encoded or verified: {:?}",
+                    proc_def_id
+                );
+            }
+            ProcedureSpecificationKind::Pure | ProcedureSpecificationKind::Impure => {
+                if let Err(error) = self.encode_procedure(proc_def_id) {
+                    self.register_encoding_error(error);
+                    debug!("Error encoding function: {:?}", proc_def_id);
+                } else {
+                    match self.finalize_viper_program(proc_name, proc_def_id) {
+                        Ok(program) => self.programs.push(program),
+                        Err(error) => {
                             self.register_encoding_error(error);
-                            debug!("Error encoding type: {:?} {}", ty, CheckMode::CoreProof);
+                            debug!("Error finalizing program: {:?}", proc_def_id);
                         }
                     }
                 }
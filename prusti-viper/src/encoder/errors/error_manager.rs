@@ -60,6 +60,12 @@ pub enum ErrorCtxt {
     Panic(PanicCause),
     /// A Viper `exhale expr` that encodes the call of a Rust procedure with precondition `expr`
     ExhaleMethodPrecondition,
+    /// A Viper `assert expr` that checks that the function pointer of a call
+    /// satisfies the contract that is used for the call
+    AssertFnPointerSpec,
+    /// A Viper `assert expr` that checks that the accessed field of a union
+    /// is its active field
+    AssertUnionActiveField,
     /// An error when assuming method's functional specification.
     UnexpectedAssumeMethodPrecondition,
     /// An error when assuming method's functional specification.
@@ -416,6 +422,22 @@ impl<'tcx> ErrorManager<'tcx> {
                     .set_failing_assertion(opt_cause_span)
             }
 
+            ("assert.failed:assertion.false", ErrorCtxt::AssertFnPointerSpec) => {
+                PrustiError::verification(
+                    "the function pointer might not satisfy the specification of the call.",
+                    error_span
+                ).set_failing_assertion(opt_cause_span)
+            }
+
+            ("assert.failed:assertion.false", ErrorCtxt::AssertUnionActiveField) |
+            ("application.precondition:assertion.false", ErrorCtxt::AssertUnionActiveField) => {
+                PrustiError::verification(
+                    "the accessed field of the union might not be its active field.",
+                    error_span
+                ).set_failing_assertion(opt_cause_span)
+                .set_help("only the field of a union that was written last can be read.")
+            }
+
             ("fold.failed:assertion.false", ErrorCtxt::ExhaleMethodPrecondition) => {
                 PrustiError::verification(
                     "implicit type invariant expected by the function call might not hold.",
@@ -93,4 +93,29 @@ impl<'tcx> PositionManager<'tcx> {
     pub fn get_span(&self, pos: Position) -> Option<&MultiSpan> {
         self.source_span.get(&pos.id())
     }
+
+    /// The spans of the VIR positions with the given (1-based) line that were
+    /// registered for `def_id`, or for any procedure if `def_id` is `None`, in
+    /// the order in which they were registered.
+    pub fn get_spans_at_line(
+        &self,
+        def_id: Option<ProcedureDefId>,
+        line: usize,
+    ) -> Vec<&MultiSpan> {
+        let mut spans: Vec<_> = self
+            .source_span
+            .iter()
+            .filter(|(pos_id, _)| def_id.is_none() || self.def_id.get(pos_id) == def_id.as_ref())
+            .filter(|(_, span)| {
+                span.primary_span().map_or(false, |primary_span| {
+                    let start = self
+                        .codemap
+                        .lookup_char_pos(primary_span.source_callsite().lo());
+                    start.line == line
+                })
+            })
+            .collect();
+        spans.sort_by_key(|(pos_id, _)| **pos_id);
+        spans.into_iter().map(|(_, span)| span).collect()
+    }
 }
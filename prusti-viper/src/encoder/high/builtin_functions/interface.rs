@@ -235,6 +235,9 @@ impl<'v, 'tcx: 'v> HighBuiltinFunctionEncoderInterface<'tcx>
             BuiltinFunctionKind::SliceLen { elem_pred_type, .. } => {
                 ("Slice$len".to_string(), vec![elem_pred_type.clone()])
             }
+            BuiltinFunctionKind::FnPointerSpec { spec_name, .. } => {
+                (format!("fn_spec${spec_name}"), vec![])
+            }
         }
     }
 }
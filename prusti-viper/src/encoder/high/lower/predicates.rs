@@ -30,7 +30,7 @@ impl IntoPredicates for vir_high::TypeDecl {
             vir_high::TypeDecl::Tuple(ty_decl) => ty_decl.lower(ty, encoder),
             vir_high::TypeDecl::Struct(ty_decl) => ty_decl.lower(ty, encoder),
             vir_high::TypeDecl::Enum(ty_decl) => ty_decl.lower(ty, encoder),
-            vir_high::TypeDecl::Union(_ty_decl) => unreachable!("Unions are not supported"),
+            vir_high::TypeDecl::Union(ty_decl) => ty_decl.lower(ty, encoder),
             vir_high::TypeDecl::Array(ty_decl) => ty_decl.lower(ty, encoder),
             vir_high::TypeDecl::Slice(ty_decl) => ty_decl.lower(ty, encoder),
             vir_high::TypeDecl::Sequence(_ty_decl) => unimplemented!(),
@@ -40,9 +40,7 @@ impl IntoPredicates for vir_high::TypeDecl {
             vir_high::TypeDecl::Never => construct_never_predicate(encoder),
             vir_high::TypeDecl::Closure(ty_decl) => ty_decl.lower(ty, encoder),
             vir_high::TypeDecl::Unsupported(ty_decl) => ty_decl.lower(ty, encoder),
-            vir_high::TypeDecl::Trusted(_ty_decl) => {
-                unreachable!("Trusted types are not supported")
-            }
+            vir_high::TypeDecl::Trusted(ty_decl) => ty_decl.lower(ty, encoder),
         }
     }
 }
@@ -98,6 +96,17 @@ impl IntoPredicates for vir_high::type_decl::TypeVar {
     }
 }
 
+impl IntoPredicates for vir_high::type_decl::Trusted {
+    fn lower(
+        &self,
+        ty: &vir_high::Type,
+        encoder: &impl HighTypeEncoderInterfacePrivate,
+    ) -> Predicates {
+        let predicate = Predicate::new_abstract(ty.lower(encoder));
+        Ok(vec![predicate])
+    }
+}
+
 impl IntoPredicates for vir_high::type_decl::Tuple {
     fn lower(
         &self,
@@ -148,44 +157,80 @@ impl IntoPredicates for vir_high::type_decl::Enum {
         ty: &vir_high::Type,
         encoder: &impl HighTypeEncoderInterfacePrivate,
     ) -> Predicates {
-        let lower_type = ty.lower(encoder);
+        lower_enum(
+            &self.variants,
+            &self.discriminant_values,
+            &self.discriminant_bounds,
+            ty,
+            encoder,
+        )
+    }
+}
 
-        let discriminant_field =
-            vir_high::FieldDecl::discriminant(vir_high::Type::MInt).lower(encoder);
-        let this = Predicate::construct_this(lower_type);
-        let discriminant_loc = vir_poly::Expr::from(this.clone()).field(discriminant_field.clone());
+/// A union is encoded as an enum whose discriminant is the index of the
+/// active field and whose variants wrap the values of the fields.
+impl IntoPredicates for vir_high::type_decl::Union {
+    fn lower(
+        &self,
+        ty: &vir_high::Type,
+        encoder: &impl HighTypeEncoderInterfacePrivate,
+    ) -> Predicates {
+        lower_enum(
+            &self.variants,
+            &self.discriminant_values,
+            &self.discriminant_bounds,
+            ty,
+            encoder,
+        )
+    }
+}
 
-        let mut variants = Vec::new();
-        for (variant, discriminant) in self.variants.iter().zip(self.discriminant_values.clone()) {
-            let guard = vir_poly::Expr::eq_cmp(discriminant_loc.clone(), discriminant.into());
-            let variant_ty = ty.clone().variant(variant.name.clone().into());
-            let predicate = lower_struct(variant, &variant_ty, encoder)?;
-            variants.push((guard, variant.name.clone(), predicate));
-        }
-        let mut predicates: Vec<_> = variants
-            .iter()
-            .filter(|(_, _, predicate)| !predicate.has_empty_body())
-            .map(|(_, _, predicate)| Predicate::Struct(predicate.clone()))
-            .collect();
-        let discriminant_bounds = self
-            .discriminant_bounds
-            .iter()
-            .map(|&(from, to)| {
-                if from == to {
-                    vir_poly::Expr::eq_cmp(discriminant_loc.clone(), from.into())
-                } else {
-                    vir_poly::Expr::and(
-                        vir_poly::Expr::le_cmp(from.into(), discriminant_loc.clone()),
-                        vir_poly::Expr::le_cmp(discriminant_loc.clone(), to.into()),
-                    )
-                }
-            })
-            .disjoin();
-        let enum_predicate =
-            Predicate::new_enum(this, discriminant_field, discriminant_bounds, variants);
-        predicates.push(enum_predicate);
-        Ok(predicates)
+fn lower_enum(
+    variants: &[vir_high::type_decl::Struct],
+    discriminant_values: &[vir_high::type_decl::DiscriminantValue],
+    discriminant_bounds: &[vir_high::type_decl::DiscriminantRange],
+    ty: &vir_high::Type,
+    encoder: &impl HighTypeEncoderInterfacePrivate,
+) -> Predicates {
+    let lower_type = ty.lower(encoder);
+
+    let discriminant_field = vir_high::FieldDecl::discriminant(vir_high::Type::MInt).lower(encoder);
+    let this = Predicate::construct_this(lower_type);
+    let discriminant_loc = vir_poly::Expr::from(this.clone()).field(discriminant_field.clone());
+
+    let mut lowered_variants = Vec::new();
+    for (variant, &discriminant) in variants.iter().zip(discriminant_values) {
+        let guard = vir_poly::Expr::eq_cmp(discriminant_loc.clone(), discriminant.into());
+        let variant_ty = ty.clone().variant(variant.name.clone().into());
+        let predicate = lower_struct(variant, &variant_ty, encoder)?;
+        lowered_variants.push((guard, variant.name.clone(), predicate));
     }
+    let mut predicates: Vec<_> = lowered_variants
+        .iter()
+        .filter(|(_, _, predicate)| !predicate.has_empty_body())
+        .map(|(_, _, predicate)| Predicate::Struct(predicate.clone()))
+        .collect();
+    let discriminant_bounds = discriminant_bounds
+        .iter()
+        .map(|&(from, to)| {
+            if from == to {
+                vir_poly::Expr::eq_cmp(discriminant_loc.clone(), from.into())
+            } else {
+                vir_poly::Expr::and(
+                    vir_poly::Expr::le_cmp(from.into(), discriminant_loc.clone()),
+                    vir_poly::Expr::le_cmp(discriminant_loc.clone(), to.into()),
+                )
+            }
+        })
+        .disjoin();
+    let enum_predicate = Predicate::new_enum(
+        this,
+        discriminant_field,
+        discriminant_bounds,
+        lowered_variants,
+    );
+    predicates.push(enum_predicate);
+    Ok(predicates)
 }
 
 impl IntoPredicates for vir_high::type_decl::Array {
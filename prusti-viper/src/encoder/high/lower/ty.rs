@@ -102,7 +102,15 @@ impl IntoPolymorphic<vir_poly::TypedRef> for vir_high::ty::Enum {
 
 impl IntoPolymorphic<vir_poly::TypedRef> for vir_high::ty::Union {
     fn lower(&self, encoder: &impl HighTypeEncoderInterfacePrivate) -> vir_poly::TypedRef {
-        vir_poly::TypedRef::new(self.name.clone(), self.arguments.lower(encoder))
+        vir_poly::TypedRef {
+            label: self.name.clone(),
+            arguments: self.arguments.lower(encoder),
+            variant: self
+                .variant
+                .as_ref()
+                .map(|variant| variant.to_string())
+                .unwrap_or_default(),
+        }
     }
 }
 
@@ -158,7 +166,7 @@ impl IntoPolymorphic<vir_poly::TypedRef> for vir_high::ty::Unsupported {
 }
 
 impl IntoPolymorphic<vir_poly::TypedRef> for vir_high::ty::Trusted {
-    fn lower(&self, _encoder: &impl HighTypeEncoderInterfacePrivate) -> vir_poly::TypedRef {
-        vir_poly::TypedRef::new(self.name.clone(), Vec::new())
+    fn lower(&self, encoder: &impl HighTypeEncoderInterfacePrivate) -> vir_poly::TypedRef {
+        vir_poly::TypedRef::new(self.name.clone(), self.arguments.lower(encoder))
     }
 }
@@ -28,6 +28,7 @@ pub(crate) fn create_value_field(ty: vir::Type) -> EncodingResult<vir::FieldDecl
         | vir::Type::Trusted(_)
         | vir::Type::Struct(_)
         | vir::Type::Enum(_)
+        | vir::Type::Union(_)
         | vir::Type::Closure(_)
         | vir::Type::FunctionDef(_)
         | vir::Type::FnPointer
@@ -42,11 +43,7 @@ pub(crate) fn create_value_field(ty: vir::Type) -> EncodingResult<vir::FieldDecl
             error_internal!("create_value_field should not be called for {}", ty);
         }
 
-        vir::Type::Union(_)
-        | vir::Type::Pointer(_)
-        | vir::Type::Never
-        | vir::Type::Str
-        | vir::Type::Unsupported(_) => {
+        vir::Type::Pointer(_) | vir::Type::Never | vir::Type::Str | vir::Type::Unsupported(_) => {
             error_unsupported!("{} type is not supported", ty);
         }
 
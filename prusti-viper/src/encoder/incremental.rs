@@ -0,0 +1,473 @@
+// © 2023, ETH Zurich
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! Incremental verification.
+//!
+//! A procedure is fingerprinted by hashing its MIR together with everything
+//! its verification depends on: its contract, the contracts of its callees,
+//! the bodies of the pure functions and predicates it uses, and the
+//! definitions and invariants of the types it mentions. Procedures whose
+//! fingerprint matches the one saved after their last successful verification
+//! are neither encoded nor verified again.
+
+use super::{mir::specifications::SpecificationsInterface, Encoder};
+use log::{error, info, warn};
+use once_cell::sync::Lazy;
+use prusti_common::config;
+use prusti_interface::{data::ProcedureDefId, specs::typed};
+use prusti_rustc_interface::{
+    hir::def::DefKind,
+    middle::{
+        mir::{
+            self,
+            pretty::{write_mir_fn, PassWhere},
+            visit::{TyContext, Visitor},
+        },
+        ty::{self, GenericArgKind},
+    },
+    span::def_id::DefId,
+};
+use regex::Regex;
+use rustc_hash::{FxHashMap, FxHashSet, FxHasher};
+use serde::{Deserialize, Serialize};
+use std::{
+    fmt::Write,
+    fs,
+    hash::{Hash, Hasher},
+    io,
+    path::PathBuf,
+};
+
+/// Computes the fingerprints of procedures.
+pub struct Fingerprinter<'p, 'v: 'p, 'tcx: 'v> {
+    encoder: &'p Encoder<'v, 'tcx>,
+    /// The hash of everything that affects all procedures: the Prusti version
+    /// and the configuration.
+    global_hash: u64,
+}
+
+impl<'p, 'v: 'p, 'tcx: 'v> Fingerprinter<'p, 'v, 'tcx> {
+    pub fn new(encoder: &'p Encoder<'v, 'tcx>) -> Self {
+        let mut hasher = FxHasher::default();
+        encoder.env().get_prusti_version().hash(&mut hasher);
+        config::dump().hash(&mut hasher);
+        Fingerprinter {
+            encoder,
+            global_hash: hasher.finish(),
+        }
+    }
+
+    /// The fingerprint of the given procedure, or `None` if the procedure is
+    /// trusted and thus not verified anyway.
+    pub fn fingerprint(&self, proc_def_id: ProcedureDefId) -> Option<u64> {
+        if self.encoder.is_trusted(proc_def_id, None) {
+            return None;
+        }
+        let mut queue = vec![Dependency::with_body(proc_def_id)];
+        let mut visited = FxHashSet::default();
+        let mut descriptions = vec![];
+        while let Some(dependency) = queue.pop() {
+            if visited.insert(dependency) {
+                let is_root = dependency.def_id == proc_def_id;
+                descriptions.push(self.describe(dependency, is_root, &mut queue));
+            }
+        }
+        // The fingerprint must not depend on the order in which dependencies
+        // are discovered.
+        descriptions.sort_unstable();
+        let mut hasher = FxHasher::default();
+        self.global_hash.hash(&mut hasher);
+        descriptions.hash(&mut hasher);
+        Some(hasher.finish())
+    }
+
+    /// Describes a single dependency and adds the items it depends on to
+    /// `queue`.
+    fn describe(
+        &self,
+        dependency: Dependency,
+        is_root: bool,
+        queue: &mut Vec<Dependency>,
+    ) -> String {
+        let env = self.encoder.env();
+        let tcx = env.tcx();
+        let def_id = dependency.def_id;
+        let mut description = env.name.get_item_def_path(def_id);
+        if !def_id.is_local() {
+            // Any change to a dependency changes its crate hash.
+            write!(description, "\n{}", tcx.crate_hash(def_id.krate)).unwrap();
+        }
+        match tcx.def_kind(def_id) {
+            DefKind::Fn | DefKind::AssocFn => {
+                let substs = env.query.identity_substs(def_id);
+                if let Some(spec) = self.encoder.get_procedure_specs(def_id, substs) {
+                    write!(description, "\n{spec:?}").unwrap();
+                    queue.extend(
+                        procedure_spec_def_ids(&spec)
+                            .into_iter()
+                            .map(Dependency::with_body),
+                    );
+                }
+                if let Some(trait_item) = tcx
+                    .opt_associated_item(def_id)
+                    .and_then(|item| item.trait_item_def_id)
+                {
+                    queue.push(Dependency::without_body(trait_item));
+                }
+                let kind = self.encoder.get_proc_kind(def_id, None);
+                let include_body = dependency.include_body || !kind.is_impure();
+                if let Some(local_def_id) = def_id.as_local() && include_body {
+                    let body = if is_root {
+                        Some(env.body.get_impure_fn_body_identity(local_def_id))
+                    } else {
+                        env.body.try_get_local_body_identity(local_def_id)
+                    };
+                    if let Some(body) = body {
+                        self.describe_body(&mut description, def_id, &body, queue);
+                    }
+                }
+            }
+            DefKind::Closure => {
+                if let Some(body) = def_id
+                    .as_local()
+                    .and_then(|local_def_id| env.body.try_get_local_body_identity(local_def_id))
+                {
+                    self.describe_body(&mut description, def_id, &body, queue);
+                }
+            }
+            DefKind::Struct | DefKind::Enum | DefKind::Union => {
+                let adt_def = tcx.adt_def(def_id);
+                write!(description, "\n{:?}", tcx.predicates_of(def_id)).unwrap();
+                for variant in adt_def.variants() {
+                    write!(description, "\n{}: {:?}", variant.name, variant.discr).unwrap();
+                    for field in &variant.fields {
+                        let field_ty = tcx.type_of(field.did).instantiate_identity();
+                        write!(description, "\n  {}: {field_ty:?}", field.name).unwrap();
+                        collect_ty_dependencies(field_ty, queue);
+                    }
+                }
+                if let Some(spec) = self.encoder.get_type_specs(def_id) {
+                    write!(description, "\n{spec:?}").unwrap();
+                    queue.extend(
+                        type_spec_def_ids(&spec)
+                            .into_iter()
+                            .map(Dependency::with_body),
+                    );
+                }
+            }
+            DefKind::Const | DefKind::AssocConst | DefKind::Static(_) if def_id.is_local() => {
+                let source_map = env.query.codemap();
+                let span = env
+                    .query
+                    .hir()
+                    .span_with_body(env.query.as_hir_id(def_id.expect_local()));
+                if let Ok(source) = source_map.span_to_snippet(span) {
+                    write!(description, "\n{source}").unwrap();
+                }
+            }
+            _ => {}
+        }
+        // Specification markers attached to closures and functions.
+        if let Some(spec) = self.encoder.get_loop_specs(def_id) {
+            write!(description, "\n{spec:?}").unwrap();
+        }
+        if let Some(spec) = self.encoder.get_prusti_assertion(def_id) {
+            write!(description, "\n{spec:?}").unwrap();
+        }
+        if let Some(spec) = self.encoder.get_prusti_assumption(def_id) {
+            write!(description, "\n{spec:?}").unwrap();
+        }
+        if let Some(spec) = self.encoder.get_prusti_refutation(def_id) {
+            write!(description, "\n{spec:?}").unwrap();
+        }
+        if let Some(spec) = self.encoder.get_ghost_begin(def_id) {
+            write!(description, "\n{spec:?}").unwrap();
+        }
+        if let Some(spec) = self.encoder.get_ghost_end(def_id) {
+            write!(description, "\n{spec:?}").unwrap();
+        }
+        normalize_description(&description)
+    }
+
+    fn describe_body(
+        &self,
+        description: &mut String,
+        def_id: DefId,
+        body: &mir::Body<'tcx>,
+        queue: &mut Vec<Dependency>,
+    ) {
+        let env = self.encoder.env();
+        let mut mir = vec![];
+        write_mir_fn(
+            env.tcx(),
+            body,
+            &mut |_: PassWhere, _: &mut dyn io::Write| Ok(()),
+            &mut mir,
+        )
+        .unwrap();
+        write!(description, "\n{}", String::from_utf8_lossy(&mir)).unwrap();
+
+        let mut collector = DependencyCollector {
+            dependencies: vec![],
+            calls: vec![],
+        };
+        collector.visit_body(body);
+        queue.extend(collector.dependencies);
+        for (called_def_id, call_substs) in collector.calls {
+            // Calls of trait methods may be resolved to an implementation,
+            // whose contract is then used.
+            let (resolved_def_id, _) =
+                env.query
+                    .resolve_method_call(def_id, called_def_id, call_substs);
+            queue.push(Dependency::without_body(resolved_def_id));
+        }
+    }
+}
+
+/// An item on which the verification of a procedure depends.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+struct Dependency {
+    def_id: DefId,
+    /// Whether the body of a function is relevant even if the function is
+    /// impure. Used for specification functions.
+    include_body: bool,
+}
+
+impl Dependency {
+    fn with_body(def_id: DefId) -> Self {
+        Dependency {
+            def_id,
+            include_body: true,
+        }
+    }
+
+    fn without_body(def_id: DefId) -> Self {
+        Dependency {
+            def_id,
+            include_body: false,
+        }
+    }
+}
+
+/// Collects the functions, closures, types and constants used in a MIR body.
+struct DependencyCollector<'tcx> {
+    dependencies: Vec<Dependency>,
+    calls: Vec<(DefId, ty::GenericArgsRef<'tcx>)>,
+}
+
+impl<'tcx> Visitor<'tcx> for DependencyCollector<'tcx> {
+    fn visit_terminator(&mut self, terminator: &mir::Terminator<'tcx>, location: mir::Location) {
+        if let mir::TerminatorKind::Call { func, .. } = &terminator.kind {
+            self.calls.extend(func.const_fn_def());
+        }
+        self.super_terminator(terminator, location);
+    }
+
+    fn visit_ty(&mut self, ty: ty::Ty<'tcx>, _: TyContext) {
+        collect_ty_dependencies(ty, &mut self.dependencies);
+    }
+
+    fn visit_constant(&mut self, constant: &mir::Constant<'tcx>, location: mir::Location) {
+        if let mir::ConstantKind::Unevaluated(unevaluated, _) = constant.literal {
+            self.dependencies
+                .push(Dependency::without_body(unevaluated.def));
+        }
+        self.super_constant(constant, location);
+    }
+}
+
+fn collect_ty_dependencies(ty: ty::Ty<'_>, dependencies: &mut Vec<Dependency>) {
+    for arg in ty.walk() {
+        if let GenericArgKind::Type(ty) = arg.unpack() {
+            match ty.kind() {
+                ty::TyKind::Adt(adt_def, _) => {
+                    dependencies.push(Dependency::without_body(adt_def.did()));
+                }
+                ty::TyKind::FnDef(def_id, _) => {
+                    dependencies.push(Dependency::without_body(*def_id));
+                }
+                ty::TyKind::Closure(def_id, _) => {
+                    dependencies.push(Dependency::with_body(*def_id));
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+/// All values of a specification item, including the refined ones.
+fn spec_item_values<T>(item: &typed::SpecificationItem<T>) -> Vec<&T> {
+    item.extract_with_strategy(|(base, value)| base.into_iter().chain([value]).collect())
+        .unwrap_or_default()
+}
+
+/// The specification functions used by a procedure contract.
+fn procedure_spec_def_ids(spec: &typed::ProcedureSpecification) -> Vec<DefId> {
+    let mut def_ids = vec![];
+    for kind in spec_item_values(&spec.kind) {
+        if let typed::ProcedureSpecificationKind::Predicate(Some(body)) = kind {
+            def_ids.push(*body);
+        }
+    }
+    for pres in spec_item_values(&spec.pres) {
+        def_ids.extend(pres);
+    }
+    for posts in spec_item_values(&spec.posts) {
+        def_ids.extend(posts);
+    }
+    for pledges in spec_item_values(&spec.pledges) {
+        def_ids.extend(pledges.iter().filter_map(|pledge| pledge.lhs));
+        def_ids.extend(pledges.iter().map(|pledge| pledge.rhs));
+    }
+    for terminates in spec_item_values(&spec.terminates).into_iter().flatten() {
+        def_ids.push(terminates.to_def_id());
+    }
+    for purity in spec_item_values(&spec.purity).into_iter().flatten() {
+        def_ids.push(*purity);
+    }
+    def_ids
+}
+
+/// The specification functions used by a type specification.
+fn type_spec_def_ids(spec: &typed::TypeSpecification) -> Vec<DefId> {
+    let mut def_ids: Vec<DefId> = spec_item_values(&spec.invariant)
+        .into_iter()
+        .flatten()
+        .copied()
+        .collect();
+    def_ids.extend(spec.model.iter().map(|(_, model)| model.to_def_id()));
+    def_ids.extend(
+        spec.counterexample_print
+            .iter()
+            .map(|(_, def_id)| def_id.to_def_id()),
+    );
+    def_ids
+}
+
+/// Removes the parts of a description that change between compilations even
+/// if the described item did not change: the indices of `DefId`s and the
+/// UUIDs of specification items.
+fn normalize_description(description: &str) -> String {
+    static DEF_INDEX: Lazy<Regex> = Lazy::new(|| Regex::new(r"DefId\(\d+:\d+ ~ ").unwrap());
+    static UUID: Lazy<Regex> = Lazy::new(|| {
+        Regex::new("[a-z0-9]{8}-[a-z0-9]{4}-[a-z0-9]{4}-[a-z0-9]{4}-[a-z0-9]{12}").unwrap()
+    });
+    static NUM_UUID: Lazy<Regex> = Lazy::new(|| Regex::new("[a-z0-9]{32}").unwrap());
+    let description = DEF_INDEX.replace_all(description, "DefId(");
+    let description = NUM_UUID.replace_all(&description, "NUM_UUID");
+    UUID.replace_all(&description, "UUID").into_owned()
+}
+
+const FINGERPRINTS_VERSION: u64 = 1;
+
+#[derive(Serialize, Deserialize)]
+struct FingerprintsFile {
+    version: u64,
+    fingerprints: FxHashMap<String, u64>,
+}
+
+/// The fingerprints of the procedures of a crate that were verified
+/// successfully, saved across compilations.
+pub struct VerifiedFingerprints {
+    path: Option<PathBuf>,
+    fingerprints: FxHashMap<String, u64>,
+    updated: bool,
+}
+
+impl VerifiedFingerprints {
+    /// Loads the fingerprints of the crate with the given file name from
+    /// `config::incremental_dir()`.
+    pub fn load(crate_filename: &str) -> Self {
+        let dir = config::incremental_dir();
+        let path = if dir.as_os_str().is_empty() {
+            warn!(
+                "Incremental verification is enabled, but the fingerprints cannot be saved \
+                because the incremental directory is empty."
+            );
+            None
+        } else {
+            Some(dir.join(format!("{crate_filename}.json")))
+        };
+        let fingerprints = path
+            .as_ref()
+            .and_then(|path| {
+                let file = fs::File::open(path).ok()?;
+                match serde_json::from_reader::<_, FingerprintsFile>(io::BufReader::new(file)) {
+                    Ok(data) if data.version == FINGERPRINTS_VERSION => {
+                        info!("Loaded fingerprints from \"{}\"", path.display());
+                        Some(data.fingerprints)
+                    }
+                    Ok(data) => {
+                        error!(
+                            "Fingerprints version mismatch when reading from \"{}\": \
+                            expected={} actual={}",
+                            path.display(),
+                            FINGERPRINTS_VERSION,
+                            data.version
+                        );
+                        None
+                    }
+                    Err(e) => {
+                        error!(
+                            "Failed to read fingerprints from \"{}\": {e}",
+                            path.display()
+                        );
+                        None
+                    }
+                }
+            })
+            .unwrap_or_default();
+        VerifiedFingerprints {
+            path,
+            fingerprints,
+            updated: false,
+        }
+    }
+
+    /// Whether the procedure with the given name was verified successfully
+    /// with the given fingerprint.
+    pub fn contains(&self, name: &str, fingerprint: u64) -> bool {
+        self.fingerprints.get(name) == Some(&fingerprint)
+    }
+
+    pub fn insert(&mut self, name: String, fingerprint: u64) {
+        if self.fingerprints.insert(name, fingerprint) != Some(fingerprint) {
+            self.updated = true;
+        }
+    }
+
+    pub fn remove(&mut self, name: &str) {
+        if self.fingerprints.remove(name).is_some() {
+            self.updated = true;
+        }
+    }
+
+    /// Saves the fingerprints, if they changed.
+    pub fn save(&mut self) {
+        let Some(path) = &self.path else {
+            return;
+        };
+        if !self.updated {
+            return;
+        }
+        let data = FingerprintsFile {
+            version: FINGERPRINTS_VERSION,
+            fingerprints: self.fingerprints.clone(),
+        };
+        let result = path
+            .parent()
+            .map_or(Ok(()), fs::create_dir_all)
+            .and_then(|()| {
+                let writer = io::BufWriter::new(fs::File::create(path)?);
+                serde_json::to_writer(writer, &data).map_err(io::Error::from)
+            });
+        match result {
+            Ok(()) => info!("Saved fingerprints to \"{}\"", path.display()),
+            Err(e) => error!("Failed to save fingerprints to \"{}\": {e}", path.display()),
+        }
+        self.updated = false;
+    }
+}
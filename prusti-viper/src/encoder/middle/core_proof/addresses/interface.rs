@@ -6,7 +6,7 @@ use crate::encoder::{
     middle::core_proof::{
         lowerer::{DomainsLowererInterface, Lowerer, VariablesLowererInterface},
         references::ReferencesInterface,
-        snapshots::IntoProcedureSnapshot,
+        snapshots::{IntoProcedureSnapshot, SnapshotValuesInterface},
     },
 };
 use vir_crate::{
@@ -70,7 +70,12 @@ impl<'p, 'v: 'p, 'tcx: 'v> AddressesInterface for Lowerer<'p, 'v, 'tcx> {
             vir_mid::Expression::LabelledOld(_) => unimplemented!(),
             vir_mid::Expression::Deref(deref) => {
                 let base_snapshot = deref.base.to_procedure_snapshot(self)?;
-                self.reference_address(deref.base.get_type(), base_snapshot, Default::default())?
+                let base_type = deref.base.get_type();
+                if base_type.is_pointer() {
+                    self.obtain_constant_value(base_type, base_snapshot, deref.position)?
+                } else {
+                    self.reference_address(base_type, base_snapshot, Default::default())?
+                }
             }
             _ => self.extract_root_address(place.get_parent_ref().unwrap())?,
         };
@@ -21,11 +21,13 @@ pub(crate) trait MidCoreProofEncoderInterface<'tcx> {
         proc_def_id: DefId,
         check_mode: CheckMode,
     ) -> SpannedEncodingResult<()>;
+    /// Encodes the checks of the given type. Returns the name of the encoded
+    /// program.
     fn encode_core_proof_for_type(
         &mut self,
         ty: ty::Ty<'tcx>,
         check_mode: CheckMode,
-    ) -> SpannedEncodingResult<()>;
+    ) -> SpannedEncodingResult<String>;
     fn take_core_proof_programs(&mut self) -> Vec<vir_low::Program>;
 }
 
@@ -73,7 +75,7 @@ impl<'v, 'tcx: 'v> MidCoreProofEncoderInterface<'tcx> for super::super::super::E
         &mut self,
         ty: ty::Ty<'tcx>,
         check_mode: CheckMode,
-    ) -> SpannedEncodingResult<()> {
+    ) -> SpannedEncodingResult<String> {
         let (check_copy, def_id) = if ty.is_trivially_pure_clone_copy() {
             (true, None)
         } else if let ty::TyKind::Adt(adt_def, ..) = ty.kind() {
@@ -103,7 +105,7 @@ impl<'v, 'tcx: 'v> MidCoreProofEncoderInterface<'tcx> for super::super::super::E
         } = super::lowerer::lower_type(self, def_id, ty, check_copy)?;
         assert!(procedures.is_empty());
         let mut program = vir_low::Program {
-            name,
+            name: name.clone(),
             check_mode,
             procedures: vec![],
             domains,
@@ -117,7 +119,7 @@ impl<'v, 'tcx: 'v> MidCoreProofEncoderInterface<'tcx> for super::super::super::E
         self.mid_core_proof_encoder_state
             .encoded_programs
             .push(program);
-        Ok(())
+        Ok(name)
     }
 
     fn take_core_proof_programs(&mut self) -> Vec<vir_low::Program> {
@@ -48,13 +48,28 @@ impl IntoLow for vir_mid::Statement {
                 Ok(Vec::new())
             }
             Self::Inhale(statement) => {
+                let mut statements = Vec::new();
                 if let vir_mid::Predicate::OwnedNonAliased(owned) = &statement.predicate {
                     lowerer.mark_owned_non_aliased_as_unfolded(owned.place.get_type())?;
+                    if let Some(pointer) = owned.place.get_parent_ref() {
+                        if owned.place.is_deref() && pointer.get_type().is_pointer() {
+                            // The memory behind the pointer could have been
+                            // changed while we did not own it.
+                            let pointer_snapshot = pointer.to_procedure_snapshot(lowerer)?;
+                            lowerer.encode_pointer_target_havoc(
+                                &mut statements,
+                                pointer.get_type(),
+                                pointer_snapshot,
+                                statement.position,
+                            )?;
+                        }
+                    }
                 }
-                Ok(vec![Statement::inhale(
+                statements.push(Statement::inhale(
                     statement.predicate.into_low(lowerer)?,
                     statement.position,
-                )])
+                ));
+                Ok(statements)
             }
             Self::Exhale(statement) => {
                 if let vir_mid::Predicate::OwnedNonAliased(owned) = &statement.predicate {
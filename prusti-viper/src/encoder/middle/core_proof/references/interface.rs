@@ -2,10 +2,11 @@ use crate::encoder::{
     errors::SpannedEncodingResult,
     middle::core_proof::{
         addresses::AddressesInterface,
-        lowerer::Lowerer,
+        lowerer::{DomainsLowererInterface, Lowerer},
         places::PlacesInterface,
         snapshots::{
             IntoSnapshot, SnapshotAdtsInterface, SnapshotDomainsInterface, SnapshotValuesInterface,
+            SnapshotVariablesInterface,
         },
         types::TypesInterface,
     },
@@ -23,6 +24,13 @@ trait Private {
         position: vir_low::Position,
         version: &str,
     ) -> SpannedEncodingResult<vir_low::Expression>;
+    fn pointer_target_snapshot_at_address(
+        &mut self,
+        pointer_type: &vir_mid::Type,
+        address: vir_low::Expression,
+        heap_version: vir_low::Expression,
+        position: vir_low::Position,
+    ) -> SpannedEncodingResult<vir_low::Expression>;
 }
 
 impl<'p, 'v: 'p, 'tcx: 'v> Private for Lowerer<'p, 'v, 'tcx> {
@@ -40,6 +48,24 @@ impl<'p, 'v: 'p, 'tcx: 'v> Private for Lowerer<'p, 'v, 'tcx> {
             .snapshot_destructor_struct_call(&domain_name, version, return_type, snapshot)?
             .set_default_position(position))
     }
+    fn pointer_target_snapshot_at_address(
+        &mut self,
+        pointer_type: &vir_mid::Type,
+        address: vir_low::Expression,
+        heap_version: vir_low::Expression,
+        position: vir_low::Position,
+    ) -> SpannedEncodingResult<vir_low::Expression> {
+        let pointer = pointer_type.clone().unwrap_pointer();
+        let domain_name = self.encode_snapshot_domain_name(pointer_type)?;
+        let return_type = pointer.target_type.to_snapshot(self)?;
+        self.create_domain_func_app(
+            &domain_name,
+            format!("target_current${domain_name}"),
+            vec![address, heap_version],
+            return_type,
+            position,
+        )
+    }
 }
 
 pub(in super::super) trait ReferencesInterface {
@@ -82,6 +108,25 @@ pub(in super::super) trait ReferencesInterface {
         &mut self,
         reference_type: &vir_mid::Type,
     ) -> SpannedEncodingResult<vir_mid::Type>;
+    fn pointer_target_heap_variable(
+        &mut self,
+        pointer_type: &vir_mid::Type,
+    ) -> SpannedEncodingResult<vir_mid::VariableDecl>;
+    fn pointer_target_snapshot(
+        &mut self,
+        pointer_type: &vir_mid::Type,
+        snapshot: vir_low::Expression,
+        heap_version: vir_low::Expression,
+        position: vir_low::Position,
+    ) -> SpannedEncodingResult<vir_low::Expression>;
+    #[allow(clippy::ptr_arg)] // Clippy false positive.
+    fn encode_pointer_target_havoc(
+        &mut self,
+        statements: &mut Vec<vir_low::Statement>,
+        pointer_type: &vir_mid::Type,
+        snapshot: vir_low::Expression,
+        position: vir_low::Position,
+    ) -> SpannedEncodingResult<(vir_low::Expression, vir_low::Expression)>;
 }
 
 impl<'p, 'v: 'p, 'tcx: 'v> ReferencesInterface for Lowerer<'p, 'v, 'tcx> {
@@ -156,4 +201,80 @@ impl<'p, 'v: 'p, 'tcx: 'v> ReferencesInterface for Lowerer<'p, 'v, 'tcx> {
         let reference_type = ty.clone().unwrap_reference();
         Ok(vir_mid::Type::pointer(*reference_type.target_type))
     }
+    fn pointer_target_heap_variable(
+        &mut self,
+        pointer_type: &vir_mid::Type,
+    ) -> SpannedEncodingResult<vir_mid::VariableDecl> {
+        let domain_name = self.encode_snapshot_domain_name(pointer_type)?;
+        Ok(vir_mid::VariableDecl::new(
+            format!("heap${domain_name}"),
+            vir_mid::Type::MInt,
+        ))
+    }
+    /// The snapshot of the memory behind a raw pointer in the given version
+    /// of the heap. Each write through a pointer of this type creates a new
+    /// version of the heap.
+    fn pointer_target_snapshot(
+        &mut self,
+        pointer_type: &vir_mid::Type,
+        snapshot: vir_low::Expression,
+        heap_version: vir_low::Expression,
+        position: vir_low::Position,
+    ) -> SpannedEncodingResult<vir_low::Expression> {
+        let address = self.obtain_constant_value(pointer_type, snapshot, position)?;
+        self.pointer_target_snapshot_at_address(pointer_type, address, heap_version, position)
+    }
+    /// Creates a new version of the heap in which the memory behind the
+    /// pointer is havocked and the memory at all other addresses is
+    /// unchanged. Returns the old and the new snapshots of the memory behind
+    /// the pointer.
+    fn encode_pointer_target_havoc(
+        &mut self,
+        statements: &mut Vec<vir_low::Statement>,
+        pointer_type: &vir_mid::Type,
+        snapshot: vir_low::Expression,
+        position: vir_low::Position,
+    ) -> SpannedEncodingResult<(vir_low::Expression, vir_low::Expression)> {
+        use vir_low::macros::*;
+        let heap = self.pointer_target_heap_variable(pointer_type)?;
+        let old_heap = self.current_snapshot_variable_version(&heap)?;
+        let new_heap = self.new_snapshot_variable_version(&heap, position)?;
+        let address = self.obtain_constant_value(pointer_type, snapshot, position)?;
+        let old_target_snapshot = self.pointer_target_snapshot_at_address(
+            pointer_type,
+            address.clone(),
+            old_heap.clone().into(),
+            position,
+        )?;
+        let new_target_snapshot = self.pointer_target_snapshot_at_address(
+            pointer_type,
+            address.clone(),
+            new_heap.clone().into(),
+            position,
+        )?;
+        let address_type = self.address_type()?;
+        var_decls! { other_address: {address_type.clone()} };
+        let old_other_target_snapshot = self.pointer_target_snapshot_at_address(
+            pointer_type,
+            other_address.clone().into(),
+            old_heap.into(),
+            position,
+        )?;
+        let new_other_target_snapshot = self.pointer_target_snapshot_at_address(
+            pointer_type,
+            other_address.clone().into(),
+            new_heap.into(),
+            position,
+        )?;
+        statements.push(stmtp! { position =>
+            assume (
+                forall(
+                    other_address: {address_type} :: [ {[new_other_target_snapshot.clone()]} ]
+                    (other_address != [address]) ==>
+                    ([new_other_target_snapshot] == [old_other_target_snapshot])
+                )
+            )
+        });
+        Ok((old_target_snapshot, new_target_snapshot))
+    }
 }
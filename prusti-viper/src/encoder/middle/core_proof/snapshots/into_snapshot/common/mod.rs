@@ -186,9 +186,15 @@ pub(super) trait IntoSnapshotLowerer<'p, 'v: 'p, 'tcx: 'v> {
         deref: &vir_mid::Deref,
         expect_math_bool: bool,
     ) -> SpannedEncodingResult<vir_low::Expression> {
+        let base_type = deref.base.get_type();
+        if base_type.is_pointer() {
+            // The memory behind raw pointers is versioned by the writes in
+            // procedure bodies.
+            unimplemented!("dereference of a raw pointer outside of a procedure: {deref}");
+        }
         let base_snapshot = self.expression_to_snapshot(lowerer, &deref.base, expect_math_bool)?;
         let result = lowerer.reference_target_current_snapshot(
-            deref.base.get_type(),
+            base_type,
             base_snapshot,
             Default::default(),
         )?;
@@ -653,6 +659,9 @@ pub(super) trait IntoSnapshotLowerer<'p, 'v: 'p, 'tcx: 'v> {
                 let value = seq(ContainerOpKind::SeqLen)?;
                 lowerer.construct_constant_snapshot(app.get_type(), value, app.position)
             }
+            BuiltinFunc::Own => {
+                unreachable!("ownership is encoded as a predicate: {}", app);
+            }
             BuiltinFunc::SnapshotEquality => {
                 assert_eq!(app.arguments[0].get_type(), app.arguments[1].get_type());
                 let value = vir_low::Expression::binary_op(
@@ -79,7 +79,19 @@ impl<'p, 'v: 'p, 'tcx: 'v> IntoSnapshotLowerer<'p, 'v, 'tcx> for ProcedureSnapsh
         deref: &vir_mid::Deref,
         expect_math_bool: bool,
     ) -> SpannedEncodingResult<vir_low::Expression> {
-        let result = if self.deref_to_final {
+        let base_type = deref.base.get_type();
+        let result = if base_type.is_pointer() {
+            let base_snapshot =
+                self.expression_to_snapshot(lowerer, &deref.base, expect_math_bool)?;
+            let heap = lowerer.pointer_target_heap_variable(base_type)?;
+            let heap_version = self.variable_to_snapshot(lowerer, &heap)?;
+            lowerer.pointer_target_snapshot(
+                base_type,
+                base_snapshot,
+                heap_version.into(),
+                Default::default(),
+            )?
+        } else if self.deref_to_final {
             self.deref_to_final = false;
             let base_snapshot =
                 self.expression_to_snapshot(lowerer, &deref.base, expect_math_bool)?;
@@ -74,6 +74,19 @@ impl<'p, 'v: 'p, 'tcx: 'v> Private for Lowerer<'p, 'v, 'tcx> {
             let parent_type = parent.get_type();
             let type_decl = self.encoder.get_type_decl_mid(parent_type)?;
             match &type_decl {
+                vir_mid::TypeDecl::Pointer(_) if place.is_deref() => {
+                    // A write through a raw pointer does not change the
+                    // pointer, but creates a new version of the heap.
+                    statements.push(stmtp! { position =>
+                        assume ([new_snapshot] == [old_snapshot.clone()])
+                    });
+                    self.encode_pointer_target_havoc(
+                        statements,
+                        parent_type,
+                        old_snapshot,
+                        position,
+                    )
+                }
                 vir_mid::TypeDecl::Bool
                 | vir_mid::TypeDecl::Int(_)
                 | vir_mid::TypeDecl::Float(_)
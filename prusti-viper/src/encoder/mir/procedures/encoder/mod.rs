@@ -19,7 +19,7 @@ use crate::encoder::{
         specifications::SpecificationsInterface,
         type_layouts::MirTypeLayoutsEncoderInterface,
     },
-    mir_encoder::PRECONDITION_LABEL,
+    mir_encoder::{find_set_union_active_field_place, PRECONDITION_LABEL},
     Encoder,
 };
 use log::debug;
@@ -64,6 +64,7 @@ mod ghost;
 mod initialisation;
 mod lifetimes;
 mod loops;
+mod raw_pointers;
 mod scc;
 pub mod specification_blocks;
 mod termination;
@@ -391,9 +392,20 @@ impl<'p, 'v: 'p, 'tcx: 'v> ProcedureEncoder<'p, 'v, 'tcx> {
         for local in self.mir.args_iter() {
             arguments.push(self.encode_local(local)?.into());
         }
-        for expression in
-            self.encode_precondition_expressions(&procedure_contract, substs, &arguments)?
-        {
+        let precondition_expressions =
+            self.encode_precondition_expressions(&procedure_contract, substs, &arguments)?;
+        let preconditions_split =
+            self.split_pointer_ownership(precondition_expressions, mir_span)?;
+        for predicate in preconditions_split.owned_places {
+            let inhale_statement = self.encoder.set_statement_error_ctxt(
+                vir_high::Statement::inhale_no_pos(predicate),
+                mir_span,
+                ErrorCtxt::UnexpectedAssumeMethodPrecondition,
+                self.def_id,
+            )?;
+            preconditions.push(inhale_statement);
+        }
+        for expression in preconditions_split.functional {
             let assume_statement = self.encoder.set_statement_error_ctxt(
                 vir_high::Statement::assume_no_pos(expression),
                 mir_span,
@@ -406,13 +418,19 @@ impl<'p, 'v: 'p, 'tcx: 'v> ProcedureEncoder<'p, 'v, 'tcx> {
             "Assert functional postconditions.".to_string(),
         )];
         let result: vir_high::Expression = self.encode_local(mir::RETURN_PLACE)?.into();
-        for expression in self.encode_postcondition_expressions(
+        let postcondition_expressions = self.encode_postcondition_expressions(
             &procedure_contract,
             substs,
             arguments,
             &result,
             PRECONDITION_LABEL,
-        )? {
+        )?;
+        let postconditions_split =
+            self.split_pointer_ownership(postcondition_expressions, mir_span)?;
+        if !postconditions_split.owned_places.is_empty() {
+            self.check_pointer_arguments_not_assigned(mir_span)?;
+        }
+        for expression in postconditions_split.functional {
             let assert_statement = self.encoder.set_statement_error_ctxt(
                 vir_high::Statement::assert_no_pos(expression),
                 mir_span,
@@ -421,6 +439,15 @@ impl<'p, 'v: 'p, 'tcx: 'v> ProcedureEncoder<'p, 'v, 'tcx> {
             )?;
             postconditions.push(assert_statement);
         }
+        for predicate in postconditions_split.owned_places {
+            let exhale_statement = self.encoder.set_statement_error_ctxt(
+                vir_high::Statement::exhale_no_pos(predicate),
+                mir_span,
+                ErrorCtxt::AssertMethodPostcondition,
+                self.def_id,
+            )?;
+            postconditions.push(exhale_statement);
+        }
         Ok((preconditions, postconditions))
     }
 
@@ -1614,9 +1641,10 @@ impl<'p, 'v: 'p, 'tcx: 'v> ProcedureEncoder<'p, 'v, 'tcx> {
             )?;
         }
 
-        for expression in
-            self.encode_precondition_expressions(&procedure_contract, call_substs, &arguments)?
-        {
+        let precondition_expressions =
+            self.encode_precondition_expressions(&procedure_contract, call_substs, &arguments)?;
+        let preconditions_split = self.split_pointer_ownership(precondition_expressions, span)?;
+        for expression in preconditions_split.functional {
             let assert_statement = self.encoder.set_statement_error_ctxt(
                 vir_high::Statement::assert_no_pos(expression),
                 span,
@@ -1627,6 +1655,14 @@ impl<'p, 'v: 'p, 'tcx: 'v> ProcedureEncoder<'p, 'v, 'tcx> {
                 block_builder.add_statement(assert_statement);
             }
         }
+        for predicate in preconditions_split.owned_places {
+            block_builder.add_statement(self.encoder.set_statement_error_ctxt(
+                vir_high::Statement::exhale_no_pos(predicate),
+                span,
+                ErrorCtxt::ExhaleMethodPrecondition,
+                self.def_id,
+            )?);
+        }
 
         if self.encoder.env().query.is_closure(called_def_id) {
             // Closure calls are wrapped around std::ops::Fn::call(), which receives
@@ -1647,6 +1683,8 @@ impl<'p, 'v: 'p, 'tcx: 'v> ProcedureEncoder<'p, 'v, 'tcx> {
                 &encoded_target_place,
                 &old_label,
             )?;
+            let postconditions_split =
+                self.split_pointer_ownership(postcondition_expressions, span)?;
             if let Some(target_place_local) = destination.as_local() {
                 let size = self.encoder.encode_type_size_expression(
                     self.encoder.get_local_type(self.mir, target_place_local)?,
@@ -1697,7 +1735,15 @@ impl<'p, 'v: 'p, 'tcx: 'v> ProcedureEncoder<'p, 'v, 'tcx> {
 
                 self.encode_lft_for_block(*target_block, location, &mut post_call_block_builder)?;
 
-                for expression in postcondition_expressions {
+                for predicate in postconditions_split.owned_places {
+                    post_call_block_builder.add_statement(self.encoder.set_statement_error_ctxt(
+                        vir_high::Statement::inhale_no_pos(predicate),
+                        span,
+                        ErrorCtxt::UnexpectedAssumeMethodPostcondition,
+                        self.def_id,
+                    )?);
+                }
+                for expression in postconditions_split.functional {
                     let assume_statement = self.encoder.set_statement_error_ctxt(
                         vir_high::Statement::assume_no_pos(expression),
                         span,
@@ -2100,33 +2146,17 @@ impl<'p, 'v: 'p, 'tcx: 'v> ProcedureEncoder<'p, 'v, 'tcx> {
                         self.encoder.env().name.get_absolute_item_name(*def_id);
                     match full_called_function_name.as_ref() {
                         "prusti_contracts::prusti_set_union_active_field" => {
-                            assert_eq!(args.len(), 1);
-                            let argument_place = if let mir::Operand::Move(place) = args[0] {
-                                place
-                            } else {
-                                unreachable!()
-                            };
-                            // Find the place whose address was stored in the argument by
-                            // iterating backwards through statements.
-                            let mut statement_index = block.statements.len() - 1;
-                            let union_variant_place = loop {
-                                if let Some(statement) = block.statements.get(statement_index) {
-                                    if let mir::StatementKind::Assign(box (
-                                        target_place,
-                                        mir::Rvalue::AddressOf(_, union_variant_place),
-                                    )) = &statement.kind
-                                    {
-                                        if *target_place == argument_place {
-                                            break union_variant_place;
-                                        }
-                                    }
-                                    statement_index -= 1;
-                                } else {
-                                    unreachable!();
-                                }
+                            let Some(union_variant_place) =
+                                find_set_union_active_field_place(block, args)
+                            else {
+                                return Err(SpannedEncodingError::unsupported(
+                                    "prusti_set_union_active_field expects the address of a \
+                                    place that is taken right before the call",
+                                    span,
+                                ));
                             };
                             let encoded_variant_place =
-                                self.encode_place(*union_variant_place, None)?;
+                                self.encode_place(union_variant_place, None)?;
                             let statement = self.encoder.set_statement_error_ctxt(
                                 vir_high::Statement::set_union_variant_no_pos(
                                     encoded_variant_place,
@@ -0,0 +1,175 @@
+//! Ownership of the memory behind raw pointers. A specification `own(p)` is
+//! not assumed or asserted like the other specifications, but encoded as the
+//! predicate `OwnedNonAliased(*p)` that is inhaled and exhaled together with
+//! the ownership of the arguments.
+
+use crate::encoder::errors::{SpannedEncodingError, SpannedEncodingResult};
+use prusti_rustc_interface::{
+    middle::{
+        mir,
+        mir::visit::{PlaceContext, Visitor},
+    },
+    span::Span,
+};
+use vir_crate::{
+    common::expression::BinaryOperationHelpers,
+    high::{self as vir_high, operations::ty::Typed, visitors::ExpressionWalker},
+};
+
+/// The specifications of a function split into the ownership of raw pointers
+/// and the functional specifications.
+#[derive(Default)]
+pub(super) struct SplitSpecifications {
+    pub(super) owned_places: Vec<vir_high::Predicate>,
+    pub(super) functional: Vec<vir_high::Expression>,
+}
+
+impl<'p, 'v: 'p, 'tcx: 'v> super::ProcedureEncoder<'p, 'v, 'tcx> {
+    pub(super) fn split_pointer_ownership(
+        &self,
+        expressions: Vec<vir_high::Expression>,
+        span: Span,
+    ) -> SpannedEncodingResult<SplitSpecifications> {
+        let mut result = SplitSpecifications::default();
+        for expression in expressions {
+            if !mentions_ownership(&expression) {
+                result.functional.push(expression);
+                continue;
+            }
+            let mut conjuncts = Vec::new();
+            collect_conjuncts(expression, &mut conjuncts);
+            let mut functional_conjuncts = Vec::new();
+            for conjunct in conjuncts {
+                match conjunct {
+                    vir_high::Expression::BuiltinFuncApp(vir_high::BuiltinFuncApp {
+                        function: vir_high::BuiltinFunc::Own,
+                        mut arguments,
+                        ..
+                    }) => {
+                        assert_eq!(arguments.len(), 1);
+                        let predicate = owned_pointee(arguments.pop().unwrap(), span)?;
+                        result.owned_places.push(predicate);
+                    }
+                    conjunct if mentions_ownership(&conjunct) => {
+                        return Err(SpannedEncodingError::unsupported(
+                            "the ownership of a raw pointer can be stated only as a conjunct \
+                            of a specification",
+                            span,
+                        ));
+                    }
+                    conjunct => functional_conjuncts.push(conjunct),
+                }
+            }
+            if let Some(functional) = functional_conjuncts
+                .into_iter()
+                .reduce(vir_high::Expression::and)
+            {
+                result.functional.push(functional);
+            }
+        }
+        Ok(result)
+    }
+
+    /// Postconditions state the ownership of the memory behind the pointer
+    /// arguments in the pre-state, which we encode with the current values of
+    /// the arguments (see `owned_pointee`). This is correct only if the
+    /// procedure does not assign to its pointer arguments.
+    pub(super) fn check_pointer_arguments_not_assigned(
+        &self,
+        span: Span,
+    ) -> SpannedEncodingResult<()> {
+        struct AssignmentFinder<'a, 'tcx> {
+            body: &'a mir::Body<'tcx>,
+            found: bool,
+        }
+        impl<'a, 'tcx> Visitor<'tcx> for AssignmentFinder<'a, 'tcx> {
+            fn visit_place(
+                &mut self,
+                place: &mir::Place<'tcx>,
+                context: PlaceContext,
+                _location: mir::Location,
+            ) {
+                if context.is_mutating_use()
+                    && place.projection.is_empty()
+                    && self.body.args_iter().any(|arg| arg == place.local)
+                    && self.body.local_decls[place.local].ty.is_unsafe_ptr()
+                {
+                    self.found = true;
+                }
+            }
+        }
+        let mut finder = AssignmentFinder {
+            body: self.mir,
+            found: false,
+        };
+        finder.visit_body(self.mir);
+        if finder.found {
+            return Err(SpannedEncodingError::unsupported(
+                "the ownership of raw pointers can be stated in a postcondition only if \
+                the function does not assign to its pointer arguments",
+                span,
+            ));
+        }
+        Ok(())
+    }
+}
+
+fn collect_conjuncts(expression: vir_high::Expression, conjuncts: &mut Vec<vir_high::Expression>) {
+    match expression {
+        vir_high::Expression::BinaryOp(vir_high::BinaryOp {
+            op_kind: vir_high::BinaryOpKind::And,
+            box left,
+            box right,
+            ..
+        }) => {
+            collect_conjuncts(left, conjuncts);
+            collect_conjuncts(right, conjuncts);
+        }
+        expression => conjuncts.push(expression),
+    }
+}
+
+fn mentions_ownership(expression: &vir_high::Expression) -> bool {
+    struct Finder {
+        found: bool,
+    }
+    impl ExpressionWalker for Finder {
+        fn walk_builtin_func_app(&mut self, app: &vir_high::BuiltinFuncApp) {
+            if app.function == vir_high::BuiltinFunc::Own {
+                self.found = true;
+            } else {
+                vir_high::visitors::default_walk_builtin_func_app(self, app);
+            }
+        }
+    }
+    let mut finder = Finder { found: false };
+    finder.walk_expression(expression);
+    finder.found
+}
+
+/// The ownership of the memory that `pointer` points to. Postconditions refer
+/// to the arguments in the pre-state, but places cannot be encoded in `old`,
+/// so we use the argument itself. This requires that the function does not
+/// assign to its pointer arguments, which is checked by
+/// `check_pointer_arguments_not_assigned`.
+fn owned_pointee(
+    pointer: vir_high::Expression,
+    span: Span,
+) -> SpannedEncodingResult<vir_high::Predicate> {
+    let pointer = match pointer {
+        vir_high::Expression::LabelledOld(vir_high::LabelledOld { box base, .. }) => base,
+        pointer => pointer,
+    };
+    if !pointer.is_place() {
+        return Err(SpannedEncodingError::unsupported(
+            "the ownership can be stated only for raw pointers stored in variables",
+            span,
+        ));
+    }
+    let vir_high::Type::Pointer(pointer_type) = pointer.get_type() else {
+        unreachable!("{pointer} is not a raw pointer");
+    };
+    let target_type = (*pointer_type.target_type).clone();
+    let place = vir_high::Expression::deref_no_pos(pointer, target_type);
+    Ok(vir_high::Predicate::owned_non_aliased_no_pos(place))
+}
@@ -304,6 +304,15 @@ impl<'p, 'v: 'p, 'tcx: 'v> ExpressionBackwardInterpreter<'p, 'v, 'tcx> {
                     ));
                 }
             }
+            mir::Rvalue::Cast(
+                mir::CastKind::PointerCoercion(ty::adjustment::PointerCoercion::MutToConstPointer),
+                operand,
+                _,
+            ) => {
+                // Raw pointers are encoded without their mutability.
+                let encoded_rhs = self.encode_operand(operand, span)?;
+                state.substitute_value(&encoded_lhs, encoded_rhs);
+            }
             mir::Rvalue::Cast(kind, _, _) => {
                 return Err(SpannedEncodingError::unsupported(
                     format!("unsupported kind of cast: {kind:?}"),
@@ -677,6 +686,17 @@ impl<'p, 'v: 'p, 'tcx: 'v> ExpressionBackwardInterpreter<'p, 'v, 'tcx> {
                 );
                 subst_with(encoded_rhs)
             }
+            "prusti_contracts::own" => {
+                let position = encoded_args[0].position();
+                let encoded_rhs = vir_high::Expression::builtin_func_app(
+                    vir_high::BuiltinFunc::Own,
+                    Vec::new(),
+                    encoded_args.into(),
+                    vir_high::Type::Bool,
+                    position,
+                );
+                subst_with(encoded_rhs)
+            }
             "prusti_contracts::before_expiry" => {
                 // self.encode_call_before_expiry()?
                 unimplemented!();
@@ -20,7 +20,8 @@ use crate::{
             types::{compute_discriminant_values, MirTypeEncoderInterface},
         },
         mir_encoder::{
-            MirEncoder, PlaceEncoder, PlaceEncoding, PRECONDITION_LABEL, WAND_LHS_LABEL,
+            MirEncoder, PlaceEncoder, PlaceEncoding, LOOP_ITERATOR_PLACEHOLDER, PRECONDITION_LABEL,
+            WAND_LHS_LABEL,
         },
         snapshot::interface::SnapshotEncoderInterface,
         Encoder,
@@ -33,6 +34,7 @@ use prusti_interface::environment::mir_utils::SliceOrArrayRef;
 use prusti_rustc_interface::{
     hir::def_id::DefId,
     middle::{mir, span_bug, ty},
+    span::Span,
 };
 use rustc_hash::FxHashMap;
 use std::{convert::TryInto, mem};
@@ -99,10 +101,81 @@ impl<'p, 'v: 'p, 'tcx: 'v> PureFunctionBackwardInterpreter<'p, 'v, 'tcx> {
                 let new_expr = vir::Expr::downcast(base, encoded_place, variant_field);
                 let _ = mem::replace(expr, new_expr);
             }
+            // The fields of unions are encoded as variants whose discriminant is
+            // the index of the active field.
+            let union_fields = self
+                .mir_encoder
+                .get_union_field_accesses_at_location(location);
+            for access in union_fields.into_iter().rev() {
+                let (encoded_place, place_ty, _) = self
+                    .encode_projection(access.union_place.local, &access.union_place.projection)
+                    .with_span(span)?;
+                let ty::TyKind::Adt(adt_def, _subst) = place_ty.kind() else {
+                    unreachable!()
+                };
+                let tcx = self.encoder.env().tcx();
+                let field_name = adt_def.non_enum_variant().fields[access.field]
+                    .ident(tcx)
+                    .to_string();
+                let variant_field = self.encoder.encode_enum_variant_field(&field_name);
+                let base = mem::replace(expr, true.into());
+                let mut new_expr = vir::Expr::downcast(base, encoded_place.clone(), variant_field);
+                if !access.is_write && adt_def.non_enum_variant().fields.len() > 1 {
+                    // Only the active field of a union can be read.
+                    let discriminant =
+                        encoded_place.field(self.encoder.encode_discriminant_field());
+                    let is_active = vir::Expr::eq_cmp(discriminant, access.field.index().into());
+                    new_expr = self.encode_union_active_field_check(is_active, new_expr, span)?;
+                }
+                let _ = mem::replace(expr, new_expr);
+            }
         }
         Ok(())
     }
 
+    /// Guards `expr` with the check that the accessed field of a union is its
+    /// active field. A failing check is handled like a failing assertion.
+    fn encode_union_active_field_check(
+        &self,
+        is_active: vir::Expr,
+        expr: vir::Expr,
+        span: Span,
+    ) -> SpannedEncodingResult<vir::Expr> {
+        let failure = match self.pure_encoding_context {
+            PureEncodingContext::Trigger => return Ok(expr),
+            PureEncodingContext::Assertion
+                if matches!(self.mir.return_ty().kind(), ty::TyKind::Bool) =>
+            {
+                false.into()
+            }
+            PureEncodingContext::Assertion | PureEncodingContext::Code => {
+                let pos = self.encoder.error_manager().register_error(
+                    span,
+                    ErrorCtxt::AssertUnionActiveField,
+                    self.caller_def_id,
+                );
+                let encoded_type = self
+                    .encoder
+                    .encode_snapshot_type(self.mir.return_ty())
+                    .with_span(span)?;
+                let (function_name, type_arguments) =
+                    self.encoder
+                        .encode_builtin_function_use(BuiltinFunctionKind::Unreachable(
+                            encoded_type.clone(),
+                        ));
+                vir::Expr::func_app(
+                    function_name,
+                    type_arguments,
+                    vec![],
+                    vec![],
+                    encoded_type,
+                    pos,
+                )
+            }
+        };
+        Ok(vir::Expr::ite(is_active, expr, failure))
+    }
+
     pub(crate) fn mir_encoder(&self) -> &MirEncoder<'p, 'v, 'tcx> {
         &self.mir_encoder
     }
@@ -454,6 +527,57 @@ impl<'p, 'v: 'p, 'tcx: 'v> BackwardMirInterpreter<'tcx>
                                 state
                             }
 
+                            "prusti_contracts::loop_iterator" => {
+                                // The iterator of the `for` loop is only known
+                                // when the loop invariant is encoded, where
+                                // the placeholder gets replaced.
+                                assert!(args.is_empty());
+                                let placeholder = vir::Expr::local(vir::LocalVar::new(
+                                    LOOP_ITERATOR_PLACEHOLDER,
+                                    encoded_lhs.get_type().clone(),
+                                ));
+                                let mut state = states[&target_block].clone();
+                                state.substitute_value(
+                                    &encoded_lhs,
+                                    vir::Expr::snap_app(placeholder),
+                                );
+                                state
+                            }
+
+                            "prusti_contracts::satisfies_spec" => {
+                                assert_eq!(args.len(), 2);
+                                let pointer_ty = self.mir_encoder.get_operand_ty(&args[0]);
+                                let spec_ty = self.mir_encoder.get_operand_ty(&args[1]);
+                                let ty::TyKind::FnDef(spec_def_id, spec_substs) = spec_ty.kind()
+                                else {
+                                    return Err(SpannedEncodingError::incorrect(
+                                        "the specification of a function pointer must be \
+                                        the name of a function",
+                                        span,
+                                    ));
+                                };
+                                let encoded_rhs = self
+                                    .mir_encoder
+                                    .encode_satisfies_spec_expr(
+                                        encoded_args[0].clone(),
+                                        pointer_ty,
+                                        *spec_def_id,
+                                        spec_substs,
+                                    )
+                                    .with_span(span)?;
+                                let mut state = states[&target_block].clone();
+                                state.substitute_value(&encoded_lhs, encoded_rhs);
+                                state
+                            }
+
+                            "prusti_contracts::own" => {
+                                return Err(SpannedEncodingError::unsupported(
+                                    "ownership of raw pointers is supported only with \
+                                    unsafe_core_proof",
+                                    span,
+                                ));
+                            }
+
                             "std::cmp::PartialEq::eq" | "core::cmp::PartialEq::eq"
                                 if self.encoder.has_structural_eq_impl(
                                     self.mir_encoder.get_operand_ty(&args[0]),
@@ -928,6 +1052,44 @@ impl<'p, 'v: 'p, 'tcx: 'v> BackwardMirInterpreter<'tcx>
                                 state.substitute_value(&encoded_lhs, snapshot);
                             }
 
+                            &mir::AggregateKind::Adt(adt_did, _, subst, _, Some(active_field)) => {
+                                // A union, whose discriminant is the index of the active field.
+                                let tcx = self.encoder.env().tcx();
+                                let adt_def = tcx.adt_def(adt_did);
+                                let field = &adt_def.non_enum_variant().fields[active_field];
+                                let field_ty = field.ty(tcx, subst);
+                                let discr_field = self.encoder.encode_discriminant_field();
+                                state.substitute_value(
+                                    &encoded_lhs.clone().field(discr_field),
+                                    active_field.index().into(),
+                                );
+                                let encoded_field = self.encoder
+                                    .encode_raw_ref_field("value".to_string(), field_ty)
+                                    .with_span(span)?;
+                                let field_place = encoded_lhs.clone()
+                                    .variant(field.ident(tcx).as_str())
+                                    .field(encoded_field);
+
+                                let operand = &operands[0usize.into()];
+                                let (encoded_rhs, is_value) = self.encode_operand(operand)
+                                    .with_span(span)?;
+                                if is_value {
+                                    state.substitute_value(
+                                        &self.encoder.encode_value_expr(field_place, field_ty)
+                                            .with_span(span)?,
+                                        encoded_rhs.clone(),
+                                    );
+                                } else {
+                                    state.substitute_value(&field_place, encoded_rhs.clone());
+                                }
+                                let snapshot = self.encoder.encode_snapshot(
+                                    ty,
+                                    Some(active_field.index()),
+                                    vec![encoded_rhs],
+                                ).with_span(span)?;
+                                state.substitute_value(&encoded_lhs, snapshot);
+                            }
+
                             &mir::AggregateKind::Adt(adt_did, variant_index, subst, _, _) => {
                                 let tcx = self.encoder.env().tcx();
                                 let adt_def = tcx.adt_def(adt_did);
@@ -61,6 +61,24 @@ impl<'p, 'v, 'r: 'v, 'tcx: 'v> TypeEncoder<'p, 'v, 'tcx> {
         }
     }
 
+    /// The type arguments of a trait object: the arguments of its principal
+    /// trait followed by the types of its associated type bindings.
+    fn encode_dynamic_arguments(
+        &self,
+        predicates: &ty::List<ty::PolyExistentialPredicate<'tcx>>,
+    ) -> SpannedEncodingResult<Vec<vir::Type>> {
+        let mut arguments = Vec::new();
+        if let Some(principal) = predicates.principal() {
+            arguments.extend(self.encode_substs(principal.skip_binder().args));
+        }
+        for projection in predicates.projection_bounds() {
+            if let Some(ty) = projection.skip_binder().term.ty() {
+                arguments.push(self.encoder.encode_type_high(ty)?);
+            }
+        }
+        Ok(arguments)
+    }
+
     fn compute_array_len(&self, size: ty::Const<'tcx>) -> EncodingResult<u64> {
         self.encoder.compute_array_len(size)
     }
@@ -243,9 +261,13 @@ impl<'p, 'v, 'r: 'v, 'tcx: 'v> TypeEncoder<'p, 'v, 'tcx> {
                 )
             }
 
-            ty::TyKind::Dynamic(..) => vir::Type::unsupported("dynamic".to_string()),
+            ty::TyKind::Dynamic(predicates, _, _) => vir::Type::trusted(
+                encode_dynamic_name(self.encoder, predicates),
+                self.encode_dynamic_arguments(predicates)?,
+                lifetimes,
+            ),
 
-            ty::TyKind::FnPtr(..) => vir::Type::unsupported("fnptr".to_string()),
+            ty::TyKind::FnPtr(..) => vir::Type::FnPointer,
 
             ty::TyKind::Foreign(..) => vir::Type::unsupported("foreign".to_string()),
 
@@ -467,6 +489,22 @@ impl<'p, 'v, 'r: 'v, 'tcx: 'v> TypeEncoder<'p, 'v, 'tcx> {
                 encode_adt_def(self.encoder, *adt_def, substs, None)?
             }
             ty::TyKind::Never => vir::TypeDecl::never(),
+            ty::TyKind::Dynamic(predicates, _, _) => {
+                // Trait objects are opaque: everything that is known about
+                // them comes from the specifications of the trait methods.
+                let lifetimes = self.encoder.get_lifetimes_from_type_high(self.ty)?;
+                let const_parameters = self.encoder.get_const_parameters_from_type_high(self.ty)?;
+                vir::TypeDecl::trusted(
+                    encode_dynamic_name(self.encoder, predicates),
+                    lifetimes,
+                    const_parameters,
+                )
+            }
+            ty::TyKind::FnPtr(_) => {
+                // Function pointers are opaque: what is known about their
+                // calls comes from the contracts that they satisfy.
+                vir::TypeDecl::trusted("FnPtr".to_string(), Vec::new(), Vec::new())
+            }
             ty::TyKind::Param(param_ty) => {
                 vir::TypeDecl::type_var(param_ty.name.as_str().to_string())
             }
@@ -694,6 +732,17 @@ fn encode_trusted_name<'v, 'tcx: 'v>(encoder: &Encoder<'v, 'tcx>, did: DefId) ->
     format!("trusted${}", encoder.encode_item_name(did))
 }
 
+fn encode_dynamic_name<'v, 'tcx: 'v>(
+    encoder: &Encoder<'v, 'tcx>,
+    predicates: &ty::List<ty::PolyExistentialPredicate<'tcx>>,
+) -> String {
+    if let Some(did) = predicates.principal_def_id() {
+        format!("dyn${}", encoder.encode_item_name(did))
+    } else {
+        "dyn$".to_string()
+    }
+}
+
 fn encode_variant<'v, 'tcx: 'v>(
     encoder: &Encoder<'v, 'tcx>,
     name: String,
@@ -752,12 +801,6 @@ pub(super) fn encode_adt_def<'v, 'tcx>(
         )?))
     } else if adt_def.is_union() {
         debug!("ADT {:?} is a union", adt_def);
-        if !config::unsafe_core_proof() {
-            return Err(SpannedEncodingError::unsupported(
-                "unions are not supported",
-                encoder.env().query.get_def_span(adt_def.did()),
-            ));
-        }
         assert!(variant_index.is_none());
         let name = encode_union_name(encoder, adt_def.did());
         // We treat union fields as variants.
@@ -12,6 +12,11 @@ pub(crate) fn compute_discriminant_values<'tcx>(
     adt_def: ty::AdtDef<'tcx>,
     tcx: ty::TyCtxt<'tcx>,
 ) -> Vec<i128> {
+    if adt_def.is_union() {
+        // The discriminant of a union is the index of its active field.
+        let num_fields = adt_def.non_enum_variant().fields.len();
+        return (0..num_fields as i128).collect();
+    }
     let mut discr_values: Vec<i128> = vec![];
     let size = ty::tls::with(|tcx| Integer::from_attr(&tcx, adt_def.repr().discr_type()).size());
     for (_variant_idx, discr) in adt_def.discriminants(tcx) {
@@ -9,15 +9,17 @@ use std::fmt::Debug;
 use super::high::types::HighTypeEncoderInterface;
 use crate::{
     encoder::{
+        builtin_encoder::BuiltinFunctionKind,
         errors::{
             EncodingError, EncodingResult, ErrorCtxt, PanicCause, SpannedEncodingError,
             SpannedEncodingResult, WithSpan,
         },
+        high::builtin_functions::HighBuiltinFunctionEncoderInterface,
         mir::{sequences::MirSequencesEncoderInterface, types::MirTypeEncoderInterface},
         snapshot::interface::SnapshotEncoderInterface,
         Encoder,
     },
-    error_internal, error_unsupported, utils,
+    error_incorrect, error_internal, error_unsupported, utils,
 };
 use log::{debug, trace};
 use prusti_common::{config, vir_expr};
@@ -34,11 +36,16 @@ use vir_crate::polymorphic as vir;
 
 mod downcast_detector;
 mod place_encoding;
+mod union_field_detector;
 
 pub use place_encoding::{ExprOrArrayBase, PlaceEncoding};
+pub use union_field_detector::{find_set_union_active_field_place, UnionFieldAccess};
 
 pub static PRECONDITION_LABEL: &str = "pre";
 pub static WAND_LHS_LABEL: &str = "lhs";
+/// The variable that stands for the iterator of the enclosing `for` loop in
+/// the encoding of a loop invariant, until it is replaced by the iterator.
+pub static LOOP_ITERATOR_PLACEHOLDER: &str = "_loop_iterator";
 
 pub trait PlaceEncoder<'v, 'tcx: 'v> {
     fn encoder(&self) -> &Encoder<'v, 'tcx>;
@@ -119,6 +126,24 @@ pub trait PlaceEncoder<'v, 'tcx: 'v> {
                         (encoded_projection, field_ty, None)
                     }
 
+                    ty::TyKind::Adt(adt_def, _) if adt_def.is_union() => {
+                        // The fields of a union are encoded as the variants of an
+                        // enumeration whose discriminant is the index of the active field.
+                        let tcx = self.encoder().env().tcx();
+                        let field = &adt_def.non_enum_variant().fields[*field];
+                        let field_ty = *proj_field_ty;
+                        if utils::is_reference(field_ty) {
+                            error_unsupported!("access to reference-typed fields is not supported");
+                        }
+                        let encoded_field = self
+                            .encoder()
+                            .encode_raw_ref_field("value".to_string(), field_ty)?;
+                        let encoded_projection = encoded_base
+                            .variant(field.ident(tcx).as_str())
+                            .field(encoded_field);
+                        (encoded_projection, field_ty, None)
+                    }
+
                     ty::TyKind::Adt(adt_def, ref subst) if !adt_def.is_box() => {
                         debug!("subst {:?}", subst);
                         let num_variants = adt_def.variants().len();
@@ -799,6 +824,47 @@ impl<'p, 'v: 'p, 'tcx: 'v> MirEncoder<'p, 'v, 'tcx> {
         vir::Expr::labelled_old(label, expr)
     }
 
+    /// Returns an `vir::Expr` that holds if the calls through the function
+    /// pointer `pointer` satisfy the contract of the function `spec`.
+    #[tracing::instrument(level = "trace", skip(self))]
+    pub fn encode_satisfies_spec_expr(
+        &self,
+        pointer: vir::Expr,
+        pointer_ty: ty::Ty<'tcx>,
+        spec: DefId,
+        spec_substs: ty::GenericArgsRef<'tcx>,
+    ) -> EncodingResult<vir::Expr> {
+        let tcx = self.encoder.env().tcx();
+        let erase_regions = |sig: ty::PolyFnSig<'tcx>| {
+            tcx.erase_regions(tcx.erase_late_bound_regions(sig))
+                .inputs_and_output
+        };
+        let spec_sig = self.encoder.env().query.get_fn_sig(spec, spec_substs);
+        if !pointer_ty.is_fn_ptr()
+            || erase_regions(spec_sig) != erase_regions(pointer_ty.fn_sig(tcx))
+        {
+            error_incorrect!(
+                "the signature of `{}` does not match the function pointer type {pointer_ty}",
+                self.encoder.env().name.get_item_name(spec)
+            );
+        }
+        let pointer_snapshot_type = self.encoder.encode_type(pointer_ty)?.convert_to_snapshot();
+        let (function_name, type_arguments) =
+            self.encoder
+                .encode_builtin_function_use(BuiltinFunctionKind::FnPointerSpec {
+                    spec_name: self.encoder.encode_item_name(spec),
+                    pointer_snapshot_type: pointer_snapshot_type.clone(),
+                });
+        Ok(vir::Expr::func_app(
+            function_name,
+            type_arguments,
+            vec![pointer],
+            vec![vir::LocalVar::new("self", pointer_snapshot_type)],
+            vir::Type::Bool,
+            vir::Position::default(),
+        ))
+    }
+
     pub fn get_span_of_location(&self, location: mir::Location) -> Span {
         self.mir.source_info(location).span
     }
@@ -810,6 +876,17 @@ impl<'p, 'v: 'p, 'tcx: 'v> MirEncoder<'p, 'v, 'tcx> {
         downcast_detector::detect_downcasts(self.mir, location)
     }
 
+    pub fn get_union_field_accesses_at_location(
+        &self,
+        location: mir::Location,
+    ) -> Vec<UnionFieldAccess<'tcx>> {
+        union_field_detector::detect_union_field_accesses(
+            self.encoder.env().tcx(),
+            self.mir,
+            location,
+        )
+    }
+
     pub fn get_span_of_basic_block(&self, bbi: mir::BasicBlock) -> Span {
         let bb_data = &self.mir.basic_blocks[bbi];
         bb_data.terminator().source_info.span
@@ -0,0 +1,97 @@
+use prusti_interface::environment::mir_utils::MirPlace;
+use prusti_rustc_interface::{
+    abi::FieldIdx,
+    middle::{
+        mir,
+        mir::visit::{MutatingUseContext, PlaceContext, Visitor},
+        ty,
+    },
+};
+
+/// An access to the field `field` of the union stored in `union_place`.
+#[derive(Debug)]
+pub struct UnionFieldAccess<'tcx> {
+    pub union_place: MirPlace<'tcx>,
+    pub field: FieldIdx,
+    /// Whether the whole field is overwritten, which makes it the active field
+    /// of the union.
+    pub is_write: bool,
+}
+
+pub fn detect_union_field_accesses<'tcx>(
+    tcx: ty::TyCtxt<'tcx>,
+    body: &mir::Body<'tcx>,
+    location: mir::Location,
+) -> Vec<UnionFieldAccess<'tcx>> {
+    let mut collector = UnionFieldCollector {
+        tcx,
+        body,
+        accesses: Vec::new(),
+    };
+    collector.visit_location(body, location);
+    collector.accesses
+}
+
+/// The place whose address is passed in `args` to
+/// `prusti_set_union_active_field`, which is the call that terminates `block`.
+/// The address has to be taken in `block` itself.
+pub fn find_set_union_active_field_place<'tcx>(
+    block: &mir::BasicBlockData<'tcx>,
+    args: &[mir::Operand<'tcx>],
+) -> Option<mir::Place<'tcx>> {
+    let [mir::Operand::Move(argument_place)] = args else {
+        return None;
+    };
+    block
+        .statements
+        .iter()
+        .rev()
+        .find_map(|statement| match &statement.kind {
+            mir::StatementKind::Assign(box (target_place, mir::Rvalue::AddressOf(_, place)))
+                if target_place == argument_place =>
+            {
+                Some(*place)
+            }
+            _ => None,
+        })
+}
+
+struct UnionFieldCollector<'a, 'tcx> {
+    tcx: ty::TyCtxt<'tcx>,
+    body: &'a mir::Body<'tcx>,
+    accesses: Vec<UnionFieldAccess<'tcx>>,
+}
+
+impl<'a, 'tcx> Visitor<'tcx> for UnionFieldCollector<'a, 'tcx> {
+    fn visit_place(
+        &mut self,
+        place: &mir::Place<'tcx>,
+        context: PlaceContext,
+        location: mir::Location,
+    ) {
+        self.super_place(place, context, location);
+        if !context.is_use() {
+            return;
+        }
+
+        let is_overwritten = matches!(
+            context,
+            PlaceContext::MutatingUse(MutatingUseContext::Store | MutatingUseContext::Call)
+        );
+        for (base, elem) in place.iter_projections() {
+            if let mir::PlaceElem::Field(field, _) = elem {
+                if base.ty(self.body, self.tcx).ty.is_union() {
+                    self.accesses.push(UnionFieldAccess {
+                        union_place: MirPlace {
+                            local: base.local,
+                            projection: base.projection.to_owned(),
+                        },
+                        field,
+                        is_write: is_overwritten
+                            && base.projection.len() + 1 == place.projection.len(),
+                    });
+                }
+            }
+        }
+    }
+}
@@ -5,6 +5,7 @@
 // file, You can obtain one at http://mozilla.org/MPL/2.0/.
 
 pub use self::encoder::Encoder;
+pub(crate) use self::errors::{EncodingErrorKind, PositionManager, SpannedEncodingError};
 
 mod builtin_encoder;
 #[allow(clippy::module_inception)]
@@ -30,5 +31,6 @@ mod typed;
 mod middle;
 mod purifier;
 pub mod counterexamples;
+pub(crate) mod incremental;
 mod definition_collector;
 mod versioning;
@@ -5,7 +5,8 @@
 // file, You can obtain one at http://mozilla.org/MPL/2.0/.
 
 use super::{
-    counterexamples::DiscriminantsStateInterface, high::generics::HighGenericsEncoderInterface,
+    counterexamples::{DiscriminantsStateInterface, TraceLabelsStateInterface},
+    high::generics::HighGenericsEncoderInterface,
 };
 use crate::{
     encoder::{
@@ -29,8 +30,8 @@ use crate::{
             types::MirTypeEncoderInterface,
         },
         mir_encoder::{
-            ExprOrArrayBase, FakeMirEncoder, MirEncoder, PlaceEncoder, PlaceEncoding,
-            PRECONDITION_LABEL,
+            find_set_union_active_field_place, ExprOrArrayBase, FakeMirEncoder, MirEncoder,
+            PlaceEncoder, PlaceEncoding, LOOP_ITERATOR_PLACEHOLDER, PRECONDITION_LABEL,
         },
         mir_successor::MirSuccessor,
         places::{Local, LocalVariableManager, Place},
@@ -72,7 +73,7 @@ use prusti_rustc_interface::{
         mir::{Mutability, TerminatorKind},
         ty::{self, GenericArgsRef},
     },
-    span::Span,
+    span::{DesugaringKind, Span},
     target::abi::{FieldIdx, Integer},
 };
 use rustc_hash::{FxHashMap, FxHashSet};
@@ -80,6 +81,7 @@ use std::{
     collections::BTreeMap,
     convert::TryInto,
     fmt::{Debug, Write},
+    time::Instant,
 };
 use vir_crate::polymorphic::{
     self as vir, borrows::Borrow, collect_assigned_vars, compute_identifier, CfgBlockIndex,
@@ -227,7 +229,8 @@ impl<'p, 'v: 'p, 'tcx: 'v> ProcedureEncoder<'p, 'v, 'tcx> {
         let block = &self.mir[bb];
         let _ = self.try_encode_assert(bb, block, encoded_statements)?
             || self.try_encode_assume(bb, block, encoded_statements)?
-            || self.try_encode_refute(bb, block, encoded_statements)?;
+            || self.try_encode_refute(bb, block, encoded_statements)?
+            || self.try_encode_set_union_active_field(bb, block, encoded_statements)?;
         Ok(())
     }
 
@@ -348,6 +351,55 @@ impl<'p, 'v: 'p, 'tcx: 'v> ProcedureEncoder<'p, 'v, 'tcx> {
         Ok(false)
     }
 
+    fn try_encode_set_union_active_field(
+        &mut self,
+        bb: mir::BasicBlock,
+        block: &mir::BasicBlockData<'tcx>,
+        encoded_statements: &mut Vec<vir::Stmt>,
+    ) -> SpannedEncodingResult<bool> {
+        let TerminatorKind::Call {
+            func: mir::Operand::Constant(box mir::Constant { literal, .. }),
+            args,
+            ..
+        } = &block.terminator().kind
+        else {
+            return Ok(false);
+        };
+        let ty::TyKind::FnDef(def_id, _) = literal.ty().kind() else {
+            return Ok(false);
+        };
+        let function_name = self.encoder.env().name.get_absolute_item_name(*def_id);
+        if function_name != "prusti_contracts::prusti_set_union_active_field" {
+            return Ok(false);
+        }
+        let span = self.mir_encoder.get_span_of_basic_block(bb);
+        let Some(field_place) = find_set_union_active_field_place(block, args) else {
+            return Err(SpannedEncodingError::unsupported(
+                "prusti_set_union_active_field expects the address of a place that is taken \
+                right before the call",
+                span,
+            ));
+        };
+        let tcx = self.encoder.env().tcx();
+        match field_place.iter_projections().last() {
+            Some((union_place, mir::ProjectionElem::Field(field, _)))
+                if union_place.ty(self.mir, tcx).ty.is_union() =>
+            {
+                encoded_statements.extend(self.encode_activate_union_field(
+                    union_place.local,
+                    union_place.projection,
+                    field,
+                    span,
+                )?);
+                Ok(true)
+            }
+            _ => Err(SpannedEncodingError::incorrect(
+                "prusti_set_union_active_field expects the address of a field of a union",
+                span,
+            )),
+        }
+    }
+
     fn translate_polonius_error(&self, error: PoloniusInfoError) -> SpannedEncodingError {
         match error {
             PoloniusInfoError::UnsupportedLoanInLoop {
@@ -577,6 +629,7 @@ impl<'p, 'v: 'p, 'tcx: 'v> ProcedureEncoder<'p, 'v, 'tcx> {
             .map(|(loan, location)| (loan.index().into(), *location))
             .collect();
         let method_pos = self.register_error(self.mir.span, ErrorCtxt::Unexpected);
+        let fold_unfold_start = Instant::now();
         let method_with_fold_unfold = foldunfold::add_fold_unfold(
             self.encoder,
             self.cfg_method,
@@ -594,6 +647,8 @@ impl<'p, 'v: 'p, 'tcx: 'v> ProcedureEncoder<'p, 'v, 'tcx> {
                 mir_span,
             ),
         })?;
+        self.encoder
+            .add_procedure_fold_unfold_time(self.proc_def_id, fold_unfold_start.elapsed());
 
         // Fix variable declarations.
         let method_with_fold_unfold = fix_ghost_vars(method_with_fold_unfold);
@@ -1356,6 +1411,12 @@ impl<'p, 'v: 'p, 'tcx: 'v> ProcedureEncoder<'p, 'v, 'tcx> {
         }
 
         self.encode_execution_flag(bbi, curr_block)?;
+        if config::counterexample_trace() {
+            // The state at the label is part of the counterexample
+            let label = self.encoder.add_trace_label(self.proc_def_id, bbi);
+            self.cfg_method
+                .add_stmt(curr_block, vir::Stmt::label(label));
+        }
         let opt_successor = self.encode_block_statements(bbi, curr_block)?;
         let mir_successor: MirSuccessor = if let Some(successor) = opt_successor {
             // In case of unsupported statements, we do not encode the terminator
@@ -1542,6 +1603,7 @@ impl<'p, 'v: 'p, 'tcx: 'v> ProcedureEncoder<'p, 'v, 'tcx> {
         location: mir::Location,
     ) -> SpannedEncodingResult<Vec<vir::Stmt>> {
         let mut stmts = vec![vir::Stmt::comment(format!("[mir] {stmt:?}"))];
+        stmts.extend(self.encode_union_field_accesses(location)?);
         let span = self.mir_encoder.get_span_of_location(location);
 
         let encoding_stmts = match stmt.kind {
@@ -1670,6 +1732,12 @@ impl<'p, 'v: 'p, 'tcx: 'v> ProcedureEncoder<'p, 'v, 'tcx> {
                 if rhs_ty.is_array_ref() && cast_ty.is_slice_ref() {
                     trace!("slice: operand={:?}, ty={:?}", operand, cast_ty);
                     self.encode_assign_slice(encoded_lhs, operand, cast_ty, location)?
+                } else if cast_ty
+                    .builtin_deref(true)
+                    .map_or(false, |pointee| pointee.ty.is_trait())
+                {
+                    trace!("trait object: operand={:?}, ty={:?}", operand, cast_ty);
+                    self.encode_assign_trait_object(encoded_lhs, operand, cast_ty, location)?
                 } else {
                     return Err(SpannedEncodingError::unsupported(
                         format!("unsizing a {rhs_ty} into a {cast_ty} is not supported"),
@@ -1677,9 +1745,14 @@ impl<'p, 'v: 'p, 'tcx: 'v> ProcedureEncoder<'p, 'v, 'tcx> {
                     ));
                 }
             }
+            mir::Rvalue::Cast(
+                mir::CastKind::PointerCoercion(ty::adjustment::PointerCoercion::ReifyFnPointer),
+                ref operand,
+                cast_ty,
+            ) => self.encode_assign_fn_pointer(encoded_lhs, operand, cast_ty, location)?,
             mir::Rvalue::Cast(mir::CastKind::PointerCoercion(_), _, _)
             | mir::Rvalue::Cast(mir::CastKind::DynStar, _, _) => {
-                return Err(SpannedEncodingError::unsupported(
+                return Err(raw_pointers_unsupported(
                     "raw pointers are not supported",
                     span,
                 ));
@@ -1691,7 +1764,7 @@ impl<'p, 'v: 'p, 'tcx: 'v> ProcedureEncoder<'p, 'v, 'tcx> {
                 ));
             }
             mir::Rvalue::AddressOf(_, _) => {
-                return Err(SpannedEncodingError::unsupported(
+                return Err(raw_pointers_unsupported(
                     "raw addresses of expressions and casts from references to raw pointers are not supported", span
                 ));
             }
@@ -1868,6 +1941,33 @@ impl<'p, 'v: 'p, 'tcx: 'v> ProcedureEncoder<'p, 'v, 'tcx> {
                     self.encode_place(place, ArrayAccessKind::Shared, location)?;
                 stmts.extend(r_stmts);
 
+                if ty
+                    .builtin_deref(true)
+                    .map_or(false, |pointee| pointee.ty.is_trait())
+                {
+                    // Give the permission of the trait object back to the
+                    // value that got unsized into it.
+                    let (object_permission, source_permission, method_equalities) = self
+                        .encode_trait_object_permissions(
+                            expiring_base.clone(),
+                            ty,
+                            restored.clone(),
+                            self.mir_encoder.get_operand_ty(operand),
+                            span,
+                        )?;
+                    stmts.push(vir::Stmt::Inhale(vir::Inhale {
+                        expr: source_permission,
+                    }));
+                    stmts.push(vir::Stmt::Inhale(vir::Inhale {
+                        expr: method_equalities,
+                    }));
+                    let pos = self.register_error(span, ErrorCtxt::Unexpected);
+                    stmts.push(vir::Stmt::Exhale(vir::Exhale {
+                        expr: object_permission,
+                        position: pos,
+                    }));
+                }
+
                 (expiring_base, Some(restored), false, stmts)
             }
 
@@ -2379,6 +2479,7 @@ impl<'p, 'v: 'p, 'tcx: 'v> ProcedureEncoder<'p, 'v, 'tcx> {
         location: mir::Location,
     ) -> SpannedEncodingResult<(Vec<vir::Stmt>, MirSuccessor)> {
         let mut stmts: Vec<vir::Stmt> = vec![vir::Stmt::comment(format!("[mir] {:?}", term.kind))];
+        stmts.extend(self.encode_union_field_accesses(location)?);
         let span = self.mir_encoder.get_span_of_location(location);
 
         let result = match term.kind {
@@ -2737,10 +2838,49 @@ impl<'p, 'v: 'p, 'tcx: 'v> ProcedureEncoder<'p, 'v, 'tcx> {
                         }
 
                         "std::iter::Iterator::next" | "core::iter::Iterator::next" => {
-                            return Err(SpannedEncodingError::unsupported(
-                                "iterators are not fully supported yet",
+                            // Calls of `next` are only encoded if something is
+                            // known about their result: either the iterator is
+                            // implemented in this crate or it has a contract,
+                            // e.g. because it implements `IteratorModel`.
+                            let (called_def_id, call_substs) = self
+                                .encoder
+                                .env()
+                                .query
+                                .resolve_method_call(self.proc_def_id, called_def_id, call_substs);
+                            let has_contract = self
+                                .encoder
+                                .get_procedure_specs_for_call(
+                                    called_def_id,
+                                    self.proc_def_id,
+                                    call_substs,
+                                )
+                                .map_or(false, |spec| {
+                                    spec.posts
+                                        .extract_with_selective_replacement_iter()
+                                        .next()
+                                        .is_some()
+                                });
+                            if !called_def_id.is_local() && !has_contract {
+                                let mut error = SpannedEncodingError::unsupported(
+                                    "iterators are not fully supported yet",
+                                    term.source_info.span,
+                                );
+                                error.set_help(
+                                    "implement `prusti_contracts::IteratorModel` for the \
+                                    iterator or give `next` a postcondition with an external \
+                                    specification",
+                                );
+                                return Err(error);
+                            }
+                            stmts.extend(self.encode_impure_function_call(
+                                location,
                                 term.source_info.span,
-                            ));
+                                args,
+                                destination,
+                                target,
+                                called_def_id,
+                                call_substs,
+                            )?);
                         }
 
                         // TODO: use extern_spec
@@ -2829,6 +2969,28 @@ impl<'p, 'v: 'p, 'tcx: 'v> ProcedureEncoder<'p, 'v, 'tcx> {
                 }
             }
 
+            TerminatorKind::Call {
+                ref args,
+                destination,
+                target,
+                ref func,
+                ..
+            } if self.mir_encoder.get_operand_ty(func).is_fn_ptr() => {
+                stmts.extend(self.encode_fn_pointer_call(
+                    location,
+                    term.source_info.span,
+                    func,
+                    args,
+                    destination,
+                    target,
+                )?);
+                if let Some(target) = target {
+                    (stmts, MirSuccessor::Goto(target))
+                } else {
+                    (stmts, MirSuccessor::Kill)
+                }
+            }
+
             TerminatorKind::Call { .. } => {
                 // Other kind of calls?
                 unimplemented!();
@@ -3731,6 +3893,192 @@ impl<'p, 'v: 'p, 'tcx: 'v> ProcedureEncoder<'p, 'v, 'tcx> {
         Ok(stmts)
     }
 
+    /// Encode a call through a function pointer with the contract of the
+    /// function whose specification the pointer satisfies.
+    #[tracing::instrument(level = "debug", skip(self))]
+    fn encode_fn_pointer_call(
+        &mut self,
+        location: mir::Location,
+        call_site_span: Span,
+        func: &mir::Operand<'tcx>,
+        args: &[mir::Operand<'tcx>],
+        destination: mir::Place<'tcx>,
+        target: Option<BasicBlockIndex>,
+    ) -> SpannedEncodingResult<Vec<vir::Stmt>> {
+        let pointer_ty = self.mir_encoder.get_operand_ty(func);
+        let (spec_def_id, spec_substs) = self.find_fn_pointer_spec(func, call_site_span)?;
+        let pointer = self
+            .mir_encoder
+            .encode_operand_place(func)
+            .with_span(call_site_span)?
+            .unwrap();
+        let satisfies_spec = self
+            .mir_encoder
+            .encode_satisfies_spec_expr(pointer, pointer_ty, spec_def_id, spec_substs)
+            .with_span(call_site_span)?;
+        let pos = self.register_error(call_site_span, ErrorCtxt::AssertFnPointerSpec);
+        let mut stmts = vec![vir::Stmt::Assert(vir::Assert {
+            expr: satisfies_spec,
+            position: pos,
+        })];
+        stmts.extend(self.encode_impure_function_call(
+            location,
+            call_site_span,
+            args,
+            destination,
+            target,
+            spec_def_id,
+            spec_substs,
+        )?);
+        Ok(stmts)
+    }
+
+    /// The function whose contract is used for a call through the function
+    /// pointer `func`: the function from which the pointer was created in
+    /// this body or else the specification that the precondition requires
+    /// the pointer to satisfy.
+    fn find_fn_pointer_spec(
+        &self,
+        func: &mir::Operand<'tcx>,
+        call_site_span: Span,
+    ) -> SpannedEncodingResult<(ProcedureDefId, GenericArgsRef<'tcx>)> {
+        if let Some(origin) = func
+            .place()
+            .and_then(|place| place.as_local())
+            .and_then(|local| self.find_fn_pointer_origin(local))
+        {
+            return Ok(origin);
+        }
+        let pointer_ty = self.mir_encoder.get_operand_ty(func);
+        let mut specs = vec![];
+        let mut visited = vec![];
+        for (assertion, assertion_substs) in self
+            .procedure_contract()
+            .functional_precondition(self.encoder.env(), self.substs)
+        {
+            let body = self.encoder.env().body.get_expression_body(
+                assertion,
+                assertion_substs,
+                self.proc_def_id,
+            );
+            self.collect_fn_pointer_specs(&body, pointer_ty, &mut specs, &mut visited);
+        }
+        match specs[..] {
+            [spec] => Ok(spec),
+            [] => {
+                let mut error = SpannedEncodingError::unsupported(
+                    "the specification of the called function pointer is unknown",
+                    call_site_span,
+                );
+                error.set_help(
+                    "create the function pointer in the calling function or require \
+                    `prusti_contracts::satisfies_spec` of it in the precondition",
+                );
+                Err(error)
+            }
+            _ => Err(SpannedEncodingError::unsupported(
+                format!(
+                    "the precondition declares several specifications for function \
+                    pointers of type {pointer_ty}"
+                ),
+                call_site_span,
+            )),
+        }
+    }
+
+    /// The function from which the function pointer stored in `local` was
+    /// created, if the pointer is created and only copied in this body.
+    fn find_fn_pointer_origin(
+        &self,
+        mut local: mir::Local,
+    ) -> Option<(ProcedureDefId, GenericArgsRef<'tcx>)> {
+        for _ in 0..self.mir.local_decls.len() {
+            let mut assignments = self
+                .mir
+                .basic_blocks
+                .iter()
+                .flat_map(|block| &block.statements)
+                .filter_map(|statement| match &statement.kind {
+                    mir::StatementKind::Assign(box (place, rvalue))
+                        if place.as_local() == Some(local) =>
+                    {
+                        Some(rvalue)
+                    }
+                    _ => None,
+                });
+            let rvalue = assignments.next()?;
+            if assignments.next().is_some() {
+                return None;
+            }
+            match rvalue {
+                mir::Rvalue::Cast(
+                    mir::CastKind::PointerCoercion(ty::adjustment::PointerCoercion::ReifyFnPointer),
+                    operand,
+                    _,
+                ) => {
+                    return match self.mir_encoder.get_operand_ty(operand).kind() {
+                        ty::TyKind::FnDef(def_id, substs) => Some((*def_id, substs)),
+                        _ => None,
+                    };
+                }
+                mir::Rvalue::Use(mir::Operand::Copy(place) | mir::Operand::Move(place)) => {
+                    local = place.as_local()?;
+                }
+                _ => return None,
+            }
+        }
+        None
+    }
+
+    /// Collects the specifications that `prusti_contracts::satisfies_spec`
+    /// requires of the function pointers of type `pointer_ty` in `body` and
+    /// in the closures that it creates, e.g. the ones of quantifiers.
+    fn collect_fn_pointer_specs(
+        &self,
+        body: &mir::Body<'tcx>,
+        pointer_ty: ty::Ty<'tcx>,
+        specs: &mut Vec<(ProcedureDefId, GenericArgsRef<'tcx>)>,
+        visited: &mut Vec<ProcedureDefId>,
+    ) {
+        let tcx = self.encoder.env().tcx();
+        for local_decl in body.local_decls.iter() {
+            if let ty::TyKind::Closure(def_id, substs) = local_decl.ty.kind() {
+                if !visited.contains(def_id) {
+                    visited.push(*def_id);
+                    let closure_body =
+                        self.encoder
+                            .env()
+                            .body
+                            .get_closure_body(*def_id, substs, self.proc_def_id);
+                    self.collect_fn_pointer_specs(&closure_body, pointer_ty, specs, visited);
+                }
+            }
+        }
+        for block in body.basic_blocks.iter() {
+            let Some(mir::Terminator {
+                kind: mir::TerminatorKind::Call { func, .. },
+                ..
+            }) = &block.terminator
+            else {
+                continue;
+            };
+            let ty::TyKind::FnDef(def_id, call_substs) = func.ty(body, tcx).kind() else {
+                continue;
+            };
+            if self.encoder.env().name.get_absolute_item_name(*def_id)
+                != "prusti_contracts::satisfies_spec"
+                || tcx.erase_regions(call_substs.type_at(0)) != tcx.erase_regions(pointer_ty)
+            {
+                continue;
+            }
+            if let ty::TyKind::FnDef(spec_def_id, spec_substs) = call_substs.type_at(1).kind() {
+                if !specs.contains(&(*spec_def_id, spec_substs)) {
+                    specs.push((*spec_def_id, spec_substs));
+                }
+            }
+        }
+    }
+
     #[allow(clippy::too_many_arguments)]
     #[tracing::instrument(level = "debug", skip(self))]
     fn encode_pure_function_call(
@@ -5573,13 +5921,33 @@ impl<'p, 'v: 'p, 'tcx: 'v> ProcedureEncoder<'p, 'v, 'tcx> {
                 )) = stmt.kind
                 {
                     if let Some(spec) = self.encoder.get_loop_specs(cl_def_id) {
-                        encoded_specs.push(self.encoder.encode_invariant(
+                        let mut encoded_spec = self.encoder.encode_invariant(
                             self.mir,
                             bbi,
                             self.proc_def_id,
                             cl_substs,
                             true,
-                        )?);
+                        )?;
+                        if let Some(placeholder) = find_loop_iterator_placeholder(&encoded_spec) {
+                            let span = self.encoder.env().query.get_def_span(cl_def_id);
+                            let Some(iterator) = self.encode_for_loop_iterator(loop_head)? else {
+                                return Err(SpannedEncodingError::incorrect(
+                                    "`loop_iterator()` can only be used in the invariant of a \
+                                    `for` loop",
+                                    span,
+                                ));
+                            };
+                            if iterator.get_type() != &placeholder.typ {
+                                return Err(SpannedEncodingError::incorrect(
+                                    "the type given to `loop_iterator()` is not the type of the \
+                                    iterator of the `for` loop",
+                                    span,
+                                ));
+                            }
+                            encoded_spec = encoded_spec
+                                .replace_place(&vir::Expr::local(placeholder), &iterator);
+                        }
+                        encoded_specs.push(encoded_spec);
                         let invariant = match spec {
                             prusti_interface::specs::typed::LoopSpecification::Invariant(inv) => {
                                 inv
@@ -5594,6 +5962,66 @@ impl<'p, 'v: 'p, 'tcx: 'v> ProcedureEncoder<'p, 'v, 'tcx> {
         Ok((encoded_specs, MultiSpan::from_spans(encoded_spec_spans)))
     }
 
+    /// Encode the iterator of the `for` loop with head `loop_head`, which is
+    /// the receiver of the `Iterator::next` call of the desugared loop.
+    fn encode_for_loop_iterator(
+        &self,
+        loop_head: BasicBlockIndex,
+    ) -> SpannedEncodingResult<Option<vir::Expr>> {
+        for bbi in self.procedure.get_reachable_cfg_blocks() {
+            if self.loop_encoder.get_loop_head(bbi) != Some(loop_head) {
+                continue;
+            }
+            let block = &self.mir.basic_blocks[bbi];
+            let term = block.terminator();
+            let span = term.source_info.span;
+            let mir::TerminatorKind::Call {
+                ref func, ref args, ..
+            } = term.kind
+            else {
+                continue;
+            };
+            let Some((called_def_id, _)) = func.const_fn_def() else {
+                continue;
+            };
+            let func_proc_name = self
+                .encoder
+                .env()
+                .name
+                .get_absolute_item_name(called_def_id);
+            if span.desugaring_kind() != Some(DesugaringKind::ForLoop)
+                || !matches!(
+                    func_proc_name.as_str(),
+                    "std::iter::Iterator::next" | "core::iter::Iterator::next"
+                )
+            {
+                continue;
+            }
+            let Some(mut receiver) = args.get(0).and_then(|arg| arg.place()) else {
+                continue;
+            };
+            // `next` gets a (re)borrow of the iterator.
+            for stmt in block.statements.iter().rev() {
+                let mir::StatementKind::Assign(box (lhs, mir::Rvalue::Ref(_, _, borrowed))) =
+                    stmt.kind
+                else {
+                    continue;
+                };
+                if lhs != receiver {
+                    continue;
+                }
+                if let [mir::ProjectionElem::Deref] = borrowed.projection[..] {
+                    receiver = borrowed.local.into();
+                } else {
+                    let (encoded_iterator, _, _) =
+                        self.mir_encoder.encode_place(borrowed).with_span(span)?;
+                    return Ok(Some(encoded_iterator.try_into_expr().with_span(span)?));
+                }
+            }
+        }
+        Ok(None)
+    }
+
     #[tracing::instrument(level = "trace", skip(self))]
     fn encode_loop_invariant_exhale_stmts(
         &mut self,
@@ -6056,7 +6484,7 @@ impl<'p, 'v: 'p, 'tcx: 'v> ProcedureEncoder<'p, 'v, 'tcx> {
                     self.encode_place(*place, ArrayAccessKind::Shared, location)?;
                 let encode_stmts = match ty.kind() {
                     ty::TyKind::RawPtr(..) => {
-                        return Err(SpannedEncodingError::unsupported(
+                        return Err(raw_pointers_unsupported(
                             "raw pointers are not supported",
                             span,
                         ));
@@ -6478,6 +6906,271 @@ impl<'p, 'v: 'p, 'tcx: 'v> ProcedureEncoder<'p, 'v, 'tcx> {
         self.encode_copy_value_assign(encoded_lhs, encoded_val, ty, location)
     }
 
+    /// Encode the unsizing of a reference or of a box into a trait object.
+    /// The trait object is opaque: the permission of the unsized value is
+    /// exhaled and turned into the permission of the trait object, which is
+    /// given back when the loan created by the unsizing expires.
+    fn encode_assign_trait_object(
+        &mut self,
+        encoded_lhs: vir::Expr,
+        operand: &mir::Operand<'tcx>,
+        ty: ty::Ty<'tcx>,
+        location: mir::Location,
+    ) -> SpannedEncodingResult<Vec<vir::Stmt>> {
+        let span = self.mir_encoder.get_span_of_location(location);
+        let source_place = match operand {
+            mir::Operand::Move(place) | mir::Operand::Copy(place) => *place,
+            mir::Operand::Constant(_) => {
+                return Err(SpannedEncodingError::unsupported(
+                    format!("unsizing a constant into a {ty} is not supported yet"),
+                    span,
+                ));
+            }
+        };
+        let (encoded_source, mut stmts, source_ty, _) =
+            self.encode_place(source_place, ArrayAccessKind::Shared, location)?;
+        let (object_permission, source_permission, method_equalities) = self
+            .encode_trait_object_permissions(
+                encoded_lhs.clone(),
+                ty,
+                encoded_source,
+                source_ty,
+                span,
+            )?;
+        stmts.extend(self.encode_havoc(&encoded_lhs).with_span(span)?);
+        let object_ty = ty.builtin_deref(true).unwrap().ty;
+        let ref_field = self
+            .encoder
+            .encode_dereference_field(object_ty)
+            .with_span(span)?;
+        stmts.push(vir::Stmt::Inhale(vir::Inhale {
+            expr: vir::Expr::FieldAccessPredicate(vir::FieldAccessPredicate {
+                base: Box::new(encoded_lhs.field(ref_field)),
+                permission: vir::PermAmount::Write,
+                position: vir::Position::default(),
+            }),
+        }));
+        stmts.push(vir::Stmt::Inhale(vir::Inhale {
+            expr: object_permission,
+        }));
+        stmts.push(vir::Stmt::Inhale(vir::Inhale {
+            expr: method_equalities,
+        }));
+        let pos = self.register_error(span, ErrorCtxt::Unexpected);
+        stmts.push(vir::Stmt::Exhale(vir::Exhale {
+            expr: source_permission,
+            position: pos,
+        }));
+        Ok(stmts)
+    }
+
+    /// Encode the permission of the trait object of type `ty` stored in
+    /// `object`, the permission of the value stored in `source` that got
+    /// unsized into it, and the equalities between the pure trait methods
+    /// applied to both.
+    fn encode_trait_object_permissions(
+        &self,
+        object: vir::Expr,
+        ty: ty::Ty<'tcx>,
+        source: vir::Expr,
+        source_ty: ty::Ty<'tcx>,
+        span: Span,
+    ) -> SpannedEncodingResult<(vir::Expr, vir::Expr, vir::Expr)> {
+        let permission = match ty.kind() {
+            ty::TyKind::Ref(_, _, mir::Mutability::Not) => vir::PermAmount::Read,
+            ty::TyKind::Ref(_, _, mir::Mutability::Mut) => vir::PermAmount::Write,
+            ty::TyKind::Adt(adt_def, _) if adt_def.is_box() => vir::PermAmount::Write,
+            _ => {
+                return Err(SpannedEncodingError::unsupported(
+                    format!("unsizing into a {ty} is not supported yet"),
+                    span,
+                ));
+            }
+        };
+        let object_ty = ty.builtin_deref(true).unwrap().ty;
+        let source_ty = source_ty.builtin_deref(true).unwrap().ty;
+        let object = object.field(
+            self.encoder
+                .encode_dereference_field(object_ty)
+                .with_span(span)?,
+        );
+        let source = source.field(
+            self.encoder
+                .encode_dereference_field(source_ty)
+                .with_span(span)?,
+        );
+        let method_equalities = self
+            .encode_trait_object_method_equalities(&object, object_ty, &source, source_ty, span)?;
+        let object_permission =
+            vir::Expr::PredicateAccessPredicate(vir::PredicateAccessPredicate {
+                predicate_type: self.encoder.encode_type(object_ty).with_span(span)?,
+                argument: Box::new(object),
+                permission,
+                position: vir::Position::default(),
+            });
+        let source_permission =
+            vir::Expr::PredicateAccessPredicate(vir::PredicateAccessPredicate {
+                predicate_type: self.encoder.encode_type(source_ty).with_span(span)?,
+                argument: Box::new(source),
+                permission,
+                position: vir::Position::default(),
+            });
+        Ok((object_permission, source_permission, method_equalities))
+    }
+
+    /// Encode that the pure methods of the principal trait of the trait
+    /// object in `object` return the same as the implementations of the type
+    /// of `source`, which got unsized into the trait object. Only the methods
+    /// whose only argument is `&self` are related.
+    fn encode_trait_object_method_equalities(
+        &self,
+        object: &vir::Expr,
+        object_ty: ty::Ty<'tcx>,
+        source: &vir::Expr,
+        source_ty: ty::Ty<'tcx>,
+        span: Span,
+    ) -> SpannedEncodingResult<vir::Expr> {
+        let tcx = self.encoder.env().tcx();
+        let ty::TyKind::Dynamic(predicates, _, _) = object_ty.kind() else {
+            unreachable!("{object_ty} is not a trait object");
+        };
+        let Some(principal) = predicates.principal() else {
+            return Ok(true.into());
+        };
+        let principal = principal.skip_binder();
+        let object_substs = principal.with_self_ty(tcx, object_ty).args;
+        let source_substs = principal.with_self_ty(tcx, source_ty).args;
+        let takes_only_self = |sig: ty::FnSig<'tcx>, self_ty| {
+            let self_ref_ty = ty::Ty::new_imm_ref(tcx, tcx.lifetimes.re_erased, self_ty);
+            matches!(sig.inputs(), [input]
+                if tcx.erase_regions(*input) == tcx.erase_regions(self_ref_ty))
+        };
+        let mut equalities = Vec::new();
+        for item in tcx.associated_items(principal.def_id).in_definition_order() {
+            if item.kind != ty::AssocKind::Fn
+                || tcx.generics_of(item.def_id).count() != object_substs.len()
+            {
+                continue;
+            }
+            let object_sig = self
+                .encoder
+                .env()
+                .query
+                .get_fn_sig(item.def_id, object_substs)
+                .skip_binder();
+            let source_sig = self
+                .encoder
+                .env()
+                .query
+                .get_fn_sig(item.def_id, source_substs)
+                .skip_binder();
+            if !takes_only_self(object_sig, object_ty)
+                || !takes_only_self(source_sig, source_ty)
+                || tcx.erase_regions(object_sig.output()) != tcx.erase_regions(source_sig.output())
+            {
+                continue;
+            }
+            let (object_def_id, object_call_substs) = self.encoder.env().query.resolve_method_call(
+                self.proc_def_id,
+                item.def_id,
+                object_substs,
+            );
+            let (source_def_id, source_call_substs) = self.encoder.env().query.resolve_method_call(
+                self.proc_def_id,
+                item.def_id,
+                source_substs,
+            );
+            if !self
+                .encoder
+                .is_pure(object_def_id, Some(object_call_substs))
+                || !self
+                    .encoder
+                    .is_pure(source_def_id, Some(source_call_substs))
+            {
+                continue;
+            }
+            equalities.push(vir::Expr::eq_cmp(
+                self.encode_self_pure_function_app(
+                    object_def_id,
+                    object_call_substs,
+                    object.clone(),
+                    object_sig.inputs()[0],
+                    span,
+                )?,
+                self.encode_self_pure_function_app(
+                    source_def_id,
+                    source_call_substs,
+                    source.clone(),
+                    source_sig.inputs()[0],
+                    span,
+                )?,
+            ));
+        }
+        Ok(equalities.into_iter().conjoin())
+    }
+
+    /// Encode the application of the pure function `def_id` to `self_arg`,
+    /// its only argument, which has type `self_ty`.
+    fn encode_self_pure_function_app(
+        &self,
+        def_id: ProcedureDefId,
+        substs: GenericArgsRef<'tcx>,
+        self_arg: vir::Expr,
+        self_ty: ty::Ty<'tcx>,
+        span: Span,
+    ) -> SpannedEncodingResult<vir::Expr> {
+        let (function_name, return_type) = self
+            .encoder
+            .encode_pure_function_use(def_id, self.proc_def_id, substs)
+            .with_span(span)?;
+        let type_arguments = self
+            .encoder
+            .encode_generic_arguments(def_id, substs)
+            .with_span(span)?;
+        let formal_arg = vir::LocalVar::new(
+            "x0",
+            self.encoder.encode_snapshot_type(self_ty).with_span(span)?,
+        );
+        Ok(vir::Expr::func_app(
+            function_name,
+            type_arguments,
+            vec![self_arg],
+            vec![formal_arg],
+            return_type,
+            vir::Position::default(),
+        ))
+    }
+
+    /// Encode the creation of a function pointer from a function item. Calls
+    /// through the pointer satisfy the contract of the function.
+    fn encode_assign_fn_pointer(
+        &mut self,
+        encoded_lhs: vir::Expr,
+        operand: &mir::Operand<'tcx>,
+        ty: ty::Ty<'tcx>,
+        location: mir::Location,
+    ) -> SpannedEncodingResult<Vec<vir::Stmt>> {
+        let span = self.mir_encoder.get_span_of_location(location);
+        let ty::TyKind::FnDef(def_id, substs) = self.mir_encoder.get_operand_ty(operand).kind()
+        else {
+            return Err(SpannedEncodingError::unsupported(
+                format!("the creation of a {ty} from {operand:?} is not supported"),
+                span,
+            ));
+        };
+        let mut stmts = self
+            .encode_havoc_and_initialization(&encoded_lhs)
+            .with_span(span)?;
+        let satisfies_spec = self
+            .mir_encoder
+            .encode_satisfies_spec_expr(encoded_lhs, ty, *def_id, substs)
+            .with_span(span)?;
+        stmts.push(vir::Stmt::Inhale(vir::Inhale {
+            expr: satisfies_spec,
+        }));
+        Ok(stmts)
+    }
+
     /// Take a slice into the RHS array
     /// (also happens for calls that you do on an array that are slice methods, like .len())
     #[tracing::instrument(level = "debug", skip(self))]
@@ -6747,6 +7440,121 @@ impl<'p, 'v: 'p, 'tcx: 'v> ProcedureEncoder<'p, 'v, 'tcx> {
         Ok(stmts)
     }
 
+    /// Encodes the tracking of the active fields of the unions whose fields are
+    /// accessed at `location`. Overwriting a whole field of a union makes it
+    /// the active field, while all other accesses require the field to be the
+    /// active one.
+    fn encode_union_field_accesses(
+        &mut self,
+        location: mir::Location,
+    ) -> SpannedEncodingResult<Vec<vir::Stmt>> {
+        let span = self.mir_encoder.get_span_of_location(location);
+        let mut stmts = vec![];
+        for access in self
+            .mir_encoder
+            .get_union_field_accesses_at_location(location)
+        {
+            let union_place = access.union_place;
+            if access.is_write {
+                stmts.extend(self.encode_activate_union_field(
+                    union_place.local,
+                    &union_place.projection,
+                    access.field,
+                    span,
+                )?);
+            } else {
+                let (encoded_union, adt_def, pre_stmts) =
+                    self.encode_union_place(union_place.local, &union_place.projection, span)?;
+                stmts.extend(pre_stmts);
+                if adt_def.non_enum_variant().fields.len() == 1 {
+                    // The only field of a union is always active.
+                    continue;
+                }
+                let discriminant = self
+                    .encoder
+                    .encode_discriminant_func_app(encoded_union, adt_def)?;
+                stmts.push(vir::Stmt::Assert(vir::Assert {
+                    expr: vir::Expr::eq_cmp(discriminant, access.field.index().into()),
+                    position: self.register_error(span, ErrorCtxt::AssertUnionActiveField),
+                }));
+            }
+        }
+        Ok(stmts)
+    }
+
+    /// Havocs the union stored in the place `local.projection` and makes
+    /// `field` its active field.
+    fn encode_activate_union_field(
+        &mut self,
+        local: mir::Local,
+        projection: &[mir::PlaceElem<'tcx>],
+        field: FieldIdx,
+        span: Span,
+    ) -> SpannedEncodingResult<Vec<vir::Stmt>> {
+        let (encoded_union, adt_def, mut stmts) =
+            self.encode_union_place(local, projection, span)?;
+        stmts.extend(
+            self.encode_havoc_and_initialization(&encoded_union)
+                .with_span(span)?,
+        );
+        stmts.extend(self.encode_set_union_active_field(&encoded_union, adt_def, field)?);
+        Ok(stmts)
+    }
+
+    /// Sets the active field of `union_place`, which has just been havocked.
+    fn encode_set_union_active_field(
+        &self,
+        union_place: &vir::Expr,
+        adt_def: ty::AdtDef<'tcx>,
+        field: FieldIdx,
+    ) -> SpannedEncodingResult<Vec<vir::Stmt>> {
+        let tcx = self.encoder.env().tcx();
+        let mut stmts = vec![];
+        // The only field of a union is always active.
+        if adt_def.non_enum_variant().fields.len() > 1 {
+            let discriminant = self
+                .encoder
+                .encode_discriminant_func_app(union_place.clone(), adt_def)?;
+            stmts.push(vir::Stmt::Inhale(vir::Inhale {
+                expr: vir::Expr::eq_cmp(discriminant, field.index().into()),
+            }));
+        }
+        let field_name = adt_def.non_enum_variant().fields[field]
+            .ident(tcx)
+            .to_string();
+        let vir::Expr::Variant(vir::Variant {
+            variant_index: variant_field,
+            ..
+        }) = union_place.clone().variant(&field_name)
+        else {
+            unreachable!()
+        };
+        stmts.push(vir::Stmt::Downcast(vir::Downcast {
+            base: union_place.clone(),
+            field: variant_field,
+        }));
+        Ok(stmts)
+    }
+
+    fn encode_union_place(
+        &mut self,
+        local: mir::Local,
+        projection: &[mir::PlaceElem<'tcx>],
+        span: Span,
+    ) -> SpannedEncodingResult<(vir::Expr, ty::AdtDef<'tcx>, Vec<vir::Stmt>)> {
+        let (place_encoding, union_ty, _) = self
+            .mir_encoder
+            .encode_projection(local, projection)
+            .with_span(span)?;
+        let ty::TyKind::Adt(adt_def, _) = union_ty.kind() else {
+            unreachable!("{union_ty:?} is not a union");
+        };
+        let (encoded_union, stmts) = self
+            .postprocess_place_encoding(place_encoding, ArrayAccessKind::Shared)
+            .with_span(span)?;
+        Ok((encoded_union, *adt_def, stmts))
+    }
+
     /// Prepare the ``dst`` to be copy target:
     ///
     /// 1.  Havoc and allocate if it is not yet allocated.
@@ -6866,9 +7674,8 @@ impl<'p, 'v: 'p, 'tcx: 'v> ProcedureEncoder<'p, 'v, 'tcx> {
             | ty::TyKind::Closure(_, _)
             | ty::TyKind::Tuple(_)
             | ty::TyKind::Param(_)
-            | ty::TyKind::Array(_, _) => {
-                self.encode_copy_snapshot_value(src, dst).with_span(span)?
-            }
+            | ty::TyKind::Array(_, _)
+            | ty::TyKind::FnPtr(_) => self.encode_copy_snapshot_value(src, dst).with_span(span)?,
 
             _ => {
                 return Err(SpannedEncodingError::unsupported(
@@ -6920,15 +7727,31 @@ impl<'p, 'v: 'p, 'tcx: 'v> ProcedureEncoder<'p, 'v, 'tcx> {
                 }
             }
 
+            mir::AggregateKind::Adt(adt_did, _, subst, _, Some(active_field)) => {
+                // A union, whose only operand initializes the active field.
+                let tcx = self.encoder.env().tcx();
+                let adt_def = tcx.adt_def(adt_did);
+                stmts.extend(self.encode_set_union_active_field(dst, adt_def, active_field)?);
+                let field = &adt_def.non_enum_variant().fields[active_field];
+                let field_ty = field.ty(tcx, subst);
+                let encoded_field = self
+                    .encoder
+                    .encode_raw_ref_field("value".to_string(), field_ty)
+                    .with_span(span)?;
+                let dst_field = dst
+                    .clone()
+                    .variant(field.ident(tcx).as_str())
+                    .field(encoded_field);
+                stmts.extend(self.encode_assign_operand(
+                    &dst_field,
+                    &operands[0usize.into()],
+                    location,
+                )?);
+            }
+
             mir::AggregateKind::Adt(adt_did, variant_index, subst, _, _) => {
                 let tcx = self.encoder.env().tcx();
                 let adt_def = tcx.adt_def(adt_did);
-                if adt_def.is_union() {
-                    return Err(SpannedEncodingError::unsupported(
-                        "unions are not supported",
-                        span,
-                    ));
-                }
                 let num_variants = adt_def.variants().len();
                 let variant_def = &adt_def.variants()[variant_index];
                 let mut dst_base = dst.clone();
@@ -7358,6 +8181,33 @@ fn assert_one_magic_wand(len: usize) -> EncodingResult<()> {
     }
 }
 
+/// The placeholder of `loop_iterator()` if it is used in a loop invariant.
+fn find_loop_iterator_placeholder(invariant: &vir::Expr) -> Option<vir::LocalVar> {
+    struct PlaceholderFinder(Option<vir::LocalVar>);
+
+    impl vir::ExprWalker for PlaceholderFinder {
+        fn walk_local(&mut self, expr: &vir::Local) {
+            if expr.variable.name == LOOP_ITERATOR_PLACEHOLDER {
+                self.0 = Some(expr.variable.clone());
+            }
+        }
+    }
+
+    let mut finder = PlaceholderFinder(None);
+    finder.walk(invariant);
+    finder.0
+}
+
+/// Raw pointers are supported only by the encoding of `unsafe_core_proof`.
+fn raw_pointers_unsupported(message: &str, span: Span) -> SpannedEncodingError {
+    let mut error = SpannedEncodingError::unsupported(message, span);
+    error.set_help(
+        "raw pointers and the ownership of the memory behind them are supported with \
+        the `unsafe_core_proof` flag",
+    );
+    error
+}
+
 // Checks if a type is a reference to a string, or a reference to a reference to a string, etc.
 fn is_str(ty: ty::Ty<'_>) -> bool {
     match ty.kind() {
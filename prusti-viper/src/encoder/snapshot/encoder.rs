@@ -805,6 +805,36 @@ impl SnapshotEncoder {
                 self.encode_complex(encoder, variants, predicate_type)
             }
 
+            ty::TyKind::Adt(adt_def, substs) if adt_def.is_union() => {
+                // Each field of a union is a variant whose discriminant is the
+                // index of the field.
+                let mut variants = vec![];
+                for (field_index, field) in adt_def.all_fields().enumerate() {
+                    let field_ty = field.ty(tcx, substs);
+                    let field_ty = tcx
+                        .try_normalize_erasing_regions(ty::ParamEnv::reveal_all(), field_ty)
+                        .unwrap_or(field_ty);
+                    let variant_name = field.ident(tcx).to_string();
+                    let value_field =
+                        encoder.encode_raw_ref_field("value".to_string(), field_ty)?;
+                    let fields = vec![SnapshotField {
+                        name: value_field.name.clone(),
+                        access: self.snap_app(
+                            encoder,
+                            Expr::field(arg_expr.clone().variant(&variant_name), value_field),
+                        )?,
+                        mir_type: field_ty,
+                        typ: self.encode_type(encoder, field_ty)?,
+                    }];
+                    variants.push(SnapshotVariant {
+                        discriminant: field_index as i128,
+                        fields,
+                        name: Some(variant_name),
+                    });
+                }
+                self.encode_complex(encoder, variants, predicate_type)
+            }
+
             ty::TyKind::Array(elem_ty, ..) => {
                 let elem_snap_ty = self.encode_type(encoder, *elem_ty)?;
                 let array_types = encoder.encode_sequence_types(ty)?;
@@ -23,5 +23,7 @@
 #![allow(clippy::needless_pass_by_ref_mut)] // see https://github.com/rust-lang/rust-clippy/issues/11179
 
 pub mod encoder;
+mod quantifier_hot_spots;
 mod utils;
+pub mod verification_report;
 pub mod verifier;
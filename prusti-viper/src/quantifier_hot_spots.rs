@@ -0,0 +1,336 @@
+// © 2023, ETH Zurich
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! Maps the quantifiers instantiated by the SMT solver back to the Rust
+//! specifications or to the families of axioms they come from.
+//!
+//! Silicon names the quantifiers of a program after the line of their VIR
+//! position (e.g. `prog.l42`), which is the line of the Rust span that the
+//! position was registered for. All other quantifiers come from axioms and
+//! are grouped by the prefix of their name.
+
+use crate::{
+    encoder::PositionManager,
+    verification_report::{QuantifierReport, SourceLocation},
+};
+use prusti_interface::data::ProcedureDefId;
+use prusti_rustc_interface::{errors::MultiSpan, span::source_map::SourceMap};
+use viper::QuantifierStatistics;
+
+/// Where a quantifier of the SMT input comes from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum QuantifierOrigin {
+    /// A `forall` or `exists` of a specification.
+    Specification(MultiSpan),
+    /// A quantifier generated for other code of a function.
+    Code(MultiSpan),
+    /// A family of axioms generated by Prusti or Viper.
+    Axioms(String),
+    /// A quantifier of a function whose line could not be mapped back.
+    Unknown,
+}
+
+impl QuantifierOrigin {
+    /// The origin of the quantifier called `name` in the SMT input of a
+    /// program of `def_id`, or of no procedure if `def_id` is `None`.
+    pub(crate) fn of(
+        name: &str,
+        def_id: Option<ProcedureDefId>,
+        position_manager: &PositionManager,
+        source_map: &SourceMap,
+    ) -> Self {
+        let Some(line) = silicon_line(name) else {
+            return QuantifierOrigin::Axioms(axiom_family(name));
+        };
+        let spans = position_manager.get_spans_at_line(def_id, line);
+        let is_quantifier = |span: &&MultiSpan| {
+            span.primary_span()
+                .and_then(|span| source_map.span_to_snippet(span.source_callsite()).ok())
+                .map_or(false, |snippet| {
+                    snippet.starts_with("forall") || snippet.starts_with("exists")
+                })
+        };
+        if let Some(span) = spans.iter().find(is_quantifier) {
+            QuantifierOrigin::Specification((*span).clone())
+        } else if let Some(span) = spans.first() {
+            QuantifierOrigin::Code((*span).clone())
+        } else {
+            QuantifierOrigin::Unknown
+        }
+    }
+
+    fn description(&self) -> String {
+        match self {
+            QuantifierOrigin::Specification(_) => "quantifier of a specification".to_string(),
+            QuantifierOrigin::Code(_) => "quantifier generated for the code".to_string(),
+            QuantifierOrigin::Axioms(family) => family.clone(),
+            QuantifierOrigin::Unknown => "quantifier of an unknown location".to_string(),
+        }
+    }
+
+    fn span(&self) -> Option<&MultiSpan> {
+        match self {
+            QuantifierOrigin::Specification(span) | QuantifierOrigin::Code(span) => Some(span),
+            QuantifierOrigin::Axioms(_) | QuantifierOrigin::Unknown => None,
+        }
+    }
+}
+
+/// The line in the name that Silicon gives to the quantifiers of a program.
+fn silicon_line(name: &str) -> Option<usize> {
+    let line = name.strip_prefix("prog.l")?;
+    let end = line
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(line.len());
+    line[..end].parse().ok()
+}
+
+/// The family of the axiom whose quantifier is called `name`.
+fn axiom_family(name: &str) -> String {
+    if name == "builtin quantifier" {
+        "built-in quantifiers of Z3".to_string()
+    } else if name.starts_with("qp.") {
+        "quantified permissions of Silicon".to_string()
+    } else if name.starts_with("quant-u-") {
+        "internal quantifiers of Silicon".to_string()
+    } else if name.starts_with('$') {
+        let theory = name.split(['.', '_', '[']).next().unwrap_or(name);
+        format!("axioms of Silicon's {theory}")
+    } else if let Some((function, _)) = name.split_once('%') {
+        format!("axioms of the pure function {function}")
+    } else {
+        format!("domain axiom {name}")
+    }
+}
+
+struct HotSpot {
+    origin: QuantifierOrigin,
+    report: QuantifierReport,
+}
+
+/// The quantifiers instantiated while verifying a crate, grouped by origin.
+#[derive(Default)]
+pub(crate) struct QuantifierHotSpots {
+    hot_spots: Vec<HotSpot>,
+}
+
+impl QuantifierHotSpots {
+    /// Adds the instantiations of a quantifier during the verification of a
+    /// program of the function `def_path`.
+    pub(crate) fn add(
+        &mut self,
+        origin: QuantifierOrigin,
+        statistics: &QuantifierStatistics,
+        def_path: Option<String>,
+        source_map: &SourceMap,
+    ) {
+        let index = match self
+            .hot_spots
+            .iter()
+            .position(|hot_spot| hot_spot.origin == origin)
+        {
+            Some(index) => index,
+            None => {
+                let report = QuantifierReport {
+                    origin: origin.description(),
+                    location: origin
+                        .span()
+                        .and_then(|span| SourceLocation::from_span(source_map, span)),
+                    quantifiers: vec![],
+                    instantiations: 0,
+                    self_triggered: 0,
+                    max_generation: 0,
+                    matching_loop: false,
+                    functions: vec![],
+                };
+                self.hot_spots.push(HotSpot { origin, report });
+                self.hot_spots.len() - 1
+            }
+        };
+        let report = &mut self.hot_spots[index].report;
+        report.instantiations += statistics.instantiations;
+        report.self_triggered += statistics.self_triggered;
+        report.max_generation = report.max_generation.max(statistics.max_generation);
+        report.matching_loop |= statistics.is_matching_loop();
+        if !report.quantifiers.contains(&statistics.name) {
+            report.quantifiers.push(statistics.name.clone());
+        }
+        if let Some(def_path) = def_path {
+            if !report.functions.contains(&def_path) {
+                report.functions.push(def_path);
+            }
+        }
+    }
+
+    /// The quantifiers of specifications that look like matching loops.
+    pub(crate) fn matching_loops(&self) -> impl Iterator<Item = (&MultiSpan, &QuantifierReport)> {
+        self.hot_spots
+            .iter()
+            .filter(|hot_spot| hot_spot.report.matching_loop)
+            .filter_map(|hot_spot| match &hot_spot.origin {
+                QuantifierOrigin::Specification(span) => Some((span, &hot_spot.report)),
+                _ => None,
+            })
+    }
+
+    /// The reports of the quantifiers, the most instantiated first.
+    pub(crate) fn into_reports(self) -> Vec<QuantifierReport> {
+        let mut reports: Vec<_> = self
+            .hot_spots
+            .into_iter()
+            .map(|hot_spot| hot_spot.report)
+            .collect();
+        reports.sort_by(|a, b| b.instantiations.cmp(&a.instantiations));
+        reports
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use prusti_rustc_interface::span::{
+        create_default_session_globals_then, source_map::FilePathMapping, BytePos, FileName,
+        RealFileName, Span,
+    };
+
+    const SOURCE: &str = "fn test() {}\n#[requires(forall(|i: usize| i > 0))]\nfn spec() {}\n";
+
+    fn statistics(
+        name: &str,
+        instantiations: usize,
+        self_triggered: usize,
+        max_generation: u32,
+    ) -> QuantifierStatistics {
+        QuantifierStatistics {
+            name: name.to_string(),
+            instantiations,
+            self_triggered,
+            max_generation,
+        }
+    }
+
+    /// A source map of `SOURCE` and the span of its `forall`.
+    fn source_map_with_quantifier() -> (SourceMap, MultiSpan) {
+        let source_map = SourceMap::new(FilePathMapping::empty());
+        let file = source_map.new_source_file(
+            FileName::Real(RealFileName::LocalPath("lib.rs".into())),
+            SOURCE.to_string(),
+        );
+        let start = file.start_pos + BytePos(SOURCE.find("forall").unwrap() as u32);
+        let end = start + BytePos("forall(|i: usize| i > 0)".len() as u32);
+        let span = MultiSpan::from_span(Span::with_root_ctxt(start, end));
+        (source_map, span)
+    }
+
+    #[test]
+    fn test_silicon_line() {
+        assert_eq!(silicon_line("prog.l42"), Some(42));
+        assert_eq!(silicon_line("prog.l42-aux"), Some(42));
+        assert_eq!(silicon_line("prog.l"), None);
+        assert_eq!(silicon_line("$Seq.append"), None);
+    }
+
+    #[test]
+    fn test_axiom_family() {
+        assert_eq!(
+            axiom_family("builtin quantifier"),
+            "built-in quantifiers of Z3"
+        );
+        assert_eq!(
+            axiom_family("qp.fvfValDef3"),
+            "quantified permissions of Silicon"
+        );
+        assert_eq!(
+            axiom_family("quant-u-12"),
+            "internal quantifiers of Silicon"
+        );
+        assert_eq!(axiom_family("$Seq.append"), "axioms of Silicon's $Seq");
+        assert_eq!(axiom_family("$Set_in[x]"), "axioms of Silicon's $Set");
+        assert_eq!(
+            axiom_family("m_len%limited"),
+            "axioms of the pure function m_len"
+        );
+        assert_eq!(axiom_family("Snap$bool"), "domain axiom Snap$bool");
+    }
+
+    #[test]
+    fn test_hot_spots() {
+        create_default_session_globals_then(|| {
+            let (source_map, span) = source_map_with_quantifier();
+            let specification = QuantifierOrigin::Specification(span);
+            let mut hot_spots = QuantifierHotSpots::default();
+            hot_spots.add(
+                specification.clone(),
+                &statistics("prog.l2", 30, 25, 12),
+                Some("spec".to_string()),
+                &source_map,
+            );
+            hot_spots.add(
+                QuantifierOrigin::Axioms(axiom_family("$Seq.append")),
+                &statistics("$Seq.append", 35, 0, 2),
+                None,
+                &source_map,
+            );
+            hot_spots.add(
+                specification,
+                &statistics("prog.l2-aux", 10, 0, 1),
+                Some("test".to_string()),
+                &source_map,
+            );
+            hot_spots.add(
+                QuantifierOrigin::Unknown,
+                &statistics("prog.l7", 5, 5, 20),
+                Some("test".to_string()),
+                &source_map,
+            );
+
+            let matching_loops: Vec<_> = hot_spots
+                .matching_loops()
+                .map(|(_, report)| report.origin.clone())
+                .collect();
+            assert_eq!(matching_loops, vec!["quantifier of a specification"]);
+
+            let reports = hot_spots.into_reports();
+            let origins: Vec<_> = reports.iter().map(|report| &report.origin[..]).collect();
+            assert_eq!(
+                origins,
+                vec![
+                    "quantifier of a specification",
+                    "axioms of Silicon's $Seq",
+                    "quantifier of an unknown location",
+                ]
+            );
+
+            let specification = &reports[0];
+            assert_eq!(
+                specification.location,
+                Some(SourceLocation {
+                    file: "lib.rs".to_string(),
+                    start_line: 2,
+                    start_column: 12,
+                    end_line: 2,
+                    end_column: 36,
+                })
+            );
+            assert_eq!(specification.instantiations, 40);
+            assert_eq!(specification.self_triggered, 25);
+            assert_eq!(specification.max_generation, 12);
+            assert!(specification.matching_loop);
+            assert_eq!(specification.quantifiers, vec!["prog.l2", "prog.l2-aux"]);
+            assert_eq!(specification.functions, vec!["spec", "test"]);
+
+            let axioms = &reports[1];
+            assert_eq!(axioms.location, None);
+            assert_eq!(axioms.instantiations, 35);
+            assert!(!axioms.matching_loop);
+            assert!(axioms.functions.is_empty());
+
+            let unknown = &reports[2];
+            assert_eq!(unknown.location, None);
+            assert!(unknown.matching_loop);
+        });
+    }
+}
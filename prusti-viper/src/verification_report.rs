@@ -0,0 +1,425 @@
+// © 2023, ETH Zurich
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! Machine-readable reports of the verification outcome of a crate.
+//!
+//! The report lists every verified item together with its status, the errors
+//! reported for it and the time spent on it. It can be written as plain JSON
+//! and as [SARIF](https://sarifweb.azurewebsites.net/), which is understood by
+//! most code-review and CI tools.
+
+use log::{error, info};
+use prusti_interface::{data::ItemVerificationResult, PrustiError};
+use prusti_rustc_interface::{errors::MultiSpan, span::source_map::SourceMap};
+use serde::Serialize;
+use std::{
+    fs,
+    io::{self, Write},
+    path::Path,
+};
+
+/// The outcome of verifying a single item.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ItemStatus {
+    /// The item was verified successfully.
+    Verified,
+    /// The item was verified successfully in an earlier run and the result was
+    /// taken from the verification cache.
+    Cached,
+    /// The item could not be verified.
+    Failed,
+    /// The item uses features that are not supported by Prusti.
+    Unsupported,
+    /// The verification of the item did not finish within its timeout.
+    TimedOut,
+    /// The item was not verified, e.g., because it is trusted or a predicate.
+    Skipped,
+}
+
+impl ItemStatus {
+    pub fn name(self) -> &'static str {
+        match self {
+            ItemStatus::Verified => "verified",
+            ItemStatus::Cached => "cached",
+            ItemStatus::Failed => "failed",
+            ItemStatus::Unsupported => "unsupported",
+            ItemStatus::TimedOut => "timed_out",
+            ItemStatus::Skipped => "skipped",
+        }
+    }
+}
+
+impl From<&ItemVerificationResult> for ItemStatus {
+    fn from(result: &ItemVerificationResult) -> Self {
+        match result {
+            ItemVerificationResult::Verified => ItemStatus::Verified,
+            ItemVerificationResult::Cached => ItemStatus::Cached,
+            ItemVerificationResult::Failed(_) => ItemStatus::Failed,
+            ItemVerificationResult::Unsupported(_) => ItemStatus::Unsupported,
+            ItemVerificationResult::TimedOut(_) => ItemStatus::TimedOut,
+            ItemVerificationResult::Skipped => ItemStatus::Skipped,
+        }
+    }
+}
+
+/// How severe a reported error is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorLevel {
+    Error,
+    Warning,
+}
+
+/// A range in a source file. Lines and columns are 1-based.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct SourceLocation {
+    pub file: String,
+    pub start_line: usize,
+    pub start_column: usize,
+    pub end_line: usize,
+    pub end_column: usize,
+}
+
+impl SourceLocation {
+    /// The location of the primary span of `span`, if it has one.
+    pub fn from_span(source_map: &SourceMap, span: &MultiSpan) -> Option<Self> {
+        let span = span.primary_span()?.source_callsite();
+        let start = source_map.lookup_char_pos(span.lo());
+        let end = source_map.lookup_char_pos(span.hi());
+        Some(SourceLocation {
+            file: source_map.span_to_filename(span).prefer_local().to_string(),
+            start_line: start.line,
+            start_column: start.col_display + 1,
+            end_line: end.line,
+            end_column: end.col_display + 1,
+        })
+    }
+}
+
+/// An error reported for an item.
+#[derive(Debug, Clone, Serialize)]
+pub struct ErrorReport {
+    /// The kind of the error: the Viper error identifier (e.g.
+    /// `assert.failed:assertion.false`) for verification errors, or the
+    /// category of the error otherwise (e.g. `unsupported`).
+    pub kind: String,
+    pub level: ErrorLevel,
+    pub message: String,
+    pub location: Option<SourceLocation>,
+    /// The execution trace of the counterexample of the error, if
+    /// `COUNTEREXAMPLE_TRACE` is enabled.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub trace: Vec<TraceStepReport>,
+}
+
+impl ErrorReport {
+    pub fn new<S: ToString>(source_map: &SourceMap, kind: S, error: &PrustiError) -> Self {
+        ErrorReport {
+            kind: kind.to_string(),
+            level: if error.is_error() {
+                ErrorLevel::Error
+            } else {
+                ErrorLevel::Warning
+            },
+            message: error.message().to_string(),
+            location: SourceLocation::from_span(source_map, error.span()),
+            trace: vec![],
+        }
+    }
+}
+
+/// The execution of a basic block in the trace of a counterexample.
+#[derive(Debug, Clone, Serialize)]
+pub struct TraceStepReport {
+    /// The name of the block, e.g. `bb3`.
+    pub block: String,
+    pub location: Option<SourceLocation>,
+    /// The variables that changed, with their new value.
+    pub values: Vec<TraceValueReport>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TraceValueReport {
+    pub name: String,
+    pub value: String,
+}
+
+/// The report of a single verified item.
+#[derive(Debug, Clone, Serialize)]
+pub struct ItemReport {
+    pub def_path: String,
+    pub location: Option<SourceLocation>,
+    pub status: ItemStatus,
+    pub errors: Vec<ErrorReport>,
+    /// Time (in milliseconds) spent on encoding the item.
+    pub encoding_time_ms: u128,
+    /// Part of the encoding time (in milliseconds) spent on adding fold/unfold
+    /// statements.
+    pub fold_unfold_time_ms: u128,
+    /// Time (in milliseconds) spent by the backend on verifying the item.
+    pub verification_time_ms: u128,
+    /// Backends that verified the programs of the item, which with the
+    /// portfolio backend may differ between programs.
+    pub backends: Vec<String>,
+    /// Number of quantifier instantiations during the verification of the
+    /// item, if the SMT solver was connected through the wrapper.
+    pub quantifier_instantiations: Option<u64>,
+    /// Number of statements in the Viper programs of the item.
+    pub vir_statements: usize,
+}
+
+/// The instantiations of the quantifiers of the same origin during the
+/// verification of a crate.
+#[derive(Debug, Clone, Serialize)]
+pub struct QuantifierReport {
+    /// Where the quantifiers come from, e.g. a specification or a family of
+    /// axioms.
+    pub origin: String,
+    /// The location of the quantifiers in the Rust sources, if they come from
+    /// a function.
+    pub location: Option<SourceLocation>,
+    /// The names of the quantifiers in the SMT input.
+    pub quantifiers: Vec<String>,
+    pub instantiations: usize,
+    /// How many instantiations were triggered by terms created by the
+    /// quantifiers themselves.
+    pub self_triggered: usize,
+    /// The length of the longest chain of instantiations.
+    pub max_generation: u32,
+    /// Whether the quantifiers look like a matching loop.
+    pub matching_loop: bool,
+    /// The functions whose verification instantiated the quantifiers.
+    pub functions: Vec<String>,
+}
+
+/// The report of a verified crate.
+#[derive(Debug, Clone, Serialize)]
+pub struct VerificationReport {
+    pub prusti_version: String,
+    pub crate_name: String,
+    /// Total time (in milliseconds) spent on encoding the crate.
+    pub encoding_time_ms: u128,
+    /// Total time (in milliseconds) spent on verifying the crate.
+    pub verification_time_ms: u128,
+    pub items: Vec<ItemReport>,
+    /// The instantiated quantifiers, the most instantiated first, if the SMT
+    /// solver was connected through the wrapper.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub quantifiers: Vec<QuantifierReport>,
+}
+
+impl VerificationReport {
+    pub fn new<S1: ToString, S2: ToString>(prusti_version: S1, crate_name: S2) -> Self {
+        VerificationReport {
+            prusti_version: prusti_version.to_string(),
+            crate_name: crate_name.to_string(),
+            encoding_time_ms: 0,
+            verification_time_ms: 0,
+            items: vec![],
+            quantifiers: vec![],
+        }
+    }
+
+    /// Writes the report to `<dir>/<crate>.prusti.json` and, in the SARIF
+    /// format, to `<dir>/<crate>.sarif`.
+    pub fn write_to_dir(&self, dir: &Path) {
+        let json_path = dir.join(format!("{}.prusti.json", self.crate_name));
+        let sarif_path = dir.join(format!("{}.sarif", self.crate_name));
+        let result = fs::create_dir_all(dir)
+            .and_then(|()| write_json(&json_path, self))
+            .and_then(|()| write_json(&sarif_path, &self.to_sarif()));
+        match result {
+            Ok(()) => info!("Saved verification report to \"{}\"", dir.display()),
+            Err(e) => error!(
+                "Failed to save verification report to \"{}\": {e}",
+                dir.display()
+            ),
+        }
+    }
+
+    /// Writes the profile of the verified items as CSV, with the items that took
+    /// the longest to encode and verify first.
+    pub fn write_profile<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        writeln!(
+            writer,
+            "def_path,status,total_time_ms,encoding_time_ms,fold_unfold_time_ms,\
+            verification_time_ms,quantifier_instantiations,vir_statements,backends"
+        )?;
+        let mut items: Vec<&ItemReport> = self.items.iter().collect();
+        items.sort_by_key(|item| {
+            std::cmp::Reverse(item.encoding_time_ms + item.verification_time_ms)
+        });
+        for item in items {
+            let quantifier_instantiations = item
+                .quantifier_instantiations
+                .map(|count| count.to_string())
+                .unwrap_or_default();
+            writeln!(
+                writer,
+                "{},{},{},{},{},{},{},{},{}",
+                csv_field(&item.def_path),
+                item.status.name(),
+                item.encoding_time_ms + item.verification_time_ms,
+                item.encoding_time_ms,
+                item.fold_unfold_time_ms,
+                item.verification_time_ms,
+                quantifier_instantiations,
+                item.vir_statements,
+                item.backends.join(" "),
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Writes the instantiated quantifiers as CSV, the most instantiated first.
+    pub fn write_quantifier_hot_spots<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        writeln!(
+            writer,
+            "origin,location,instantiations,self_triggered,max_generation,matching_loop,\
+            quantifiers,functions"
+        )?;
+        for quantifier in &self.quantifiers {
+            let location = quantifier
+                .location
+                .as_ref()
+                .map(|location| {
+                    format!(
+                        "{}:{}:{}",
+                        location.file, location.start_line, location.start_column
+                    )
+                })
+                .unwrap_or_default();
+            writeln!(
+                writer,
+                "{},{},{},{},{},{},{},{}",
+                csv_field(&quantifier.origin),
+                csv_field(&location),
+                quantifier.instantiations,
+                quantifier.self_triggered,
+                quantifier.max_generation,
+                quantifier.matching_loop,
+                csv_field(&quantifier.quantifiers.join(" ")),
+                csv_field(&quantifier.functions.join(" ")),
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Converts the report to a SARIF 2.1.0 log with one result per error.
+    pub fn to_sarif(&self) -> serde_json::Value {
+        let mut rules: Vec<&str> = self
+            .items
+            .iter()
+            .flat_map(|item| item.errors.iter().map(|error| error.kind.as_str()))
+            .collect();
+        rules.sort_unstable();
+        rules.dedup();
+        let results: Vec<_> = self
+            .items
+            .iter()
+            .flat_map(|item| {
+                item.errors.iter().map(|error| {
+                    let locations: Vec<_> = error
+                        .location
+                        .iter()
+                        .map(|location| {
+                            serde_json::json!({
+                                "physicalLocation": {
+                                    "artifactLocation": { "uri": location.file },
+                                    "region": {
+                                        "startLine": location.start_line,
+                                        "startColumn": location.start_column,
+                                        "endLine": location.end_line,
+                                        "endColumn": location.end_column,
+                                    },
+                                },
+                                "logicalLocations": [{
+                                    "fullyQualifiedName": item.def_path,
+                                    "kind": "function",
+                                }],
+                            })
+                        })
+                        .collect();
+                    let mut result = serde_json::json!({
+                        "ruleId": error.kind,
+                        "level": match error.level {
+                            ErrorLevel::Error => "error",
+                            ErrorLevel::Warning => "warning",
+                        },
+                        "message": { "text": error.message },
+                        "locations": locations,
+                    });
+                    if !error.trace.is_empty() {
+                        // The counterexample trace, as a single thread flow.
+                        let steps: Vec<_> = error
+                            .trace
+                            .iter()
+                            .map(|step| {
+                                let values: Vec<_> = step
+                                    .values
+                                    .iter()
+                                    .map(|value| format!("{} = {}", value.name, value.value))
+                                    .collect();
+                                let mut location = serde_json::json!({
+                                    "message": { "text": format!("{}: {}", step.block, values.join(", ")) },
+                                });
+                                if let Some(source) = &step.location {
+                                    location["physicalLocation"] = serde_json::json!({
+                                        "artifactLocation": { "uri": source.file },
+                                        "region": {
+                                            "startLine": source.start_line,
+                                            "startColumn": source.start_column,
+                                            "endLine": source.end_line,
+                                            "endColumn": source.end_column,
+                                        },
+                                    });
+                                }
+                                serde_json::json!({ "location": location })
+                            })
+                            .collect();
+                        result["codeFlows"] =
+                            serde_json::json!([{ "threadFlows": [{ "locations": steps }] }]);
+                    }
+                    result
+                })
+            })
+            .collect();
+        serde_json::json!({
+            "$schema": "https://json.schemastore.org/sarif-2.1.0.json",
+            "version": "2.1.0",
+            "runs": [{
+                "tool": {
+                    "driver": {
+                        "name": "Prusti",
+                        "version": self.prusti_version,
+                        "informationUri": "https://github.com/viperproject/prusti-dev",
+                        "rules": rules
+                            .into_iter()
+                            .map(|rule| serde_json::json!({ "id": rule }))
+                            .collect::<Vec<_>>(),
+                    },
+                },
+                "results": results,
+            }],
+        })
+    }
+}
+
+/// Quotes `value` if it cannot be used as a CSV field as it is.
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+fn write_json<T: Serialize>(path: &Path, value: &T) -> io::Result<()> {
+    let mut writer = io::BufWriter::new(fs::File::create(path)?);
+    serde_json::to_writer_pretty(&mut writer, value)?;
+    writer.flush()
+}
@@ -4,9 +4,18 @@
 // License, v. 2.0. If a copy of the MPL was not distributed with this
 // file, You can obtain one at http://mozilla.org/MPL/2.0/.
 
-use crate::encoder::{
-    counterexamples::{counterexample_translation, counterexample_translation_refactored},
-    Encoder,
+use crate::{
+    encoder::{
+        counterexamples::{
+            counterexample_test, counterexample_translation, counterexample_translation_refactored,
+        },
+        incremental::{Fingerprinter, VerifiedFingerprints},
+        Encoder, EncodingErrorKind, SpannedEncodingError,
+    },
+    quantifier_hot_spots::{QuantifierHotSpots, QuantifierOrigin},
+    verification_report::{
+        ErrorReport, ItemReport, ItemStatus, SourceLocation, VerificationReport,
+    },
 };
 use ::log::{debug, error, info};
 use once_cell::sync::Lazy;
@@ -17,17 +26,19 @@ use prusti_common::{
     Stopwatch,
 };
 use prusti_interface::{
-    data::{VerificationResult, VerificationTask},
+    data::{ItemVerificationResult, ProcedureDefId, VerificationResult, VerificationTask},
     environment::Environment,
     specs::typed,
     PrustiError,
 };
-use prusti_rustc_interface::span::DUMMY_SP;
+use prusti_rustc_interface::{middle::ty, span::DUMMY_SP};
 use prusti_server::{
-    process_verification_request, spawn_server_thread, tokio::runtime::Builder, PrustiClient,
-    VerificationRequest, ViperBackendConfig,
+    load_persistent_cache, process_verification_request, spawn_server_thread,
+    tokio::runtime::Builder, Interrupt, PrustiClient, VerificationRequest, ViperBackendConfig,
 };
-use viper::{self, PersistentCache, Viper};
+use rustc_hash::{FxHashMap, FxHashSet};
+use std::time::Duration;
+use viper::{self, Viper};
 use vir_crate::common::check_mode::CheckMode;
 
 /// A verifier is an object for verifying a single crate, potentially
@@ -38,6 +49,7 @@ where
 {
     env: &'v Environment<'tcx>,
     encoder: Encoder<'v, 'tcx>,
+    report: VerificationReport,
 }
 
 impl<'v, 'tcx> Verifier<'v, 'tcx> {
@@ -45,11 +57,17 @@ impl<'v, 'tcx> Verifier<'v, 'tcx> {
         Verifier {
             env,
             encoder: Encoder::new(env, def_spec),
+            report: VerificationReport::new(env.get_prusti_version(), env.name.local_crate_name()),
         }
     }
 
+    /// The machine-readable report of the last verification.
+    pub fn report(&self) -> &VerificationReport {
+        &self.report
+    }
+
     #[tracing::instrument(name = "prusti_viper::verify", level = "info", skip(self))]
-    pub fn verify(&mut self, task: &VerificationTask<'tcx>) -> VerificationResult {
+    pub fn verify(&mut self, task: &VerificationTask<'tcx>) -> VerificationResult<'tcx> {
         let mut stopwatch = Stopwatch::start("prusti-viper", "encoding to Viper");
 
         // Dump the configuration
@@ -64,9 +82,32 @@ impl<'v, 'tcx> Verifier<'v, 'tcx> {
             info!(" - {} ({})", proc_name, proc_def_path);
             info!("   Source: {:?}", proc_span);
         }
+
+        // Skip the procedures that did not change since their last successful
+        // verification
+        let mut verified_fingerprints = config::incremental_verification()
+            .then(|| VerifiedFingerprints::load(&self.env.name.local_crate_filename()));
+        let mut fingerprints: FxHashMap<ProcedureDefId, u64> = FxHashMap::default();
+        let mut unchanged_procedures: FxHashSet<ProcedureDefId> = FxHashSet::default();
+        if let Some(verified_fingerprints) = &verified_fingerprints {
+            let fingerprinter = Fingerprinter::new(&self.encoder);
+            for &proc_id in &task.procedures {
+                if let Some(fingerprint) = fingerprinter.fingerprint(proc_id) {
+                    let proc_def_path = self.env.name.get_item_def_path(proc_id);
+                    if verified_fingerprints.contains(&proc_def_path, fingerprint) {
+                        info!("Skipping unchanged procedure {}", proc_def_path);
+                        unchanged_procedures.insert(proc_id);
+                    }
+                    fingerprints.insert(proc_id, fingerprint);
+                }
+            }
+        }
+
         for &proc_id in task.procedures.iter().rev() {
             // FIXME: Use the loop above.
-            self.encoder.queue_procedure_encoding(proc_id);
+            if !unchanged_procedures.contains(&proc_id) {
+                self.encoder.queue_procedure_encoding(proc_id);
+            }
         }
         for &type_id in task.types.iter().rev() {
             // FIXME: Use the loop above.
@@ -78,8 +119,9 @@ impl<'v, 'tcx> Verifier<'v, 'tcx> {
 
         let polymorphic_programs = self.encoder.get_viper_programs();
 
+        let mut encoding_time = Duration::ZERO;
         let mut programs: Vec<Program> = if config::simplify_encoding() {
-            stopwatch.start_next("optimizing Viper program");
+            encoding_time += stopwatch.start_next("optimizing Viper program");
             let source_file_name = self.encoder.env().name.source_file_name();
             polymorphic_programs
                 .into_iter()
@@ -93,65 +135,272 @@ impl<'v, 'tcx> Verifier<'v, 'tcx> {
         };
         programs.extend(self.encoder.get_core_proof_programs());
 
-        stopwatch.start_next("verifying Viper program");
-        let verification_results = verify_programs(self.env, programs);
-        stopwatch.finish();
+        let program_sizes: FxHashMap<String, usize> = programs
+            .iter()
+            .map(|program| (program.get_name().to_string(), program.statement_count()))
+            .collect();
+
+        // The verification timeout of each program, in seconds
+        let mut timeouts: FxHashMap<String, u64> = FxHashMap::default();
+        for &proc_id in &task.procedures {
+            let timeout = self
+                .env
+                .query
+                .get_prusti_attribute(proc_id, "timeout")
+                .and_then(|timeout| timeout.parse().ok())
+                .or_else(config::verification_timeout);
+            if let Some(timeout) = timeout {
+                timeouts.insert(self.env.name.get_unique_item_name(proc_id), timeout);
+                timeouts.insert(self.env.name.get_absolute_item_name(proc_id), timeout);
+            }
+        }
+
+        encoding_time += stopwatch.start_next("verifying Viper program");
+        let verification_results = verify_programs(self.env, programs, &timeouts);
+        let verification_time = stopwatch.finish();
+
+        // Collect the results of the encoding
+        let source_map = self.env.query.codemap();
+        let mut program_owners: FxHashMap<String, VerifiedItem<'tcx>> = FxHashMap::default();
+        let mut results: FxHashMap<VerifiedItem<'tcx>, ItemVerificationResult> =
+            FxHashMap::default();
+        let mut items: FxHashMap<ProcedureDefId, ItemReport> = FxHashMap::default();
+        for &proc_id in &task.procedures {
+            let item = VerifiedItem::Procedure(proc_id);
+            program_owners.insert(self.env.name.get_unique_item_name(proc_id), item);
+            program_owners.insert(self.env.name.get_absolute_item_name(proc_id), item);
+            let mut item_report = ItemReport {
+                def_path: self.env.name.get_item_def_path(proc_id),
+                location: SourceLocation::from_span(
+                    source_map,
+                    &self.env.query.get_def_span(proc_id).into(),
+                ),
+                status: ItemStatus::Skipped,
+                errors: vec![],
+                encoding_time_ms: self
+                    .encoder
+                    .get_procedure_encoding_time(proc_id)
+                    .map_or(0, |duration| duration.as_millis()),
+                fold_unfold_time_ms: self
+                    .encoder
+                    .get_procedure_fold_unfold_time(proc_id)
+                    .map_or(0, |duration| duration.as_millis()),
+                verification_time_ms: 0,
+                backends: vec![],
+                quantifier_instantiations: None,
+                vir_statements: 0,
+            };
+            let encoding_errors = self.encoder.get_procedure_encoding_errors(proc_id);
+            for encoding_error in &encoding_errors {
+                let prusti_error = PrustiError::from(encoding_error.clone());
+                item_report.errors.push(ErrorReport::new(
+                    source_map,
+                    encoding_error_kind(encoding_error),
+                    &prusti_error,
+                ));
+            }
+            let item_result = if unchanged_procedures.contains(&proc_id) {
+                ItemVerificationResult::Cached
+            } else {
+                encoding_result(encoding_errors)
+            };
+            results.insert(item, item_result);
+            items.insert(proc_id, item_report);
+        }
+        for &ty in &task.types {
+            let item = VerifiedItem::Type(ty);
+            if let Some(program_name) = self.encoder.get_type_program_name(ty) {
+                program_owners.insert(program_name, item);
+            }
+            results.insert(
+                item,
+                encoding_result(self.encoder.get_type_encoding_errors(ty)),
+            );
+        }
+        let mut other_errors: Vec<PrustiError> = self
+            .encoder
+            .get_other_encoding_errors()
+            .into_iter()
+            .map(PrustiError::from)
+            .collect();
+
+        // Record the outcome of each program
+        for (method_name, result) in &verification_results {
+            if let Some(item) = program_owners.get(method_name) {
+                if let Some(item_result) = results.get_mut(item) {
+                    record_program_result(item_result, result);
+                }
+                if let VerifiedItem::Procedure(proc_id) = item {
+                    if let Some(item_report) = items.get_mut(proc_id) {
+                        item_report.verification_time_ms += result.time_ms;
+                        item_report.vir_statements +=
+                            program_sizes.get(method_name).copied().unwrap_or_default();
+                        if let Some(count) = result.quantifier_instantiations {
+                            *item_report.quantifier_instantiations.get_or_insert(0) += count;
+                        }
+                        if let Some(backend) = result.backend {
+                            let backend = backend.to_string();
+                            if !item_report.backends.contains(&backend) {
+                                item_report.backends.push(backend);
+                            }
+                        }
+                    }
+                }
+            }
+        }
+
+        // Map the instantiated quantifiers back to their origin
+        let mut hot_spots = QuantifierHotSpots::default();
+        {
+            let error_manager = self.encoder.error_manager();
+            for (method_name, result) in &verification_results {
+                let proc_id = match program_owners.get(method_name) {
+                    Some(VerifiedItem::Procedure(proc_id)) => Some(*proc_id),
+                    _ => None,
+                };
+                for statistics in &result.quantifiers {
+                    let origin = QuantifierOrigin::of(
+                        &statistics.name,
+                        proc_id,
+                        error_manager.position_manager(),
+                        source_map,
+                    );
+                    let def_path = proc_id.map(|proc_id| self.env.name.get_item_def_path(proc_id));
+                    hot_spots.add(origin, statistics, def_path, source_map);
+                }
+            }
+        }
+        if config::dump_quantifier_hot_spots() {
+            for (span, report) in hot_spots.matching_loops() {
+                PrustiError::warning(
+                    format!(
+                        "this quantifier may cause a matching loop: {} of its {} instantiations \
+                        were triggered by its own instances",
+                        report.self_triggered, report.instantiations
+                    ),
+                    span.clone(),
+                )
+                .set_help("choose triggers that do not match the terms created by its body")
+                .emit(&self.env.diagnostic);
+            }
+        }
 
         // Group verification results
         let mut verification_errors: Vec<_> = vec![];
         let mut consistency_errors: Vec<_> = vec![];
         let mut java_exceptions: Vec<_> = vec![];
+        let mut interrupted: Vec<_> = vec![];
         for (method_name, result) in verification_results.into_iter() {
-            match result {
-                viper::VerificationResult::Success => {}
-                viper::VerificationResult::ConsistencyErrors(errors) => {
+            match result.kind {
+                viper::VerificationResultKind::Success => {}
+                viper::VerificationResultKind::ConsistencyErrors(errors) => {
                     for error in errors.into_iter() {
                         consistency_errors.push((method_name.clone(), error));
                     }
                 }
-                viper::VerificationResult::Failure(errors) => {
+                viper::VerificationResultKind::Failure(errors) => {
                     for error in errors.into_iter() {
                         verification_errors.push((method_name.clone(), error));
                     }
                 }
-                viper::VerificationResult::JavaException(exception) => {
+                viper::VerificationResultKind::JavaException(exception) => {
                     java_exceptions.push((method_name, exception));
                 }
+                kind @ (viper::VerificationResultKind::TimedOut
+                | viper::VerificationResultKind::Cancelled) => {
+                    interrupted.push((method_name, kind));
+                }
             }
         }
 
         // Convert verification results to Prusti errors
         let error_manager = self.encoder.error_manager();
-        let mut result = VerificationResult::Success;
 
         for (method, error) in consistency_errors.into_iter() {
-            PrustiError::internal(
+            let prusti_error = PrustiError::internal(
                 format!("consistency error in {method}: {error}"),
                 DUMMY_SP.into(),
-            )
-            .emit(&self.env.diagnostic);
-            result = VerificationResult::Failure;
+            );
+            let owner = program_owners.get(&method).copied();
+            record_error(
+                &mut results,
+                &mut items,
+                &mut other_errors,
+                owner,
+                ErrorReport::new(source_map, "consistency_error", &prusti_error),
+                prusti_error.clone(),
+            );
+            prusti_error.emit(&self.env.diagnostic);
         }
 
         for (method, exception) in java_exceptions.into_iter() {
             error!("Java exception: {}", exception.get_stack_trace());
-            PrustiError::internal(format!("in {method}: {exception}"), DUMMY_SP.into())
-                .emit(&self.env.diagnostic);
-            result = VerificationResult::Failure;
+            let prusti_error =
+                PrustiError::internal(format!("in {method}: {exception}"), DUMMY_SP.into());
+            let owner = program_owners.get(&method).copied();
+            record_error(
+                &mut results,
+                &mut items,
+                &mut other_errors,
+                owner,
+                ErrorReport::new(source_map, "java_exception", &prusti_error),
+                prusti_error.clone(),
+            );
+            prusti_error.emit(&self.env.diagnostic);
+        }
+
+        for (method, kind) in interrupted.into_iter() {
+            let owner = program_owners.get(&method).copied();
+            let span = match owner {
+                Some(VerifiedItem::Procedure(proc_id)) => self.env.query.get_def_span(proc_id),
+                _ => DUMMY_SP,
+            };
+            let (prusti_error, report_kind) = match kind {
+                viper::VerificationResultKind::TimedOut => {
+                    let timeout = program_timeout(&timeouts, &method).unwrap_or_default();
+                    let prusti_error = PrustiError::timeout(
+                        format!("verification of {method} did not finish within {timeout} seconds"),
+                        span.into(),
+                    )
+                    .set_help(
+                        "increase the timeout with `#[timeout(seconds)]` or the \
+                        `VERIFICATION_TIMEOUT` flag",
+                    );
+                    (prusti_error, "timed_out")
+                }
+                _ => (
+                    PrustiError::verification(
+                        format!("verification of {method} was cancelled"),
+                        span.into(),
+                    ),
+                    "cancelled",
+                ),
+            };
+            record_error(
+                &mut results,
+                &mut items,
+                &mut other_errors,
+                owner,
+                ErrorReport::new(source_map, report_kind, &prusti_error),
+                prusti_error.clone(),
+            );
+            prusti_error.emit(&self.env.diagnostic);
         }
 
         // Report verification errors
         let mut prusti_errors: Vec<_> = vec![];
+        let mut counterexample_tests: Vec<counterexample_test::CounterexampleTest> = vec![];
         for (method, verification_error) in verification_errors.into_iter() {
             debug!("Verification error in {}: {:?}", method, verification_error);
             let mut prusti_error = error_manager.translate_verification_error(&verification_error);
+            let mut trace = None;
 
             // annotate with counterexample, if requested
             if config::counterexample() {
                 if config::unsafe_core_proof() {
                     if let Some(silicon_counterexample) = &verification_error.counterexample {
                         if let Some(def_id) = error_manager.get_def_id(&verification_error) {
-                            let counterexample =
+                            let mut counterexample =
                                 counterexample_translation_refactored::backtranslate(
                                     &self.encoder,
                                     error_manager.position_manager(),
@@ -159,6 +408,7 @@ impl<'v, 'tcx> Verifier<'v, 'tcx> {
                                     silicon_counterexample,
                                 );
                             prusti_error = counterexample.annotate_error(prusti_error);
+                            trace = counterexample.take_trace();
                         } else {
                             prusti_error = prusti_error.add_note(
                                 format!(
@@ -170,12 +420,24 @@ impl<'v, 'tcx> Verifier<'v, 'tcx> {
                     }
                 } else if let Some(silicon_counterexample) = &verification_error.counterexample {
                     if let Some(def_id) = error_manager.get_def_id(&verification_error) {
-                        let counterexample = counterexample_translation::backtranslate(
+                        let mut counterexample = counterexample_translation::backtranslate(
                             &self.encoder,
                             def_id,
                             silicon_counterexample,
                         );
                         prusti_error = counterexample.annotate_error(prusti_error);
+                        trace = counterexample.take_trace();
+                        // Only the first counterexample of each procedure is
+                        // turned into a test.
+                        if let Some(mut test) = counterexample.take_test() {
+                            if !counterexample_tests
+                                .iter()
+                                .any(|other| other.function() == test.function())
+                            {
+                                test.set_error(prusti_error.message());
+                                counterexample_tests.push(test);
+                            }
+                        }
                     } else {
                         prusti_error = prusti_error.add_note(
                             format!(
@@ -187,10 +449,40 @@ impl<'v, 'tcx> Verifier<'v, 'tcx> {
                 }
             }
 
+            let mut error_report =
+                ErrorReport::new(source_map, &verification_error.full_id, &prusti_error);
+            if let Some(trace) = trace.filter(|trace| !trace.is_empty()) {
+                prusti_error = trace.annotate_error(source_map, prusti_error);
+                error_report.trace = trace.to_report(source_map);
+            }
+
+            let owner = program_owners.get(&method).copied().or_else(|| {
+                error_manager
+                    .get_def_id(&verification_error)
+                    .map(VerifiedItem::Procedure)
+            });
+            record_error(
+                &mut results,
+                &mut items,
+                &mut other_errors,
+                owner,
+                error_report,
+                prusti_error.clone(),
+            );
+
             prusti_errors.push(prusti_error);
         }
         prusti_errors.sort();
 
+        if let Some(test_dir) = config::counterexample_test_dir() {
+            counterexample_tests.sort_by(|a, b| a.function().cmp(b.function()));
+            counterexample_test::write_tests_to_dir(
+                &test_dir,
+                &self.env.name.local_crate_name(),
+                &counterexample_tests,
+            );
+        }
+
         for prusti_error in prusti_errors {
             debug!("Prusti error: {:?}", prusti_error);
             if prusti_error.is_disabled() {
@@ -198,22 +490,150 @@ impl<'v, 'tcx> Verifier<'v, 'tcx> {
             } else {
                 prusti_error.emit(&self.env.diagnostic);
             }
-            result = VerificationResult::Failure;
         }
 
-        if encoding_errors_count != 0 {
-            result = VerificationResult::Failure;
+        let mut result = VerificationResult {
+            other_errors,
+            ..Default::default()
+        };
+        for (item, item_result) in results {
+            match item {
+                VerifiedItem::Procedure(proc_id) => {
+                    if let Some(item_report) = items.get_mut(&proc_id) {
+                        item_report.status = ItemStatus::from(&item_result);
+                    }
+                    result.procedures.insert(proc_id, item_result);
+                }
+                VerifiedItem::Type(ty) => {
+                    result.types.insert(ty, item_result);
+                }
+            }
+        }
+        debug_assert!(
+            encoding_errors_count == 0 || !result.is_success(),
+            "encoding errors were not recorded in the verification result"
+        );
+
+        if let Some(verified_fingerprints) = &mut verified_fingerprints {
+            for (proc_id, fingerprint) in fingerprints {
+                let proc_def_path = self.env.name.get_item_def_path(proc_id);
+                match result.procedures.get(&proc_id) {
+                    Some(ItemVerificationResult::Verified | ItemVerificationResult::Cached)
+                        if result.other_errors.is_empty() =>
+                    {
+                        verified_fingerprints.insert(proc_def_path, fingerprint);
+                    }
+                    _ => verified_fingerprints.remove(&proc_def_path),
+                }
+            }
+            verified_fingerprints.save();
         }
 
+        self.report.encoding_time_ms = encoding_time.as_millis();
+        self.report.verification_time_ms = verification_time.as_millis();
+        self.report.items = task
+            .procedures
+            .iter()
+            .filter_map(|proc_id| items.remove(proc_id))
+            .collect();
+        self.report.quantifiers = hot_spots.into_reports();
+
         result
     }
 }
 
-/// Verify a list of programs.
+/// An item of a `VerificationTask`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum VerifiedItem<'tcx> {
+    Procedure(ProcedureDefId),
+    Type(ty::Ty<'tcx>),
+}
+
+fn encoding_error_kind(encoding_error: &SpannedEncodingError) -> &'static str {
+    match encoding_error.kind() {
+        EncodingErrorKind::Unsupported(_) => "unsupported",
+        EncodingErrorKind::Incorrect(_) => "incorrect",
+        EncodingErrorKind::Internal(_) => "internal",
+    }
+}
+
+/// The result of an item before its programs are verified.
+fn encoding_result(encoding_errors: Vec<SpannedEncodingError>) -> ItemVerificationResult {
+    let is_unsupported = encoding_errors
+        .iter()
+        .any(|error| matches!(error.kind(), EncodingErrorKind::Unsupported(_)));
+    let errors: Vec<PrustiError> = encoding_errors.into_iter().map(PrustiError::from).collect();
+    if is_unsupported {
+        ItemVerificationResult::Unsupported(errors)
+    } else if errors.iter().any(|error| error.is_error()) {
+        ItemVerificationResult::Failed(errors)
+    } else {
+        ItemVerificationResult::Skipped
+    }
+}
+
+/// Updates the result of an item with the result of one of its programs.
+fn record_program_result(
+    item_result: &mut ItemVerificationResult,
+    program_result: &viper::VerificationResult,
+) {
+    *item_result = match item_result {
+        ItemVerificationResult::Failed(_) | ItemVerificationResult::Unsupported(_) => return,
+        _ if program_result.kind == viper::VerificationResultKind::TimedOut => {
+            ItemVerificationResult::TimedOut(vec![])
+        }
+        _ if !program_result.is_success() => ItemVerificationResult::Failed(vec![]),
+        ItemVerificationResult::TimedOut(_) => return,
+        ItemVerificationResult::Skipped | ItemVerificationResult::Cached
+            if program_result.cached =>
+        {
+            ItemVerificationResult::Cached
+        }
+        _ => ItemVerificationResult::Verified,
+    };
+}
+
+/// Records an error reported for `owner`, or for no item if there is no owner.
+fn record_error<'tcx>(
+    results: &mut FxHashMap<VerifiedItem<'tcx>, ItemVerificationResult>,
+    items: &mut FxHashMap<ProcedureDefId, ItemReport>,
+    other_errors: &mut Vec<PrustiError>,
+    owner: Option<VerifiedItem<'tcx>>,
+    error_report: ErrorReport,
+    prusti_error: PrustiError,
+) {
+    if let Some(VerifiedItem::Procedure(proc_id)) = owner {
+        if let Some(item_report) = items.get_mut(&proc_id) {
+            if !prusti_error.is_disabled() {
+                item_report.errors.push(error_report);
+            }
+        }
+    }
+    match owner.and_then(|item| results.get_mut(&item)) {
+        Some(
+            ItemVerificationResult::Failed(errors)
+            | ItemVerificationResult::Unsupported(errors)
+            | ItemVerificationResult::TimedOut(errors),
+        ) => errors.push(prusti_error),
+        Some(item_result) => *item_result = ItemVerificationResult::Failed(vec![prusti_error]),
+        None => other_errors.push(prusti_error),
+    }
+}
+
+/// The verification timeout (in seconds) of the program called `program_name`.
+fn program_timeout(timeouts: &FxHashMap<String, u64>, program_name: &str) -> Option<u64> {
+    timeouts
+        .get(program_name)
+        .copied()
+        .or_else(config::verification_timeout)
+}
+
+/// Verify a list of programs, each within its timeout.
 /// Returns a list of (program_name, verification_result) tuples.
 fn verify_programs(
     env: &Environment,
     programs: Vec<Program>,
+    timeouts: &FxHashMap<String, u64>,
 ) -> Vec<(String, viper::VerificationResult)> {
     let source_path = env.name.source_path();
     let rust_program_name = source_path
@@ -237,6 +657,7 @@ fn verify_programs(
         let request = VerificationRequest {
             program,
             backend_config: ViperBackendConfig::new(backend),
+            deadline: program_timeout(timeouts, &program_name),
         };
         (program_name, request)
     });
@@ -274,10 +695,15 @@ fn verify_programs(
             Lazy::new(|| Viper::new_with_args(&config::viper_home(), config::extra_jvm_args()));
         let viper_thread = Lazy::new(|| viper.attach_current_thread());
         stopwatch.finish();
-        let mut cache = PersistentCache::load_cache(config::cache_path());
+        let mut cache = load_persistent_cache();
         verification_requests
             .map(|(program_name, request)| {
-                let result = process_verification_request(&viper_thread, request, &mut cache);
+                let result = process_verification_request(
+                    &viper_thread,
+                    request,
+                    &mut cache,
+                    &Interrupt::default(),
+                );
                 (program_name, result)
             })
             .collect()
@@ -0,0 +1,12 @@
+use prusti_contracts::*;
+
+#[requires(x <= 10)]
+fn find_seven(x: u8) {
+    assert!(x != 7);
+}
+
+fn both(a: bool, b: bool) {
+    assert!(a || b);
+}
+
+fn main() {}
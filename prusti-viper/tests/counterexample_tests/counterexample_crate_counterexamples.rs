@@ -0,0 +1,17 @@
+// Tests reproducing the counterexamples found by Prusti in `counterexample_crate`.
+// This file is generated; it is overwritten whenever the crate is verified.
+
+#[allow(unused_imports)]
+use super::*;
+
+/// [Prusti: verification error] the asserted expression might not hold
+#[test]
+fn counterexample_both() {
+    let _ = both(false, false);
+}
+
+/// [Prusti: verification error] the asserted expression might not hold
+#[test]
+fn counterexample_find_seven() {
+    let _ = find_seven(7_u8);
+}
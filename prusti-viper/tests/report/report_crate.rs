@@ -0,0 +1,8 @@
+fn verified() { assert!(true) }
+
+fn failing() { assert!(false) }
+
+fn main() {
+    verified();
+    failing();
+}
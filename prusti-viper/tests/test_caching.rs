@@ -130,7 +130,7 @@ fn test_prusti_rustc_caching_hash() {
 #[test]
 fn test_prusti_rustc_caching_error() {
     let prusti_rustc = find_executable_path("prusti-rustc");
-    let cache_file = PathBuf::from("tests/error/cache.bin");
+    let cache_dir = PathBuf::from("tests/error/cache");
 
     let mut run = |program: &Path| {
         println!("Running {prusti_rustc:?} on {program:?}...");
@@ -139,10 +139,7 @@ fn test_prusti_rustc_caching_error() {
             .arg("--crate-type=lib")
             .arg(program)
             .env("RUST_BACKTRACE", "1")
-            .env(
-                "PRUSTI_CACHE_PATH",
-                cache_file.to_string_lossy().to_string(),
-            )
+            .env("PRUSTI_CACHE_PATH", cache_dir.to_string_lossy().to_string())
             .output()
             .expect("failed to execute prusti-rustc");
         assert!(!out.status.success());
@@ -159,5 +156,5 @@ fn test_prusti_rustc_caching_error() {
         );
     };
     run_on_files(&PathBuf::from("tests/error/"), &mut run);
-    std::fs::remove_file(&cache_file).unwrap();
+    std::fs::remove_dir_all(&cache_dir).unwrap();
 }
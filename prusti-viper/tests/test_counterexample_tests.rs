@@ -0,0 +1,64 @@
+// © 2023, ETH Zurich
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+use std::{fs, path::PathBuf, process::Command};
+
+fn find_executable_path(base_name: &str) -> PathBuf {
+    let target_directory = if cfg!(debug_assertions) {
+        "debug"
+    } else {
+        "release"
+    };
+    let executable_name = if cfg!(windows) {
+        format!("{base_name}.exe")
+    } else {
+        base_name.to_string()
+    };
+    let local_prusti_rustc_path: PathBuf = ["target", target_directory, &executable_name]
+        .iter()
+        .collect();
+    if local_prusti_rustc_path.exists() {
+        return local_prusti_rustc_path;
+    }
+    let workspace_prusti_rustc_path: PathBuf = ["..", "target", target_directory, &executable_name]
+        .iter()
+        .collect();
+    if workspace_prusti_rustc_path.exists() {
+        return workspace_prusti_rustc_path;
+    }
+    panic!(
+        "Could not find the {target_directory:?} prusti-rustc binary to be used in tests. \
+        It might be that Prusti has not been compiled correctly."
+    );
+}
+
+#[test]
+fn test_prusti_rustc_counterexample_tests() {
+    let prusti_rustc = find_executable_path("prusti-rustc");
+    let test_dir = PathBuf::from("tests/counterexample_tests/out");
+
+    let out = Command::new(&prusti_rustc)
+        .arg("--edition=2018")
+        .arg("tests/counterexample_tests/counterexample_crate.rs")
+        .env("RUST_BACKTRACE", "1")
+        .env("PRUSTI_COUNTEREXAMPLE", "true")
+        .env(
+            "PRUSTI_COUNTEREXAMPLE_TEST_DIR",
+            test_dir.to_string_lossy().to_string(),
+        )
+        .output()
+        .expect("failed to execute prusti-rustc");
+    assert!(!out.status.success());
+
+    let generated =
+        fs::read_to_string(test_dir.join("counterexample_crate_counterexamples.rs")).unwrap();
+    let expected =
+        fs::read_to_string("tests/counterexample_tests/counterexample_crate_counterexamples.rs")
+            .unwrap();
+    assert_eq!(generated, expected);
+
+    fs::remove_dir_all(&test_dir).unwrap();
+}
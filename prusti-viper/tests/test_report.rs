@@ -0,0 +1,316 @@
+// © 2023, ETH Zurich
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+use std::{fs, path::PathBuf, process::Command};
+
+fn find_executable_path(base_name: &str) -> PathBuf {
+    let target_directory = if cfg!(debug_assertions) {
+        "debug"
+    } else {
+        "release"
+    };
+    let executable_name = if cfg!(windows) {
+        format!("{base_name}.exe")
+    } else {
+        base_name.to_string()
+    };
+    let local_prusti_rustc_path: PathBuf = ["target", target_directory, &executable_name]
+        .iter()
+        .collect();
+    if local_prusti_rustc_path.exists() {
+        return local_prusti_rustc_path;
+    }
+    let workspace_prusti_rustc_path: PathBuf = ["..", "target", target_directory, &executable_name]
+        .iter()
+        .collect();
+    if workspace_prusti_rustc_path.exists() {
+        return workspace_prusti_rustc_path;
+    }
+    panic!(
+        "Could not find the {target_directory:?} prusti-rustc binary to be used in tests. \
+        It might be that Prusti has not been compiled correctly."
+    );
+}
+
+fn read_json(path: PathBuf) -> serde_json::Value {
+    let file = fs::File::open(&path).unwrap_or_else(|_| panic!("Did not find file: {path:?}"));
+    serde_json::from_reader(file).unwrap()
+}
+
+#[test]
+fn test_prusti_rustc_report() {
+    let prusti_rustc = find_executable_path("prusti-rustc");
+    let report_dir = PathBuf::from("tests/report/out");
+
+    let out = Command::new(&prusti_rustc)
+        .arg("--edition=2018")
+        .arg("tests/report/report_crate.rs")
+        .env("RUST_BACKTRACE", "1")
+        .env(
+            "PRUSTI_REPORT_DIR",
+            report_dir.to_string_lossy().to_string(),
+        )
+        .output()
+        .expect("failed to execute prusti-rustc");
+    assert!(!out.status.success());
+
+    let report = read_json(report_dir.join("report_crate.prusti.json"));
+    assert_eq!(report["crate_name"], "report_crate");
+    let items = report["items"].as_array().unwrap();
+    let item = |def_path: &str| {
+        items
+            .iter()
+            .find(|item| item["def_path"] == def_path)
+            .unwrap_or_else(|| panic!("Did not find item {def_path} in {items:?}"))
+    };
+    assert_eq!(item("report_crate::verified")["status"], "verified");
+    let failing = item("report_crate::failing");
+    assert_eq!(failing["status"], "failed");
+    assert_eq!(failing["errors"][0]["location"]["start_line"], 3);
+
+    let sarif = read_json(report_dir.join("report_crate.sarif"));
+    assert_eq!(sarif["version"], "2.1.0");
+    let results = sarif["runs"][0]["results"].as_array().unwrap();
+    assert_eq!(results.len(), 1);
+    assert_eq!(results[0]["level"], "error");
+    assert_eq!(
+        results[0]["locations"][0]["logicalLocations"][0]["fullyQualifiedName"],
+        "report_crate::failing"
+    );
+
+    fs::remove_dir_all(&report_dir).unwrap();
+}
+
+#[test]
+fn test_prusti_rustc_incremental_report() {
+    let prusti_rustc = find_executable_path("prusti-rustc");
+    // A fresh directory per run, so that no procedure is cached by an earlier run.
+    let out_dir = std::env::temp_dir().join(format!(
+        "prusti-incremental-report-{}-{}",
+        std::process::id(),
+        std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_nanos()
+    ));
+    let report_dir = out_dir.join("report");
+    let incremental_dir = out_dir.join("incremental");
+
+    let run = || {
+        let out = Command::new(&prusti_rustc)
+            .arg("--edition=2018")
+            .arg("tests/report/report_crate.rs")
+            .env("RUST_BACKTRACE", "1")
+            .env(
+                "PRUSTI_REPORT_DIR",
+                report_dir.to_string_lossy().to_string(),
+            )
+            .env("PRUSTI_INCREMENTAL_VERIFICATION", "true")
+            .env(
+                "PRUSTI_INCREMENTAL_DIR",
+                incremental_dir.to_string_lossy().to_string(),
+            )
+            .output()
+            .expect("failed to execute prusti-rustc");
+        assert!(!out.status.success());
+        let report = read_json(report_dir.join("report_crate.prusti.json"));
+        let items = report["items"].as_array().unwrap().clone();
+        move |def_path: &str| {
+            items
+                .iter()
+                .find(|item| item["def_path"] == def_path)
+                .unwrap_or_else(|| panic!("Did not find item {def_path} in {items:?}"))["status"]
+                .clone()
+        }
+    };
+
+    let status = run();
+    assert_eq!(status("report_crate::verified"), "verified");
+    assert_eq!(status("report_crate::failing"), "failed");
+
+    // Only the unchanged procedures that verified are skipped.
+    let status = run();
+    assert_eq!(status("report_crate::verified"), "cached");
+    assert_eq!(status("report_crate::failing"), "failed");
+
+    fs::remove_dir_all(&out_dir).unwrap();
+}
+
+/// A crate whose procedures depend on the contract of `callee` and on the
+/// invariant of `Percentage`.
+const DEPENDENCIES_CRATE: &str = r#"
+use prusti_contracts::*;
+
+#[ensures(result > 0)]
+fn callee() -> u8 { 1 }
+
+#[ensures(result > 0)]
+fn caller() -> u8 { callee() }
+
+#[invariant(self.value <= 100)]
+struct Percentage { value: u8 }
+
+#[ensures(result <= 100)]
+fn get(p: Percentage) -> u8 { p.value }
+
+fn main() {}
+"#;
+
+#[test]
+fn test_prusti_rustc_incremental_report_dependencies() {
+    let prusti_rustc = find_executable_path("prusti-rustc");
+    // A fresh directory per run, so that no procedure is cached by an earlier run.
+    let out_dir = std::env::temp_dir().join(format!(
+        "prusti-incremental-dependencies-{}-{}",
+        std::process::id(),
+        std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_nanos()
+    ));
+    let crate_path = out_dir.join("dependencies_crate.rs");
+    let report_dir = out_dir.join("report");
+    let incremental_dir = out_dir.join("incremental");
+    fs::create_dir_all(&out_dir).unwrap();
+
+    let run = |source: &str| {
+        fs::write(&crate_path, source).unwrap();
+        let out = Command::new(&prusti_rustc)
+            .arg("--edition=2018")
+            .arg("--out-dir")
+            .arg(&out_dir)
+            .arg(&crate_path)
+            .env("RUST_BACKTRACE", "1")
+            .env(
+                "PRUSTI_REPORT_DIR",
+                report_dir.to_string_lossy().to_string(),
+            )
+            .env("PRUSTI_ENABLE_TYPE_INVARIANTS", "true")
+            .env("PRUSTI_INCREMENTAL_VERIFICATION", "true")
+            .env(
+                "PRUSTI_INCREMENTAL_DIR",
+                incremental_dir.to_string_lossy().to_string(),
+            )
+            .output()
+            .expect("failed to execute prusti-rustc");
+        assert!(
+            out.status.success(),
+            "{}",
+            String::from_utf8_lossy(&out.stderr)
+        );
+        let report = read_json(report_dir.join("dependencies_crate.prusti.json"));
+        let items = report["items"].as_array().unwrap().clone();
+        move |def_path: &str| {
+            items
+                .iter()
+                .find(|item| item["def_path"] == def_path)
+                .unwrap_or_else(|| panic!("Did not find item {def_path} in {items:?}"))["status"]
+                .clone()
+        }
+    };
+
+    let status = run(DEPENDENCIES_CRATE);
+    assert_eq!(status("dependencies_crate::caller"), "verified");
+    assert_eq!(status("dependencies_crate::get"), "verified");
+
+    let status = run(DEPENDENCIES_CRATE);
+    assert_eq!(status("dependencies_crate::caller"), "cached");
+    assert_eq!(status("dependencies_crate::get"), "cached");
+
+    // Changing the contract of a callee verifies its callers again.
+    let status = run(&DEPENDENCIES_CRATE.replace(
+        "#[ensures(result > 0)]\nfn callee()",
+        "#[ensures(result == 1)]\nfn callee()",
+    ));
+    assert_eq!(status("dependencies_crate::callee"), "verified");
+    assert_eq!(status("dependencies_crate::caller"), "verified");
+    assert_eq!(status("dependencies_crate::get"), "cached");
+
+    // Changing a type invariant verifies the procedures using the type again.
+    let status = run(&DEPENDENCIES_CRATE
+        .replace(
+            "#[ensures(result > 0)]\nfn callee()",
+            "#[ensures(result == 1)]\nfn callee()",
+        )
+        .replace("self.value <= 100", "self.value <= 50"));
+    assert_eq!(status("dependencies_crate::get"), "verified");
+    assert_eq!(status("dependencies_crate::caller"), "cached");
+
+    fs::remove_dir_all(&out_dir).unwrap();
+}
+
+#[test]
+fn test_prusti_rustc_profile() {
+    let prusti_rustc = find_executable_path("prusti-rustc");
+    let out_dir = std::env::temp_dir().join(format!("prusti-profile-{}", std::process::id()));
+    let report_dir = out_dir.join("report");
+    let log_dir = out_dir.join("log");
+
+    let out = Command::new(&prusti_rustc)
+        .arg("--edition=2018")
+        .arg("tests/report/report_crate.rs")
+        .env("RUST_BACKTRACE", "1")
+        .env(
+            "PRUSTI_REPORT_DIR",
+            report_dir.to_string_lossy().to_string(),
+        )
+        .env("PRUSTI_LOG_DIR", log_dir.to_string_lossy().to_string())
+        .env("PRUSTI_DUMP_PROFILE", "true")
+        .env("PRUSTI_USE_SMT_WRAPPER", "true")
+        .output()
+        .expect("failed to execute prusti-rustc");
+    assert!(!out.status.success());
+
+    let report = read_json(report_dir.join("report_crate.prusti.json"));
+    let items = report["items"].as_array().unwrap();
+    let verified = items
+        .iter()
+        .find(|item| item["def_path"] == "report_crate::verified")
+        .unwrap_or_else(|| panic!("Did not find item report_crate::verified in {items:?}"));
+    for field in [
+        "encoding_time_ms",
+        "fold_unfold_time_ms",
+        "verification_time_ms",
+    ] {
+        assert!(verified[field].is_u64(), "{field} is missing in {verified}");
+    }
+    assert!(
+        verified["encoding_time_ms"].as_u64() >= verified["fold_unfold_time_ms"].as_u64(),
+        "{verified}"
+    );
+    // The SMT wrapper counts the quantifier instantiations.
+    assert!(verified["quantifier_instantiations"].is_u64(), "{verified}");
+    assert!(
+        verified["vir_statements"].as_u64().unwrap() > 0,
+        "{verified}"
+    );
+    assert_eq!(verified["backends"][0], "Silicon");
+
+    let profile = fs::read_to_string(log_dir.join("profile").join("report_crate.csv")).unwrap();
+    let mut lines = profile.lines();
+    assert_eq!(
+        lines.next(),
+        Some(
+            "def_path,status,total_time_ms,encoding_time_ms,fold_unfold_time_ms,\
+            verification_time_ms,quantifier_instantiations,vir_statements,backends"
+        )
+    );
+    let rows: Vec<Vec<&str>> = lines.map(|line| line.split(',').collect()).collect();
+    let verified_row = rows
+        .iter()
+        .find(|row| row[0] == "report_crate::verified")
+        .unwrap_or_else(|| panic!("Did not find report_crate::verified in {profile}"));
+    assert_eq!(verified_row[1], "verified");
+    assert_eq!(
+        verified_row[7],
+        verified["vir_statements"].as_u64().unwrap().to_string()
+    );
+    // The slowest functions come first.
+    let total_times: Vec<u64> = rows.iter().map(|row| row[2].parse().unwrap()).collect();
+    assert!(total_times.windows(2).all(|pair| pair[0] >= pair[1]));
+
+    fs::remove_dir_all(&out_dir).unwrap();
+}
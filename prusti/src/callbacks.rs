@@ -17,6 +17,7 @@ use prusti_rustc_interface::{
         ty::TyCtxt,
     },
     session::Session,
+    span::Symbol,
 };
 
 #[derive(Default)]
@@ -99,6 +100,17 @@ impl prusti_rustc_interface::driver::Callbacks for PrustiCompilerCalls {
                 .emit();
         }
         compiler.session().abort_if_errors();
+        // Let Cargo run Prusti again when the selection of the items to verify
+        // changes, even if the crate did not.
+        for var in ["PRUSTI_VERIFY_ONLY", "PRUSTI_SKIP_VERIFICATION"] {
+            let value = std::env::var(var).ok();
+            compiler
+                .session()
+                .parse_sess
+                .env_depinfo
+                .borrow_mut()
+                .insert((Symbol::intern(var), value.as_deref().map(Symbol::intern)));
+        }
         if config::print_desugared_specs() {
             // based on the implementation of rustc_driver::pretty::print_after_parsing
             queries.global_ctxt().unwrap().enter(|tcx| {
@@ -1,13 +1,18 @@
 //! A module that invokes the verifier `prusti-viper`
 
 use log::{debug, warn};
-use prusti_common::{config, report::user};
+use prusti_common::{
+    config,
+    report::{log::report_with_writer, user},
+    utils::glob,
+};
 use prusti_interface::{
     data::{VerificationResult, VerificationTask},
     environment::Environment,
     specs::typed,
 };
-use prusti_viper::verifier::Verifier;
+use prusti_rustc_interface::span::def_id::DefId;
+use prusti_viper::{verification_report::VerificationReport, verifier::Verifier};
 
 #[tracing::instrument(name = "prusti::verify", level = "debug", skip(env))]
 pub fn verify(env: Environment<'_>, def_spec: typed::DefSpecificationMap) {
@@ -17,7 +22,9 @@ pub fn verify(env: Environment<'_>, def_spec: typed::DefSpecificationMap) {
         debug!("Prepare verification task...");
         // TODO: can we replace `get_annotated_procedures` with information
         // that is already in `def_spec`?
-        let (annotated_procedures, types) = env.get_annotated_procedures_and_types();
+        let (mut annotated_procedures, mut types) = env.get_annotated_procedures_and_types();
+        annotated_procedures.retain(|&procedure| is_selected(&env, Some(procedure)));
+        types.retain(|ty| is_selected(&env, ty.ty_adt_def().map(|adt| adt.did())));
         let verification_task = VerificationTask {
             procedures: annotated_procedures,
             types,
@@ -45,7 +52,11 @@ pub fn verify(env: Environment<'_>, def_spec: typed::DefSpecificationMap) {
 
         let verification_result =
             if verification_task.procedures.is_empty() && verification_task.types.is_empty() {
-                VerificationResult::Success
+                if let Some(report_dir) = config::report_dir() {
+                    VerificationReport::new(env.get_prusti_version(), env.name.local_crate_name())
+                        .write_to_dir(&report_dir);
+                }
+                VerificationResult::default()
             } else {
                 debug!("Dump borrow checker info...");
                 env.dump_borrowck_info(&verification_task.procedures);
@@ -54,32 +65,65 @@ pub fn verify(env: Environment<'_>, def_spec: typed::DefSpecificationMap) {
                 let verification_result = verifier.verify(&verification_task);
                 debug!("Verifier returned {:?}", verification_result);
 
+                if let Some(report_dir) = config::report_dir() {
+                    verifier.report().write_to_dir(&report_dir);
+                }
+                if config::dump_profile() {
+                    let report = verifier.report();
+                    report_with_writer("profile", format!("{}.csv", report.crate_name), |writer| {
+                        report.write_profile(writer).unwrap()
+                    });
+                }
+                if config::dump_quantifier_hot_spots() {
+                    let report = verifier.report();
+                    report_with_writer(
+                        "quantifier_hot_spots",
+                        format!("{}.csv", report.crate_name),
+                        |writer| report.write_quantifier_hot_spots(writer).unwrap(),
+                    );
+                }
+
                 verification_result
             };
 
-        match verification_result {
-            VerificationResult::Success => {
-                if env.diagnostic.has_errors() {
-                    user::message(
-                        "Verification result is inconclusive because errors \
+        if verification_result.is_success() {
+            if env.diagnostic.has_errors() {
+                user::message(
+                    "Verification result is inconclusive because errors \
                                        were encountered during encoding.",
-                    );
-                } else {
-                    user::message(format!(
-                        "Successful verification of {} items",
-                        verification_task.procedures.len()
-                    ));
-                }
-            }
-            VerificationResult::Failure => {
-                user::message("Verification failed");
-                assert!(
-                    env.diagnostic.has_errors()
-                        || config::internal_errors_as_warnings()
-                        || (config::skip_unsupported_features()
-                            && config::allow_unreachable_unsupported_code())
                 );
+            } else {
+                user::message(format!(
+                    "Successful verification of {} items",
+                    verification_task.procedures.len()
+                ));
             }
-        };
+        } else {
+            user::message("Verification failed");
+            assert!(
+                env.diagnostic.has_errors()
+                    || config::internal_errors_as_warnings()
+                    || (config::skip_unsupported_features()
+                        && config::allow_unreachable_unsupported_code())
+            );
+        }
     }
 }
+
+/// Whether the item `def_id` is selected for verification by the
+/// `VERIFY_ONLY` and `SKIP_VERIFICATION` flags. Items without a definition,
+/// such as tuple types, are selected unless `VERIFY_ONLY` is set.
+fn is_selected(env: &Environment<'_>, def_id: Option<DefId>) -> bool {
+    let verify_only = config::verify_only();
+    let Some(def_id) = def_id else {
+        return verify_only.is_empty();
+    };
+    let def_path = env.name.get_item_def_path(def_id);
+    (verify_only.is_empty()
+        || verify_only
+            .iter()
+            .any(|pattern| glob::matches_def_path(pattern, &def_path)))
+        && !config::skip_verification()
+            .iter()
+            .any(|pattern| glob::matches_def_path(pattern, &def_path))
+}
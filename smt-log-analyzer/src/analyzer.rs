@@ -26,5 +26,6 @@ fn main() -> Result<(), std::io::Error> {
         pop_scopes_by_one: false,
         trace_quantifier_triggers,
     };
-    analyze(&PathBuf::from(input_file), settings)
+    analyze(&PathBuf::from(input_file), settings)?;
+    Ok(())
 }
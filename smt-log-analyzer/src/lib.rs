@@ -13,6 +13,8 @@ use std::{
 };
 use types::{QuantifierId, BUILTIN_QUANTIFIER_ID};
 
+pub use state::QuantifierStatistics;
+
 mod error;
 mod parser;
 mod state;
@@ -32,6 +34,15 @@ pub struct Settings {
     pub trace_quantifier_triggers: Option<QuantifierId>,
 }
 
+/// A summary of an analyzed Z3 trace.
+#[derive(Debug, Default, Clone)]
+pub struct Statistics {
+    /// The number of quantifier instantiations in the trace.
+    pub quantifier_instantiations: usize,
+    /// The instantiated quantifiers, the most instantiated first.
+    pub quantifiers: Vec<QuantifierStatistics>,
+}
+
 fn process_line(settings: &Settings, state: &mut State, line: &str) -> Result<(), Error> {
     let mut parser = Parser::from_line(line);
     match parser.parse_event_kind()? {
@@ -102,18 +113,23 @@ fn process_line(settings: &Settings, state: &mut State, line: &str) -> Result<()
                 let _trigger_id = parser.parse_id()?;
                 while let Some(_variable_instantiation) = parser.try_parse_id()? {}
                 parser.consume(';')?;
+                let mut blamed_terms = Vec::new();
                 while let Some(matched_term) = parser.try_parse_quant_term()? {
                     match matched_term {
                         QuantTerm::Single(matched) => {
                             state.register_matched_trigger_term(quantifier_id, matched)?;
+                            blamed_terms.push(matched);
                         }
                         QuantTerm::Pair(original, matched) => {
                             state.register_matched_trigger_term(quantifier_id, original)?;
                             state.register_matched_trigger_term(quantifier_id, matched)?;
+                            blamed_terms.push(original);
+                            blamed_terms.push(matched);
                         }
                     }
                 }
                 parser.check_eof()?;
+                state.register_match(fingerprint, quantifier_id, &blamed_terms);
             } else {
                 state.register_matched_quantifier(BUILTIN_QUANTIFIER_ID)?;
             }
@@ -127,7 +143,18 @@ fn process_line(settings: &Settings, state: &mut State, line: &str) -> Result<()
             state.register_inst_discovered(theory)?;
         }
         EventKind::Instance => {
-            state.register_instance()?;
+            let fingerprint = parser.parse_hex_number()?;
+            let _proof_id = parser.try_parse_id()?;
+            parser.skip_whitespace();
+            let generation = if parser.try_consume(';') {
+                Some(parser.parse_number()?)
+            } else {
+                None
+            };
+            state.register_instance(fingerprint, generation)?;
+        }
+        EventKind::EndOfInstance => {
+            state.register_end_of_instance();
         }
         EventKind::Unrecognized => {}
     }
@@ -137,7 +164,7 @@ fn process_line(settings: &Settings, state: &mut State, line: &str) -> Result<()
 pub fn analyze(
     z3_trace_path: &std::path::PathBuf,
     settings: Settings,
-) -> Result<(), std::io::Error> {
+) -> Result<Statistics, std::io::Error> {
     // TODO: Collect the quantifier definitions from the smt file.
 
     let file = File::open(z3_trace_path)?;
@@ -188,5 +215,8 @@ pub fn analyze(
         settings.unique_triggers_bound,
         settings.unique_triggers_bound_total,
     );
-    Ok(())
+    Ok(Statistics {
+        quantifier_instantiations: state.quantifier_instance_count(),
+        quantifiers: state.quantifier_statistics(),
+    })
 }
@@ -12,6 +12,7 @@ pub(crate) enum EventKind {
     NewMatch,
     InstDiscovered,
     Instance,
+    EndOfInstance,
     Unrecognized,
     AttachMeaning,
     MkVar,
@@ -136,12 +137,11 @@ impl<'a> Parser<'a> {
                 "new-match" => EventKind::NewMatch,
                 "inst-discovered" => EventKind::InstDiscovered,
                 "instance" => EventKind::Instance,
+                "end-of-instance" => EventKind::EndOfInstance,
                 "attach-meaning" => EventKind::AttachMeaning,
-                "tool-version" | "attach-var-names" | "mk-proof" | "attach-enode"
-                | "end-of-instance" | "mk-lambda" | "begin-check" | "assign" | "eq-expl"
-                | "decide-and-or" | "resolve-lit" | "resolve-process" | "conflict" | "eof" => {
-                    EventKind::Unrecognized
-                }
+                "tool-version" | "attach-var-names" | "mk-proof" | "attach-enode" | "mk-lambda"
+                | "begin-check" | "assign" | "eq-expl" | "decide-and-or" | "resolve-lit"
+                | "resolve-process" | "conflict" | "eof" => EventKind::Unrecognized,
                 x => unimplemented!("got: {:?}", x),
             };
             self.consume(']')?;
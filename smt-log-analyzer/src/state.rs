@@ -3,7 +3,7 @@ use csv::Writer;
 use crate::{
     error::Error,
     parser::TheoryKind,
-    types::{Level, QuantifierId, TermId, BUILTIN_QUANTIFIER_ID},
+    types::{Fingerprint, Level, QuantifierId, TermId, BUILTIN_QUANTIFIER_ID},
 };
 use rustc_hash::{FxHashMap, FxHashSet};
 use std::fmt::Write;
@@ -57,6 +57,48 @@ impl QuantifierInstanceEvent {
     }
 }
 
+#[derive(Debug, Default)]
+/// The instantiations of a quantifier (ignoring push/pop).
+struct QuantifierInstances {
+    count: usize,
+    /// How many instantiations were triggered by a term created by another
+    /// instantiation of the same quantifier.
+    self_triggered: usize,
+    /// The largest generation of an instantiation. The generation of terms
+    /// in the input is 0 and an instantiation has the generation of the
+    /// largest generation of its triggering terms plus one.
+    max_generation: u32,
+}
+
+/// The instantiations of a single quantifier in a trace.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct QuantifierStatistics {
+    /// The name of the quantifier as given by the SMT solver's input.
+    pub name: String,
+    pub instantiations: usize,
+    /// How many instantiations were triggered by a term created by another
+    /// instantiation of the same quantifier.
+    pub self_triggered: usize,
+    /// The largest generation of an instantiation.
+    pub max_generation: u32,
+}
+
+impl QuantifierStatistics {
+    /// Whether the instantiations of the quantifier look like a matching
+    /// loop: most of them were triggered by the quantifier itself and they
+    /// formed long chains.
+    pub fn is_matching_loop(&self) -> bool {
+        self.self_triggered * 2 > self.instantiations && self.max_generation >= 10
+    }
+
+    /// Merges the statistics of the same quantifier in another trace.
+    pub fn merge(&mut self, other: &Self) {
+        self.instantiations += other.instantiations;
+        self.self_triggered += other.self_triggered;
+        self.max_generation = self.max_generation.max(other.max_generation);
+    }
+}
+
 #[derive(Debug, Clone)]
 /// The basic block label was visited event.
 pub(crate) struct BasicBlockVisitedEvent {
@@ -116,6 +158,14 @@ pub(crate) struct State {
     current_active_scopes_count: Level,
     traced_quantifier: Option<QuantifierId>,
     traced_quantifier_triggers: Option<String>,
+    /// The matched quantifier of each match fingerprint and whether the match
+    /// was triggered by a term created by the same quantifier.
+    matches: FxHashMap<Fingerprint, (QuantifierId, bool)>,
+    /// The quantifier whose instance is currently being logged.
+    current_instance: Option<QuantifierId>,
+    /// The quantifier whose instantiation created the term.
+    term_creators: FxHashMap<TermId, QuantifierId>,
+    quantifier_instances: FxHashMap<QuantifierId, QuantifierInstances>,
 }
 
 impl State {
@@ -259,7 +309,41 @@ impl State {
         }
     }
 
-    pub(crate) fn register_instance(&mut self) -> Result<(), Error> {
+    pub(crate) fn quantifier_instance_count(&self) -> usize {
+        self.total_quantifiers_instance_counters
+    }
+
+    pub(crate) fn register_match(
+        &mut self,
+        fingerprint: Fingerprint,
+        quantifier_id: QuantifierId,
+        blamed_terms: &[TermId],
+    ) {
+        let self_triggered = blamed_terms
+            .iter()
+            .any(|term_id| self.term_creators.get(term_id) == Some(&quantifier_id));
+        self.matches
+            .insert(fingerprint, (quantifier_id, self_triggered));
+    }
+
+    pub(crate) fn register_instance(
+        &mut self,
+        fingerprint: Fingerprint,
+        generation: Option<u32>,
+    ) -> Result<(), Error> {
+        // Instances of built-in quantifiers and theory lemmas have no match.
+        self.current_instance = None;
+        if let Some(&(quantifier_id, self_triggered)) = self.matches.get(&fingerprint) {
+            self.current_instance = Some(quantifier_id);
+            let instances = self.quantifier_instances.entry(quantifier_id).or_default();
+            instances.count += 1;
+            if self_triggered {
+                instances.self_triggered += 1;
+            }
+            if let Some(generation) = generation {
+                instances.max_generation = instances.max_generation.max(generation);
+            }
+        }
         self.total_quantifiers_instance_counters += 1;
         let events = &mut self.quantifiers_instance_events;
         if let Some(last) = events.last_mut() {
@@ -274,12 +358,44 @@ impl State {
         Ok(())
     }
 
+    pub(crate) fn register_end_of_instance(&mut self) {
+        self.current_instance = None;
+    }
+
+    /// The statistics of the instantiated quantifiers, the most instantiated
+    /// first.
+    pub(crate) fn quantifier_statistics(&self) -> Vec<QuantifierStatistics> {
+        let mut statistics: Vec<_> = self
+            .quantifier_instances
+            .iter()
+            .map(|(quantifier_id, instances)| QuantifierStatistics {
+                name: self.quantifiers[quantifier_id].name.clone(),
+                instantiations: instances.count,
+                self_triggered: instances.self_triggered,
+                max_generation: instances.max_generation,
+            })
+            .collect();
+        statistics.sort_by(|a, b| {
+            b.instantiations
+                .cmp(&a.instantiations)
+                .then_with(|| a.name.cmp(&b.name))
+        });
+        statistics
+    }
+
     pub(crate) fn register_term_function_application(
         &mut self,
         term_id: TermId,
         name: String,
         args: Vec<TermId>,
     ) {
+        // Z3 logs the terms created by an instantiation between its
+        // [instance] and [end-of-instance] lines.
+        if let Some(quantifier_id) = self.current_instance {
+            self.term_creators.insert(term_id, quantifier_id);
+        } else {
+            self.term_creators.remove(&term_id);
+        }
         self.terms
             .insert(term_id, Term::FunctionApplication { name, args });
     }
@@ -657,6 +773,31 @@ impl State {
             }
         }
 
+        {
+            // [instance] – The quantifiers ranked by their instantiations.
+            let mut writer = Writer::from_path(format!("{input_file}.hot-spots.csv")).unwrap();
+            writer
+                .write_record([
+                    "Quantifier Name",
+                    "Instances",
+                    "Self-triggered Instances",
+                    "Max Generation",
+                    "Matching Loop?",
+                ])
+                .unwrap();
+            for statistics in self.quantifier_statistics() {
+                writer
+                    .write_record([
+                        &statistics.name,
+                        &statistics.instantiations.to_string(),
+                        &statistics.self_triggered.to_string(),
+                        &statistics.max_generation.to_string(),
+                        &statistics.is_matching_loop().to_string(),
+                    ])
+                    .unwrap();
+            }
+        }
+
         if let Some(quantifier_id) = self.traced_quantifier {
             let mut file = std::fs::File::create(format!(
                 "{input_file}.quantifier-{quantifier_id}-triggers.csv"
@@ -779,3 +920,106 @@ impl State {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{process_line, Settings};
+
+    /// Analyzes the lines of a Z3 trace, which end with a newline like the
+    /// lines read from a file.
+    fn analyze_trace(trace: &[&str]) -> State {
+        let settings = Settings {
+            write_statistics: false,
+            quantifier_instantiations_ignore_builtin: false,
+            quantifier_instantiations_bound_global_kind: None,
+            quantifier_instantiations_bound_trace: None,
+            quantifier_instantiations_bound_trace_kind: None,
+            unique_triggers_bound: None,
+            unique_triggers_bound_total: None,
+            check_active_scopes_count: None,
+            pop_scopes_by_one: false,
+            trace_quantifier_triggers: None,
+        };
+        let mut state = State::default();
+        state.register_quantifier(BUILTIN_QUANTIFIER_ID, "builtin quantifier".to_string());
+        for line in trace {
+            process_line(&settings, &mut state, &format!("{line}\n")).unwrap();
+        }
+        state
+    }
+
+    #[test]
+    fn test_quantifier_statistics() {
+        let state = analyze_trace(&[
+            "[mk-quant] #10 prog.l5 1 #9 #8",
+            "[mk-quant] #20 $Seq.append 1 #19 #18",
+            "[mk-app] #1 f",
+            "[new-match] 0x1 #10 #9 #1 ; #1",
+            "[instance] 0x1 ; 1",
+            "[mk-app] #2 f #1",
+            "[end-of-instance]",
+            "[new-match] 0x2 #10 #9 #2 ; #2",
+            "[instance] 0x2 ; 2",
+            "[mk-app] #3 f #2",
+            "[end-of-instance]",
+            "[new-match] 0x3 #10 #9 #3 ; (#1 #3)",
+            "[instance] 0x3 ; 3",
+            "[end-of-instance]",
+            "[new-match] 0x4 #20 #19 #3 ; #3",
+            "[instance] 0x4 ; 4",
+            "[end-of-instance]",
+        ]);
+        assert_eq!(state.quantifier_instance_count(), 4);
+        assert_eq!(
+            state.quantifier_statistics(),
+            vec![
+                QuantifierStatistics {
+                    name: "prog.l5".to_string(),
+                    instantiations: 3,
+                    self_triggered: 2,
+                    max_generation: 3,
+                },
+                QuantifierStatistics {
+                    name: "$Seq.append".to_string(),
+                    instantiations: 1,
+                    self_triggered: 0,
+                    max_generation: 4,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_quantifier_statistics_ignore_builtin_quantifiers() {
+        let state = analyze_trace(&[
+            "[mk-quant] #10 prog.l5 1 #9 #8",
+            "[new-match] 0x0 #10 #9 #1 ; #1",
+            "[instance] 0x0 ; 1",
+            "[end-of-instance]",
+        ]);
+        assert_eq!(state.quantifier_instance_count(), 1);
+        assert!(state.quantifier_statistics().is_empty());
+    }
+
+    #[test]
+    fn test_matching_loop() {
+        let mut statistics = QuantifierStatistics {
+            name: "prog.l5".to_string(),
+            instantiations: 20,
+            self_triggered: 15,
+            max_generation: 9,
+        };
+        assert!(!statistics.is_matching_loop());
+        statistics.merge(&QuantifierStatistics {
+            name: "prog.l5".to_string(),
+            instantiations: 10,
+            self_triggered: 1,
+            max_generation: 12,
+        });
+        assert_eq!(statistics.instantiations, 30);
+        assert_eq!(statistics.self_triggered, 16);
+        assert_eq!(statistics.max_generation, 12);
+        assert!(statistics.is_matching_loop());
+    }
+}
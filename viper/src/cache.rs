@@ -4,127 +4,483 @@
 // License, v. 2.0. If a copy of the MPL was not distributed with this
 // file, You can obtain one at http://mozilla.org/MPL/2.0/.
 
+//! A verification cache that can be shared by concurrent processes.
+//!
+//! The cache is a directory with one file per entry, named after the hash of
+//! the verification request. Entries are written to a temporary file that is
+//! then atomically renamed, so that readers never observe partially written
+//! entries and concurrent writers of the same entry cannot corrupt it. The
+//! entries of each version of Prusti and Viper are kept in a separate
+//! subdirectory. Only the eviction of entries takes a file lock, so that at
+//! most one process at a time scans and prunes the directory.
+
 use log::{error, info, warn};
 
 use crate::verification_result::VerificationResult;
-use rustc_hash::FxHashMap;
+use fs2::FileExt;
+use rustc_hash::{FxHashMap, FxHashSet, FxHasher};
 use std::{
-    fs, io,
+    fs,
+    hash::{Hash, Hasher},
+    io::{self, Read, Seek, Write},
     ops::DerefMut,
     path::{Path, PathBuf},
     sync::{Arc, Mutex},
+    time::{Duration, SystemTime, UNIX_EPOCH},
 };
 
 pub trait Cache {
-    fn get(&self, request: u64) -> Option<VerificationResult>;
+    fn get(&mut self, request: u64) -> Option<VerificationResult>;
     fn insert(self, request: u64, result: VerificationResult) -> Option<VerificationResult>;
 }
-// We can change the representation here (e.g. adding fields):
-#[derive(Debug, Clone)]
+
+/// Bounds on the entries kept in a [`PersistentCache`]. Entries exceeding
+/// them are evicted when the cache is pruned or saved.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CacheLimits {
+    /// Maximal total size (in bytes) of the serialized entries. When
+    /// exceeded, the least recently used entries are evicted first. `0` means
+    /// unlimited.
+    pub max_size: u64,
+    /// Maximal time since the last use of an entry. `None` means unlimited.
+    pub max_age: Option<Duration>,
+}
+
+impl CacheLimits {
+    fn is_unlimited(&self) -> bool {
+        self.max_size == 0 && self.max_age.is_none()
+    }
+}
+
+/// A cached verification result together with its bookkeeping data.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+struct CacheEntry {
+    result: VerificationResult,
+    /// Creation time, in seconds since the UNIX epoch.
+    created: u64,
+    /// Time of the last lookup, in seconds since the UNIX epoch.
+    last_used: u64,
+}
+
+/// A summary of a cache entry, as shown when listing the cache.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct CacheEntryInfo {
+    /// The hash of the verification request.
+    pub hash: u64,
+    /// The name of the verified program.
+    pub item_name: String,
+    /// Whether the cached result is a successful verification.
+    pub success: bool,
+    /// The size (in bytes) of the serialized entry.
+    pub size: u64,
+    /// Creation time, in seconds since the UNIX epoch.
+    pub created: u64,
+    /// Time of the last lookup, in seconds since the UNIX epoch.
+    pub last_used: u64,
+}
+
+#[derive(Debug)]
 pub struct PersistentCache {
-    updated: bool,
-    load_loc: PathBuf,
-    data: FxHashMap<u64, VerificationResult>,
+    /// The directory holding the entries of the current version, or `None` if
+    /// the cache is only kept in memory.
+    dir: Option<PathBuf>,
+    limits: CacheLimits,
+    /// The entries that were inserted or looked up by this process.
+    data: FxHashMap<u64, CacheEntry>,
+    /// The entries whose last use was not yet written to disk.
+    touched: FxHashSet<u64>,
 }
 
-const RESULT_CACHE_VERSION: u64 = 4;
+const RESULT_CACHE_VERSION: u64 = 7;
+
+/// Temporary files older than this were left behind by crashed processes.
+const STALE_TEMPORARY_FILE_AGE: Duration = Duration::from_secs(3600);
 
-#[derive(Debug, serde::Serialize, serde::Deserialize)]
-struct ResultCache {
-    data: FxHashMap<u64, VerificationResult>,
-    version: u64,
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or_default()
 }
 
-impl From<(PathBuf, ResultCache)> for PersistentCache {
-    /// Used when loading cache from disk
-    fn from((load_loc, rc): (PathBuf, ResultCache)) -> Self {
-        assert_eq!(rc.version, RESULT_CACHE_VERSION);
-        let data = rc.data;
-        PersistentCache {
-            updated: false,
-            load_loc,
-            data,
+fn entry_size(entry: &CacheEntry) -> u64 {
+    bincode::serialized_size(entry).unwrap_or_default()
+}
+
+fn entry_path(dir: &Path, request: u64) -> PathBuf {
+    dir.join(format!("{request:016x}.bin"))
+}
+
+fn read_entry(path: &Path) -> Option<CacheEntry> {
+    let f = fs::File::open(path).ok()?;
+    match bincode::deserialize_from(io::BufReader::new(f)) {
+        Ok(entry) => Some(entry),
+        Err(e) => {
+            error!("Failed to read cache entry \"{}\": {e}", path.display());
+            None
         }
     }
 }
-impl From<&PersistentCache> for ResultCache {
-    /// Used when saving cache to disk
-    fn from(cache: &PersistentCache) -> Self {
-        ResultCache {
-            data: cache.data.clone(),
-            version: RESULT_CACHE_VERSION,
+
+/// Atomically replaces the entry in `dir`.
+fn write_entry(dir: &Path, request: u64, entry: &CacheEntry) {
+    let tmp_path = dir.join(format!(".{request:016x}.{}.tmp", uuid::Uuid::new_v4()));
+    let result = fs::create_dir_all(dir)
+        .and_then(|()| {
+            let mut writer = io::BufWriter::new(fs::File::create(&tmp_path)?);
+            bincode::serialize_into(&mut writer, entry)
+                .map_err(|e| io::Error::new(io::ErrorKind::Other, e))?;
+            writer.flush()?;
+            writer.into_inner()?.sync_all()
+        })
+        .and_then(|()| fs::rename(&tmp_path, entry_path(dir, request)));
+    if let Err(e) = result {
+        error!("Failed to write cache entry to \"{}\": {e}", dir.display());
+        let _ = fs::remove_file(&tmp_path);
+    }
+}
+
+/// Reads all entries stored in `dir`, together with their size.
+fn read_entries(dir: &Path) -> Vec<(u64, CacheEntry, u64)> {
+    let Ok(dir_entries) = fs::read_dir(dir) else {
+        return vec![];
+    };
+    dir_entries
+        .filter_map(|dir_entry| {
+            let path = dir_entry.ok()?.path();
+            let file_stem = path.file_stem()?.to_str()?;
+            if path.extension()? != "bin" {
+                return None;
+            }
+            let request = u64::from_str_radix(file_stem, 16).ok()?;
+            let entry = read_entry(&path)?;
+            let size = entry_size(&entry);
+            Some((request, entry, size))
+        })
+        .collect()
+}
+
+/// The request, time of the last use and size (in bytes) of the entries stored
+/// in `dir`. Only the file metadata is read: the entries are rewritten
+/// whenever they are used, so their modification time is their last use.
+fn entry_files(dir: &Path) -> Vec<(u64, u64, u64)> {
+    let Ok(dir_entries) = fs::read_dir(dir) else {
+        return vec![];
+    };
+    dir_entries
+        .filter_map(|dir_entry| {
+            let dir_entry = dir_entry.ok()?;
+            let path = dir_entry.path();
+            if path.extension()? != "bin" {
+                return None;
+            }
+            let request = u64::from_str_radix(path.file_stem()?.to_str()?, 16).ok()?;
+            let metadata = dir_entry.metadata().ok()?;
+            let last_used = metadata
+                .modified()
+                .ok()?
+                .duration_since(UNIX_EPOCH)
+                .ok()?
+                .as_secs();
+            Some((request, last_used, metadata.len()))
+        })
+        .collect()
+}
+
+/// The name of the directory holding the entries of the version whose stamp
+/// has the hash `version_hash`.
+fn entries_dir_name(version_hash: u64) -> String {
+    format!("v{RESULT_CACHE_VERSION}-{version_hash:016x}")
+}
+
+/// Whether `path` is a directory of entries, named by [`entries_dir_name`].
+/// Other files next to the entries directories are never removed.
+fn is_entries_dir(path: &Path) -> bool {
+    let prefix = format!("v{RESULT_CACHE_VERSION}-");
+    path.is_dir()
+        && path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .and_then(|name| name.strip_prefix(&prefix))
+            .map_or(false, |hash| {
+                hash.len() == 16 && hash.bytes().all(|byte| byte.is_ascii_hexdigit())
+            })
+}
+
+/// Whether `path` is a cache file of an older version of Prusti, which stored
+/// the whole cache in one file. It contains a map of results serialized with
+/// `bincode`, which starts with the number of entries, followed by the
+/// version of the cache format.
+fn is_old_cache_file(path: &Path) -> bool {
+    let read_header_and_version = || -> io::Result<(u64, u64, u64)> {
+        let mut file = fs::File::open(path)?;
+        let len = file.metadata()?.len();
+        let mut word = [0; 8];
+        file.read_exact(&mut word)?;
+        let count = u64::from_le_bytes(word);
+        file.seek(io::SeekFrom::End(-8))?;
+        file.read_exact(&mut word)?;
+        Ok((len, count, u64::from_le_bytes(word)))
+    };
+    match read_header_and_version() {
+        Ok((len, count, version)) => {
+            len >= 16 && (1..RESULT_CACHE_VERSION).contains(&version) && (count == 0) == (len == 16)
         }
+        Err(_) => false,
     }
 }
 
+/// Counts the entries in `dir` and removes the directory.
+fn remove_entries_dir(dir: &Path) -> usize {
+    let count = fs::read_dir(dir)
+        .map(|dir_entries| {
+            dir_entries
+                .filter_map(|dir_entry| dir_entry.ok())
+                .filter(|dir_entry| {
+                    dir_entry
+                        .path()
+                        .extension()
+                        .map_or(false, |ext| ext == "bin")
+                })
+                .count()
+        })
+        .unwrap_or_default();
+    if let Err(e) = fs::remove_dir_all(dir) {
+        error!(
+            "Failed to remove cache directory \"{}\": {e}",
+            dir.display()
+        );
+    }
+    count
+}
+
 impl PersistentCache {
+    /// Opens the cache in the directory `cache_loc`. The entries are stored in
+    /// a subdirectory specific to `version_stamp`, so that results of other
+    /// versions of Prusti or Viper are never used. An empty path keeps the
+    /// cache only in memory.
     #[tracing::instrument(level = "debug")]
-    pub fn load_cache(cache_loc: PathBuf) -> Self {
-        let mut data_res: Option<ResultCache> = None;
-        if !cache_loc.as_os_str().is_empty() {
-            if let Ok(f) = fs::File::open(&cache_loc) {
-                match bincode::deserialize_from::<_, ResultCache>(&mut io::BufReader::new(f)) {
-                    Ok(data) => {
-                        if data.version == RESULT_CACHE_VERSION {
-                            info!("Loaded cache from \"{}\"", cache_loc.display());
-                            data_res = Some(data);
-                        } else {
-                            error!(
-                                "Cache version mismatch when reading from \"{}\": \
-                                    expected={} actual={}",
-                                cache_loc.display(),
-                                RESULT_CACHE_VERSION,
-                                data.version
-                            )
-                        }
+    pub fn load_cache(cache_loc: PathBuf, version_stamp: String, limits: CacheLimits) -> Self {
+        let dir = if cache_loc.as_os_str().is_empty() {
+            None
+        } else {
+            if cache_loc.is_file() {
+                if is_old_cache_file(&cache_loc) {
+                    info!(
+                        "Removing the cache file \"{}\" of an older version of Prusti",
+                        cache_loc.display()
+                    );
+                    if let Err(e) = fs::remove_file(&cache_loc) {
+                        error!("Failed to remove old cache file: {e}");
                     }
-                    Err(e) => error!("Failed to read cache from \"{}\": {e}", cache_loc.display()),
+                } else {
+                    warn!(
+                        "The cache path \"{}\" is a file that is not a cache. Using an \
+                            in-memory cache.",
+                        cache_loc.display()
+                    );
+                }
+            }
+            if cache_loc.is_file() {
+                None
+            } else {
+                let mut hasher = FxHasher::default();
+                version_stamp.hash(&mut hasher);
+                let dir = cache_loc.join(entries_dir_name(hasher.finish()));
+                info!("Using cache at \"{}\"", dir.display());
+                Some(dir)
+            }
+        };
+        PersistentCache {
+            dir,
+            limits,
+            data: FxHashMap::default(),
+            touched: FxHashSet::default(),
+        }
+    }
+
+    /// Writes the last use of the looked up entries to disk and evicts the
+    /// entries that exceed the limits of the cache.
+    pub fn save(&mut self) {
+        self.write_touched_entries();
+        if !self.limits.is_unlimited() {
+            self.evict();
+        }
+    }
+
+    /// Summaries of all entries, the most recently used first.
+    pub fn entries(&self) -> Vec<CacheEntryInfo> {
+        let stored_entries = match &self.dir {
+            Some(dir) => read_entries(dir),
+            None => self
+                .data
+                .iter()
+                .map(|(&request, entry)| (request, entry.clone(), entry_size(entry)))
+                .collect(),
+        };
+        let mut entries: Vec<_> = stored_entries
+            .into_iter()
+            .map(|(hash, entry, size)| {
+                // This process might have used the entry more recently.
+                let last_used = self
+                    .data
+                    .get(&hash)
+                    .map_or(entry.last_used, |own| own.last_used.max(entry.last_used));
+                CacheEntryInfo {
+                    hash,
+                    item_name: entry.result.item_name,
+                    success: entry.result.kind.is_success(),
+                    size,
+                    created: entry.created,
+                    last_used,
+                }
+            })
+            .collect();
+        entries.sort_by(|a, b| b.last_used.cmp(&a.last_used).then(a.hash.cmp(&b.hash)));
+        entries
+    }
+
+    /// Evicts the entries that exceed the limits of the cache, together with
+    /// the entries of other versions of Prusti or Viper. Returns the number of
+    /// evicted entries.
+    pub fn prune(&mut self) -> usize {
+        self.write_touched_entries();
+        let mut evicted = self.evict();
+        let Some(dir) = &self.dir else {
+            return evicted;
+        };
+        let _lock = self.lock();
+        let now = SystemTime::now();
+        if let Ok(dir_entries) = fs::read_dir(dir.parent().unwrap()) {
+            for dir_entry in dir_entries.filter_map(|dir_entry| dir_entry.ok()) {
+                let path = dir_entry.path();
+                if is_entries_dir(&path) && &path != dir {
+                    evicted += remove_entries_dir(&path);
                 }
             }
         }
-        PersistentCache::from((
-            cache_loc,
-            data_res.unwrap_or_else(|| {
-                info!("Cache file doesn't exist or is invalid. Using fresh cache.");
-                ResultCache {
-                    data: FxHashMap::default(),
-                    version: RESULT_CACHE_VERSION,
+        // Remove the temporary files of crashed processes.
+        if let Ok(dir_entries) = fs::read_dir(dir) {
+            for dir_entry in dir_entries.filter_map(|dir_entry| dir_entry.ok()) {
+                let path = dir_entry.path();
+                let is_stale = dir_entry
+                    .metadata()
+                    .and_then(|metadata| metadata.modified())
+                    .ok()
+                    .and_then(|modified| now.duration_since(modified).ok())
+                    .map_or(false, |age| age > STALE_TEMPORARY_FILE_AGE);
+                if path.extension().map_or(false, |ext| ext == "tmp") && is_stale {
+                    let _ = fs::remove_file(path);
                 }
-            }),
-        ))
+            }
+        }
+        evicted
     }
-    #[tracing::instrument(level = "debug")]
-    pub fn save_cache(&self, cache_loc: &Path) {
-        match fs::File::create(cache_loc) {
-            Ok(f) => {
-                info!("Saving cache to \"{}\"", cache_loc.display());
-                let mut cache_buffer = io::BufWriter::new(f);
-                bincode::serialize_into(&mut cache_buffer, &ResultCache::from(self))
-                    .unwrap_or_else(|e| error!("Failed to serialize the cache: {e}"));
-                match cache_buffer.into_inner() {
-                    Err(e) => error!("Failed to flush the cache file: {e}"),
-                    Ok(f) => f
-                        .sync_all()
-                        .unwrap_or_else(|e| error!("Failed to sync the cache file to disk: {e}")),
+
+    /// Removes all entries, including those of other versions of Prusti or
+    /// Viper. Returns the number of removed entries.
+    pub fn clear(&mut self) -> usize {
+        let mut removed = self.data.len();
+        self.data.clear();
+        self.touched.clear();
+        if let Some(dir) = &self.dir {
+            let _lock = self.lock();
+            removed = 0;
+            if let Ok(dir_entries) = fs::read_dir(dir.parent().unwrap()) {
+                for dir_entry in dir_entries.filter_map(|dir_entry| dir_entry.ok()) {
+                    let path = dir_entry.path();
+                    if is_entries_dir(&path) {
+                        removed += remove_entries_dir(&path);
+                    }
                 }
             }
-            Err(e) => error!("Failed to create cache file: {e}"),
         }
+        removed
     }
-    pub fn save(&mut self) {
-        // Save cache to disk, if changed and save path is valid
-        if self.updated {
-            if self.load_loc.as_os_str().is_empty() {
-                warn!("Cannot save cache, because the cache path is empty.")
-            } else {
-                let mut save_dir = self.load_loc.clone();
-                save_dir.pop();
-                match fs::create_dir_all(&save_dir) {
-                    Ok(()) => self.save_cache(&self.load_loc),
-                    Err(e) => error!("Failed to create cache dir: {e}"),
+
+    fn write_touched_entries(&mut self) {
+        if let Some(dir) = &self.dir {
+            for request in self.touched.drain() {
+                write_entry(dir, request, &self.data[&request]);
+            }
+        }
+    }
+
+    /// Evicts the entries of the current version that exceed the limits of
+    /// the cache. Returns the number of evicted entries.
+    fn evict(&mut self) -> usize {
+        let _lock = self.lock();
+        let mut entries: Vec<_> = match &self.dir {
+            Some(dir) => entry_files(dir)
+                .into_iter()
+                .map(|(request, last_used, size)| (last_used, request, size))
+                .collect(),
+            None => self
+                .data
+                .iter()
+                .map(|(&request, entry)| (entry.last_used, request, entry_size(entry)))
+                .collect(),
+        };
+        let mut evicted = vec![];
+        if let Some(max_age) = self.limits.max_age {
+            let oldest = now().saturating_sub(max_age.as_secs());
+            entries.retain(|&(last_used, request, _)| {
+                if last_used < oldest {
+                    evicted.push(request);
+                }
+                last_used >= oldest
+            });
+        }
+        if self.limits.max_size > 0 {
+            let mut total_size: u64 = entries.iter().map(|&(_, _, size)| size).sum();
+            // Evict the least recently used entries first.
+            entries.sort_unstable();
+            for (_, request, size) in entries {
+                if total_size <= self.limits.max_size {
+                    break;
+                }
+                evicted.push(request);
+                total_size -= size;
+            }
+        }
+        for request in &evicted {
+            self.data.remove(request);
+            self.touched.remove(request);
+            if let Some(dir) = &self.dir {
+                match fs::remove_file(entry_path(dir, *request)) {
+                    Err(e) if e.kind() != io::ErrorKind::NotFound => {
+                        error!("Failed to remove cache entry {request:016x}: {e}")
+                    }
+                    _ => {}
                 }
             }
         }
+        if !evicted.is_empty() {
+            info!("Evicted {} entries from the cache", evicted.len());
+        }
+        evicted.len()
+    }
+
+    /// Takes the lock of the cache directory, which is released when the
+    /// returned file is dropped.
+    fn lock(&self) -> Option<fs::File> {
+        let root = self.dir.as_ref()?.parent()?;
+        let lock_path = root.join(".lock");
+        let lock_file = fs::create_dir_all(root)
+            .and_then(|()| {
+                fs::OpenOptions::new()
+                    .create(true)
+                    .write(true)
+                    .open(&lock_path)
+            })
+            .and_then(|lock_file| lock_file.lock_exclusive().map(|()| lock_file));
+        match lock_file {
+            Ok(lock_file) => Some(lock_file),
+            Err(e) => {
+                warn!("Failed to lock \"{}\": {e}", lock_path.display());
+                None
+            }
+        }
     }
 }
 
@@ -135,17 +491,34 @@ impl Drop for PersistentCache {
 }
 
 impl Cache for &mut PersistentCache {
-    fn get(&self, request: u64) -> Option<VerificationResult> {
-        self.data.get(&request).cloned()
+    fn get(&mut self, request: u64) -> Option<VerificationResult> {
+        if !self.data.contains_key(&request) {
+            // The entry might have been stored by another process.
+            let entry = read_entry(&entry_path(self.dir.as_ref()?, request))?;
+            self.data.insert(request, entry);
+        }
+        let entry = self.data.get_mut(&request).unwrap();
+        entry.last_used = now();
+        self.touched.insert(request);
+        Some(entry.result.clone())
     }
     fn insert(self, request: u64, result: VerificationResult) -> Option<VerificationResult> {
-        self.updated = true;
-        self.data.insert(request, result)
+        let now = now();
+        let entry = CacheEntry {
+            result,
+            created: now,
+            last_used: now,
+        };
+        if let Some(dir) = &self.dir {
+            write_entry(dir, request, &entry);
+        }
+        self.touched.remove(&request);
+        self.data.insert(request, entry).map(|entry| entry.result)
     }
 }
 
 impl Cache for &Arc<Mutex<PersistentCache>> {
-    fn get(&self, request: u64) -> Option<VerificationResult> {
+    fn get(&mut self, request: u64) -> Option<VerificationResult> {
         let mut cache = self.lock().unwrap();
         cache.deref_mut().get(request)
     }
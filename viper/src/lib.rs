@@ -28,3 +28,4 @@ pub use crate::{
     verification_backend::*, verification_context::*, verification_result::*, verifier::*,
     viper::*,
 };
+pub use smt_log_analyzer::QuantifierStatistics;
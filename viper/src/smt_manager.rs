@@ -1,4 +1,5 @@
 use futures::FutureExt;
+use smt_log_analyzer::Statistics;
 use std::{
     path::PathBuf,
     thread::{self, JoinHandle},
@@ -106,38 +107,71 @@ impl SmtManager {
         self.connection.as_ref().unwrap().port
     }
 
-    pub fn stop_and_check(&mut self) {
+    /// Stops listening for SMT solver connections without analyzing their
+    /// traces, e.g. because the verification was interrupted.
+    pub fn stop(&mut self) {
         if let Some(connection) = self.connection.take() {
-            connection.termination_shot.send(()).unwrap();
-            let trace_files = connection.smt_listener.join().unwrap();
-            for trace_file in trace_files {
-                let trace_file = PathBuf::from(trace_file);
-                // Since Silicon kills the SMT solver, there is no guarantee
-                // that the scopes will be fully popped.
-                let expected_scopes_count = None;
-                let settings = smt_log_analyzer::Settings {
-                    write_statistics: self.write_smt_statistics,
-                    quantifier_instantiations_ignore_builtin: self
-                        .quantifier_instantiations_ignore_builtin,
-                    quantifier_instantiations_bound_global_kind: self
-                        .quantifier_instantiations_bound_global_kind,
-                    quantifier_instantiations_bound_trace: self
-                        .quantifier_instantiations_bound_trace,
-                    quantifier_instantiations_bound_trace_kind: self
-                        .quantifier_instantiations_bound_trace_kind,
-                    unique_triggers_bound: self.unique_triggers_bound,
-                    unique_triggers_bound_total: self.unique_triggers_bound_total,
-                    check_active_scopes_count: expected_scopes_count,
-                    pop_scopes_by_one: false,
-                    trace_quantifier_triggers: None,
-                };
-                smt_log_analyzer::analyze(&trace_file, settings).unwrap();
-                if !self.preserve_trace_files {
-                    std::fs::remove_file(trace_file).unwrap();
-                }
+            let _ = connection.termination_shot.send(());
+            let _ = connection.smt_listener.join();
+        }
+    }
+
+    /// Stops listening for SMT solver connections and analyzes their traces.
+    /// Returns the statistics of all traces together, if the SMT solver was
+    /// connected through the wrapper.
+    pub fn stop_and_check(&mut self) -> Option<Statistics> {
+        let connection = self.connection.take()?;
+        connection.termination_shot.send(()).unwrap();
+        let trace_files = connection.smt_listener.join().unwrap();
+        let mut total = Statistics::default();
+        for trace_file in trace_files {
+            let trace_file = PathBuf::from(trace_file);
+            // Since Silicon kills the SMT solver, there is no guarantee
+            // that the scopes will be fully popped.
+            let expected_scopes_count = None;
+            let settings = smt_log_analyzer::Settings {
+                write_statistics: self.write_smt_statistics,
+                quantifier_instantiations_ignore_builtin: self
+                    .quantifier_instantiations_ignore_builtin,
+                quantifier_instantiations_bound_global_kind: self
+                    .quantifier_instantiations_bound_global_kind,
+                quantifier_instantiations_bound_trace: self.quantifier_instantiations_bound_trace,
+                quantifier_instantiations_bound_trace_kind: self
+                    .quantifier_instantiations_bound_trace_kind,
+                unique_triggers_bound: self.unique_triggers_bound,
+                unique_triggers_bound_total: self.unique_triggers_bound_total,
+                check_active_scopes_count: expected_scopes_count,
+                pop_scopes_by_one: false,
+                trace_quantifier_triggers: None,
+            };
+            let statistics = smt_log_analyzer::analyze(&trace_file, settings).unwrap();
+            merge_statistics(&mut total, statistics);
+            if !self.preserve_trace_files {
+                std::fs::remove_file(trace_file).unwrap();
             }
         }
+        Some(total)
+    }
+}
+
+/// Adds the statistics of another trace to `total`, merging the statistics
+/// of the quantifiers with the same name.
+fn merge_statistics(total: &mut Statistics, statistics: Statistics) {
+    total.quantifier_instantiations += statistics.quantifier_instantiations;
+    for quantifier in statistics.quantifiers {
+        if let Some(existing) = total
+            .quantifiers
+            .iter_mut()
+            .find(|existing| existing.name == quantifier.name)
+        {
+            existing.merge(&quantifier);
+        } else {
+            total.quantifiers.push(quantifier);
+        }
     }
+    total
+        .quantifiers
+        .sort_by(|a, b| b.instantiations.cmp(&a.instantiations));
 }
 
 impl Drop for SmtManager {
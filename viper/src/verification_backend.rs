@@ -10,6 +10,13 @@ use std::fmt;
 pub enum VerificationBackend {
     Silicon,
     Carbon,
+    /// Lowers programs to SMT-LIB and checks them with Z3 directly, without
+    /// starting a JVM. Only used by `prusti-server`, which falls back to
+    /// Silicon for programs that cannot be lowered.
+    Smt,
+    /// Verifies programs with Silicon and, if Silicon does not verify them,
+    /// with Carbon. Only used by `prusti-server`.
+    Portfolio,
 }
 
 #[derive(Clone, Debug)]
@@ -19,7 +26,7 @@ impl fmt::Display for UknownBackendError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(
             f,
-            "Invalid verification backend: '{}'. Allowed values are 'Silicon' and 'Carbon'",
+            "Invalid verification backend: '{}'. Allowed values are 'Silicon', 'Carbon', 'Smt' and 'Portfolio'",
             self.0
         )
     }
@@ -31,6 +38,8 @@ impl std::str::FromStr for VerificationBackend {
         match backend.to_lowercase().as_str() {
             "silicon" => Ok(VerificationBackend::Silicon),
             "carbon" => Ok(VerificationBackend::Carbon),
+            "smt" => Ok(VerificationBackend::Smt),
+            "portfolio" => Ok(VerificationBackend::Portfolio),
             _ => Err(UknownBackendError(backend.to_string())),
         }
     }
@@ -41,6 +50,8 @@ impl fmt::Display for VerificationBackend {
         match self {
             VerificationBackend::Silicon => write!(f, "Silicon"),
             VerificationBackend::Carbon => write!(f, "Carbon"),
+            VerificationBackend::Smt => write!(f, "Smt"),
+            VerificationBackend::Portfolio => write!(f, "Portfolio"),
         }
     }
 }
@@ -7,7 +7,7 @@
 use crate::{
     ast_factory::*, ast_utils::*, verification_backend::VerificationBackend, verifier::Verifier,
 };
-use jni::AttachGuard;
+use jni::{AttachGuard, JavaVM};
 use log::{debug, info};
 use std::{
     env,
@@ -25,6 +25,14 @@ impl<'a> VerificationContext<'a> {
         VerificationContext { env: env_guard }
     }
 
+    /// The JVM of this context, to which other threads can attach to create
+    /// their own contexts.
+    pub fn java_vm(&self) -> JavaVM {
+        self.env
+            .get_java_vm()
+            .expect("failed to get the JVM of the verification context")
+    }
+
     pub fn new_ast_factory(&self) -> AstFactory {
         AstFactory::new(&self.env)
     }
@@ -4,11 +4,43 @@
 // License, v. 2.0. If a copy of the MPL was not distributed with this
 // file, You can obtain one at http://mozilla.org/MPL/2.0/.
 
-use crate::{silicon_counterexample::SiliconCounterexample, JavaException};
+use crate::{
+    silicon_counterexample::SiliconCounterexample, JavaException, QuantifierStatistics,
+    VerificationBackend,
+};
 
 /// The result of a verification request on a Viper program.
 #[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
-pub enum VerificationResult {
+pub struct VerificationResult {
+    /// The name of the verified program.
+    pub item_name: String,
+    /// The outcome of the verification.
+    pub kind: VerificationResultKind,
+    /// Whether the outcome was taken from the verification cache.
+    pub cached: bool,
+    /// The time (in milliseconds) that the backend took to produce the
+    /// outcome. For cached results, this is the time of the original
+    /// verification.
+    pub time_ms: u128,
+    /// The backend that produced the outcome, if the program was verified.
+    pub backend: Option<VerificationBackend>,
+    /// The number of quantifier instantiations during the verification, if
+    /// the SMT solver was connected through the wrapper.
+    pub quantifier_instantiations: Option<u64>,
+    /// The instantiated quantifiers, the most instantiated first. Empty if
+    /// the SMT solver was not connected through the wrapper.
+    pub quantifiers: Vec<QuantifierStatistics>,
+}
+
+impl VerificationResult {
+    pub fn is_success(&self) -> bool {
+        self.kind.is_success()
+    }
+}
+
+/// The outcome of verifying a Viper program.
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub enum VerificationResultKind {
     /// The program verified.
     Success,
     /// The program did not verify.
@@ -17,12 +49,21 @@ pub enum VerificationResult {
     ConsistencyErrors(Vec<String>),
     /// The verification raised a Java exception.
     JavaException(JavaException),
+    /// The verification was stopped because it exceeded its deadline.
+    TimedOut,
+    /// The verification was cancelled before it finished.
+    Cancelled,
 }
 
-impl VerificationResult {
+impl VerificationResultKind {
     pub fn is_success(&self) -> bool {
         matches!(self, Self::Success)
     }
+
+    /// Whether the verification was stopped before producing an outcome.
+    pub fn is_interrupted(&self) -> bool {
+        matches!(self, Self::TimedOut | Self::Cancelled)
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
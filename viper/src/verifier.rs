@@ -11,11 +11,21 @@ use crate::{
     silicon_counterexample::SiliconCounterexample,
     smt_manager::SmtManager,
     verification_backend::VerificationBackend,
-    verification_result::{VerificationError, VerificationResult},
+    verification_result::{VerificationError, VerificationResultKind},
+};
+use jni::{
+    objects::{GlobalRef, JObject},
+    JNIEnv, JavaVM,
+};
+use log::{debug, error, info, warn};
+use smt_log_analyzer::{QuantifierStatistics, Statistics};
+use std::{
+    path::PathBuf,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
 };
-use jni::{objects::JObject, JNIEnv};
-use log::{debug, error, info};
-use std::path::PathBuf;
 use viper_sys::wrappers::{scala, viper::*};
 
 pub struct Verifier<'a> {
@@ -25,6 +35,40 @@ pub struct Verifier<'a> {
     jni: JniUtils<'a>,
     ast_utils: AstUtils<'a>,
     smt_manager: SmtManager,
+    /// The statistics of the SMT traces of the last verification, if the SMT
+    /// solver was connected through the wrapper.
+    smt_statistics: Option<Statistics>,
+    /// Set when the verification is stopped through a [`StopHandle`].
+    stopped: Arc<AtomicBool>,
+}
+
+/// Allows stopping a running verification from another thread.
+#[derive(Clone)]
+pub struct StopHandle {
+    vm: Arc<JavaVM>,
+    frontend_instance: GlobalRef,
+    stopped: Arc<AtomicBool>,
+}
+
+impl StopHandle {
+    /// Stops the verification, which then returns
+    /// [`VerificationResultKind::Cancelled`]. Stopping the verifier also
+    /// terminates its SMT solver processes. The call has no effect on
+    /// verification steps that already started and cannot be interrupted, so
+    /// it may need to be repeated.
+    pub fn stop(&self) {
+        self.stopped.store(true, Ordering::SeqCst);
+        match self.vm.attach_current_thread() {
+            Ok(env) => {
+                let frontend_wrapper = silver::frontend::ViperFrontendAPI::with(&env);
+                if let Err(e) = frontend_wrapper.call_stop(self.frontend_instance.as_obj()) {
+                    warn!("Failed to stop the verifier: {e}");
+                    let _ = env.exception_clear();
+                }
+            }
+            Err(e) => warn!("Failed to attach to the JVM to stop the verifier: {e}"),
+        }
+    }
 }
 
 impl<'a> Verifier<'a> {
@@ -56,6 +100,9 @@ impl<'a> Verifier<'a> {
                     VerificationBackend::Carbon => {
                         carbon::CarbonFrontendAPI::with(env).new(reporter)
                     }
+                    VerificationBackend::Smt | VerificationBackend::Portfolio => {
+                        unreachable!("the {backend} backend does not run a single verifier")
+                    }
                 }
             };
             let frontend_instance = jni.unwrap_result(unwrapped_frontend_instance);
@@ -70,9 +117,44 @@ impl<'a> Verifier<'a> {
             jni,
             ast_utils,
             smt_manager,
+            smt_statistics: None,
+            stopped: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    /// A handle to stop the verification from another thread.
+    pub fn stop_handle(&self) -> StopHandle {
+        StopHandle {
+            vm: Arc::new(self.jni.unwrap_result(self.env.get_java_vm())),
+            frontend_instance: self
+                .jni
+                .unwrap_result(self.env.new_global_ref(self.frontend_instance)),
+            stopped: self.stopped.clone(),
         }
     }
 
+    /// The number of quantifier instantiations of the last verification, if
+    /// the SMT solver was connected through the wrapper.
+    pub fn quantifier_instantiations(&self) -> Option<u64> {
+        self.smt_statistics
+            .as_ref()
+            .map(|statistics| statistics.quantifier_instantiations as u64)
+    }
+
+    /// The instantiated quantifiers of the last verification, the most
+    /// instantiated first. Empty if the SMT solver was not connected through
+    /// the wrapper.
+    pub fn quantifier_statistics(&self) -> Vec<QuantifierStatistics> {
+        self.smt_statistics
+            .as_ref()
+            .map(|statistics| statistics.quantifiers.clone())
+            .unwrap_or_default()
+    }
+
+    fn is_stopped(&self) -> bool {
+        self.stopped.load(Ordering::SeqCst)
+    }
+
     #[must_use]
     #[tracing::instrument(level = "debug", skip_all)]
     pub fn initialize(self, args: &[String]) -> Self {
@@ -106,9 +188,14 @@ impl<'a> Verifier<'a> {
     }
 
     #[tracing::instrument(name = "viper::verify", level = "debug", skip_all)]
-    pub fn verify(&mut self, program: Program) -> VerificationResult {
+    pub fn verify(&mut self, program: Program) -> VerificationResultKind {
         let ast_utils = self.ast_utils;
         ast_utils.with_local_frame(16, || {
+            if self.is_stopped() {
+                self.smt_manager.stop();
+                return VerificationResultKind::Cancelled;
+            }
+
             debug!(
                 "Program to be verified:\n{}",
                 self.ast_utils.pretty_print(program)
@@ -118,7 +205,7 @@ impl<'a> Verifier<'a> {
                 let consistency_errors = match self.ast_utils.check_consistency(program) {
                     Ok(errors) => errors,
                     Err(java_exception) => {
-                        return VerificationResult::JavaException(java_exception);
+                        return VerificationResultKind::JavaException(java_exception);
                     }
                 };
             );
@@ -128,7 +215,7 @@ impl<'a> Verifier<'a> {
                     "The provided Viper program has {} consistency errors.",
                     consistency_errors.len()
                 );
-                return VerificationResult::ConsistencyErrors(
+                return VerificationResultKind::ConsistencyErrors(
                     consistency_errors
                         .into_iter()
                         .map(|e| self.jni.to_string(e))
@@ -137,8 +224,16 @@ impl<'a> Verifier<'a> {
             }
 
             run_timed!("Viper verification", debug,
-                let viper_result = self.jni.unwrap_result(self.frontend_wrapper.call_verify(self.frontend_instance, program.to_jobject()));
+                let viper_result = self.frontend_wrapper.call_verify(self.frontend_instance, program.to_jobject());
             );
+            if self.is_stopped() {
+                // The outcome of a stopped verification is meaningless, and
+                // might be an exception.
+                let _ = self.env.exception_clear();
+                self.smt_manager.stop();
+                return VerificationResultKind::Cancelled;
+            }
+            let viper_result = self.jni.unwrap_result(viper_result);
             debug!(
                 "Viper verification result: {}",
                 self.jni.to_string(viper_result)
@@ -148,7 +243,7 @@ impl<'a> Verifier<'a> {
                 .jni
                 .is_instance_of(viper_result, "viper/silver/verifier/Failure");
 
-            self.smt_manager.stop_and_check();
+            self.smt_statistics = self.smt_manager.stop_and_check();
 
             if is_failure {
                 let mut errors: Vec<VerificationError> = vec![];
@@ -328,9 +423,9 @@ impl<'a> Verifier<'a> {
                     ))
                 }
 
-                VerificationResult::Failure(errors)
+                VerificationResultKind::Failure(errors)
             } else {
-                VerificationResult::Success
+                VerificationResultKind::Success
             }
         })
     }
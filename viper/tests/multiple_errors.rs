@@ -58,7 +58,7 @@ fn verify_multiple_errors(backend: viper::VerificationBackend, args: Vec<String>
 
     let verification_result = verifier.verify(program);
 
-    if let VerificationResult::Failure(errors) = verification_result {
+    if let VerificationResultKind::Failure(errors) = verification_result {
         assert_eq!(errors.len(), 2);
         assert_eq!(
             errors[0].full_id,
@@ -0,0 +1,155 @@
+use std::{fs, path::PathBuf, thread, time::Duration};
+use viper::*;
+
+fn result(item_name: &str) -> VerificationResult {
+    VerificationResult {
+        item_name: item_name.to_string(),
+        kind: VerificationResultKind::Success,
+        cached: false,
+        time_ms: 0,
+        backend: None,
+        quantifier_instantiations: None,
+        quantifiers: Vec::new(),
+    }
+}
+
+fn cache_dir(name: &str) -> PathBuf {
+    let path = std::env::temp_dir().join(format!("prusti-{}-{name}", std::process::id()));
+    let _ = fs::remove_dir_all(&path);
+    path
+}
+
+#[test]
+fn cache_is_not_shared_between_versions() {
+    let path = cache_dir("version");
+    {
+        let mut cache = PersistentCache::load_cache(path.clone(), "v1".into(), Default::default());
+        (&mut cache).insert(1, result("first"));
+    }
+    {
+        let mut cache = PersistentCache::load_cache(path.clone(), "v1".into(), Default::default());
+        assert_eq!((&mut cache).get(1), Some(result("first")));
+    }
+    {
+        let mut cache = PersistentCache::load_cache(path.clone(), "v2".into(), Default::default());
+        assert_eq!((&mut cache).get(1), None);
+        assert!(cache.entries().is_empty());
+        // Pruning removes the entries of other versions.
+        assert_eq!(cache.prune(), 1);
+    }
+    fs::remove_dir_all(&path).unwrap();
+}
+
+#[test]
+fn cache_evicts_least_recently_used_entries() {
+    let path = cache_dir("size");
+    let mut cache = PersistentCache::load_cache(path.clone(), "v1".into(), Default::default());
+    (&mut cache).insert(1, result("first"));
+    (&mut cache).insert(2, result("second"));
+    let entry_size = cache
+        .entries()
+        .iter()
+        .map(|entry| entry.size)
+        .max()
+        .unwrap();
+    drop(cache);
+
+    let limits = CacheLimits {
+        max_size: entry_size,
+        max_age: Some(Duration::from_secs(3600)),
+    };
+    let mut cache = PersistentCache::load_cache(path.clone(), "v1".into(), limits);
+    assert_eq!(cache.prune(), 1);
+    assert_eq!(cache.entries().len(), 1);
+    drop(cache);
+    fs::remove_dir_all(&path).unwrap();
+}
+
+#[test]
+fn cache_can_be_cleared() {
+    let path = cache_dir("clear");
+    {
+        let mut cache = PersistentCache::load_cache(path.clone(), "v1".into(), Default::default());
+        (&mut cache).insert(1, result("first"));
+        (&mut cache).insert(2, result("second"));
+        assert_eq!(cache.prune(), 0);
+        assert_eq!(cache.clear(), 2);
+    }
+    let cache = PersistentCache::load_cache(path.clone(), "v1".into(), Default::default());
+    assert!(cache.entries().is_empty());
+    drop(cache);
+    fs::remove_dir_all(&path).unwrap();
+}
+
+#[test]
+fn cache_is_shared_by_concurrent_users() {
+    let path = cache_dir("concurrent");
+    let handles: Vec<_> = (0..8)
+        .map(|thread_id| {
+            let path = path.clone();
+            thread::spawn(move || {
+                let mut cache = PersistentCache::load_cache(path, "v1".into(), Default::default());
+                for request in 0..16 {
+                    (&mut cache).insert(request, result(&format!("{thread_id}")));
+                }
+            })
+        })
+        .collect();
+    for handle in handles {
+        handle.join().unwrap();
+    }
+
+    // Every entry was written completely by one of the threads.
+    let mut cache = PersistentCache::load_cache(path.clone(), "v1".into(), Default::default());
+    for request in 0..16 {
+        assert!((&mut cache).get(request).is_some());
+    }
+    assert_eq!(cache.entries().len(), 16);
+    drop(cache);
+    fs::remove_dir_all(&path).unwrap();
+}
+
+#[test]
+fn cache_only_removes_its_own_directories() {
+    let path = cache_dir("foreign");
+    fs::create_dir_all(path.join("notes")).unwrap();
+    fs::write(path.join("notes").join("todo.txt"), "keep me").unwrap();
+    fs::create_dir_all(path.join("v7-not-a-hash")).unwrap();
+    {
+        let mut cache = PersistentCache::load_cache(path.clone(), "v1".into(), Default::default());
+        (&mut cache).insert(1, result("first"));
+    }
+    {
+        let mut cache = PersistentCache::load_cache(path.clone(), "v2".into(), Default::default());
+        (&mut cache).insert(2, result("second"));
+        assert_eq!(cache.prune(), 1);
+        assert_eq!(cache.clear(), 1);
+    }
+    assert!(path.join("notes").join("todo.txt").is_file());
+    assert!(path.join("v7-not-a-hash").is_dir());
+    fs::remove_dir_all(&path).unwrap();
+}
+
+#[test]
+fn cache_replaces_only_old_cache_files() {
+    let path = cache_dir("old-file");
+    // An empty cache of the single-file format: no entries and version 6.
+    let mut old_cache = 0u64.to_le_bytes().to_vec();
+    old_cache.extend(6u64.to_le_bytes());
+    fs::write(&path, old_cache).unwrap();
+    {
+        let mut cache = PersistentCache::load_cache(path.clone(), "v1".into(), Default::default());
+        (&mut cache).insert(1, result("first"));
+    }
+    assert!(path.is_dir());
+    fs::remove_dir_all(&path).unwrap();
+
+    fs::write(&path, "not a cache").unwrap();
+    {
+        let mut cache = PersistentCache::load_cache(path.clone(), "v1".into(), Default::default());
+        (&mut cache).insert(1, result("first"));
+        assert_eq!((&mut cache).get(1), Some(result("first")));
+    }
+    assert_eq!(fs::read_to_string(&path).unwrap(), "not a cache");
+    fs::remove_file(&path).unwrap();
+}
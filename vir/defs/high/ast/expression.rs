@@ -249,6 +249,9 @@ pub enum BuiltinFunc {
     NewInt,
     Index,
     Len,
+    /// Ownership of the memory behind a raw pointer. Only used in
+    /// specifications, which turn it into an `OwnedNonAliased` predicate.
+    Own,
 }
 
 #[display(fmt = "__builtin__{}({})", function, "display::cjoin(arguments)")]
@@ -124,21 +124,22 @@ impl Expression {
     }
     /// Check whether the place is a dereference of a reference and if that is
     /// the case, returns the uniqueness guarantees given by this reference.
+    /// Dereferences of raw pointers give no guarantees: the memory behind them
+    /// is owned like a local variable.
     pub fn get_dereference_kind(&self) -> Option<(ty::LifetimeConst, ty::Uniqueness)> {
         assert!(self.is_place());
         if let Some(parent) = self.get_parent_ref() {
             if let Some(result) = parent.get_dereference_kind() {
                 return Some(result);
             } else if self.is_deref() {
-                if let Type::Reference(ty::Reference {
-                    lifetime,
-                    uniqueness,
-                    ..
-                }) = parent.get_type()
-                {
-                    return Some((lifetime.clone(), *uniqueness));
-                } else {
-                    unreachable!();
+                match parent.get_type() {
+                    Type::Reference(ty::Reference {
+                        lifetime,
+                        uniqueness,
+                        ..
+                    }) => return Some((lifetime.clone(), *uniqueness)),
+                    Type::Pointer(_) => {}
+                    _ => unreachable!(),
                 }
             }
         }